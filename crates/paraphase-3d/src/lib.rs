@@ -9,11 +9,68 @@
 //! - `gltf` — glTF/GLB via `gltf`
 
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    Comparator, ComparatorDecl, ConvertError, FormatDecl, Inspector, InspectorDecl, Properties,
+    PropertyDecl, PropertyPattern, PropertyType, Registry,
 };
+#[cfg(all(feature = "obj", feature = "gltf"))]
+use paraphase_core::{ConvertOutput, Converter, ConverterDecl};
 
 /// Register all enabled 3D converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Property keys produced by the converters and inspectors below
+    registry.declare_property(PropertyDecl::new(
+        "vertex_count",
+        PropertyType::Int,
+        "Number of mesh vertices",
+    ));
+    registry.declare_property(PropertyDecl::new(
+        "triangle_count",
+        PropertyType::Int,
+        "Number of mesh triangles",
+    ));
+
+    // Formats handled by the converters and inspectors below
+    #[cfg(feature = "stl")]
+    registry.register_format(
+        FormatDecl::new("stl")
+            .extension("stl")
+            .mime("model/stl")
+            .description("Stereolithography mesh"),
+    );
+    #[cfg(feature = "obj")]
+    registry.register_format(
+        FormatDecl::new("obj")
+            .extension("obj")
+            .mime("model/obj")
+            .sidecar("mtl")
+            .description("Wavefront OBJ mesh"),
+    );
+    #[cfg(feature = "obj")]
+    registry.register_format(
+        FormatDecl::new("mtl")
+            .extension("mtl")
+            .description("Wavefront material library"),
+    );
+    #[cfg(feature = "ply")]
+    registry.register_format(
+        FormatDecl::new("ply")
+            .extension("ply")
+            .magic(b"ply")
+            .description("Polygon File Format"),
+    );
+    #[cfg(feature = "gltf")]
+    registry.register_format(
+        FormatDecl::new("glb")
+            .extension("glb")
+            .mime("model/gltf-binary")
+            .magic(b"glTF")
+            .description("Binary glTF"),
+    );
+
     #[cfg(feature = "stl")]
     {
         #[cfg(feature = "obj")]
@@ -42,6 +99,7 @@ pub fn register_all(registry: &mut Registry) {
         #[cfg(feature = "gltf")]
         {
             registry.register(ObjToGltf);
+            registry.register(ObjBundleToGltf);
             registry.register(GltfToObj);
         }
     }
@@ -50,6 +108,30 @@ pub fn register_all(registry: &mut Registry) {
         registry.register(PlyToGltf);
         registry.register(GltfToPly);
     }
+
+    #[cfg(feature = "stl")]
+    registry.register_inspector(MeshInspector::new("stl", stl_counts));
+    #[cfg(feature = "obj")]
+    registry.register_inspector(MeshInspector::new("obj", obj_counts));
+    #[cfg(feature = "ply")]
+    registry.register_inspector(MeshInspector::new("ply", ply_counts));
+    #[cfg(feature = "gltf")]
+    {
+        registry.register_inspector(MeshInspector::new("gltf", gltf_counts));
+        registry.register_inspector(MeshInspector::new("glb", gltf_counts));
+    }
+
+    #[cfg(feature = "stl")]
+    registry.register_comparator(MeshComparator::new("stl", stl_to_mesh));
+    #[cfg(feature = "obj")]
+    registry.register_comparator(MeshComparator::new("obj", obj_to_mesh));
+    #[cfg(feature = "ply")]
+    registry.register_comparator(MeshComparator::new("ply", ply_to_mesh));
+    #[cfg(feature = "gltf")]
+    {
+        registry.register_comparator(MeshComparator::new("gltf", gltf_to_mesh));
+        registry.register_comparator(MeshComparator::new("glb", gltf_to_mesh));
+    }
 }
 
 // ============================================================
@@ -61,6 +143,134 @@ struct Mesh {
     faces: Vec<[u32; 3]>,
 }
 
+/// Mesh sizes read from headers or a line scan, without decoding geometry.
+struct MeshCounts {
+    /// `None` for formats that store no shared vertices (STL).
+    vertices: Option<u64>,
+    triangles: u64,
+}
+
+/// Inspector reporting vertex and triangle counts of a mesh.
+///
+/// Counts come from headers (STL, PLY, glTF) or a line scan (OBJ), so large
+/// meshes are not decoded just to be measured.
+pub struct MeshInspector {
+    decl: InspectorDecl,
+    count: fn(&[u8]) -> Result<MeshCounts, ConvertError>,
+}
+
+impl MeshInspector {
+    fn new(format: &str, count: fn(&[u8]) -> Result<MeshCounts, ConvertError>) -> Self {
+        let decl = InspectorDecl::new(
+            format!("3d.inspect-{format}"),
+            PropertyPattern::new().eq("format", format),
+        )
+        .description(format!(
+            "Count {} vertices and triangles",
+            format.to_uppercase()
+        ))
+        .provides("vertex_count")
+        .provides("triangle_count");
+
+        Self { decl, count }
+    }
+}
+
+impl Inspector for MeshInspector {
+    fn decl(&self) -> &InspectorDecl {
+        &self.decl
+    }
+
+    fn inspect(&self, input: &[u8], _props: &Properties) -> Result<Properties, ConvertError> {
+        let counts = (self.count)(input)?;
+        let mut props = Properties::new();
+        if let Some(vertices) = counts.vertices {
+            props.insert("vertex_count".into(), (vertices as i64).into());
+        }
+        props.insert("triangle_count".into(), (counts.triangles as i64).into());
+        Ok(props)
+    }
+}
+
+/// Comparator checking that two meshes describe the same triangles.
+///
+/// Vertex order, indexing and triangle order are ignored; winding and
+/// positions (within [`MeshComparator::EPSILON`]) must match.
+pub struct MeshComparator {
+    decl: ComparatorDecl,
+    parse: fn(&[u8]) -> Result<Mesh, ConvertError>,
+}
+
+impl MeshComparator {
+    /// Largest coordinate difference treated as equal.
+    pub const EPSILON: f32 = 1e-5;
+
+    fn new(format: &str, parse: fn(&[u8]) -> Result<Mesh, ConvertError>) -> Self {
+        let decl = ComparatorDecl::new(
+            format!("3d.compare-{format}"),
+            PropertyPattern::new().eq("format", format),
+        )
+        .description(format!("Compare {} geometry", format.to_uppercase()));
+
+        Self { decl, parse }
+    }
+
+    /// Triangles as vertex positions, rotated to start at their smallest
+    /// vertex and sorted.
+    fn triangles(mesh: &Mesh) -> Vec<[[f32; 3]; 3]> {
+        let mut triangles: Vec<[[f32; 3]; 3]> = mesh
+            .faces
+            .iter()
+            .map(|face| {
+                let mut t =
+                    face.map(|i| mesh.vertices.get(i as usize).copied().unwrap_or_default());
+                let first = (0..3)
+                    .min_by(|&a, &b| t[a].partial_cmp(&t[b]).unwrap_or(std::cmp::Ordering::Equal))
+                    .unwrap_or(0);
+                t.rotate_left(first);
+                t
+            })
+            .collect();
+        triangles.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        triangles
+    }
+}
+
+impl Comparator for MeshComparator {
+    fn decl(&self) -> &ComparatorDecl {
+        &self.decl
+    }
+
+    fn compare(
+        &self,
+        original: &[u8],
+        roundtrip: &[u8],
+        _props: &Properties,
+    ) -> Result<Vec<String>, ConvertError> {
+        let a = Self::triangles(&(self.parse)(original)?);
+        let b = Self::triangles(&(self.parse)(roundtrip)?);
+        if a.len() != b.len() {
+            return Ok(vec![format!("triangles: {}, got {}", a.len(), b.len())]);
+        }
+
+        let close = |x: &[[f32; 3]; 3], y: &[[f32; 3]; 3]| {
+            x.iter()
+                .flatten()
+                .zip(y.iter().flatten())
+                .all(|(p, q)| (p - q).abs() <= Self::EPSILON)
+        };
+        let differing = a.iter().zip(&b).filter(|(x, y)| !close(x, y)).count();
+        if differing == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(vec![format!(
+            "{} of {} triangles differ in position",
+            differing,
+            a.len()
+        )])
+    }
+}
+
 fn compute_normal(v0: [f32; 3], v1: [f32; 3], v2: [f32; 3]) -> [f32; 3] {
     let ax = v1[0] - v0[0];
     let ay = v1[1] - v0[1];
@@ -104,6 +314,34 @@ fn stl_to_mesh(input: &[u8]) -> Result<Mesh, ConvertError> {
     Ok(Mesh { vertices, faces })
 }
 
+/// Triangle count from the binary header, or the `facet` lines of ASCII STL.
+#[cfg(feature = "stl")]
+fn stl_counts(input: &[u8]) -> Result<MeshCounts, ConvertError> {
+    // Binary: 80-byte header, u32 triangle count, 50 bytes per triangle
+    if let Some(count) = input.get(80..84).and_then(|c| <[u8; 4]>::try_from(c).ok()) {
+        let triangles = u32::from_le_bytes(count) as u64;
+        if 84 + 50 * triangles == input.len() as u64 {
+            return Ok(MeshCounts {
+                vertices: None,
+                triangles,
+            });
+        }
+    }
+    if input.trim_ascii_start().starts_with(b"solid") {
+        let triangles = input
+            .split(|&b| b == b'\n')
+            .filter(|line| line.trim_ascii_start().starts_with(b"facet"))
+            .count() as u64;
+        return Ok(MeshCounts {
+            vertices: None,
+            triangles,
+        });
+    }
+    Err(ConvertError::InvalidInput(
+        "Invalid STL: size does not match the triangle count".into(),
+    ))
+}
+
 #[cfg(feature = "stl")]
 fn mesh_to_stl(mesh: &Mesh) -> Result<Vec<u8>, ConvertError> {
     let triangles: Vec<stl_io::Triangle> = mesh
@@ -171,6 +409,26 @@ fn obj_to_mesh(input: &[u8]) -> Result<Mesh, ConvertError> {
     Ok(Mesh { vertices, faces })
 }
 
+/// Count `v` lines and the triangles `f` lines fan out to.
+#[cfg(feature = "obj")]
+fn obj_counts(input: &[u8]) -> Result<MeshCounts, ConvertError> {
+    let (mut vertices, mut triangles) = (0u64, 0u64);
+    for line in input.split(|&b| b == b'\n') {
+        let mut fields = line
+            .split(u8::is_ascii_whitespace)
+            .filter(|field| !field.is_empty());
+        match fields.next() {
+            Some(b"v") => vertices += 1,
+            Some(b"f") => triangles += (fields.count() as u64).saturating_sub(2),
+            _ => {}
+        }
+    }
+    Ok(MeshCounts {
+        vertices: Some(vertices),
+        triangles,
+    })
+}
+
 #[cfg(feature = "obj")]
 fn mesh_to_obj(mesh: &Mesh) -> Vec<u8> {
     let mut out = String::new();
@@ -255,6 +513,209 @@ fn ply_get_list(elem: &ply_rs::ply::DefaultElement, key: &str) -> Result<Vec<i64
     }
 }
 
+/// Vertex count from the header; triangles by walking the face list sizes.
+///
+/// Elements before `face` are skipped without being parsed where their
+/// rows have a fixed size.
+#[cfg(feature = "ply")]
+fn ply_counts(input: &[u8]) -> Result<MeshCounts, ConvertError> {
+    let invalid = |msg: &str| ConvertError::InvalidInput(format!("Invalid PLY: {msg}"));
+
+    let end = input
+        .windows(b"end_header".len())
+        .position(|w| w == b"end_header")
+        .ok_or_else(|| invalid("missing end_header"))?;
+    let header = std::str::from_utf8(&input[..end]).map_err(|_| invalid("header is not UTF-8"))?;
+    let body = input[end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(&[][..], |nl| &input[end + nl + 1..]);
+
+    let mut body = PlyBody {
+        data: body,
+        offset: 0,
+        ascii: true,
+        big_endian: false,
+    };
+    let mut elements: Vec<PlyElement> = Vec::new();
+    for line in header.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["format", "ascii", ..] => body.ascii = true,
+            ["format", "binary_little_endian", ..] => body.ascii = false,
+            ["format", "binary_big_endian", ..] => {
+                body.ascii = false;
+                body.big_endian = true;
+            }
+            ["element", name, count] => elements.push(PlyElement {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| invalid("bad element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let property = PlyProperty {
+                    name: name.to_string(),
+                    size: ply_type_size(item).ok_or_else(|| invalid("unknown type"))?,
+                    list: Some(ply_type_size(count).ok_or_else(|| invalid("unknown type"))?),
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("property outside an element"))?
+                    .properties
+                    .push(property);
+            }
+            ["property", ty, name] => {
+                let property = PlyProperty {
+                    name: name.to_string(),
+                    size: ply_type_size(ty).ok_or_else(|| invalid("unknown type"))?,
+                    list: None,
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| invalid("property outside an element"))?
+                    .properties
+                    .push(property);
+            }
+            _ => {}
+        }
+    }
+
+    let vertices = elements
+        .iter()
+        .find(|e| e.name == "vertex")
+        .ok_or_else(|| invalid("missing 'vertex' element"))?
+        .count;
+
+    let mut triangles = 0u64;
+    for element in &elements {
+        let is_face = element.name == "face";
+        let fixed = element.properties.iter().all(|p| p.list.is_none());
+        if fixed && !is_face {
+            let row: usize = element.properties.iter().map(|p| p.size).sum();
+            body.skip(row, element.properties.len(), element.count)?;
+            continue;
+        }
+        for _ in 0..element.count {
+            for property in &element.properties {
+                let Some(count_size) = property.list else {
+                    body.skip(property.size, 1, 1)?;
+                    continue;
+                };
+                let len = body.read(count_size)?;
+                if is_face && matches!(property.name.as_str(), "vertex_indices" | "vertex_index") {
+                    triangles += len.saturating_sub(2);
+                }
+                body.skip(property.size, 1, len as usize)?;
+            }
+        }
+        if is_face {
+            break;
+        }
+    }
+
+    Ok(MeshCounts {
+        vertices: Some(vertices as u64),
+        triangles,
+    })
+}
+
+#[cfg(feature = "ply")]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+/// A PLY property: its value size in bytes, and the size of the length
+/// prefix for list properties.
+#[cfg(feature = "ply")]
+struct PlyProperty {
+    name: String,
+    size: usize,
+    list: Option<usize>,
+}
+
+#[cfg(feature = "ply")]
+fn ply_type_size(ty: &str) -> Option<usize> {
+    match ty {
+        "char" | "uchar" | "int8" | "uint8" => Some(1),
+        "short" | "ushort" | "int16" | "uint16" => Some(2),
+        "int" | "uint" | "int32" | "uint32" | "float" | "float32" => Some(4),
+        "double" | "float64" => Some(8),
+        _ => None,
+    }
+}
+
+/// Cursor over a PLY body, ASCII tokens or binary values.
+#[cfg(feature = "ply")]
+struct PlyBody<'a> {
+    data: &'a [u8],
+    offset: usize,
+    ascii: bool,
+    big_endian: bool,
+}
+
+#[cfg(feature = "ply")]
+impl<'a> PlyBody<'a> {
+    fn truncated() -> ConvertError {
+        ConvertError::InvalidInput("Invalid PLY: body is truncated".into())
+    }
+
+    /// Next whitespace-separated ASCII token.
+    fn token(&mut self) -> Result<&'a [u8], ConvertError> {
+        let rest = &self.data[self.offset..];
+        let start = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .ok_or_else(Self::truncated)?;
+        let len = rest[start..]
+            .iter()
+            .position(u8::is_ascii_whitespace)
+            .unwrap_or(rest.len() - start);
+        self.offset += start + len;
+        Ok(&rest[start..start + len])
+    }
+
+    /// Read an unsigned value of `size` bytes (or one ASCII token).
+    fn read(&mut self, size: usize) -> Result<u64, ConvertError> {
+        if self.ascii {
+            let token = self.token()?;
+            return std::str::from_utf8(token)
+                .ok()
+                .and_then(|t| t.parse().ok())
+                .ok_or_else(|| ConvertError::InvalidInput("Invalid PLY: bad list length".into()));
+        }
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + size)
+            .ok_or_else(Self::truncated)?;
+        self.offset += size;
+        let fold = |acc: u64, &b: &u8| acc << 8 | b as u64;
+        Ok(if self.big_endian {
+            bytes.iter().fold(0, fold)
+        } else {
+            bytes.iter().rev().fold(0, fold)
+        })
+    }
+
+    /// Skip `rows` rows of `values` values taking `row_size` bytes in total.
+    fn skip(&mut self, row_size: usize, values: usize, rows: usize) -> Result<(), ConvertError> {
+        if self.ascii {
+            for _ in 0..values * rows {
+                self.token()?;
+            }
+            return Ok(());
+        }
+        let end = row_size
+            .checked_mul(rows)
+            .and_then(|n| n.checked_add(self.offset))
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(Self::truncated)?;
+        self.offset = end;
+        Ok(())
+    }
+}
+
 #[cfg(feature = "ply")]
 fn mesh_to_ply(mesh: &Mesh) -> Vec<u8> {
     let mut out = String::new();
@@ -276,13 +737,20 @@ fn mesh_to_ply(mesh: &Mesh) -> Vec<u8> {
 // glTF / GLB
 // ============================================================
 
+/// Read the triangles of every mesh primitive.
+///
+/// The mesh IR keeps positions only, so materials, texture coordinates,
+/// normals and vertex colors are reported as fidelity losses.
 #[cfg(feature = "gltf")]
 fn gltf_to_mesh(input: &[u8]) -> Result<Mesh, ConvertError> {
+    use paraphase_core::{LossNote, Severity, report_loss};
+
     let (document, buffers, _) = gltf::import_slice(input)
         .map_err(|e| ConvertError::InvalidInput(format!("Invalid glTF/GLB: {e}")))?;
 
     let mut vertices: Vec<[f32; 3]> = Vec::new();
     let mut faces: Vec<[u32; 3]> = Vec::new();
+    let (mut uvs, mut normals, mut colors) = (0u64, 0u64, 0u64);
 
     for mesh in document.meshes() {
         for primitive in mesh.primitives() {
@@ -292,6 +760,9 @@ fn gltf_to_mesh(input: &[u8]) -> Result<Mesh, ConvertError> {
                 continue;
             };
             let prim_verts: Vec<[f32; 3]> = pos_iter.collect();
+            uvs += reader.read_tex_coords(0).is_some() as u64;
+            normals += reader.read_normals().is_some() as u64;
+            colors += reader.read_colors(0).is_some() as u64;
             let prim_count = prim_verts.len() as u32;
             vertices.extend(prim_verts);
 
@@ -315,16 +786,61 @@ fn gltf_to_mesh(input: &[u8]) -> Result<Mesh, ConvertError> {
         }
     }
 
+    let materials = document.materials().len() as u64;
+    for (feature, severity, count) in [
+        ("materials", Severity::Major, materials),
+        ("texture coordinates", Severity::Major, uvs),
+        ("normals", Severity::Minor, normals),
+        ("vertex colors", Severity::Major, colors),
+    ] {
+        if count > 0 {
+            report_loss(LossNote::new(feature, severity).count(count));
+        }
+    }
+
     Ok(Mesh { vertices, faces })
 }
 
+/// Count vertices and triangles from accessor counts, without loading
+/// buffers.
+#[cfg(feature = "gltf")]
+fn gltf_counts(input: &[u8]) -> Result<MeshCounts, ConvertError> {
+    let gltf = gltf::Gltf::from_slice(input)
+        .map_err(|e| ConvertError::InvalidInput(format!("Invalid glTF/GLB: {e}")))?;
+    let (mut vertices, mut triangles) = (0u64, 0u64);
+    for primitive in gltf.meshes().flat_map(|mesh| mesh.primitives()) {
+        let Some(positions) = primitive.get(&gltf::Semantic::Positions) else {
+            continue;
+        };
+        vertices += positions.count() as u64;
+        let indices = primitive.indices().map_or(positions.count(), |i| i.count());
+        triangles += indices as u64 / 3;
+    }
+    Ok(MeshCounts {
+        vertices: Some(vertices),
+        triangles,
+    })
+}
+
 #[cfg(feature = "gltf")]
 fn mesh_to_glb(mesh: &Mesh) -> Vec<u8> {
+    write_glb(&mesh.vertices, &[(&mesh.faces, None)], &[])
+}
+
+/// Write a GLB with a single mesh. Each primitive is a set of faces into
+/// `vertices` with an optional index into `materials`, given as RGBA base
+/// colors.
+#[cfg(feature = "gltf")]
+fn write_glb(
+    vertices: &[[f32; 3]],
+    primitives: &[(&[[u32; 3]], Option<usize>)],
+    materials: &[[f32; 4]],
+) -> Vec<u8> {
     // Vertex buffer: f32 x3 per vertex, little-endian
-    let mut vertex_buf: Vec<u8> = Vec::with_capacity(mesh.vertices.len() * 12);
+    let mut vertex_buf: Vec<u8> = Vec::with_capacity(vertices.len() * 12);
     let mut min = [f32::INFINITY; 3];
     let mut max = [f32::NEG_INFINITY; 3];
-    for v in &mesh.vertices {
+    for v in vertices {
         for (i, &c) in v.iter().enumerate() {
             if c < min[i] {
                 min[i] = c;
@@ -337,18 +853,60 @@ fn mesh_to_glb(mesh: &Mesh) -> Vec<u8> {
             vertex_buf.extend_from_slice(&c.to_le_bytes());
         }
     }
-    if mesh.vertices.is_empty() {
+    if vertices.is_empty() {
         min = [0.0; 3];
         max = [0.0; 3];
     }
 
-    // Index buffer: u32 per index, little-endian
-    let mut index_buf: Vec<u8> = Vec::with_capacity(mesh.faces.len() * 12);
-    for f in &mesh.faces {
-        for &idx in f.iter() {
-            index_buf.extend_from_slice(&idx.to_le_bytes());
+    // Index buffer: u32 per index, little-endian, one accessor per primitive
+    let mut index_buf: Vec<u8> = Vec::new();
+    let mut accessors = vec![format!(
+        r#"{{"bufferView":0,"componentType":5126,"count":{nv},"type":"VEC3","min":[{minx},{miny},{minz}],"max":[{maxx},{maxy},{maxz}]}}"#,
+        nv = vertices.len(),
+        minx = min[0],
+        miny = min[1],
+        minz = min[2],
+        maxx = max[0],
+        maxy = max[1],
+        maxz = max[2],
+    )];
+    let mut mesh_primitives = Vec::with_capacity(primitives.len());
+    for (faces, material) in primitives {
+        let offset = match index_buf.len() {
+            0 => String::new(),
+            n => format!(r#","byteOffset":{n}"#),
+        };
+        accessors.push(format!(
+            r#"{{"bufferView":1{offset},"componentType":5125,"count":{ni},"type":"SCALAR"}}"#,
+            ni = faces.len() * 3,
+        ));
+        let material = match material {
+            Some(m) => format!(r#","material":{m}"#),
+            None => String::new(),
+        };
+        mesh_primitives.push(format!(
+            r#"{{"attributes":{{"POSITION":0}},"indices":{}{material}}}"#,
+            accessors.len() - 1
+        ));
+        for f in faces.iter() {
+            for &idx in f.iter() {
+                index_buf.extend_from_slice(&idx.to_le_bytes());
+            }
         }
     }
+    let materials = if materials.is_empty() {
+        String::new()
+    } else {
+        let list: Vec<_> = materials
+            .iter()
+            .map(|[r, g, b, a]| {
+                format!(
+                    r#"{{"pbrMetallicRoughness":{{"baseColorFactor":[{r},{g},{b},{a}],"metallicFactor":0.0}}}}"#
+                )
+            })
+            .collect();
+        format!(r#","materials":[{}]"#, list.join(","))
+    };
 
     // Pad to 4-byte alignment
     while !vertex_buf.len().is_multiple_of(4) {
@@ -363,15 +921,9 @@ fn mesh_to_glb(mesh: &Mesh) -> Vec<u8> {
     let buffer_byte_len = vertex_byte_len + index_byte_len;
 
     let json = format!(
-        r#"{{"asset":{{"version":"2.0"}},"buffers":[{{"byteLength":{buffer_byte_len}}}],"bufferViews":[{{"buffer":0,"byteOffset":0,"byteLength":{vertex_byte_len},"target":34962}},{{"buffer":0,"byteOffset":{vertex_byte_len},"byteLength":{index_byte_len},"target":34963}}],"accessors":[{{"bufferView":0,"componentType":5126,"count":{nv},"type":"VEC3","min":[{minx},{miny},{minz}],"max":[{maxx},{maxy},{maxz}]}},{{"bufferView":1,"componentType":5125,"count":{ni},"type":"SCALAR"}}],"meshes":[{{"name":"mesh","primitives":[{{"attributes":{{"POSITION":0}},"indices":1}}]}}],"nodes":[{{"mesh":0}}],"scenes":[{{"nodes":[0]}}],"scene":0}}"#,
-        nv = mesh.vertices.len(),
-        ni = mesh.faces.len() * 3,
-        minx = min[0],
-        miny = min[1],
-        minz = min[2],
-        maxx = max[0],
-        maxy = max[1],
-        maxz = max[2],
+        r#"{{"asset":{{"version":"2.0"}},"buffers":[{{"byteLength":{buffer_byte_len}}}],"bufferViews":[{{"buffer":0,"byteOffset":0,"byteLength":{vertex_byte_len},"target":34962}},{{"buffer":0,"byteOffset":{vertex_byte_len},"byteLength":{index_byte_len},"target":34963}}],"accessors":[{accessors}],"meshes":[{{"name":"mesh","primitives":[{primitives}]}}]{materials},"nodes":[{{"mesh":0}}],"scenes":[{{"nodes":[0]}}],"scene":0}}"#,
+        accessors = accessors.join(","),
+        primitives = mesh_primitives.join(","),
     );
 
     let mut json_bytes = json.into_bytes();
@@ -401,349 +953,206 @@ fn mesh_to_glb(mesh: &Mesh) -> Vec<u8> {
 }
 
 // ============================================================
-// Converter structs — STL ↔ OBJ
+// Converters — STL ↔ OBJ
 // ============================================================
 
+/// Convert STL triangle mesh to Wavefront OBJ
 #[cfg(all(feature = "stl", feature = "obj"))]
-pub struct StlToObj;
-
-#[cfg(all(feature = "stl", feature = "obj"))]
-impl Converter for StlToObj {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.stl-to-obj",
-                PropertyPattern::new().eq("format", "stl"),
-                PropertyPattern::new().eq("format", "obj"),
-            )
-            .description("Convert STL triangle mesh to Wavefront OBJ")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = stl_to_mesh(input)?;
-        let output = mesh_to_obj(&mesh);
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "obj".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.stl-to-obj", from = "stl", to = "obj")]
+pub fn stl_to_obj(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = stl_to_mesh(input)?;
+    Ok(mesh_to_obj(&mesh))
 }
 
+/// Convert Wavefront OBJ to STL binary triangle mesh
 #[cfg(all(feature = "stl", feature = "obj"))]
-pub struct ObjToStl;
-
-#[cfg(all(feature = "stl", feature = "obj"))]
-impl Converter for ObjToStl {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.obj-to-stl",
-                PropertyPattern::new().eq("format", "obj"),
-                PropertyPattern::new().eq("format", "stl"),
-            )
-            .description("Convert Wavefront OBJ to STL binary triangle mesh")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = obj_to_mesh(input)?;
-        let output = mesh_to_stl(&mesh)?;
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "stl".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.obj-to-stl", from = "obj", to = "stl")]
+pub fn obj_to_stl(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = obj_to_mesh(input)?;
+    mesh_to_stl(&mesh)
 }
 
 // ============================================================
-// Converter structs — STL ↔ PLY
+// Converters — STL ↔ PLY
 // ============================================================
 
+/// Convert STL triangle mesh to PLY
 #[cfg(all(feature = "stl", feature = "ply"))]
-pub struct StlToPly;
-
-#[cfg(all(feature = "stl", feature = "ply"))]
-impl Converter for StlToPly {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.stl-to-ply",
-                PropertyPattern::new().eq("format", "stl"),
-                PropertyPattern::new().eq("format", "ply"),
-            )
-            .description("Convert STL triangle mesh to PLY")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = stl_to_mesh(input)?;
-        let output = mesh_to_ply(&mesh);
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "ply".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.stl-to-ply", from = "stl", to = "ply")]
+pub fn stl_to_ply(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = stl_to_mesh(input)?;
+    Ok(mesh_to_ply(&mesh))
 }
 
+/// Convert PLY mesh to STL binary triangle mesh
 #[cfg(all(feature = "stl", feature = "ply"))]
-pub struct PlyToStl;
-
-#[cfg(all(feature = "stl", feature = "ply"))]
-impl Converter for PlyToStl {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.ply-to-stl",
-                PropertyPattern::new().eq("format", "ply"),
-                PropertyPattern::new().eq("format", "stl"),
-            )
-            .description("Convert PLY mesh to STL binary triangle mesh")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = ply_to_mesh(input)?;
-        let output = mesh_to_stl(&mesh)?;
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "stl".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.ply-to-stl", from = "ply", to = "stl")]
+pub fn ply_to_stl(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = ply_to_mesh(input)?;
+    mesh_to_stl(&mesh)
 }
 
 // ============================================================
-// Converter structs — STL ↔ glTF
+// Converters — STL ↔ glTF
 // ============================================================
 
+/// Convert STL triangle mesh to binary glTF (GLB)
 #[cfg(all(feature = "stl", feature = "gltf"))]
-pub struct StlToGltf;
-
-#[cfg(all(feature = "stl", feature = "gltf"))]
-impl Converter for StlToGltf {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.stl-to-glb",
-                PropertyPattern::new().eq("format", "stl"),
-                PropertyPattern::new().eq("format", "glb"),
-            )
-            .description("Convert STL triangle mesh to binary glTF (GLB)")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = stl_to_mesh(input)?;
-        let output = mesh_to_glb(&mesh);
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "glb".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.stl-to-glb", from = "stl", to = "glb", name = StlToGltf)]
+pub fn stl_to_glb(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = stl_to_mesh(input)?;
+    Ok(mesh_to_glb(&mesh))
 }
 
+/// Convert glTF/GLB to STL binary triangle mesh
 #[cfg(all(feature = "stl", feature = "gltf"))]
-pub struct GltfToStl;
-
-#[cfg(all(feature = "stl", feature = "gltf"))]
-impl Converter for GltfToStl {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            use paraphase_core::{Predicate, Value as PValue};
-            ConverterDecl::simple(
-                "3d.gltf-to-stl",
-                PropertyPattern::new().with(
-                    "format",
-                    Predicate::OneOf(vec![PValue::from("gltf"), PValue::from("glb")]),
-                ),
-                PropertyPattern::new().eq("format", "stl"),
-            )
-            .description("Convert glTF/GLB to STL binary triangle mesh")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = gltf_to_mesh(input)?;
-        let output = mesh_to_stl(&mesh)?;
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "stl".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.gltf-to-stl", from = ["gltf", "glb"], to = "stl")]
+pub fn gltf_to_stl(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = gltf_to_mesh(input)?;
+    mesh_to_stl(&mesh)
 }
 
 // ============================================================
-// Converter structs — OBJ ↔ PLY
+// Converters — OBJ ↔ PLY
 // ============================================================
 
+/// Convert Wavefront OBJ to PLY
 #[cfg(all(feature = "obj", feature = "ply"))]
-pub struct ObjToPly;
-
-#[cfg(all(feature = "obj", feature = "ply"))]
-impl Converter for ObjToPly {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.obj-to-ply",
-                PropertyPattern::new().eq("format", "obj"),
-                PropertyPattern::new().eq("format", "ply"),
-            )
-            .description("Convert Wavefront OBJ to PLY")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = obj_to_mesh(input)?;
-        let output = mesh_to_ply(&mesh);
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "ply".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.obj-to-ply", from = "obj", to = "ply")]
+pub fn obj_to_ply(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = obj_to_mesh(input)?;
+    Ok(mesh_to_ply(&mesh))
 }
 
+/// Convert PLY mesh to Wavefront OBJ
 #[cfg(all(feature = "obj", feature = "ply"))]
-pub struct PlyToObj;
-
-#[cfg(all(feature = "obj", feature = "ply"))]
-impl Converter for PlyToObj {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.ply-to-obj",
-                PropertyPattern::new().eq("format", "ply"),
-                PropertyPattern::new().eq("format", "obj"),
-            )
-            .description("Convert PLY mesh to Wavefront OBJ")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = ply_to_mesh(input)?;
-        let output = mesh_to_obj(&mesh);
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "obj".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.ply-to-obj", from = "ply", to = "obj")]
+pub fn ply_to_obj(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = ply_to_mesh(input)?;
+    Ok(mesh_to_obj(&mesh))
 }
 
 // ============================================================
-// Converter structs — OBJ ↔ glTF
+// Converters — OBJ ↔ glTF
 // ============================================================
 
+/// Convert Wavefront OBJ to binary glTF (GLB)
 #[cfg(all(feature = "obj", feature = "gltf"))]
-pub struct ObjToGltf;
+#[paraphase_macros::paraphase_converter(id = "3d.obj-to-glb", from = "obj", to = "glb", name = ObjToGltf)]
+pub fn obj_to_glb(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = obj_to_mesh(input)?;
+    Ok(mesh_to_glb(&mesh))
+}
 
+/// Converts an OBJ bundled with its MTL sidecars to GLB, keeping the
+/// diffuse colors of its materials.
 #[cfg(all(feature = "obj", feature = "gltf"))]
-impl Converter for ObjToGltf {
+pub struct ObjBundleToGltf;
+
+#[cfg(all(feature = "obj", feature = "gltf"))]
+impl Converter for ObjBundleToGltf {
     fn decl(&self) -> &ConverterDecl {
         static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
         DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.obj-to-glb",
-                PropertyPattern::new().eq("format", "obj"),
-                PropertyPattern::new().eq("format", "glb"),
-            )
-            .description("Convert Wavefront OBJ to binary glTF (GLB)")
+            use paraphase_core::PortDecl;
+            ConverterDecl::new("3d.obj-bundle-to-glb")
+                .description("Convert Wavefront OBJ with its MTL materials to binary glTF (GLB)")
+                .input("in", PortDecl::bundle("obj"))
+                .output(
+                    "out",
+                    PortDecl::single(PropertyPattern::new().eq("format", "glb")),
+                )
         })
     }
 
     fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = obj_to_mesh(input)?;
-        let output = mesh_to_glb(&mesh);
+        use paraphase_core::{Bundle, LossNote, Severity, report_loss};
+        use std::io::{BufReader, Cursor};
+
+        let bundle = Bundle::from_bytes(input)?;
+        let opts = tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        };
+        // `mtllib` paths are relative to the OBJ, whose sidecars are members
+        let load_mtl = |path: &std::path::Path| {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            let member = bundle.get(name).ok_or(tobj::LoadError::OpenFileFailed)?;
+            tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(&member.data)))
+        };
+        let mut reader = BufReader::new(Cursor::new(&bundle.primary().data));
+        let (models, materials) = tobj::load_obj_buf(&mut reader, &opts, load_mtl)
+            .map_err(|e| ConvertError::InvalidInput(format!("Invalid OBJ: {e}")))?;
+        let materials: Vec<[f32; 4]> = match materials {
+            Ok(materials) => materials
+                .iter()
+                .map(|m| {
+                    let [r, g, b] = m.diffuse.unwrap_or([0.8; 3]);
+                    [r, g, b, m.dissolve.unwrap_or(1.0)]
+                })
+                .collect(),
+            Err(_) => {
+                report_loss(LossNote::new("materials", Severity::Minor));
+                Vec::new()
+            }
+        };
+
+        let mut vertices: Vec<[f32; 3]> = Vec::new();
+        let mut groups: Vec<(Vec<[u32; 3]>, Option<usize>)> = Vec::new();
+        for model in &models {
+            let m = &model.mesh;
+            let offset = vertices.len() as u32;
+            vertices.extend(m.positions.chunks_exact(3).map(|p| [p[0], p[1], p[2]]));
+            let faces = m
+                .indices
+                .chunks_exact(3)
+                .map(|f| [offset + f[0], offset + f[1], offset + f[2]])
+                .collect();
+            let material = m.material_id.filter(|&id| id < materials.len());
+            groups.push((faces, material));
+        }
+        let primitives: Vec<_> = groups
+            .iter()
+            .map(|(faces, material)| (faces.as_slice(), *material))
+            .collect();
+        let output = write_glb(&vertices, &primitives, &materials);
+
         let mut out_props = props.clone();
+        out_props.shift_remove("bundle_format");
+        out_props.shift_remove("members");
         out_props.insert("format".into(), "glb".into());
         Ok(ConvertOutput::Single(output, out_props))
     }
 }
 
+/// Convert glTF/GLB to Wavefront OBJ
 #[cfg(all(feature = "obj", feature = "gltf"))]
-pub struct GltfToObj;
-
-#[cfg(all(feature = "obj", feature = "gltf"))]
-impl Converter for GltfToObj {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            use paraphase_core::{Predicate, Value as PValue};
-            ConverterDecl::simple(
-                "3d.gltf-to-obj",
-                PropertyPattern::new().with(
-                    "format",
-                    Predicate::OneOf(vec![PValue::from("gltf"), PValue::from("glb")]),
-                ),
-                PropertyPattern::new().eq("format", "obj"),
-            )
-            .description("Convert glTF/GLB to Wavefront OBJ")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = gltf_to_mesh(input)?;
-        let output = mesh_to_obj(&mesh);
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "obj".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.gltf-to-obj", from = ["gltf", "glb"], to = "obj")]
+pub fn gltf_to_obj(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = gltf_to_mesh(input)?;
+    Ok(mesh_to_obj(&mesh))
 }
 
 // ============================================================
-// Converter structs — PLY ↔ glTF
+// Converters — PLY ↔ glTF
 // ============================================================
 
+/// Convert PLY mesh to binary glTF (GLB)
 #[cfg(all(feature = "ply", feature = "gltf"))]
-pub struct PlyToGltf;
-
-#[cfg(all(feature = "ply", feature = "gltf"))]
-impl Converter for PlyToGltf {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            ConverterDecl::simple(
-                "3d.ply-to-glb",
-                PropertyPattern::new().eq("format", "ply"),
-                PropertyPattern::new().eq("format", "glb"),
-            )
-            .description("Convert PLY mesh to binary glTF (GLB)")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = ply_to_mesh(input)?;
-        let output = mesh_to_glb(&mesh);
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "glb".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.ply-to-glb", from = "ply", to = "glb", name = PlyToGltf)]
+pub fn ply_to_glb(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = ply_to_mesh(input)?;
+    Ok(mesh_to_glb(&mesh))
 }
 
+/// Convert glTF/GLB to PLY mesh
 #[cfg(all(feature = "ply", feature = "gltf"))]
-pub struct GltfToPly;
-
-#[cfg(all(feature = "ply", feature = "gltf"))]
-impl Converter for GltfToPly {
-    fn decl(&self) -> &ConverterDecl {
-        static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-        DECL.get_or_init(|| {
-            use paraphase_core::{Predicate, Value as PValue};
-            ConverterDecl::simple(
-                "3d.gltf-to-ply",
-                PropertyPattern::new().with(
-                    "format",
-                    Predicate::OneOf(vec![PValue::from("gltf"), PValue::from("glb")]),
-                ),
-                PropertyPattern::new().eq("format", "ply"),
-            )
-            .description("Convert glTF/GLB to PLY mesh")
-        })
-    }
-
-    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let mesh = gltf_to_mesh(input)?;
-        let output = mesh_to_ply(&mesh);
-        let mut out_props = props.clone();
-        out_props.insert("format".into(), "ply".into());
-        Ok(ConvertOutput::Single(output, out_props))
-    }
+#[paraphase_macros::paraphase_converter(id = "3d.gltf-to-ply", from = ["gltf", "glb"], to = "ply")]
+pub fn gltf_to_ply(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let mesh = gltf_to_mesh(input)?;
+    Ok(mesh_to_ply(&mesh))
 }
@@ -5,12 +5,14 @@
 
 use hound::{WavSpec, WavWriter};
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    Comparator, ComparatorDecl, ConvertError, ConvertOutput, Converter, ConverterDecl, FormatDecl,
+    Inspector, InspectorDecl, Predicate, Properties, PropertiesExt, PropertyDecl, PropertyPattern,
+    PropertyType, Registry,
 };
 use std::io::Cursor;
 use symphonia::core::audio::AudioBufferRef;
 use symphonia::core::codecs::{CODEC_TYPE_NULL, DecoderOptions};
-use symphonia::core::formats::FormatOptions;
+use symphonia::core::formats::{FormatOptions, FormatReader};
 use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
@@ -67,6 +69,41 @@ impl AudioFormat {
             AudioFormat::Aac => "aac",
         }
     }
+
+    /// Format descriptor for the registry
+    pub fn format_decl(&self) -> FormatDecl {
+        let decl = FormatDecl::new(self.as_str()).extensions(self.extensions().iter().copied());
+        match self {
+            AudioFormat::Wav => decl
+                .mime("audio/wav")
+                .mime("audio/x-wav")
+                .mime("audio/wave")
+                .magic_at(8, b"WAVE")
+                .description("Waveform audio"),
+            AudioFormat::Flac => decl
+                .mime("audio/flac")
+                .mime("audio/x-flac")
+                .magic(b"fLaC")
+                .description("Free Lossless Audio Codec"),
+            AudioFormat::Mp3 => decl
+                .mime("audio/mpeg")
+                .magic(b"ID3")
+                .alias("mpeg")
+                .description("MPEG-1 Audio Layer III"),
+            AudioFormat::Ogg => decl
+                .mime("audio/ogg")
+                .mime("audio/x-vorbis+ogg")
+                .magic(b"OggS")
+                .alias("vorbis")
+                .description("Ogg Vorbis"),
+            AudioFormat::Aac => decl
+                .mime("audio/aac")
+                .mime("audio/x-aac")
+                .magic(b"\xFF\xF1")
+                .magic(b"\xFF\xF9")
+                .description("Advanced Audio Coding"),
+        }
+    }
 }
 
 /// Decoded audio data in a common format
@@ -76,10 +113,19 @@ struct DecodedAudio {
     sample_rate: u32,
 }
 
-/// Decode audio from any supported format
-fn decode_audio(input: &[u8], hint: Option<&str>) -> Result<DecodedAudio, ConvertError> {
-    let cursor = Cursor::new(input.to_vec());
-    let mss = MediaSourceStream::new(Box::new(cursor), Default::default());
+/// Probe the container of any supported format and run `f` on its reader.
+///
+/// The input is read in place rather than copied.
+fn with_audio_reader<T>(
+    input: &[u8],
+    hint: Option<&str>,
+    f: impl FnOnce(&mut dyn FormatReader) -> Result<T, ConvertError>,
+) -> Result<T, ConvertError> {
+    // SAFETY: symphonia only takes 'static sources. The reader owning this
+    // slice is dropped before we return or unwind, and `f` only borrows it,
+    // so the slice is never used after `input`'s borrow ends.
+    let data: &'static [u8] = unsafe { std::slice::from_raw_parts(input.as_ptr(), input.len()) };
+    let mss = MediaSourceStream::new(Box::new(Cursor::new(data)), Default::default());
 
     let mut format_hint = Hint::new();
     if let Some(ext) = hint {
@@ -96,7 +142,16 @@ fn decode_audio(input: &[u8], hint: Option<&str>) -> Result<DecodedAudio, Conver
         .map_err(|e| ConvertError::InvalidInput(format!("Failed to probe audio format: {}", e)))?;
 
     let mut format = probed.format;
+    f(format.as_mut())
+}
 
+/// Decode audio from any supported format
+fn decode_audio(input: &[u8], hint: Option<&str>) -> Result<DecodedAudio, ConvertError> {
+    with_audio_reader(input, hint, decode_tracks)
+}
+
+/// Decode the first audio track of a container
+fn decode_tracks(format: &mut dyn FormatReader) -> Result<DecodedAudio, ConvertError> {
     let track = format
         .tracks()
         .iter()
@@ -341,8 +396,196 @@ impl Converter for WavPassthroughConverter {
     }
 }
 
+/// Inspector reading stream parameters from an audio container
+pub struct AudioInspector {
+    decl: InspectorDecl,
+    format: AudioFormat,
+}
+
+impl AudioInspector {
+    pub fn new(format: AudioFormat) -> Self {
+        let decl = InspectorDecl::new(
+            format!("audio.inspect-{}", format.as_str()),
+            PropertyPattern::new().eq("format", format.as_str()),
+        )
+        .description(format!(
+            "Read {} sample rate, channels and duration",
+            format.as_str().to_uppercase()
+        ))
+        .provides("sample_rate")
+        .provides("channels")
+        .provides("duration");
+
+        Self { decl, format }
+    }
+
+    /// Sample rate, channels and duration of the first audio track
+    fn stream_properties(format: &mut dyn FormatReader) -> Result<Properties, ConvertError> {
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| ConvertError::InvalidInput("No audio track found".into()))?;
+        let params = &track.codec_params;
+
+        let mut props = Properties::new();
+        if let Some(rate) = params.sample_rate {
+            props = props.with("sample_rate", rate as i64);
+        }
+        if let Some(channels) = params.channels {
+            props = props.with("channels", channels.count() as i64);
+        }
+        // Duration in seconds, when the container records the frame count
+        if let (Some(frames), Some(rate)) = (params.n_frames, params.sample_rate) {
+            props = props.with("duration", frames as f64 / rate as f64);
+        }
+        Ok(props)
+    }
+}
+
+impl Inspector for AudioInspector {
+    fn decl(&self) -> &InspectorDecl {
+        &self.decl
+    }
+
+    fn inspect(&self, input: &[u8], _props: &Properties) -> Result<Properties, ConvertError> {
+        with_audio_reader(input, Some(self.format.as_str()), Self::stream_properties)
+    }
+}
+
+/// Compares two audio files by their decoded samples.
+pub struct SampleComparator {
+    decl: ComparatorDecl,
+    tolerance: u16,
+}
+
+impl SampleComparator {
+    /// Default tolerance in 16-bit sample steps, enough to absorb dithering
+    /// and rounding but not lossy re-encoding.
+    pub const DEFAULT_TOLERANCE: u16 = 1;
+
+    /// Create a comparator accepting sample differences up to `tolerance`.
+    pub fn new(tolerance: u16) -> Self {
+        let formats = [
+            #[cfg(feature = "wav")]
+            AudioFormat::Wav,
+            #[cfg(feature = "flac")]
+            AudioFormat::Flac,
+            #[cfg(feature = "mp3")]
+            AudioFormat::Mp3,
+            #[cfg(feature = "ogg")]
+            AudioFormat::Ogg,
+            #[cfg(feature = "aac")]
+            AudioFormat::Aac,
+        ];
+        let decl = ComparatorDecl::new(
+            "audio.samples",
+            PropertyPattern::new().with(
+                "format",
+                Predicate::OneOf(formats.iter().map(|f| f.as_str().into()).collect()),
+            ),
+        )
+        .description(format!(
+            "Compare decoded samples (tolerance {} per sample)",
+            tolerance
+        ));
+        Self { decl, tolerance }
+    }
+}
+
+impl Comparator for SampleComparator {
+    fn decl(&self) -> &ComparatorDecl {
+        &self.decl
+    }
+
+    fn compare(
+        &self,
+        original: &[u8],
+        roundtrip: &[u8],
+        props: &Properties,
+    ) -> Result<Vec<String>, ConvertError> {
+        let hint = props.get("format").and_then(|v| v.as_str());
+        let a = decode_audio(original, hint)?;
+        let b = decode_audio(roundtrip, hint)?;
+
+        let mut diffs = Vec::new();
+        if a.channels != b.channels {
+            diffs.push(format!("channels: {}, got {}", a.channels, b.channels));
+        }
+        if a.sample_rate != b.sample_rate {
+            diffs.push(format!(
+                "sample rate: {}, got {}",
+                a.sample_rate, b.sample_rate
+            ));
+        }
+        if a.samples.len() != b.samples.len() {
+            diffs.push(format!(
+                "length: {} samples, got {}",
+                a.samples.len(),
+                b.samples.len()
+            ));
+        }
+        if !diffs.is_empty() {
+            return Ok(diffs);
+        }
+
+        let deltas = a
+            .samples
+            .iter()
+            .zip(&b.samples)
+            .map(|(x, y)| x.abs_diff(*y));
+        let (differing, max) = deltas.fold((0u64, 0u16), |(n, max), d| {
+            (n + (d > self.tolerance) as u64, max.max(d))
+        });
+        if differing > 0 {
+            diffs.push(format!(
+                "{} of {} samples differ by more than {} (max {})",
+                differing,
+                a.samples.len(),
+                self.tolerance,
+                max
+            ));
+        }
+        Ok(diffs)
+    }
+}
+
 /// Register all audio converters
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Property keys produced by the converters and inspectors below
+    registry.declare_property(
+        PropertyDecl::new("sample_rate", PropertyType::Int, "Samples per second").unit("Hz"),
+    );
+    registry.declare_property(PropertyDecl::new(
+        "channels",
+        PropertyType::Int,
+        "Number of audio channels",
+    ));
+    registry.declare_property(
+        PropertyDecl::new("bits_per_sample", PropertyType::Int, "Sample bit depth").unit("bits"),
+    );
+
+    // Formats handled by the converters below
+    let formats: &[AudioFormat] = &[
+        #[cfg(feature = "wav")]
+        AudioFormat::Wav,
+        #[cfg(feature = "flac")]
+        AudioFormat::Flac,
+        #[cfg(feature = "mp3")]
+        AudioFormat::Mp3,
+        #[cfg(feature = "ogg")]
+        AudioFormat::Ogg,
+        #[cfg(feature = "aac")]
+        AudioFormat::Aac,
+    ];
+    for format in formats {
+        registry.register_format(format.format_decl());
+    }
+
     // X -> WAV converters
     #[cfg(feature = "flac")]
     registry.register(AudioToWavConverter::new(AudioFormat::Flac));
@@ -359,6 +602,24 @@ pub fn register_all(registry: &mut Registry) {
     // WAV passthrough (always available with wav feature)
     #[cfg(feature = "wav")]
     registry.register(WavPassthroughConverter::new());
+
+    // Inspectors for every decodable format
+    #[cfg(feature = "wav")]
+    registry.register_inspector(AudioInspector::new(AudioFormat::Wav));
+
+    #[cfg(feature = "flac")]
+    registry.register_inspector(AudioInspector::new(AudioFormat::Flac));
+
+    #[cfg(feature = "mp3")]
+    registry.register_inspector(AudioInspector::new(AudioFormat::Mp3));
+
+    #[cfg(feature = "ogg")]
+    registry.register_inspector(AudioInspector::new(AudioFormat::Ogg));
+
+    #[cfg(feature = "aac")]
+    registry.register_inspector(AudioInspector::new(AudioFormat::Aac));
+
+    registry.register_comparator(SampleComparator::new(SampleComparator::DEFAULT_TOLERANCE));
 }
 
 #[cfg(test)]
@@ -374,4 +635,64 @@ mod tests {
         assert_eq!(AudioFormat::parse("ogg"), Some(AudioFormat::Ogg));
         assert_eq!(AudioFormat::parse("invalid"), None);
     }
+
+    #[cfg(feature = "wav")]
+    fn wav(samples: &[i16]) -> Vec<u8> {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut wav = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut wav, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        wav.into_inner()
+    }
+
+    #[cfg(feature = "wav")]
+    #[test]
+    fn test_inspect_wav() {
+        let wav = wav(&[0; 8000 * 2]);
+
+        let inspector = AudioInspector::new(AudioFormat::Wav);
+        let props = inspector.inspect(&wav, &Properties::new()).unwrap();
+        assert_eq!(
+            props.get("sample_rate").and_then(|v| v.as_i64()),
+            Some(8000)
+        );
+        assert_eq!(props.get("channels").and_then(|v| v.as_i64()), Some(2));
+        assert_eq!(props.get("duration").and_then(|v| v.as_f64()), Some(1.0));
+    }
+
+    #[cfg(feature = "wav")]
+    #[test]
+    fn test_sample_comparator() {
+        let comparator = SampleComparator::new(1);
+        let props = Properties::new().with("format", "wav");
+        let original = wav(&[0, 100, -100, 2000]);
+
+        let close = wav(&[1, 100, -101, 2000]);
+        assert!(
+            comparator
+                .compare(&original, &close, &props)
+                .unwrap()
+                .is_empty()
+        );
+
+        let far = wav(&[0, 100, -100, 1000]);
+        assert_eq!(
+            comparator.compare(&original, &far, &props).unwrap(),
+            vec!["1 of 4 samples differ by more than 1 (max 1000)"]
+        );
+
+        let short = wav(&[0, 100]);
+        assert_eq!(
+            comparator.compare(&original, &short, &props).unwrap(),
+            vec!["length: 4 samples, got 2"]
+        );
+    }
 }
@@ -1,6 +1,7 @@
 //! Configuration file and presets support.
 #![allow(dead_code)]
 
+use paraphase_core::{CommandDecl, Limits};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -14,6 +15,17 @@ pub struct Config {
     /// User-defined presets.
     #[serde(default)]
     pub presets: HashMap<String, Preset>,
+    /// Converters implemented by external commands.
+    ///
+    /// ```toml
+    /// [[converters]]
+    /// id = "acme.foo-to-bar"
+    /// input = { format = { eq = "foo" } }
+    /// output = { format = { eq = "bar" } }
+    /// command = ["foo2bar", "{input}", "-o", "{output}"]
+    /// timeout = 30
+    /// ```
+    pub converters: Vec<CommandDecl>,
 }
 
 /// Default CLI options.
@@ -26,6 +38,22 @@ pub struct Defaults {
     pub quiet: bool,
     /// Default memory limit in bytes.
     pub memory_limit: Option<usize>,
+    /// Default directory for spilling intermediates to disk.
+    pub spill_dir: Option<PathBuf>,
+    /// Default file for learned converter costs.
+    pub cost_store: Option<PathBuf>,
+    /// Extra directories to load converter plugins from.
+    pub plugin_dirs: Vec<PathBuf>,
+    /// Converters to run in worker processes (IDs, or prefixes like `video.*`).
+    pub isolate: Vec<String>,
+    /// Default safety limits for untrusted input.
+    ///
+    /// ```toml
+    /// [defaults.limits]
+    /// max_expansion_ratio = 100
+    /// max_megapixels = 50
+    /// ```
+    pub limits: Limits,
 }
 
 // ============================================================================
@@ -4,41 +4,11 @@
 #![allow(clippy::collapsible_match)]
 #![allow(clippy::manual_find)]
 
-use paraphase_core::Registry;
+use paraphase_core::{Formats, Registry};
 use std::path::Path;
 
-/// Supported format categories for error messages.
-const DATA_FORMATS: &[&str] = &[
-    "json",
-    "yaml",
-    "toml",
-    "xml",
-    "csv",
-    "ron",
-    "json5",
-    "msgpack",
-    "cbor",
-    "bincode",
-    "postcard",
-    "bson",
-    "flexbuffers",
-    "bencode",
-    "pickle",
-    "plist",
-    "lexpr",
-];
-
-const IMAGE_FORMATS: &[&str] = &[
-    "png", "jpg", "webp", "gif", "bmp", "ico", "tiff", "tga", "pnm", "farbfeld", "qoi", "avif",
-    "exr", "hdr",
-];
-
-const AUDIO_FORMATS: &[&str] = &["wav", "flac", "mp3", "ogg", "aac"];
-
-const VIDEO_FORMATS: &[&str] = &["mp4", "webm", "mkv", "avi", "mov"];
-
 /// Build an error message for when format detection fails.
-pub fn format_detection_error(path: &str, is_source: bool) -> String {
+pub fn format_detection_error(path: &str, is_source: bool, formats: &Formats) -> String {
     let direction = if is_source { "source" } else { "target" };
     let flag = if is_source { "--from" } else { "--to" };
 
@@ -51,7 +21,7 @@ pub fn format_detection_error(path: &str, is_source: bool) -> String {
 
     if let Some(ref ext) = ext {
         // Check for similar known formats
-        if let Some(suggestion) = find_similar_format(ext) {
+        if let Some(suggestion) = find_similar_format(ext, formats) {
             msg.push_str(&format!(
                 ".\n\nDid you mean '{}'? Use {} {} to specify.",
                 suggestion, flag, suggestion
@@ -65,15 +35,38 @@ pub fn format_detection_error(path: &str, is_source: bool) -> String {
     }
 
     msg.push_str(&format!(".\n\nUse {} <format> to specify. ", flag));
-    msg.push_str("Supported formats:\n");
-    msg.push_str(&format!("  Data:  {}\n", DATA_FORMATS.join(", ")));
-    msg.push_str(&format!("  Image: {}\n", IMAGE_FORMATS.join(", ")));
-    msg.push_str(&format!("  Audio: {}\n", AUDIO_FORMATS.join(", ")));
-    msg.push_str(&format!("  Video: {}", VIDEO_FORMATS.join(", ")));
+    msg.push_str("Supported formats:");
+    for (category, ids) in format_categories(formats) {
+        msg.push_str(&format!(
+            "\n  {:<6} {}",
+            format!("{}:", category),
+            ids.join(", ")
+        ));
+    }
 
     msg
 }
 
+/// Registered format IDs grouped by media type, in registration order.
+fn format_categories(formats: &Formats) -> Vec<(&'static str, Vec<&str>)> {
+    let mut categories: Vec<(&'static str, Vec<&str>)> = Vec::new();
+    for decl in formats.iter() {
+        let category = match decl.media_type() {
+            Some("image") => "Image",
+            Some("audio") => "Audio",
+            Some("video") => "Video",
+            Some("font") => "Font",
+            Some("model") => "3D",
+            _ => "Data",
+        };
+        match categories.iter_mut().find(|(c, _)| *c == category) {
+            Some((_, ids)) => ids.push(&decl.id),
+            None => categories.push((category, vec![&decl.id])),
+        }
+    }
+    categories
+}
+
 /// Build an error message for when no conversion path is found.
 pub fn no_path_error(source_format: &str, target_format: &str, registry: &Registry) -> String {
     let mut msg = format!(
@@ -82,8 +75,9 @@ pub fn no_path_error(source_format: &str, target_format: &str, registry: &Regist
     );
 
     // Check if formats are known
-    let source_known = is_known_format(source_format);
-    let target_known = is_known_format(target_format);
+    let formats = registry.formats();
+    let source_known = is_known_format(source_format, formats);
+    let target_known = is_known_format(target_format, formats);
 
     if !source_known && !target_known {
         msg.push_str(".\n\nNeither format is recognized. Check spelling or install plugins.");
@@ -92,7 +86,7 @@ pub fn no_path_error(source_format: &str, target_format: &str, registry: &Regist
             ".\n\nSource format '{}' is not recognized.",
             source_format
         ));
-        if let Some(suggestion) = find_similar_format(source_format) {
+        if let Some(suggestion) = find_similar_format(source_format, formats) {
             msg.push_str(&format!(" Did you mean '{}'?", suggestion));
         }
     } else if !target_known {
@@ -100,7 +94,7 @@ pub fn no_path_error(source_format: &str, target_format: &str, registry: &Regist
             ".\n\nTarget format '{}' is not recognized.",
             target_format
         ));
-        if let Some(suggestion) = find_similar_format(target_format) {
+        if let Some(suggestion) = find_similar_format(target_format, formats) {
             msg.push_str(&format!(" Did you mean '{}'?", suggestion));
         }
     } else {
@@ -167,49 +161,25 @@ pub fn file_read_error(path: &str, err: &std::io::Error) -> String {
     msg
 }
 
-/// Check if a format is in our known list.
-fn is_known_format(format: &str) -> bool {
-    let format = format.to_lowercase();
-    DATA_FORMATS.contains(&format.as_str())
-        || IMAGE_FORMATS.contains(&format.as_str())
-        || AUDIO_FORMATS.contains(&format.as_str())
-        || VIDEO_FORMATS.contains(&format.as_str())
+/// Check if a format (or one of its aliases) is registered.
+fn is_known_format(format: &str, formats: &Formats) -> bool {
+    formats.resolve(format).is_some()
 }
 
 /// Find a similar format name (for typo suggestions).
-fn find_similar_format(input: &str) -> Option<&'static str> {
-    let input = input.to_lowercase();
-
-    // Common typos and aliases
-    let aliases: &[(&str, &str)] = &[
-        ("jpeg", "jpg"),
-        ("tif", "tiff"),
-        ("yml", "yaml"),
-        ("htm", "html"),
-        ("wave", "wav"),
-        ("mpeg", "mp3"),
-        ("msgpk", "msgpack"),
-        ("jsonl", "ndjson"),
-    ];
-
-    for (alias, canonical) in aliases {
-        if input == *alias {
-            return Some(canonical);
+fn find_similar_format(input: &str, formats: &Formats) -> Option<String> {
+    // Aliases and extensions of registered formats
+    if let Some(id) = formats.resolve(input) {
+        if id != input {
+            return Some(id.to_string());
         }
     }
 
     // Levenshtein distance 1-2 for short strings
-    let all_formats: Vec<&str> = DATA_FORMATS
-        .iter()
-        .chain(IMAGE_FORMATS)
-        .chain(AUDIO_FORMATS)
-        .chain(VIDEO_FORMATS)
-        .copied()
-        .collect();
-
-    for format in all_formats {
-        if levenshtein(&input, format) <= 2 && input != format {
-            return Some(format);
+    let input = input.to_lowercase();
+    for decl in formats.iter() {
+        if levenshtein(&input, &decl.id) <= 2 && input != decl.id {
+            return Some(decl.id.clone());
         }
     }
 
@@ -335,13 +305,35 @@ fn find_similar_files(path: &str) -> Option<Vec<String>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use paraphase_core::FormatDecl;
+
+    fn formats() -> Formats {
+        let mut formats = Formats::well_known();
+        formats.register(FormatDecl::new("png").extension("png").mime("image/png"));
+        formats.register(
+            FormatDecl::new("jpg")
+                .extensions(["jpg", "jpeg"])
+                .mime("image/jpeg"),
+        );
+        formats
+    }
 
     #[test]
     fn test_find_similar_format() {
-        assert_eq!(find_similar_format("jpeg"), Some("jpg"));
-        assert_eq!(find_similar_format("yml"), Some("yaml"));
-        assert_eq!(find_similar_format("pngg"), Some("png")); // typo
-        assert_eq!(find_similar_format("zzzzz"), None); // nothing close
+        let formats = formats();
+        assert_eq!(
+            find_similar_format("jpeg", &formats).as_deref(),
+            Some("jpg")
+        );
+        assert_eq!(
+            find_similar_format("yml", &formats).as_deref(),
+            Some("yaml")
+        );
+        assert_eq!(
+            find_similar_format("pngg", &formats).as_deref(),
+            Some("png")
+        ); // typo
+        assert_eq!(find_similar_format("zzzzz", &formats), None); // nothing close
     }
 
     #[test]
@@ -354,8 +346,21 @@ mod tests {
 
     #[test]
     fn test_is_known_format() {
-        assert!(is_known_format("png"));
-        assert!(is_known_format("JSON")); // case insensitive
-        assert!(!is_known_format("unknown"));
+        let formats = formats();
+        assert!(is_known_format("png", &formats));
+        assert!(is_known_format("JSON", &formats)); // case insensitive
+        assert!(!is_known_format("unknown", &formats));
+    }
+
+    #[test]
+    fn test_format_detection_error() {
+        let formats = formats();
+        let msg = format_detection_error("photo.jpe", true, &formats);
+        assert!(msg.contains("Did you mean 'jpg'? Use --from jpg"));
+
+        let msg = format_detection_error("notes.qqqqq", false, &formats);
+        assert!(msg.contains("Unknown extension '.qqqqq'"));
+        assert!(msg.contains("Data:  json, yaml, toml"));
+        assert!(msg.contains("Image: png, jpg"));
     }
 }
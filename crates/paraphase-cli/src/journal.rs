@@ -0,0 +1,290 @@
+//! Batch job journal for checkpoint/resume and incremental conversion.
+//!
+//! Each completed conversion is appended to a JSON Lines file as soon as its
+//! output is written, so an interrupted run loses at most the file that was
+//! in flight. On the next run, inputs whose journal entry still matches
+//! (same output, output present, input unchanged) are skipped.
+//!
+//! Entries always record the input's hash, so a journal written while
+//! comparing mtimes can later be checked by hash.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Journal file name used in the output directory when no path is given.
+pub const DEFAULT_JOURNAL_NAME: &str = ".paraphase-journal.jsonl";
+
+/// How to decide whether an input changed since it was converted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ChangeDetection {
+    /// Compare size and modification time (cheap).
+    #[default]
+    Mtime,
+    /// Compare a SHA-256 of the contents (robust to touched files).
+    Hash,
+}
+
+/// Identity of an input file at a point in time.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// File size in bytes.
+    pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtime_ns: Option<u64>,
+    /// Hex SHA-256 of the contents. Always recorded; computed for checks
+    /// in hash mode only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl Fingerprint {
+    /// Fill in the hash from the input's contents, if not computed yet.
+    pub fn hashed(mut self, data: &[u8]) -> Self {
+        self.sha256.get_or_insert_with(|| digest(data));
+        self
+    }
+}
+
+/// A completed conversion, with canonical input and output paths.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Entry {
+    input: PathBuf,
+    output: PathBuf,
+    #[serde(flatten)]
+    fingerprint: Fingerprint,
+}
+
+/// Append-only record of completed conversions.
+pub struct Journal {
+    path: PathBuf,
+    mode: ChangeDetection,
+    entries: HashMap<PathBuf, Entry>,
+    file: File,
+}
+
+impl Journal {
+    /// Open (or create) a journal, loading existing entries.
+    ///
+    /// A truncated last line, as left by a crash mid-write, is ignored.
+    pub fn open(path: impl Into<PathBuf>, mode: ChangeDetection) -> Result<Self> {
+        let path = path.into();
+        let mut entries = HashMap::new();
+        let mut needs_newline = false;
+
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read journal {}", path.display()))?;
+            for line in contents.lines() {
+                if let Ok(entry) = serde_json::from_str::<Entry>(line) {
+                    entries.insert(entry.input.clone(), entry);
+                }
+            }
+            needs_newline = !contents.is_empty() && !contents.ends_with('\n');
+        } else if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create journal directory")?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open journal {}", path.display()))?;
+
+        // Terminate a truncated line so new entries start cleanly
+        if needs_newline {
+            file.write_all(b"\n").context("Failed to write journal")?;
+        }
+
+        Ok(Self {
+            path,
+            mode,
+            entries,
+            file,
+        })
+    }
+
+    /// Path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Fingerprint an input according to the journal's change detection mode.
+    pub fn fingerprint(&self, input: &str) -> Result<Fingerprint> {
+        let meta = std::fs::metadata(input)
+            .with_context(|| format!("Failed to read metadata for {}", input))?;
+        let mtime_ns = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos() as u64);
+        let sha256 = match self.mode {
+            ChangeDetection::Mtime => None,
+            ChangeDetection::Hash => Some(Self::hash(input)?),
+        };
+
+        Ok(Fingerprint {
+            size: meta.len(),
+            mtime_ns,
+            sha256,
+        })
+    }
+
+    /// Check if `input` was already converted to `output` and is unchanged.
+    pub fn is_current(&self, input: &str, output: &Path, fingerprint: &Fingerprint) -> bool {
+        let Some(entry) = self.entries.get(&canonical(Path::new(input))) else {
+            return false;
+        };
+        if !output.exists() || entry.output != canonical(output) {
+            return false;
+        }
+
+        let recorded = &entry.fingerprint;
+        match self.mode {
+            ChangeDetection::Mtime => {
+                recorded.size == fingerprint.size
+                    && recorded.mtime_ns.is_some()
+                    && recorded.mtime_ns == fingerprint.mtime_ns
+            }
+            ChangeDetection::Hash => {
+                recorded.sha256.is_some() && recorded.sha256 == fingerprint.sha256
+            }
+        }
+    }
+
+    /// Record a completed conversion and flush it to disk.
+    ///
+    /// Use [`Fingerprint::hashed`] with the contents that were converted so
+    /// the entry can later be checked by hash.
+    pub fn record(&mut self, input: &str, output: &Path, fingerprint: Fingerprint) -> Result<()> {
+        let entry = Entry {
+            input: canonical(Path::new(input)),
+            output: canonical(output),
+            fingerprint,
+        };
+
+        let mut line = serde_json::to_string(&entry).context("Failed to encode journal entry")?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|_| self.file.flush())
+            .with_context(|| format!("Failed to write journal {}", self.path.display()))?;
+
+        self.entries.insert(entry.input.clone(), entry);
+        Ok(())
+    }
+
+    /// Hex SHA-256 of a file's contents.
+    fn hash(input: &str) -> Result<String> {
+        let data = std::fs::read(input).with_context(|| format!("Failed to read {}", input))?;
+        Ok(digest(&data))
+    }
+}
+
+/// Hex SHA-256 of some contents.
+fn digest(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// A path in canonical form when it resolves, so entries survive a change
+/// of working directory.
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_skips_recorded_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let input = dir.join("a.json");
+        let output = dir.join("a.yaml");
+        let journal_path = dir.join("journal.jsonl");
+        std::fs::write(&input, b"{}").unwrap();
+        std::fs::write(&output, b"{}").unwrap();
+        let input = input.to_string_lossy().into_owned();
+
+        let mut journal = Journal::open(&journal_path, ChangeDetection::Mtime).unwrap();
+        let fp = journal.fingerprint(&input).unwrap();
+        assert!(!journal.is_current(&input, &output, &fp));
+        journal.record(&input, &output, fp).unwrap();
+        drop(journal);
+
+        // Simulate a crash mid-write of the next entry
+        let mut file = OpenOptions::new().append(true).open(&journal_path).unwrap();
+        file.write_all(b"{\"input\":").unwrap();
+        drop(file);
+
+        let mut journal = Journal::open(&journal_path, ChangeDetection::Mtime).unwrap();
+        let fp = journal.fingerprint(&input).unwrap();
+        assert!(journal.is_current(&input, &output, &fp));
+
+        // Entries written after the truncated line are still readable
+        journal.record(&input, &output, fp.clone()).unwrap();
+        let journal = Journal::open(&journal_path, ChangeDetection::Mtime).unwrap();
+        assert_eq!(journal.entries.len(), 1);
+        assert!(journal.is_current(&input, &output, &fp));
+
+        // The same output reached by another path still matches
+        let other = dir.join(".").join("a.yaml");
+        assert!(journal.is_current(&input, &other, &fp));
+
+        // Missing output means it must be converted again
+        std::fs::remove_file(&output).unwrap();
+        assert!(!journal.is_current(&input, &output, &fp));
+    }
+
+    #[test]
+    fn test_hash_mode_detects_content_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let input = dir.join("a.json");
+        let output = dir.join("a.yaml");
+        std::fs::write(&input, b"{\"a\":1}").unwrap();
+        std::fs::write(&output, b"a: 1").unwrap();
+        let input = input.to_string_lossy().into_owned();
+
+        let mut journal = Journal::open(dir.join("journal.jsonl"), ChangeDetection::Hash).unwrap();
+        let fp = journal.fingerprint(&input).unwrap();
+        journal.record(&input, &output, fp).unwrap();
+
+        let fp = journal.fingerprint(&input).unwrap();
+        assert!(journal.is_current(&input, &output, &fp));
+
+        // Same size, different contents
+        std::fs::write(&input, b"{\"a\":2}").unwrap();
+        let fp = journal.fingerprint(&input).unwrap();
+        assert!(!journal.is_current(&input, &output, &fp));
+    }
+
+    #[test]
+    fn test_mtime_journal_checked_by_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let input = dir.join("a.json");
+        let output = dir.join("a.yaml");
+        let journal_path = dir.join("journal.jsonl");
+        std::fs::write(&input, b"{}").unwrap();
+        std::fs::write(&output, b"{}").unwrap();
+        let input = input.to_string_lossy().into_owned();
+
+        let mut journal = Journal::open(&journal_path, ChangeDetection::Mtime).unwrap();
+        let fp = journal.fingerprint(&input).unwrap();
+        assert!(fp.sha256.is_none());
+        journal.record(&input, &output, fp.hashed(b"{}")).unwrap();
+        drop(journal);
+
+        let journal = Journal::open(&journal_path, ChangeDetection::Hash).unwrap();
+        let fp = journal.fingerprint(&input).unwrap();
+        assert!(journal.is_current(&input, &output, &fp));
+    }
+}
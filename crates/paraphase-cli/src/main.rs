@@ -10,6 +10,8 @@ mod config;
 mod errors;
 #[cfg(feature = "wick")]
 mod expr;
+mod journal;
+mod manifest;
 
 use anyhow::{Context, Result, bail};
 use clap::{CommandFactory, Parser, Subcommand};
@@ -17,13 +19,19 @@ use clap_complete::{Shell, generate};
 use config::{Config, Preset};
 use indexmap::IndexMap;
 use indicatif::{ProgressBar, ProgressStyle};
+use journal::{ChangeDetection, Journal};
+use manifest::{FileRecord, Manifest, ManifestEntry, ManifestRecorder};
 use paraphase_core::{
-    BoundedExecutor, Cardinality, ConvertOutput, ExecutionContext, Executor, NamedInput,
-    OptimizeTarget, Plan, Planner, Properties, PropertiesExt, PropertyPattern, Registry,
-    SimpleExecutor, Sink, Source, Workflow,
+    BoundedExecutor, Bundle, Cardinality, CommandConverter, ConflictPolicy, ConvertOutput,
+    ConverterDecl, CostStore, ExecuteError, ExecutionContext, ExecutionResult, Executor,
+    IsolationPolicy, Limits, LossNote, NamedInput, OptimizeTarget, OptionDecl, Plan, PlanStep,
+    Planner, Properties, PropertiesExt, PropertyPattern, Registry, RoundTripReport, Severity,
+    SimpleExecutor, Sink, Source, WorkerPool, Workflow, serve_worker, worst_loss,
 };
+use std::cell::{Ref, RefCell};
 use std::io::{Cursor, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Arc;
 
 /// Parse --optimize argument.
@@ -39,6 +47,11 @@ fn parse_optimize_target(s: &str) -> Result<OptimizeTarget, String> {
     }
 }
 
+/// Parse --fail-on-loss argument.
+fn parse_severity(s: &str) -> Result<Severity, String> {
+    Severity::parse(s).ok_or_else(|| format!("Invalid severity '{}'. Use: info, minor, major", s))
+}
+
 /// Collect files from patterns, directories, and globs.
 ///
 /// When `recursive` is true, directories are walked recursively.
@@ -170,6 +183,41 @@ enum BatchMode {
     PerDir,
 }
 
+/// Output format of `paraphase graph`.
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT.
+    #[default]
+    Dot,
+    /// Mermaid flowchart.
+    Mermaid,
+    /// JSON (nodes and edges).
+    Json,
+}
+
+/// Checkpoint/resume settings for batch conversions.
+#[derive(Default, Clone)]
+struct ResumeOptions {
+    /// Journal file recording completed conversions.
+    journal: Option<PathBuf>,
+    /// Skip up-to-date inputs, detecting changes this way.
+    skip_existing: Option<ChangeDetection>,
+}
+
+impl ResumeOptions {
+    /// Open the journal for a batch writing to `out_dir`, if enabled.
+    ///
+    /// `--skip-existing` without `--journal` uses a journal in the output directory.
+    fn open(&self, out_dir: &std::path::Path) -> Result<Option<Journal>> {
+        let path = match (&self.journal, self.skip_existing) {
+            (Some(path), _) => path.clone(),
+            (None, Some(_)) => out_dir.join(journal::DEFAULT_JOURNAL_NAME),
+            (None, None) => return Ok(None),
+        };
+        Journal::open(path, self.skip_existing.unwrap_or_default()).map(Some)
+    }
+}
+
 /// Output verbosity level.
 #[derive(Clone, Copy)]
 enum Verbosity {
@@ -206,6 +254,162 @@ impl Verbosity {
             println!("{msg}");
         }
     }
+
+    fn warn(self, msg: &str) {
+        if !matches!(self, Verbosity::Quiet) {
+            eprintln!("warning: {msg}");
+        }
+    }
+}
+
+/// Execution settings shared by all commands that run plans.
+#[derive(Default, Clone)]
+struct ExecSettings {
+    /// Memory limit in bytes; selects the bounded executor when set.
+    memory_limit: Option<usize>,
+    /// Directory for spilling intermediates when over the memory limit.
+    spill_dir: Option<PathBuf>,
+    /// File to load learned converter costs from and record new ones into.
+    cost_store: Option<PathBuf>,
+    /// Learned costs, updated after each successful execution.
+    learned: Rc<RefCell<CostStore>>,
+    /// Worker processes for isolated converters.
+    isolation: Option<Arc<WorkerPool>>,
+    /// Fail when a conversion loses data at or above this severity.
+    fail_on_loss: Option<Severity>,
+    /// Convert results back and compare them with their input.
+    verify_roundtrip: bool,
+    /// Check step outputs against the property vocabulary.
+    check_properties: bool,
+    /// Produce byte-identical output across runs.
+    deterministic: bool,
+    /// Safety limits for untrusted input.
+    limits: Limits,
+}
+
+impl ExecSettings {
+    /// Build an execution context for the given registry.
+    fn context(&self, registry: &Registry) -> ExecutionContext {
+        let mut ctx = ExecutionContext::new(Arc::new(registry.clone())).with_limits(self.limits);
+        if let Some(limit) = self.memory_limit {
+            ctx = ctx.with_memory_limit(limit);
+        }
+        #[cfg(feature = "spill")]
+        if let Some(dir) = &self.spill_dir {
+            ctx = ctx.with_spill_dir(dir);
+        }
+        if let Some(pool) = &self.isolation {
+            ctx = ctx.with_isolation(Arc::clone(pool));
+        }
+        if self.check_properties {
+            ctx = ctx.with_property_checks();
+        }
+        if self.deterministic {
+            ctx = ctx.with_deterministic();
+        }
+        ctx
+    }
+
+    /// The learned costs, if a cost store is configured.
+    ///
+    /// Release the borrow before executing: execution records into them.
+    fn learned_costs(&self) -> Option<Ref<'_, CostStore>> {
+        self.cost_store.as_ref().map(|_| self.learned.borrow())
+    }
+
+    /// Execute a plan with the executor matching these settings.
+    fn execute(
+        &self,
+        ctx: &ExecutionContext,
+        plan: &Plan,
+        data: Vec<u8>,
+        props: Properties,
+    ) -> std::result::Result<ExecutionResult, ExecuteError> {
+        let result = if self.memory_limit.is_some() {
+            BoundedExecutor::new().execute(ctx, plan, data, props)
+        } else {
+            SimpleExecutor::new().execute(ctx, plan, data, props)
+        }?;
+
+        if self.cost_store.is_some() {
+            self.learned.borrow_mut().record(&result.stats);
+        }
+        Ok(result)
+    }
+
+    /// Execute an aggregating plan with the executor matching these
+    /// settings.
+    fn execute_aggregating(
+        &self,
+        ctx: &ExecutionContext,
+        plan: &Plan,
+        inputs: Vec<(Vec<u8>, Properties)>,
+    ) -> std::result::Result<ExecutionResult, ExecuteError> {
+        let result = if self.memory_limit.is_some() {
+            BoundedExecutor::new().execute_aggregating(ctx, plan, inputs)
+        } else {
+            SimpleExecutor::new().execute_aggregating(ctx, plan, inputs)
+        }?;
+
+        if self.cost_store.is_some() {
+            self.learned.borrow_mut().record(&result.stats);
+        }
+        Ok(result)
+    }
+
+    /// Report fidelity losses, failing if one reaches the `--fail-on-loss`
+    /// threshold.
+    fn check_losses(&self, losses: &[LossNote], v: Verbosity) -> Result<()> {
+        for note in losses {
+            v.warn(&format!("lossy conversion: {note}"));
+        }
+        if let (Some(threshold), Some(worst)) = (self.fail_on_loss, worst_loss(losses))
+            && worst >= threshold
+        {
+            bail!(
+                "Conversion loses {} data (--fail-on-loss {}); no output written",
+                worst,
+                threshold
+            );
+        }
+        Ok(())
+    }
+
+    /// Convert a result back with the executor matching these settings and
+    /// compare it with the original input.
+    fn verify_roundtrip(
+        &self,
+        ctx: &ExecutionContext,
+        original: &[u8],
+        original_props: &Properties,
+        result: &ExecutionResult,
+    ) -> std::result::Result<RoundTripReport, ExecuteError> {
+        if self.memory_limit.is_some() {
+            BoundedExecutor::new().verify_roundtrip(ctx, original, original_props, result)
+        } else {
+            SimpleExecutor::new().verify_roundtrip(ctx, original, original_props, result)
+        }
+    }
+
+    /// Load learned costs from the configured cost store.
+    fn load_learned_costs(&self) -> Result<()> {
+        if let Some(path) = &self.cost_store {
+            *self.learned.borrow_mut() = CostStore::load(path)
+                .with_context(|| format!("Failed to load cost store {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Write learned costs back to the configured cost store.
+    fn save_learned_costs(&self) -> Result<()> {
+        if let Some(path) = &self.cost_store {
+            self.learned
+                .borrow()
+                .save(path)
+                .with_context(|| format!("Failed to save cost store {}", path.display()))?;
+        }
+        Ok(())
+    }
 }
 
 /// Options for image/video transforms passed to converters.
@@ -284,6 +488,82 @@ struct Cli {
     #[arg(long, global = true)]
     memory_limit: Option<usize>,
 
+    /// Spill intermediates to this directory instead of failing when over --memory-limit
+    #[arg(long, global = true)]
+    spill_dir: Option<PathBuf>,
+
+    /// Learn converter costs from executions, stored in this file, and plan with them
+    #[arg(long, global = true)]
+    cost_store: Option<PathBuf>,
+
+    /// Also load converter plugins from this directory (repeatable)
+    #[arg(long = "plugin-dir", global = true, value_name = "DIR")]
+    plugin_dirs: Vec<PathBuf>,
+
+    /// Don't load converter plugins
+    #[arg(long, global = true)]
+    no_plugins: bool,
+
+    /// Keep built-in converters when a plugin or config command reuses
+    /// their ID, instead of replacing them
+    #[arg(long, global = true)]
+    no_override: bool,
+
+    /// Remove a converter, or every converter in a namespace such as
+    /// `video`, before planning (repeatable)
+    #[arg(long = "disable", global = true, value_name = "ID")]
+    disable: Vec<String>,
+
+    /// Run this converter in a worker process so a crash fails only its
+    /// conversion (repeatable; `video.*` matches an ID prefix)
+    #[arg(long = "isolate", global = true, value_name = "ID")]
+    isolate: Vec<String>,
+
+    /// Run every converter in a worker process
+    #[arg(long, global = true)]
+    isolate_all: bool,
+
+    /// Kill a worker process whose conversion takes longer than this many
+    /// seconds, failing the conversion
+    #[arg(long, global = true, value_name = "SECS")]
+    isolate_timeout: Option<f64>,
+
+    /// Fail instead of writing output when a conversion loses data of at
+    /// least this severity (info, minor, major)
+    #[arg(long, global = true, value_name = "SEVERITY", value_parser = parse_severity)]
+    fail_on_loss: Option<Severity>,
+
+    /// Fail when a converter produces undeclared property keys or values of
+    /// the wrong type (for debugging converters)
+    #[arg(long, global = true)]
+    check_properties: bool,
+
+    /// Produce byte-identical output across runs: fixed archive timestamps,
+    /// sorted entries and no volatile metadata
+    #[arg(long, global = true)]
+    deterministic: bool,
+
+    /// Fail when a conversion would output more than this many bytes
+    #[arg(long, global = true, value_name = "BYTES")]
+    max_output_bytes: Option<u64>,
+
+    /// Fail when a conversion would output more than this many bytes per
+    /// input byte (decompression bombs)
+    #[arg(long, global = true, value_name = "RATIO")]
+    max_expansion_ratio: Option<f64>,
+
+    /// Fail on images larger than this many megapixels
+    #[arg(long, global = true, value_name = "MP")]
+    max_megapixels: Option<f64>,
+
+    /// Fail on archives with more than this many entries
+    #[arg(long, global = true, value_name = "N")]
+    max_entries: Option<u64>,
+
+    /// Fail on input nested deeper than this, e.g. SVG groups
+    #[arg(long, global = true, value_name = "N")]
+    max_depth: Option<usize>,
+
     /// Verbose output (show debug info)
     #[arg(short, long, global = true)]
     verbose: bool,
@@ -303,7 +583,11 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// List available converters
-    List,
+    List {
+        /// Only list converters in this namespace, e.g. `serde`
+        #[arg(long)]
+        namespace: Option<String>,
+    },
 
     /// Plan a conversion (show steps without executing)
     Plan {
@@ -322,6 +606,31 @@ enum Commands {
         optimize: Option<OptimizeTarget>,
     },
 
+    /// Export the converter graph (formats as nodes, converters as edges)
+    Graph {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// Only include converters whose ID starts with this prefix (repeatable)
+        #[arg(long = "prefix")]
+        prefixes: Vec<String>,
+        /// Label edges with their cost
+        #[arg(long)]
+        costs: bool,
+        /// Cost model for --costs and the highlighted path: quality, speed, size
+        #[arg(long, value_parser = parse_optimize_target)]
+        optimize: Option<OptimizeTarget>,
+        /// Highlight the planned path from this format (requires --to)
+        #[arg(long, requires = "to")]
+        from: Option<String>,
+        /// Highlight the planned path to this format (requires --from)
+        #[arg(long, requires = "from")]
+        to: Option<String>,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
     /// Convert file(s)
     Convert {
         /// Input file(s) (use "-" for stdin). Supports multiple files for batch.
@@ -390,6 +699,24 @@ enum Commands {
         /// Video quality preset (low, medium, high, lossless)
         #[arg(long)]
         quality: Option<String>,
+
+        // Checkpoint/resume options
+        /// Record completed batch conversions in this journal; reruns resume where they stopped
+        #[arg(long)]
+        journal: Option<PathBuf>,
+        /// Skip batch inputs already converted and unchanged since (by mtime or hash)
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "mtime")]
+        skip_existing: Option<ChangeDetection>,
+
+        /// Convert the result back to the input format and fail unless it
+        /// matches the input (compares values, pixels, samples or geometry)
+        #[arg(long)]
+        verify_roundtrip: bool,
+
+        /// Record how each output was made in a `<output>.manifest.json`
+        /// sidecar, or in one manifest at PATH for the whole run
+        #[arg(long, value_name = "PATH", num_args = 0..=1)]
+        manifest: Option<Option<PathBuf>>,
     },
 
     /// Run a workflow file
@@ -410,6 +737,93 @@ enum Commands {
 
     /// List available presets
     Presets,
+
+    /// List well-known property keys with their types and units
+    Properties,
+
+    /// Check outputs recorded in provenance manifests against their hashes
+    VerifyManifest {
+        /// Manifest file(s) to check
+        #[arg(required = true)]
+        manifests: Vec<PathBuf>,
+    },
+
+    /// Serve conversions for a parent process using --isolate (internal)
+    #[command(hide = true)]
+    Worker,
+}
+
+/// Load converter plugins from the standard locations plus `extra_dirs`.
+///
+/// A plugin that fails to load is reported and skipped.
+#[cfg(feature = "plugins")]
+fn load_plugins(registry: &mut Registry, extra_dirs: &[PathBuf], v: Verbosity) {
+    let loader = extra_dirs.iter().fold(
+        paraphase_plugin::loader::PluginLoader::with_default_dirs(),
+        |loader, dir| loader.dir(dir),
+    );
+
+    use paraphase_plugin::{PluginError, Registration};
+
+    let report = |result: Result<(PathBuf, Registration), PluginError>| match result {
+        Ok((path, registration)) => {
+            v.debug(&format!(
+                "Loaded {} converter(s) from {}",
+                registration.registered,
+                path.display()
+            ));
+            for e in registration.rejected {
+                v.warn(&format!(
+                    "skipping converter from {}: {}",
+                    path.display(),
+                    e
+                ));
+            }
+        }
+        Err(e) => v.warn(&format!("skipping plugin: {}", e)),
+    };
+
+    // Safety: plugin directories are trusted; --no-plugins opts out
+    unsafe { loader.load_into(registry) }
+        .into_iter()
+        .for_each(report);
+
+    // WebAssembly plugins (*.wasm) run sandboxed
+    #[cfg(feature = "wasm-plugins")]
+    loader
+        .load_wasm_into(registry, &paraphase_plugin::wasm::WasmLimits::default())
+        .into_iter()
+        .for_each(report);
+}
+
+/// Worker pool re-running this executable with the options that shape the
+/// registry, so workers see the same converters.
+fn worker_pool(cli: &Cli) -> Result<WorkerPool> {
+    let mut args: Vec<std::ffi::OsString> = Vec::new();
+    if let Some(path) = &cli.config {
+        args.extend(["--config".into(), path.into()]);
+    }
+    for dir in &cli.plugin_dirs {
+        args.extend(["--plugin-dir".into(), dir.into()]);
+    }
+    if cli.no_plugins {
+        args.push("--no-plugins".into());
+    }
+    if cli.no_override {
+        args.push("--no-override".into());
+    }
+    for id in &cli.disable {
+        args.extend(["--disable".into(), id.into()]);
+    }
+    args.push("worker".into());
+    let pool =
+        WorkerPool::current_exe(args).context("Failed to locate the paraphase executable")?;
+    Ok(match cli.isolate_timeout {
+        Some(secs) => pool.timeout(
+            std::time::Duration::try_from_secs_f64(secs).context("Invalid --isolate-timeout")?,
+        ),
+        None => pool,
+    })
 }
 
 fn main() -> Result<()> {
@@ -422,6 +836,10 @@ fn main() -> Result<()> {
         Config::load()
     };
 
+    let verbose = cli.verbose || config.defaults.verbose;
+    let quiet = cli.quiet || config.defaults.quiet;
+    let verbosity = Verbosity::from_flags(verbose, quiet);
+
     // Create registry with enabled converters
     let mut registry = Registry::new();
 
@@ -458,14 +876,102 @@ fn main() -> Result<()> {
     #[cfg(feature = "mesh")]
     paraphase_3d::register_all(&mut registry);
 
+    // Plugins come last so they can override built-in converters
+    if cli.no_override {
+        registry = registry.conflict_policy(ConflictPolicy::Reject);
+    }
+    #[cfg(feature = "plugins")]
+    if !cli.no_plugins {
+        let mut plugin_dirs = config.defaults.plugin_dirs.clone();
+        plugin_dirs.extend(cli.plugin_dirs.iter().cloned());
+        load_plugins(&mut registry, &plugin_dirs, verbosity);
+    }
+    #[cfg(not(feature = "plugins"))]
+    if !cli.plugin_dirs.is_empty() {
+        bail!("--plugin-dir requires the 'plugins' feature");
+    }
+
+    // Commands declared in the config file override everything else
+    for command in &config.converters {
+        if let Err(e) = command.timeout_duration() {
+            verbosity.warn(&format!("skipping command converter {}: {}", command.id, e));
+            continue;
+        }
+        let existing = registry.get_decl(&command.id).is_some();
+        match registry.try_register(CommandConverter::new(command.clone())) {
+            Ok(()) if existing => verbosity.warn(&format!(
+                "command converter {} replaces a registered converter",
+                command.id
+            )),
+            Ok(()) => verbosity.debug(&format!("Registered command converter {}", command.id)),
+            Err(e) => verbosity.warn(&format!("skipping command converter: {}", e)),
+        }
+    }
+
+    // Disabled converters go last, whoever registered them
+    for id in &cli.disable {
+        if registry.unregister(id).is_none() && registry.unregister_namespace(id) == 0 {
+            verbosity.warn(&format!("--disable {}: no such converter or namespace", id));
+        }
+    }
+
+    // Isolated converters run in copies of this executable
+    let mut isolate = config.defaults.isolate.clone();
+    isolate.extend(cli.isolate.iter().cloned());
+    let isolation = if cli.isolate_all || !isolate.is_empty() {
+        let policy = if cli.isolate_all {
+            IsolationPolicy::All
+        } else {
+            IsolationPolicy::Only(isolate)
+        };
+        Some(Arc::new(worker_pool(&cli)?.policy(policy)))
+    } else {
+        None
+    };
+
     // Apply config defaults, CLI flags override
-    let memory_limit = cli.memory_limit.or(config.defaults.memory_limit);
-    let verbose = cli.verbose || config.defaults.verbose;
-    let quiet = cli.quiet || config.defaults.quiet;
-    let verbosity = Verbosity::from_flags(verbose, quiet);
+    let mut limits = config.defaults.limits;
+    if let Some(bytes) = cli.max_output_bytes {
+        limits.max_output_bytes = Some(bytes);
+    }
+    if let Some(ratio) = cli.max_expansion_ratio {
+        limits.max_expansion_ratio = Some(ratio);
+    }
+    if let Some(megapixels) = cli.max_megapixels {
+        limits.max_megapixels = Some(megapixels);
+    }
+    if let Some(entries) = cli.max_entries {
+        limits.max_entries = Some(entries);
+    }
+    if let Some(depth) = cli.max_depth {
+        limits.max_depth = Some(depth);
+    }
+    let exec = ExecSettings {
+        memory_limit: cli.memory_limit.or(config.defaults.memory_limit),
+        spill_dir: cli.spill_dir.or_else(|| config.defaults.spill_dir.clone()),
+        cost_store: cli
+            .cost_store
+            .or_else(|| config.defaults.cost_store.clone()),
+        learned: Default::default(),
+        isolation,
+        fail_on_loss: cli.fail_on_loss,
+        verify_roundtrip: false,
+        check_properties: cli.check_properties,
+        deterministic: cli.deterministic,
+        limits,
+    };
+    if exec.spill_dir.is_some() && !cfg!(feature = "spill") {
+        bail!("--spill-dir requires the 'spill' feature");
+    }
+
+    // Only conversions use and update learned costs
+    let learns = matches!(cli.command, Commands::Convert { .. } | Commands::Run { .. });
+    if learns {
+        exec.load_learned_costs()?;
+    }
 
-    match cli.command {
-        Commands::List => cmd_list(&registry, verbosity),
+    let result = match cli.command {
+        Commands::List { namespace } => cmd_list(&registry, namespace.as_deref(), verbosity),
         Commands::Plan {
             input,
             output,
@@ -473,6 +979,24 @@ fn main() -> Result<()> {
             to,
             optimize,
         } => cmd_plan(&registry, &input, output, from, to, optimize, verbosity),
+        Commands::Graph {
+            format,
+            prefixes,
+            costs,
+            optimize,
+            from,
+            to,
+            output,
+        } => cmd_graph(
+            &registry,
+            format,
+            &prefixes,
+            costs,
+            optimize.unwrap_or_default(),
+            from.zip(to),
+            output.as_deref(),
+            verbosity,
+        ),
         Commands::Convert {
             input,
             output,
@@ -494,6 +1018,10 @@ fn main() -> Result<()> {
             watermark_opacity,
             watermark_margin,
             quality,
+            journal,
+            skip_existing,
+            verify_roundtrip,
+            manifest,
         } => {
             // Build options from preset (if any) + CLI overrides
             let opts = build_convert_options(
@@ -511,6 +1039,11 @@ fn main() -> Result<()> {
                 quality,
             )?;
 
+            let resume = ResumeOptions {
+                journal,
+                skip_existing,
+            };
+
             // Collect files (handles globs, directories, and recursion)
             let collected = collect_files(input, recursive, from.as_deref(), verbosity);
             if collected.is_empty() {
@@ -523,6 +1056,17 @@ fn main() -> Result<()> {
             // Auto-detect aggregation for archive formats (including compound like tar.gz)
             let should_aggregate =
                 aggregate || to.as_deref().map_or(false, |t| is_archive_format(t));
+            if verify_roundtrip && should_aggregate {
+                bail!("--verify-roundtrip is not supported when aggregating inputs");
+            }
+            let exec = ExecSettings {
+                verify_roundtrip,
+                ..exec.clone()
+            };
+            let mut recorder = manifest.map(|path| match path {
+                Some(path) => ManifestRecorder::Batch(path, Manifest::new()),
+                None => ManifestRecorder::Sidecar,
+            });
 
             // Process each batch
             for (batch_name, files) in batches {
@@ -538,14 +1082,19 @@ fn main() -> Result<()> {
                     to.clone(),
                     opts.clone(),
                     optimize,
-                    memory_limit,
+                    &exec,
+                    &resume,
+                    recorder.as_mut(),
                     should_aggregate,
                     verbosity,
                 )?;
             }
+            if let Some(recorder) = recorder {
+                recorder.finish()?;
+            }
             Ok(())
         }
-        Commands::Run { workflow } => cmd_run(&registry, &workflow, memory_limit, verbosity),
+        Commands::Run { workflow } => cmd_run(&registry, &workflow, &exec, verbosity),
         Commands::Completions { shell } => {
             let mut cmd = Cli::command();
             generate(shell, &mut cmd, "paraphase", &mut std::io::stdout());
@@ -558,7 +1107,21 @@ fn main() -> Result<()> {
             Ok(())
         }
         Commands::Presets => cmd_presets(&config, verbosity),
+        Commands::Properties => cmd_properties(&registry, verbosity),
+        Commands::VerifyManifest { manifests } => cmd_verify_manifest(&manifests, verbosity),
+        Commands::Worker => {
+            let stdin = std::io::stdin();
+            let stdout = std::io::stdout();
+            serve_worker(&registry, stdin.lock(), stdout.lock())?;
+            Ok(())
+        }
+    };
+
+    // Keep what was learned even if the command failed part-way
+    if learns {
+        exec.save_learned_costs()?;
     }
+    result
 }
 
 /// Build ConvertOptions from preset + CLI overrides.
@@ -683,10 +1246,63 @@ fn cmd_presets(config: &Config, v: Verbosity) -> Result<()> {
     Ok(())
 }
 
-fn cmd_list(registry: &Registry, v: Verbosity) -> Result<()> {
+/// List the declared property keys with their types and units.
+fn cmd_properties(registry: &Registry, v: Verbosity) -> Result<()> {
+    v.info("Property vocabulary:\n");
+    for decl in registry.vocabulary().iter() {
+        let ty = if decl.unit.is_empty() {
+            decl.ty.to_string()
+        } else {
+            format!("{} ({})", decl.ty, decl.unit)
+        };
+        v.info(&format!(
+            "  {:<16} {:<16} {}",
+            decl.key, ty, decl.description
+        ));
+    }
+    Ok(())
+}
+
+/// Check the outputs recorded in provenance manifests.
+fn cmd_verify_manifest(manifests: &[PathBuf], v: Verbosity) -> Result<()> {
+    let mut checked = 0;
+    let mut problems = 0;
+
+    for path in manifests {
+        let manifest = Manifest::load(path)?;
+        v.info(&format!(
+            "{} (paraphase {})",
+            path.display(),
+            manifest.paraphase
+        ));
+        for (entry, status) in manifest.verify() {
+            checked += 1;
+            if !status.is_current() {
+                problems += 1;
+            }
+            v.info(&format!("  {}: {}", entry.output.path, status));
+        }
+    }
+
+    if problems > 0 {
+        bail!(
+            "{} of {} outputs do not match their manifest",
+            problems,
+            checked
+        );
+    }
+    v.result(&format!("All {} outputs match their manifest", checked));
+    Ok(())
+}
+
+fn cmd_list(registry: &Registry, namespace: Option<&str>, v: Verbosity) -> Result<()> {
     v.info("Available converters:\n");
 
-    for decl in registry.declarations() {
+    let decls: Vec<&ConverterDecl> = match namespace {
+        Some(namespace) => registry.namespace(namespace).collect(),
+        None => registry.declarations().collect(),
+    };
+    for decl in &decls {
         let inputs: Vec<_> = decl.inputs.keys().collect();
         let outputs: Vec<_> = decl.outputs.keys().collect();
 
@@ -694,15 +1310,55 @@ fn cmd_list(registry: &Registry, v: Verbosity) -> Result<()> {
         if !decl.description.is_empty() {
             v.info(&format!("    {}", decl.description));
         }
+        if !decl.provider.is_empty() || !decl.version.is_empty() {
+            let version = if decl.version.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", decl.version)
+            };
+            v.info(&format!("    provider: {}{}", decl.provider, version));
+        }
+        if decl.priority != 0 {
+            v.info(&format!("    priority: {}", decl.priority));
+        }
         v.info(&format!("    inputs:  {:?}", inputs));
         v.info(&format!("    outputs: {:?}", outputs));
+        if !decl.options.is_empty() {
+            v.info("    options:");
+            for (name, opt) in &decl.options {
+                v.info(&format!("      {}", describe_option(name, opt)));
+            }
+        }
         v.info("");
     }
 
-    v.info(&format!("Total: {} converters", registry.len()));
+    v.info(&format!("Total: {} converters", decls.len()));
     Ok(())
 }
 
+/// One-line summary of a declared option, e.g.
+/// `quality: float 0..100 (default 80) - Output quality`.
+fn describe_option(name: &str, opt: &OptionDecl) -> String {
+    let mut line = format!("{}: {}", name, opt.ty.name());
+    if !opt.choices.is_empty() {
+        line.push_str(&format!(" [{}]", opt.choices.join("|")));
+    }
+    match (opt.min, opt.max) {
+        (Some(min), Some(max)) => line.push_str(&format!(" {}..{}", min, max)),
+        (Some(min), None) => line.push_str(&format!(" >= {}", min)),
+        (None, Some(max)) => line.push_str(&format!(" <= {}", max)),
+        (None, None) => {}
+    }
+    if let Some(default) = &opt.default {
+        let default = serde_json::to_string(default).unwrap_or_default();
+        line.push_str(&format!(" (default {})", default));
+    }
+    if !opt.description.is_empty() {
+        line.push_str(&format!(" - {}", opt.description));
+    }
+    line
+}
+
 fn cmd_plan(
     registry: &Registry,
     input: &str,
@@ -721,12 +1377,24 @@ fn cmd_plan(
     let output = output.context("Output required for non-workflow planning")?;
 
     let source_format = from
-        .or_else(|| detect_format(input))
-        .ok_or_else(|| anyhow::anyhow!("{}", errors::format_detection_error(input, true)))?;
+        .map(|f| resolve_format(registry, f))
+        .or_else(|| detect_format(registry, input))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "{}",
+                errors::format_detection_error(input, true, registry.formats())
+            )
+        })?;
 
     let target_format = to
-        .or_else(|| detect_format(&output))
-        .ok_or_else(|| anyhow::anyhow!("{}", errors::format_detection_error(&output, false)))?;
+        .map(|f| resolve_format(registry, f))
+        .or_else(|| detect_format(registry, &output))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "{}",
+                errors::format_detection_error(&output, false, registry.formats())
+            )
+        })?;
 
     let opt_str = match optimize {
         Some(OptimizeTarget::Quality) => " (optimize: quality)",
@@ -781,6 +1449,59 @@ fn cmd_plan(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
+fn cmd_graph(
+    registry: &Registry,
+    format: GraphFormat,
+    prefixes: &[String],
+    costs: bool,
+    optimize: OptimizeTarget,
+    path: Option<(String, String)>,
+    output: Option<&std::path::Path>,
+    v: Verbosity,
+) -> Result<()> {
+    let mut graph = registry.graph(optimize);
+    graph.retain_prefixes(prefixes);
+
+    if let Some((from, to)) = path {
+        let plan = Planner::new(registry)
+            .optimize(optimize)
+            .plan(
+                &Properties::new().with("format", from.as_str()),
+                &PropertyPattern::new().eq("format", to.as_str()),
+                Cardinality::One,
+                Cardinality::One,
+            )
+            .ok_or_else(|| anyhow::anyhow!("{}", errors::no_path_error(&from, &to, registry)))?;
+        graph.highlight(&plan, &from);
+    }
+
+    let rendered = match format {
+        GraphFormat::Dot => graph.to_dot(costs),
+        GraphFormat::Mermaid => graph.to_mermaid(costs),
+        GraphFormat::Json => {
+            let mut json = serde_json::to_string_pretty(&graph)?;
+            json.push('\n');
+            json
+        }
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            v.info(&format!(
+                "Wrote {} formats and {} edges to {}",
+                graph.nodes.len(),
+                graph.edges.len(),
+                path.display()
+            ));
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
 fn cmd_plan_workflow(
     registry: &Registry,
     path: &str,
@@ -832,7 +1553,10 @@ fn cmd_plan_workflow(
             }
         }
         v.info("");
-        v.info("Status: Complete workflow (ready to run)");
+        match workflow.validate(registry) {
+            Ok(()) => v.info("Status: Complete workflow (ready to run)"),
+            Err(e) => v.info(&format!("Status: Invalid workflow ({})", e)),
+        }
     } else if workflow.needs_planning() {
         // Auto-plan
         v.info("Steps: (auto-planning...)");
@@ -841,8 +1565,8 @@ fn cmd_plan_workflow(
         let source = workflow.source.as_ref().unwrap();
         let sink = workflow.sink.as_ref().unwrap();
 
-        let source_props = source.to_properties();
-        let target_pattern = sink.to_pattern();
+        let source_props = source.to_properties_with(registry.formats());
+        let target_pattern = sink.to_pattern_with(registry.formats());
 
         let source_cardinality = if source.is_batch() {
             Cardinality::Many
@@ -892,7 +1616,7 @@ fn cmd_plan_workflow(
 fn cmd_run(
     registry: &Registry,
     workflow_path: &PathBuf,
-    memory_limit: Option<usize>,
+    exec: &ExecSettings,
     v: Verbosity,
 ) -> Result<()> {
     let data = std::fs::read(workflow_path).context("Failed to read workflow file")?;
@@ -906,11 +1630,22 @@ fn cmd_run(
         .context("Workflow missing source")?;
     let sink = workflow.sink.as_ref().context("Workflow missing sink")?;
 
+    if !workflow.steps.is_empty() {
+        workflow
+            .validate(registry)
+            .map_err(|e| anyhow::anyhow!("Invalid workflow: {}", e))?;
+    }
+
+    // Sidecar files of the source, bundled with it if the plan starts with a
+    // converter taking a bundle
+    let sidecars = source.sidecars(registry.formats());
+    let use_bundle;
+
     // Determine plan (explicit steps or auto-planned)
-    let plan = if workflow.steps.is_empty() {
+    let mut plan = if workflow.steps.is_empty() {
         // Auto-plan
-        let source_props = source.to_properties();
-        let target_pattern = sink.to_pattern();
+        let source_props = source.to_properties_with(registry.formats());
+        let target_pattern = sink.to_pattern_with(registry.formats());
 
         let source_cardinality = if source.is_batch() {
             Cardinality::Many
@@ -919,15 +1654,38 @@ fn cmd_run(
         };
 
         let planner = Planner::new(registry);
-        planner
-            .plan(
-                &source_props,
-                &target_pattern,
-                source_cardinality,
-                Cardinality::One,
-            )
+        let plain_plan = planner.plan(
+            &source_props,
+            &target_pattern,
+            source_cardinality,
+            Cardinality::One,
+        );
+        let bundle_plan = match source {
+            Source::File { path } if !sidecars.is_empty() => {
+                let names = member_names(Path::new(path), &sidecars);
+                let props = Bundle::describe(&source_props, names.iter().map(String::as_str));
+                planner
+                    .plan(
+                        &props,
+                        &target_pattern,
+                        source_cardinality,
+                        Cardinality::One,
+                    )
+                    .filter(|plan| prefer_bundle(registry, plan, plain_plan.as_ref()))
+            }
+            _ => None,
+        };
+        use_bundle = bundle_plan.is_some();
+        bundle_plan
+            .or(plain_plan)
             .context("No conversion path found for workflow")?
     } else {
+        use_bundle = !sidecars.is_empty()
+            && workflow
+                .steps
+                .first()
+                .and_then(|step| registry.get_decl(&step.converter))
+                .is_some_and(|decl| decl.inputs.values().any(|port| port.bundle));
         // Build plan from explicit steps
         Plan {
             steps: workflow
@@ -938,11 +1696,15 @@ fn cmd_run(
                     input_port: "in".into(),
                     output_port: "out".into(),
                     output_properties: Properties::new(),
+                    options: Properties::new(),
                 })
                 .collect(),
             cost: workflow.steps.len() as f64,
         }
     };
+    workflow
+        .apply_options(&mut plan, registry)
+        .map_err(|e| anyhow::anyhow!("Invalid workflow: {}", e))?;
 
     // Get input file path
     let input_path = match source {
@@ -959,8 +1721,15 @@ fn cmd_run(
     };
 
     // Read input
-    let input_data = std::fs::read(&input_path).context("Failed to read input file")?;
-    let input_props = source.to_properties();
+    let data = std::fs::read(&input_path).context("Failed to read input file")?;
+    let props = registry.inspect(&data, &source.to_properties_with(registry.formats()));
+    let (input_data, input_props) = if use_bundle {
+        let bundle = Bundle::gather(&input_path, data, props, registry.formats())
+            .context("Failed to read sidecar files")?;
+        (bundle.to_bytes(), bundle.properties())
+    } else {
+        (data, props)
+    };
 
     v.info(&format!("Running workflow: {}", workflow_path.display()));
     v.info(&format!(
@@ -975,17 +1744,11 @@ fn cmd_run(
     }
 
     // Execute using appropriate executor
-    let mut ctx = ExecutionContext::new(Arc::new(registry.clone()));
-    if let Some(limit) = memory_limit {
-        ctx = ctx.with_memory_limit(limit);
-    }
-
-    let result = if memory_limit.is_some() {
-        BoundedExecutor::new().execute(&ctx, &plan, input_data, input_props)
-    } else {
-        SimpleExecutor::new().execute(&ctx, &plan, input_data, input_props)
-    }
-    .map_err(|e| anyhow::anyhow!("Execution failed: {}", e))?;
+    let ctx = exec.context(registry);
+    let result = exec
+        .execute(&ctx, &plan, input_data, input_props)
+        .map_err(|e| anyhow::anyhow!("Execution failed: {}", e))?;
+    exec.check_losses(&result.losses, v)?;
 
     // Write output
     std::fs::write(&output_path, &result.data).context("Failed to write output file")?;
@@ -1063,12 +1826,10 @@ fn cmd_convert_aggregate(
     from: Option<String>,
     to: Option<String>,
     _optimize: Option<OptimizeTarget>,
-    memory_limit: Option<usize>,
+    exec: &ExecSettings,
+    manifest: Option<&mut ManifestRecorder>,
     v: Verbosity,
 ) -> Result<()> {
-    use paraphase_core::{ExecutionContext, Executor, SimpleExecutor};
-    use std::sync::Arc;
-
     let target_format = to.context("Aggregation requires --to format")?;
     let output_path = output.context("Aggregation requires -o/--output file")?;
 
@@ -1081,16 +1842,20 @@ fn cmd_convert_aggregate(
 
     // Read all input files with their properties
     let mut input_data: Vec<(Vec<u8>, Properties)> = Vec::new();
+    let mut records = Vec::new();
 
     for input_path in &inputs {
         let data = std::fs::read(input_path)
             .map_err(|e| anyhow::anyhow!("{}", errors::file_read_error(input_path, &e)))?;
+        if manifest.is_some() {
+            records.push(FileRecord::new(input_path, &data));
+        }
 
         // Detect format for this file
         let format = from
             .clone()
-            .or_else(|| detect_format_from_magic(&data))
-            .or_else(|| detect_format(input_path))
+            .map(|f| resolve_format(registry, f))
+            .or_else(|| detect_source_format(registry, Some(input_path), &data))
             .unwrap_or_else(|| "raw".into());
 
         // Use relative path for archive entry
@@ -1125,6 +1890,7 @@ fn cmd_convert_aggregate(
         input_port: "in".into(),
         output_port: "out".into(),
         output_properties: Properties::new().with("format", archive_format),
+        options: Properties::new(),
     }];
 
     // Add compression step if needed
@@ -1140,23 +1906,32 @@ fn cmd_convert_aggregate(
             input_port: "in".into(),
             output_port: "out".into(),
             output_properties: Properties::new().with("format", comp),
+            options: Properties::new(),
         });
     }
 
     let plan = paraphase_core::Plan { steps, cost: 1.0 };
 
     // Execute aggregation
-    let ctx = ExecutionContext::new(Arc::new(registry.clone()))
-        .with_memory_limit(memory_limit.unwrap_or(usize::MAX));
+    let ctx = exec.context(registry);
 
-    let executor = SimpleExecutor::new();
-    let result = executor
+    let result = exec
         .execute_aggregating(&ctx, &plan, input_data)
         .map_err(|e| anyhow::anyhow!("Aggregation failed: {}", e))?;
+    exec.check_losses(&result.losses, v)?;
 
     // Write output
     std::fs::write(&output_path, &result.data).context("Failed to write output")?;
 
+    if let Some(manifest) = manifest {
+        manifest.record(ManifestEntry {
+            inputs: records,
+            output: FileRecord::new(&output_path, &result.data),
+            plan,
+            stats: Some(result.stats),
+        })?;
+    }
+
     v.result(&format!(
         "Created {} ({} bytes from {} files)",
         output_path,
@@ -1177,21 +1952,16 @@ fn cmd_convert(
     to: Option<String>,
     opts: ConvertOptions,
     optimize: Option<OptimizeTarget>,
-    memory_limit: Option<usize>,
+    exec: &ExecSettings,
+    resume: &ResumeOptions,
+    mut manifest: Option<&mut ManifestRecorder>,
     aggregate: bool,
     v: Verbosity,
 ) -> Result<()> {
     // Aggregation mode: N inputs → 1 output (archive formats always use this path)
     if aggregate {
         return cmd_convert_aggregate(
-            registry,
-            inputs,
-            output,
-            from,
-            to,
-            optimize,
-            memory_limit,
-            v,
+            registry, inputs, output, from, to, optimize, exec, manifest, v,
         );
     }
 
@@ -1203,6 +1973,8 @@ fn cmd_convert(
         let target_format = to.context("Batch conversion requires --to")?;
 
         std::fs::create_dir_all(&out_dir).context("Failed to create output directory")?;
+        let mut journal = resume.open(&out_dir)?;
+        let mut skipped = 0;
 
         // Progress bar for batch
         let pb = if !matches!(v, Verbosity::Quiet) {
@@ -1224,25 +1996,49 @@ fn cmd_convert(
                 .file_stem()
                 .and_then(|s| s.to_str())
                 .unwrap_or("output");
-            let output_name = format!("{}.{}", stem, target_format);
+            let output_name = format!("{}.{}", stem, output_extension(registry, &target_format));
             let output_path = out_dir.join(&output_name);
 
             if let Some(ref pb) = pb {
                 pb.set_message(stem.to_string());
             }
 
+            let (fingerprint, data) = match &journal {
+                Some(journal) => {
+                    let fingerprint = journal.fingerprint(input)?;
+                    if journal.is_current(input, &output_path, &fingerprint) {
+                        skipped += 1;
+                        if let Some(ref pb) = pb {
+                            pb.inc(1);
+                        }
+                        continue;
+                    }
+                    // Read once: the journal hashes the contents that are converted
+                    let data = std::fs::read(input)
+                        .map_err(|e| anyhow::anyhow!("{}", errors::file_read_error(input, &e)))?;
+                    (Some(fingerprint.hashed(&data)), Some(data))
+                }
+                None => (None, None),
+            };
+
             convert_single_file(
                 registry,
                 input,
+                data,
                 &output_path.to_string_lossy(),
                 from.clone(),
                 Some(target_format.clone()),
                 &opts,
                 optimize,
-                memory_limit,
+                exec,
+                manifest.as_deref_mut(),
                 Verbosity::Quiet, // Suppress per-file output in batch
             )?;
 
+            if let (Some(journal), Some(fingerprint)) = (&mut journal, fingerprint) {
+                journal.record(input, &output_path, fingerprint)?;
+            }
+
             if let Some(ref pb) = pb {
                 pb.inc(1);
             }
@@ -1253,9 +2049,12 @@ fn cmd_convert(
         }
         v.info(&format!(
             "Converted {} files to {}",
-            inputs.len(),
+            inputs.len() - skipped,
             out_dir.display()
         ));
+        if skipped > 0 {
+            v.info(&format!("Skipped {} up-to-date files", skipped));
+        }
         return Ok(());
     }
 
@@ -1264,44 +2063,56 @@ fn cmd_convert(
     let output = output
         .or_else(|| {
             // If --to specified, derive output from input
-            to.as_ref().map(|ext| {
+            to.as_ref().map(|format| {
                 let p = PathBuf::from(&input);
                 let stem = p.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
-                format!("{}.{}", stem, ext)
+                format!("{}.{}", stem, output_extension(registry, format))
             })
         })
         .context("Output file required. Use -o/--output or --to to specify.")?;
 
     convert_single_file(
-        registry,
-        &input,
-        &output,
-        from,
-        to,
-        &opts,
-        optimize,
-        memory_limit,
-        v,
+        registry, &input, None, &output, from, to, &opts, optimize, exec, manifest, v,
     )
 }
 
+/// Plan step describing a transform applied outside a plan, for manifests.
+fn applied_step(
+    converter_id: &str,
+    input_port: &str,
+    output_properties: &Properties,
+    options: Properties,
+) -> PlanStep {
+    PlanStep {
+        converter_id: converter_id.into(),
+        input_port: input_port.into(),
+        output_port: "out".into(),
+        output_properties: output_properties.clone(),
+        options,
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn convert_single_file(
     registry: &Registry,
     input: &str,
+    data: Option<Vec<u8>>,
     output: &str,
     from: Option<String>,
     to: Option<String>,
     opts: &ConvertOptions,
     optimize: Option<OptimizeTarget>,
-    memory_limit: Option<usize>,
+    exec: &ExecSettings,
+    manifest: Option<&mut ManifestRecorder>,
     v: Verbosity,
 ) -> Result<()> {
     let is_stdin = input == "-";
     let is_stdout = output == "-";
 
-    // Read input (from stdin or file)
-    let mut current_data = if is_stdin {
+    // Read input (from stdin or file), unless the caller already has it
+    let mut current_data = if let Some(data) = data {
+        data
+    } else if is_stdin {
         let mut buf = Vec::new();
         std::io::stdin()
             .read_to_end(&mut buf)
@@ -1312,31 +2123,63 @@ fn convert_single_file(
             .map_err(|e| anyhow::anyhow!("{}", errors::file_read_error(input, &e)))?
     };
 
-    // Detect source format: --from flag > magic bytes > extension
+    // Provenance: inputs read and steps applied, for the manifest
+    let mut inputs = Vec::new();
+    if manifest.is_some() {
+        inputs.push(FileRecord::new(input, &current_data));
+    }
+    let mut applied: Vec<PlanStep> = Vec::new();
+    let mut cost = 0.0;
+    let mut stats = None;
+
+    // Detect source format: --from flag > magic bytes and extension
     let source_format = from
-        .or_else(|| detect_format_from_magic(&current_data))
-        .or_else(|| if is_stdin { None } else { detect_format(input) })
+        .map(|f| resolve_format(registry, f))
+        .or_else(|| {
+            let path = (!is_stdin).then_some(input);
+            detect_source_format(registry, path, &current_data)
+        })
         .ok_or_else(|| {
             let path = if is_stdin { "<stdin>" } else { input };
-            anyhow::anyhow!("{}", errors::format_detection_error(path, true))
+            anyhow::anyhow!(
+                "{}",
+                errors::format_detection_error(path, true, registry.formats())
+            )
         })?;
 
     // Detect target format: --to flag > extension (no magic for output)
     let target_format = to
+        .map(|f| resolve_format(registry, f))
         .or_else(|| {
             if is_stdout {
                 None
             } else {
-                detect_format(output)
+                detect_format(registry, output)
             }
         })
         .ok_or_else(|| {
             let path = if is_stdout { "<stdout>" } else { output };
-            anyhow::anyhow!("{}", errors::format_detection_error(path, false))
+            anyhow::anyhow!(
+                "{}",
+                errors::format_detection_error(path, false, registry.formats())
+            )
         })?;
 
     v.debug(&format!("Detected: {} -> {}", source_format, target_format));
-    let mut current_props = Properties::new().with("format", source_format.as_str());
+
+    // Let inspectors fill in content properties (dimensions, sample rate, ...)
+    let mut current_props = registry.inspect(
+        &current_data,
+        &Properties::new().with("format", source_format.as_str()),
+    );
+    if current_props.len() > 1 {
+        let found: Vec<String> = current_props
+            .iter()
+            .skip(1)
+            .map(|(k, v)| format!("{}={}", k, serde_json::to_string(v).unwrap_or_default()))
+            .collect();
+        v.debug(&format!("Inspected: {}", found.join(", ")));
+    }
 
     // Apply image transforms if any options are set
     let needs_resize = opts.needs_resize();
@@ -1345,7 +2188,7 @@ fn convert_single_file(
     if needs_resize || needs_crop {
         // Get image dimensions first (we need them for the converters and expression evaluation)
         #[cfg(feature = "image")]
-        {
+        if !current_props.contains_key("width") || !current_props.contains_key("height") {
             // Decode to get dimensions
             let img = image::load_from_memory(&current_data)
                 .context("Failed to decode image for transform")?;
@@ -1355,40 +2198,39 @@ fn convert_single_file(
 
         // Apply aspect crop first (before resize)
         if let Some(ref aspect) = opts.aspect {
-            current_props.insert("aspect".into(), aspect.clone().into());
-            current_props.insert("gravity".into(), opts.gravity.clone().into());
+            let options = Properties::new()
+                .with("aspect", aspect.as_str())
+                .with("gravity", opts.gravity.as_str());
 
             let crop_converter = registry
                 .get("image.crop-aspect")
                 .context("Crop converter not available")?;
 
             let result = crop_converter
-                .convert(&current_data, &current_props)
+                .convert_with_options(&current_data, &current_props, &options)
                 .map_err(|e| anyhow::anyhow!("Crop failed: {}", e))?;
 
             match result {
                 ConvertOutput::Single(data, props) => {
+                    applied.push(applied_step("image.crop-aspect", "in", &props, options));
                     current_data = data;
                     current_props = props;
                 }
                 _ => bail!("Unexpected output from crop converter"),
             }
-
-            // Remove crop-specific props
-            current_props.shift_remove("aspect");
-            current_props.shift_remove("gravity");
         }
 
         // Apply resize (evaluate expressions with current properties)
         if needs_resize {
+            let mut options = Properties::new();
             if let Some(mw) = opts.eval_max_width(&current_props) {
-                current_props.insert("max_width".into(), (mw as i64).into());
+                options.insert("max_width".into(), (mw as i64).into());
             }
             if let Some(mh) = opts.eval_max_height(&current_props) {
-                current_props.insert("max_height".into(), (mh as i64).into());
+                options.insert("max_height".into(), (mh as i64).into());
             }
             if let Some(s) = opts.eval_scale(&current_props) {
-                current_props.insert("scale".into(), s.into());
+                options.insert("scale".into(), s.into());
             }
 
             let resize_converter = registry
@@ -1396,21 +2238,17 @@ fn convert_single_file(
                 .context("Resize converter not available")?;
 
             let result = resize_converter
-                .convert(&current_data, &current_props)
+                .convert_with_options(&current_data, &current_props, &options)
                 .map_err(|e| anyhow::anyhow!("Resize failed: {}", e))?;
 
             match result {
                 ConvertOutput::Single(data, props) => {
+                    applied.push(applied_step("image.resize", "in", &props, options));
                     current_data = data;
                     current_props = props;
                 }
                 _ => bail!("Unexpected output from resize converter"),
             }
-
-            // Remove resize-specific props
-            current_props.shift_remove("max_width");
-            current_props.shift_remove("max_height");
-            current_props.shift_remove("scale");
         }
     }
 
@@ -1421,6 +2259,12 @@ fn convert_single_file(
             // Read watermark file
             let watermark_data =
                 std::fs::read(watermark_path).context("Failed to read watermark file")?;
+            if manifest.is_some() {
+                inputs.push(FileRecord::new(
+                    &watermark_path.to_string_lossy(),
+                    &watermark_data,
+                ));
+            }
 
             // Get watermark dimensions
             let watermark_img = image::load_from_memory(&watermark_data)
@@ -1437,16 +2281,11 @@ fn convert_single_file(
                 current_props.insert("height".into(), (img.height() as i64).into());
             }
 
-            // Set watermark options on base image props (evaluate expressions)
-            current_props.insert("position".into(), opts.watermark_position.clone().into());
-            current_props.insert(
-                "opacity".into(),
-                opts.eval_watermark_opacity(&current_props).into(),
-            );
-            current_props.insert(
-                "margin".into(),
-                (opts.eval_watermark_margin(&current_props) as i64).into(),
-            );
+            // Evaluate watermark options against the base image
+            let options = Properties::new()
+                .with("position", opts.watermark_position.as_str())
+                .with("opacity", opts.eval_watermark_opacity(&current_props))
+                .with("margin", opts.eval_watermark_margin(&current_props) as i64);
 
             // Build multi-input map
             let mut inputs = IndexMap::new();
@@ -1470,21 +2309,17 @@ fn convert_single_file(
                 .context("Watermark converter not available")?;
 
             let result = watermark_converter
-                .convert_multi(&inputs)
+                .convert_multi_with_options(&inputs, &options)
                 .map_err(|e| anyhow::anyhow!("Watermark failed: {}", e))?;
 
             match result {
                 ConvertOutput::Single(data, props) => {
+                    applied.push(applied_step("image.watermark", "image", &props, options));
                     current_data = data;
                     current_props = props;
                 }
                 _ => bail!("Unexpected output from watermark converter"),
             }
-
-            // Remove watermark-specific props
-            current_props.shift_remove("position");
-            current_props.shift_remove("opacity");
-            current_props.shift_remove("margin");
         }
 
         #[cfg(not(feature = "image"))]
@@ -1495,37 +2330,87 @@ fn convert_single_file(
     if source_format != target_format {
         let target_pattern = PropertyPattern::new().eq("format", target_format.as_str());
 
-        let mut planner = Planner::new(registry);
+        let learned = exec.learned_costs();
+        let mut planner = Planner::new(registry).input_size(current_data.len());
         if let Some(opt) = optimize {
             planner = planner.optimize(opt);
         }
-        let plan = planner
-            .plan(
-                &current_props,
-                &target_pattern,
-                Cardinality::One,
-                Cardinality::One,
-            )
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "{}",
-                    errors::no_path_error(&source_format, &target_format, registry)
-                )
-            })?;
+        if let Some(store) = learned.as_deref() {
+            planner = planner.learned_costs(store);
+        }
 
-        // Execute format conversion plan using appropriate executor
-        let mut ctx = ExecutionContext::new(Arc::new(registry.clone()));
-        if let Some(limit) = memory_limit {
-            ctx = ctx.with_memory_limit(limit);
+        let plain_plan = planner.plan(
+            &current_props,
+            &target_pattern,
+            Cardinality::One,
+            Cardinality::One,
+        );
+
+        // Bundle sidecar files (subtitles next to a video, an OBJ's MTL) with
+        // the input when the plan for the bundle starts with a converter
+        // taking one and is no worse. Sidecars are only read then.
+        let mut bundle_plan = None;
+        if !is_stdin && applied.is_empty() {
+            let path = Path::new(input);
+            let sidecars = Bundle::sidecars(path, registry.formats());
+            if !sidecars.is_empty() {
+                let names = member_names(path, &sidecars);
+                let props = Bundle::describe(&current_props, names.iter().map(String::as_str));
+                bundle_plan = planner
+                    .plan(&props, &target_pattern, Cardinality::One, Cardinality::One)
+                    .filter(|plan| prefer_bundle(registry, plan, plain_plan.as_ref()));
+            }
+            if bundle_plan.is_some() {
+                let bundle = Bundle::gather(path, current_data, current_props, registry.formats())
+                    .context("Failed to read sidecar files")?;
+                let sidecars: Vec<_> = bundle.members().skip(1).collect();
+                let names: Vec<_> = sidecars.iter().map(|m| m.name.as_str()).collect();
+                v.info(&format!("Bundling {} with {}", input, names.join(", ")));
+                if manifest.is_some() {
+                    for member in sidecars {
+                        let path = member.props.get("path").and_then(|p| p.as_str());
+                        inputs.push(FileRecord::new(path.unwrap_or(&member.name), &member.data));
+                    }
+                }
+                current_props = bundle.properties();
+                current_data = bundle.to_bytes();
+            }
         }
 
-        let result = if memory_limit.is_some() {
-            BoundedExecutor::new().execute(&ctx, &plan, current_data, current_props)
-        } else {
-            SimpleExecutor::new().execute(&ctx, &plan, current_data, current_props)
+        let plan = match bundle_plan.or(plain_plan) {
+            Some(plan) => plan,
+            None => bail!(
+                "{}",
+                errors::no_path_error(&source_format, &target_format, registry)
+            ),
+        };
+        drop(learned);
+
+        // Execute format conversion plan using appropriate executor
+        let ctx = exec.context(registry);
+        let original = exec
+            .verify_roundtrip
+            .then(|| (current_data.clone(), current_props.clone()));
+        let result = exec
+            .execute(&ctx, &plan, current_data, current_props)
+            .map_err(|e| anyhow::anyhow!("Conversion failed: {}", e))?;
+        exec.check_losses(&result.losses, v)?;
+
+        if let Some((data, props)) = original {
+            let report = exec
+                .verify_roundtrip(&ctx, &data, &props, &result)
+                .map_err(|e| anyhow::anyhow!("Round-trip verification failed: {}", e))?;
+            if !report.passed() {
+                bail!("{}; no output written", report);
+            }
+            if !is_stdout {
+                v.info(&report.to_string());
+            }
         }
-        .map_err(|e| anyhow::anyhow!("Conversion failed: {}", e))?;
 
+        cost = plan.cost;
+        applied.extend(plan.steps);
+        stats = Some(result.stats);
         current_data = result.data;
         current_props = result.props;
     }
@@ -1539,6 +2424,18 @@ fn convert_single_file(
         std::fs::write(output, &current_data).context("Failed to write output file")?;
     }
 
+    if let Some(manifest) = manifest {
+        manifest.record(ManifestEntry {
+            inputs,
+            output: FileRecord::new(output, &current_data),
+            plan: Plan {
+                steps: applied,
+                cost,
+            },
+            stats,
+        })?;
+    }
+
     // Report what was done (only if not using stdout for data)
     if !is_stdout {
         let has_watermark = opts.watermark.is_some();
@@ -1570,125 +2467,60 @@ fn convert_single_file(
     Ok(())
 }
 
-/// Detect format from magic bytes using pure-magic.
-fn detect_format_from_magic(data: &[u8]) -> Option<String> {
+/// Bundle member names of a file and its sidecars.
+fn member_names(path: &Path, sidecars: &[PathBuf]) -> Vec<String> {
+    std::iter::once(path)
+        .chain(sidecars.iter().map(PathBuf::as_path))
+        .map(Bundle::member_name)
+        .collect()
+}
+
+/// Whether to convert the input bundled with its sidecars using `plan`
+/// rather than on its own using `plain`: the bundle must be taken by the
+/// first step, and the plan may not cost more.
+fn prefer_bundle(registry: &Registry, plan: &Plan, plain: Option<&Plan>) -> bool {
+    let takes_bundle = plan
+        .steps
+        .first()
+        .and_then(|step| {
+            registry
+                .get_decl(&step.converter_id)?
+                .inputs
+                .get(&step.input_port)
+        })
+        .is_some_and(|port| port.bundle);
+    takes_bundle && plain.is_none_or(|plain| plan.cost <= plain.cost)
+}
+
+/// Detect the format of input data: registered signatures and the file
+/// extension first (see `Formats::detect`), then pure-magic's MIME database
+/// mapped through the registered MIME types.
+fn detect_source_format(registry: &Registry, path: Option<&str>, data: &[u8]) -> Option<String> {
+    let formats = registry.formats();
+    if let Some(format) = formats.detect(path, data) {
+        return Some(format.into());
+    }
+
     let db = magic_db::load().ok()?;
     let mut cursor = Cursor::new(data);
     let magic = db.best_magic(&mut cursor).ok()?;
+    formats.from_mime(magic.mime_type()).map(String::from)
+}
 
-    // Map MIME type to our format names
-    mime_to_format(magic.mime_type())
+/// Detect format from file extension.
+fn detect_format(registry: &Registry, path: &str) -> Option<String> {
+    registry.formats().from_path(path).map(String::from)
 }
 
-/// Map MIME type to cambium format name.
-fn mime_to_format(mime: &str) -> Option<String> {
-    match mime {
-        // Images
-        "image/png" => Some("png".into()),
-        "image/jpeg" => Some("jpg".into()),
-        "image/webp" => Some("webp".into()),
-        "image/gif" => Some("gif".into()),
-        "image/bmp" | "image/x-ms-bmp" => Some("bmp".into()),
-        "image/x-icon" | "image/vnd.microsoft.icon" => Some("ico".into()),
-        "image/tiff" => Some("tiff".into()),
-        "image/avif" => Some("avif".into()),
-        "image/x-exr" => Some("exr".into()),
-        "image/vnd.radiance" => Some("hdr".into()),
-        // Audio
-        "audio/x-wav" | "audio/wav" => Some("wav".into()),
-        "audio/flac" | "audio/x-flac" => Some("flac".into()),
-        "audio/mpeg" => Some("mp3".into()),
-        "audio/ogg" | "audio/x-vorbis+ogg" => Some("ogg".into()),
-        "audio/aac" | "audio/x-aac" => Some("aac".into()),
-        // Video
-        "video/mp4" => Some("mp4".into()),
-        "video/webm" => Some("webm".into()),
-        "video/x-matroska" => Some("mkv".into()),
-        "video/x-msvideo" => Some("avi".into()),
-        "video/quicktime" => Some("mov".into()),
-        // Data formats
-        "image/svg+xml" => Some("svg".into()),
-        "application/json" => Some("json".into()),
-        "application/xml" | "text/xml" => Some("xml".into()),
-        "application/x-yaml" | "text/yaml" => Some("yaml".into()),
-        "application/toml" | "text/x-toml" => Some("toml".into()),
-        "application/cbor" => Some("cbor".into()),
-        "application/msgpack" | "application/x-msgpack" => Some("msgpack".into()),
-        _ => None,
+/// Resolve a format named on the command line (`jpeg`, `yml`) to its ID.
+fn resolve_format(registry: &Registry, name: String) -> String {
+    match registry.formats().resolve(&name) {
+        Some(id) => id.to_string(),
+        None => name,
     }
 }
 
-/// Detect format from file extension.
-fn detect_format(path: &str) -> Option<String> {
-    let ext = path.rsplit('.').next()?;
-    match ext.to_lowercase().as_str() {
-        // Serde text formats
-        "json" => Some("json".into()),
-        "yaml" | "yml" => Some("yaml".into()),
-        "toml" => Some("toml".into()),
-        "ron" => Some("ron".into()),
-        "json5" => Some("json5".into()),
-        "xml" => Some("xml".into()),
-        "lisp" | "sexp" | "lexpr" => Some("lexpr".into()),
-        "csv" => Some("csv".into()),
-        // Serde binary formats
-        "msgpack" | "mp" => Some("msgpack".into()),
-        "cbor" => Some("cbor".into()),
-        "bincode" | "bc" => Some("bincode".into()),
-        "postcard" | "pc" => Some("postcard".into()),
-        "bson" => Some("bson".into()),
-        "flexbuf" | "flexbuffers" => Some("flexbuffers".into()),
-        "bencode" | "torrent" => Some("bencode".into()),
-        "pickle" | "pkl" => Some("pickle".into()),
-        "plist" => Some("plist".into()),
-        // Image formats
-        "png" => Some("png".into()),
-        "jpg" | "jpeg" => Some("jpg".into()),
-        "webp" => Some("webp".into()),
-        "gif" => Some("gif".into()),
-        "bmp" => Some("bmp".into()),
-        "ico" => Some("ico".into()),
-        "tif" | "tiff" => Some("tiff".into()),
-        "tga" => Some("tga".into()),
-        "pnm" | "pbm" | "pgm" | "ppm" | "pam" => Some("pnm".into()),
-        "ff" | "farbfeld" => Some("farbfeld".into()),
-        "qoi" => Some("qoi".into()),
-        "avif" => Some("avif".into()),
-        "exr" => Some("exr".into()),
-        "hdr" => Some("hdr".into()),
-        // Video formats
-        "mp4" | "m4v" => Some("mp4".into()),
-        "webm" => Some("webm".into()),
-        "mkv" => Some("mkv".into()),
-        "avi" => Some("avi".into()),
-        "mov" | "qt" => Some("mov".into()),
-        // Audio formats
-        "wav" | "wave" => Some("wav".into()),
-        "flac" => Some("flac".into()),
-        "mp3" => Some("mp3".into()),
-        "ogg" | "oga" => Some("ogg".into()),
-        "aac" | "m4a" => Some("aac".into()),
-        // Vector formats
-        "svg" | "svgz" => Some("svg".into()),
-        // Font formats
-        "ttf" => Some("ttf".into()),
-        "otf" => Some("otf".into()),
-        "woff" => Some("woff".into()),
-        "woff2" => Some("woff2".into()),
-        // Geo formats
-        "gpx" => Some("gpx".into()),
-        "geojson" => Some("geojson".into()),
-        // PKI formats
-        "pem" | "crt" | "cer" | "key" => Some("pem".into()),
-        "der" => Some("der".into()),
-        // Subtitle formats
-        "srt" => Some("srt".into()),
-        "vtt" => Some("vtt".into()),
-        "sbv" => Some("sbv".into()),
-        // Color palette formats
-        "gpl" => Some("gpl".into()),
-        "aco" => Some("aco".into()),
-        "ase" => Some("ase".into()),
-        _ => None,
-    }
+/// File extension for outputs in the given format.
+fn output_extension<'a>(registry: &'a Registry, format: &'a str) -> &'a str {
+    registry.formats().extension_for(format).unwrap_or(format)
 }
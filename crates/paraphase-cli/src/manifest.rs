@@ -0,0 +1,359 @@
+//! Provenance manifests: how each output was produced.
+//!
+//! A manifest records, per output, the inputs and their hashes, the plan
+//! that ran (converter IDs and options), the execution statistics and the
+//! output hash, along with the paraphase versions involved. Manifests are
+//! written as a sidecar next to each output or as one file per batch, and
+//! `paraphase verify-manifest` re-hashes the files to find stale outputs.
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use paraphase_core::{ExecutionStats, Plan};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Version of the manifest format.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Suffix appended to an output path to name its sidecar manifest.
+pub const SIDECAR_SUFFIX: &str = ".manifest.json";
+
+/// A file and the hash of its contents at the time of recording.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    /// Absolute path when resolvable, `-` for stdin/stdout.
+    pub path: String,
+    /// Size in bytes.
+    pub size: u64,
+    /// Hex SHA-256 of the contents.
+    pub sha256: String,
+}
+
+impl FileRecord {
+    /// Record a file from its path and contents.
+    pub fn new(path: &str, data: &[u8]) -> Self {
+        let path = if path == "-" {
+            path.to_string()
+        } else {
+            std::path::absolute(path)
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_else(|_| path.to_string())
+        };
+        Self {
+            path,
+            size: data.len() as u64,
+            sha256: sha256(data),
+        }
+    }
+}
+
+/// How one output was produced.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Inputs read (several when aggregating).
+    pub inputs: Vec<FileRecord>,
+    /// Output written.
+    pub output: FileRecord,
+    /// Steps applied, in order, with their options.
+    pub plan: Plan,
+    /// Statistics of the executed plan (absent when no conversion ran).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ExecutionStats>,
+}
+
+/// A provenance manifest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Manifest format version.
+    pub manifest_version: u32,
+    /// Version of the paraphase CLI that wrote the manifest.
+    pub paraphase: String,
+    /// Versions of the paraphase crates compiled in.
+    pub crates: IndexMap<String, String>,
+    /// One entry per output.
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Manifest {
+    /// An empty manifest stamped with the current versions.
+    pub fn new() -> Self {
+        Self {
+            manifest_version: MANIFEST_VERSION,
+            paraphase: env!("CARGO_PKG_VERSION").to_string(),
+            crates: crate_versions(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Load a manifest from disk.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read manifest {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    /// Write the manifest to disk as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self).context("Failed to encode manifest")?;
+        json.push('\n');
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write manifest {}", path.display()))
+    }
+
+    /// Check every entry against the files on disk.
+    pub fn verify(&self) -> Vec<(&ManifestEntry, Status)> {
+        self.entries.iter().map(|e| (e, e.status())).collect()
+    }
+}
+
+/// State of a recorded output compared with the files on disk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// Output and inputs are unchanged.
+    Current,
+    /// The output is gone.
+    OutputMissing,
+    /// The output was modified after it was produced.
+    OutputModified,
+    /// An input changed (or disappeared) since; the output is stale.
+    InputChanged(String),
+}
+
+impl Status {
+    /// Whether the output still matches the manifest.
+    pub fn is_current(&self) -> bool {
+        *self == Status::Current
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Current => write!(f, "ok"),
+            Status::OutputMissing => write!(f, "output missing"),
+            Status::OutputModified => write!(f, "output modified"),
+            Status::InputChanged(path) => write!(f, "stale (input changed: {})", path),
+        }
+    }
+}
+
+impl ManifestEntry {
+    /// Compare the recorded hashes with the files on disk.
+    ///
+    /// Files recorded as `-` (stdin/stdout) cannot be checked and are
+    /// assumed unchanged.
+    pub fn status(&self) -> Status {
+        match hash_file(&self.output.path) {
+            Some(None) => return Status::OutputMissing,
+            Some(Some(hash)) if hash != self.output.sha256 => return Status::OutputModified,
+            _ => {}
+        }
+        for input in &self.inputs {
+            match hash_file(&input.path) {
+                Some(Some(hash)) if hash == input.sha256 => {}
+                None => {}
+                _ => return Status::InputChanged(input.path.clone()),
+            }
+        }
+        Status::Current
+    }
+}
+
+/// Where manifests are written during a conversion run.
+pub enum ManifestRecorder {
+    /// One `<output>.manifest.json` per output.
+    Sidecar,
+    /// All entries in one manifest, written by [`ManifestRecorder::finish`].
+    Batch(PathBuf, Manifest),
+}
+
+impl ManifestRecorder {
+    /// Record the production of an output.
+    pub fn record(&mut self, entry: ManifestEntry) -> Result<()> {
+        match self {
+            ManifestRecorder::Sidecar => {
+                if entry.output.path == "-" {
+                    anyhow::bail!("Sidecar manifests need an output file; use --manifest <PATH>");
+                }
+                let mut manifest = Manifest::new();
+                let path = sidecar_path(Path::new(&entry.output.path));
+                manifest.entries.push(entry);
+                manifest.save(&path)
+            }
+            ManifestRecorder::Batch(_, manifest) => {
+                manifest.entries.push(entry);
+                Ok(())
+            }
+        }
+    }
+
+    /// Write the batch manifest, if any.
+    pub fn finish(self) -> Result<()> {
+        match self {
+            ManifestRecorder::Sidecar => Ok(()),
+            ManifestRecorder::Batch(path, manifest) => manifest.save(&path),
+        }
+    }
+}
+
+/// Sidecar manifest path for an output.
+pub fn sidecar_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(SIDECAR_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Hex SHA-256 of some bytes.
+pub fn sha256(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Hash of a file on disk: `None` for `-`, `Some(None)` if unreadable.
+fn hash_file(path: &str) -> Option<Option<String>> {
+    if path == "-" {
+        return None;
+    }
+    Some(std::fs::read(path).ok().map(|data| sha256(&data)))
+}
+
+/// Versions of the paraphase crates linked into this binary.
+///
+/// Backends are workspace members released in lockstep with the CLI, so
+/// they share its version.
+fn crate_versions() -> IndexMap<String, String> {
+    let cli = env!("CARGO_PKG_VERSION");
+    let mut crates = IndexMap::new();
+    crates.insert(
+        "paraphase-core".to_string(),
+        paraphase_core::VERSION.to_string(),
+    );
+    let backends = [
+        #[cfg(feature = "serde")]
+        "paraphase-serde",
+        #[cfg(feature = "image")]
+        "paraphase-image",
+        #[cfg(feature = "video")]
+        "paraphase-video",
+        #[cfg(feature = "audio")]
+        "paraphase-audio",
+        #[cfg(feature = "vector")]
+        "paraphase-vector",
+        #[cfg(feature = "font")]
+        "paraphase-font",
+        #[cfg(feature = "geo")]
+        "paraphase-geo",
+        #[cfg(feature = "pki")]
+        "paraphase-pki",
+        #[cfg(feature = "subtitle")]
+        "paraphase-subtitle",
+        #[cfg(feature = "color")]
+        "paraphase-color",
+        #[cfg(feature = "mesh")]
+        "paraphase-3d",
+        #[cfg(feature = "plugins")]
+        "paraphase-plugin",
+    ];
+    for name in backends {
+        crates.insert(name.to_string(), cli.to_string());
+    }
+    crates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use paraphase_core::{PlanStep, Properties, PropertiesExt};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "paraphase-manifest-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn entry(input: &Path, output: &Path) -> ManifestEntry {
+        let plan = Plan {
+            steps: vec![PlanStep {
+                converter_id: "serde.json-to-yaml".into(),
+                input_port: "in".into(),
+                output_port: "out".into(),
+                output_properties: Properties::new().with("format", "yaml"),
+                options: Properties::new(),
+            }],
+            cost: 1.0,
+        };
+        ManifestEntry {
+            inputs: vec![FileRecord::new(
+                &input.to_string_lossy(),
+                &std::fs::read(input).unwrap(),
+            )],
+            output: FileRecord::new(&output.to_string_lossy(), &std::fs::read(output).unwrap()),
+            plan,
+            stats: Some(ExecutionStats::default()),
+        }
+    }
+
+    #[test]
+    fn test_sidecar_roundtrip_and_verify() {
+        let dir = temp_dir("sidecar");
+        let input = dir.join("a.json");
+        let output = dir.join("a.yaml");
+        std::fs::write(&input, b"{\"a\": 1}").unwrap();
+        std::fs::write(&output, b"a: 1\n").unwrap();
+
+        let mut recorder = ManifestRecorder::Sidecar;
+        recorder.record(entry(&input, &output)).unwrap();
+        recorder.finish().unwrap();
+
+        let manifest = Manifest::load(&dir.join("a.yaml.manifest.json")).unwrap();
+        assert_eq!(manifest.manifest_version, MANIFEST_VERSION);
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(
+            manifest.entries[0].plan.steps[0].converter_id,
+            "serde.json-to-yaml"
+        );
+        assert_eq!(manifest.verify()[0].1, Status::Current);
+
+        std::fs::write(&output, b"a: 2\n").unwrap();
+        assert_eq!(manifest.verify()[0].1, Status::OutputModified);
+
+        std::fs::write(&output, b"a: 1\n").unwrap();
+        std::fs::write(&input, b"{\"a\": 2}").unwrap();
+        assert!(matches!(manifest.verify()[0].1, Status::InputChanged(_)));
+
+        std::fs::remove_file(&output).unwrap();
+        assert_eq!(manifest.verify()[0].1, Status::OutputMissing);
+    }
+
+    #[test]
+    fn test_batch_manifest() {
+        let dir = temp_dir("batch");
+        let path = dir.join("batch.json");
+        let mut recorder = ManifestRecorder::Batch(path.clone(), Manifest::new());
+        for name in ["a", "b"] {
+            let input = dir.join(format!("{name}.json"));
+            let output = dir.join(format!("{name}.yaml"));
+            std::fs::write(&input, b"{}").unwrap();
+            std::fs::write(&output, b"{}\n").unwrap();
+            recorder.record(entry(&input, &output)).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        let manifest = Manifest::load(&path).unwrap();
+        assert_eq!(manifest.entries.len(), 2);
+        assert!(manifest.verify().iter().all(|(_, s)| s.is_current()));
+        assert!(!dir.join("a.yaml.manifest.json").exists());
+    }
+}
@@ -8,12 +8,46 @@
 //! - `ase` (default) — Adobe Swatch Exchange
 
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    ConvertError, ConvertOutput, Converter, ConverterDecl, FormatDecl, Properties, PropertyPattern,
+    Registry,
 };
 use serde_json::{Value, json};
 
 /// Register all enabled color converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Formats handled by the converters below
+    #[cfg(feature = "gpl")]
+    registry.register_format(
+        FormatDecl::new("gpl")
+            .extension("gpl")
+            .magic(b"GIMP Palette")
+            .description("GIMP palette"),
+    );
+    #[cfg(feature = "aco")]
+    registry.register_format(
+        FormatDecl::new("aco")
+            .extension("aco")
+            .description("Adobe Color swatches"),
+    );
+    #[cfg(feature = "ase")]
+    registry.register_format(
+        FormatDecl::new("ase")
+            .extension("ase")
+            .magic(b"ASEF")
+            .description("Adobe Swatch Exchange"),
+    );
+    #[cfg(feature = "cssvar")]
+    registry.register_format(
+        FormatDecl::new("css")
+            .extension("css")
+            .mime("text/css")
+            .description("CSS stylesheet"),
+    );
+
     #[cfg(feature = "gpl")]
     {
         registry.register(GplToJson);
@@ -0,0 +1,418 @@
+//! Bundles: inputs made of several files.
+//!
+//! Some formats are really several files: OBJ with its MTL, a video with
+//! subtitle sidecars. A [`Bundle`] groups them as named members, each with
+//! its own data and properties; the first member is the primary one.
+//!
+//! Plans carry one blob per item, so a bundle travels through the executor
+//! encoded with [`Bundle::to_bytes`], described by [`Bundle::properties`]
+//! (`format = "bundle"`, `bundle_format` = the primary member's format).
+//! Converters declare a [`PortDecl::bundle`](crate::PortDecl::bundle) input
+//! and decode it with [`Bundle::from_bytes`].
+
+use crate::converter::ConvertError;
+use crate::format::Formats;
+use crate::properties::{Properties, Value};
+use std::path::{Path, PathBuf};
+
+/// Value of the `format` property of an encoded bundle.
+pub const BUNDLE_FORMAT: &str = "bundle";
+
+/// Signature at the start of an encoded bundle.
+pub const BUNDLE_MAGIC: &[u8; 8] = b"PPBUNDLE";
+
+/// One file of a bundle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleMember {
+    /// Member name, usually the file name.
+    pub name: String,
+    /// Member data.
+    pub data: Vec<u8>,
+    /// Member properties (`format`, `path`, ...).
+    pub props: Properties,
+}
+
+/// A group of named files converted together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    members: Vec<BundleMember>,
+}
+
+impl Bundle {
+    /// Create a bundle from its primary member.
+    pub fn new(name: impl Into<String>, data: Vec<u8>, props: Properties) -> Self {
+        Self {
+            members: vec![BundleMember {
+                name: name.into(),
+                data,
+                props,
+            }],
+        }
+    }
+
+    /// Add a member, replacing any member with the same name.
+    pub fn insert(&mut self, name: impl Into<String>, data: Vec<u8>, props: Properties) {
+        let member = BundleMember {
+            name: name.into(),
+            data,
+            props,
+        };
+        match self.members.iter_mut().find(|m| m.name == member.name) {
+            Some(existing) => *existing = member,
+            None => self.members.push(member),
+        }
+    }
+
+    /// Add a member (builder style).
+    pub fn with(mut self, name: impl Into<String>, data: Vec<u8>, props: Properties) -> Self {
+        self.insert(name, data, props);
+        self
+    }
+
+    /// The primary member.
+    pub fn primary(&self) -> &BundleMember {
+        &self.members[0]
+    }
+
+    /// Look up a member by name.
+    pub fn get(&self, name: &str) -> Option<&BundleMember> {
+        self.members.iter().find(|m| m.name == name)
+    }
+
+    /// Members with the given `format`, in order.
+    pub fn by_format<'a>(&'a self, format: &'a str) -> impl Iterator<Item = &'a BundleMember> {
+        self.members
+            .iter()
+            .filter(move |m| m.props.get("format").and_then(Value::as_str) == Some(format))
+    }
+
+    /// All members, primary first.
+    pub fn members(&self) -> impl Iterator<Item = &BundleMember> {
+        self.members.iter()
+    }
+
+    /// Number of members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Always false: a bundle has at least its primary member.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Properties describing the encoded bundle, for planning.
+    ///
+    /// The primary member's properties, with `format` moved to
+    /// `bundle_format` and the member names under `members`.
+    pub fn properties(&self) -> Properties {
+        Self::describe(
+            &self.primary().props,
+            self.members.iter().map(|m| m.name.as_str()),
+        )
+    }
+
+    /// Properties of a bundle with the given primary member properties and
+    /// member names, without building it.
+    pub fn describe<'a>(
+        primary: &Properties,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Properties {
+        let mut props = primary.clone();
+        if let Some(format) = props.shift_remove("format") {
+            props.insert("bundle_format".into(), format);
+        }
+        props.insert("format".into(), BUNDLE_FORMAT.into());
+        props.insert(
+            "members".into(),
+            Value::Array(names.into_iter().map(Value::from).collect()),
+        );
+        props
+    }
+
+    /// Encode the bundle into a single blob.
+    ///
+    /// Layout: [`BUNDLE_MAGIC`], a little-endian `u32` member count, then per
+    /// member the name, the properties as JSON and the data, each prefixed
+    /// with its length (`u32`, `u32`, `u64`).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let size: usize = self.members.iter().map(|m| m.data.len() + 64).sum();
+        let mut out = Vec::with_capacity(BUNDLE_MAGIC.len() + 4 + size);
+        out.extend_from_slice(BUNDLE_MAGIC);
+        out.extend_from_slice(&(self.members.len() as u32).to_le_bytes());
+        for member in &self.members {
+            let props = serde_json::to_vec(&member.props).unwrap_or_else(|_| b"{}".to_vec());
+            out.extend_from_slice(&(member.name.len() as u32).to_le_bytes());
+            out.extend_from_slice(member.name.as_bytes());
+            out.extend_from_slice(&(props.len() as u32).to_le_bytes());
+            out.extend_from_slice(&props);
+            out.extend_from_slice(&(member.data.len() as u64).to_le_bytes());
+            out.extend_from_slice(&member.data);
+        }
+        out
+    }
+
+    /// Decode a blob written by [`Bundle::to_bytes`].
+    pub fn from_bytes(data: &[u8]) -> Result<Self, ConvertError> {
+        let mut reader = Reader { data, pos: 0 };
+        if reader.take(BUNDLE_MAGIC.len())? != BUNDLE_MAGIC {
+            return Err(invalid("missing bundle signature"));
+        }
+        let count = reader.u32()?;
+        let mut members = Vec::new();
+        for _ in 0..count {
+            let len = reader.u32()? as usize;
+            let name = std::str::from_utf8(reader.take(len)?)
+                .map_err(|_| invalid("member name is not UTF-8"))?
+                .to_string();
+            let len = reader.u32()? as usize;
+            let props = serde_json::from_slice(reader.take(len)?)
+                .map_err(|e| invalid(&format!("member `{}` properties: {}", name, e)))?;
+            let len = usize::try_from(reader.u64()?).map_err(|_| invalid("member too large"))?;
+            let data = reader.take(len)?.to_vec();
+            members.push(BundleMember { name, data, props });
+        }
+        if members.is_empty() {
+            return Err(invalid("bundle has no members"));
+        }
+        Ok(Self { members })
+    }
+
+    /// Find the sidecars of a file without reading them.
+    ///
+    /// Sidecars are the files next to `path` with the same stem and one of
+    /// the extensions its format declares with
+    /// [`FormatDecl::sidecar`](crate::FormatDecl::sidecar), e.g.
+    /// `movie.srt` next to `movie.mp4`.
+    pub fn sidecars(path: &Path, formats: &Formats) -> Vec<PathBuf> {
+        let Some(decl) = formats
+            .from_path(&path.to_string_lossy())
+            .and_then(|id| formats.get(id))
+        else {
+            return Vec::new();
+        };
+        let Some(stem) = path.file_stem() else {
+            return Vec::new();
+        };
+
+        decl.sidecars
+            .iter()
+            .map(|ext| {
+                let mut file = stem.to_os_string();
+                file.push(".");
+                file.push(ext);
+                path.with_file_name(file)
+            })
+            .filter(|p| p != path && p.is_file())
+            .collect()
+    }
+
+    /// Bundle a file that was already read with its sidecars.
+    ///
+    /// `data` and `props` become the primary member, so properties inspected
+    /// from the file carry over. Each sidecar found by [`Bundle::sidecars`]
+    /// is read from disk, with its `format` and `path` as properties.
+    pub fn gather(
+        path: &Path,
+        data: Vec<u8>,
+        props: Properties,
+        formats: &Formats,
+    ) -> std::io::Result<Self> {
+        let mut bundle = Self::new(Self::member_name(path), data, props);
+        for sidecar in Self::sidecars(path, formats) {
+            let mut props = Properties::new();
+            if let Some(format) = formats.from_path(&sidecar.to_string_lossy()) {
+                props.insert("format".into(), format.into());
+            }
+            props.insert("path".into(), sidecar.to_string_lossy().as_ref().into());
+            let data = std::fs::read(&sidecar)?;
+            bundle.insert(Self::member_name(&sidecar), data, props);
+        }
+        Ok(bundle)
+    }
+
+    /// Member name for a file: its file name.
+    pub fn member_name(path: &Path) -> String {
+        path.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+fn invalid(msg: &str) -> ConvertError {
+    ConvertError::InvalidInput(format!("invalid bundle: {}", msg))
+}
+
+/// Cursor over an encoded bundle.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ConvertError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid("truncated"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, ConvertError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, ConvertError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PropertiesExt;
+    use crate::format::FormatDecl;
+
+    fn bundle() -> Bundle {
+        Bundle::new(
+            "model.obj",
+            b"mtllib model.mtl\n".to_vec(),
+            Properties::new().with("format", "obj"),
+        )
+        .with(
+            "model.mtl",
+            b"newmtl red\n".to_vec(),
+            Properties::new().with("format", "mtl"),
+        )
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let bundle = bundle();
+        let bytes = bundle.to_bytes();
+        assert!(bytes.starts_with(BUNDLE_MAGIC));
+        assert_eq!(Bundle::from_bytes(&bytes).unwrap(), bundle);
+
+        assert!(Bundle::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Bundle::from_bytes(b"not a bundle").is_err());
+    }
+
+    #[test]
+    fn test_properties() {
+        let bundle = bundle();
+        assert_eq!(bundle.primary().name, "model.obj");
+        assert_eq!(bundle.by_format("mtl").count(), 1);
+
+        let props = bundle.properties();
+        assert_eq!(props.get("format").unwrap().as_str(), Some(BUNDLE_FORMAT));
+        assert_eq!(props.get("bundle_format").unwrap().as_str(), Some("obj"));
+        assert_eq!(props.get("members").unwrap().as_array().unwrap().len(), 2);
+        assert!(crate::Vocabulary::well_known().check(&props).is_empty());
+    }
+
+    /// Counts the MTL members of an OBJ bundle.
+    struct CountMaterials(crate::ConverterDecl);
+
+    impl crate::Converter for CountMaterials {
+        fn decl(&self) -> &crate::ConverterDecl {
+            &self.0
+        }
+
+        fn convert(
+            &self,
+            input: &[u8],
+            _props: &Properties,
+        ) -> Result<crate::ConvertOutput, ConvertError> {
+            let bundle = Bundle::from_bytes(input)?;
+            let count = bundle.by_format("mtl").count().to_string();
+            Ok(crate::ConvertOutput::Single(
+                count.into_bytes(),
+                Properties::new().with("format", "count"),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_bundle_port() {
+        use crate::{
+            Cardinality, ConverterDecl, ExecutionContext, Executor, Planner, PortDecl,
+            PropertyPattern, Registry, SimpleExecutor,
+        };
+        use std::sync::Arc;
+
+        let decl = ConverterDecl::new("test.count-materials")
+            .input("in", PortDecl::bundle("obj"))
+            .output(
+                "out",
+                PortDecl::single(PropertyPattern::new().eq("format", "count")),
+            );
+        assert!(decl.inputs["in"].bundle);
+        let mut registry = Registry::new();
+        registry.register(CountMaterials(decl));
+
+        let bundle = bundle();
+        let target = PropertyPattern::new().eq("format", "count");
+        let planner = Planner::new(&registry);
+        assert!(
+            planner
+                .plan(
+                    &bundle.primary().props,
+                    &target,
+                    Cardinality::One,
+                    Cardinality::One
+                )
+                .is_none()
+        );
+        let plan = planner
+            .plan(
+                &bundle.properties(),
+                &target,
+                Cardinality::One,
+                Cardinality::One,
+            )
+            .unwrap();
+
+        let ctx = ExecutionContext::new(Arc::new(registry));
+        let result = SimpleExecutor::new()
+            .execute(&ctx, &plan, bundle.to_bytes(), bundle.properties())
+            .unwrap();
+        assert_eq!(result.data, b"1");
+    }
+
+    #[test]
+    fn test_gather() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        std::fs::write(dir.join("movie.mp4"), b"video").unwrap();
+        std::fs::write(dir.join("movie.srt"), b"1\n").unwrap();
+        std::fs::write(dir.join("other.srt"), b"2\n").unwrap();
+        std::fs::write(dir.join("clip.mp4"), b"video").unwrap();
+
+        let mut formats = Formats::new();
+        formats.register(FormatDecl::new("mp4").extension("mp4").sidecar("srt"));
+        formats.register(FormatDecl::new("srt").extension("srt"));
+
+        let movie = dir.join("movie.mp4");
+        assert_eq!(Bundle::sidecars(&movie, &formats), [dir.join("movie.srt")]);
+        let props = Properties::new()
+            .with("format", "mp4")
+            .with("duration", 1.5);
+        let bundle = Bundle::gather(&movie, b"video".to_vec(), props, &formats).unwrap();
+        let names: Vec<_> = bundle.members().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["movie.mp4", "movie.srt"]);
+        assert_eq!(bundle.get("movie.srt").unwrap().data, b"1\n");
+        assert_eq!(bundle.by_format("srt").count(), 1);
+
+        // Inspected properties of the primary member are kept
+        let described = Bundle::describe(&bundle.primary().props, names);
+        assert_eq!(described, bundle.properties());
+        assert_eq!(described.get("duration").unwrap().as_f64(), Some(1.5));
+
+        // No sidecar next to it, or a format without sidecars
+        assert!(Bundle::sidecars(&dir.join("clip.mp4"), &formats).is_empty());
+        assert!(Bundle::sidecars(&dir.join("movie.srt"), &formats).is_empty());
+    }
+}
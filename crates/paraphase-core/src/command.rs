@@ -0,0 +1,449 @@
+//! Converters backed by external commands.
+//!
+//! A [`CommandDecl`] describes a command-line tool as a converter: the
+//! patterns it accepts and produces, its costs, and an argv template. The
+//! placeholders `{input}` and `{output}` are replaced with temporary file
+//! paths; without them, input is piped to stdin and output read from stdout.
+//!
+//! ```toml
+//! id = "acme.foo-to-bar"
+//! input = { format = { eq = "foo" } }
+//! output = { format = { eq = "bar" } }
+//! command = ["foo2bar", "{input}", "-o", "{output}"]
+//! costs = { quality_loss = 0.0 }
+//! timeout = 30
+//! ```
+
+use crate::converter::{ConvertError, ConvertOutput, Converter, ConverterDecl};
+use crate::pattern::{Predicate, PropertyPattern};
+use crate::properties::{Properties, Value};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Placeholder for the input file path.
+pub const INPUT_PLACEHOLDER: &str = "{input}";
+/// Placeholder for the output file path.
+pub const OUTPUT_PLACEHOLDER: &str = "{output}";
+
+/// How long to keep reading output after the command exits when no timeout
+/// is set. Processes it started in the background can hold its pipes open.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Declaration of a converter implemented by an external command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandDecl {
+    /// Unique identifier for this converter.
+    pub id: String,
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: String,
+    /// Pattern the input must match.
+    pub input: PropertyPattern,
+    /// Pattern of the output.
+    pub output: PropertyPattern,
+    /// Program and arguments, with optional `{input}`/`{output}` placeholders.
+    pub command: Vec<String>,
+    /// Cost metrics for path optimization.
+    #[serde(default)]
+    pub costs: Properties,
+    /// Seconds before the command is killed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<f64>,
+}
+
+impl CommandDecl {
+    /// Create a declaration for `command` (program followed by arguments).
+    pub fn new(
+        id: impl Into<String>,
+        input: PropertyPattern,
+        output: PropertyPattern,
+        command: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            description: String::new(),
+            input,
+            output,
+            command: command.into_iter().map(Into::into).collect(),
+            costs: Properties::new(),
+            timeout: None,
+        }
+    }
+
+    /// Set the description.
+    pub fn description(mut self, desc: impl Into<String>) -> Self {
+        self.description = desc.into();
+        self
+    }
+
+    /// Set a cost property for path optimization.
+    pub fn cost(mut self, key: impl Into<String>, value: f64) -> Self {
+        self.costs.insert(key.into(), Value::from(value));
+        self
+    }
+
+    /// Kill the command if it runs longer than `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout.as_secs_f64());
+        self
+    }
+
+    /// The timeout as a duration. Fails unless it is finite and positive.
+    pub fn timeout_duration(&self) -> Result<Option<Duration>, String> {
+        let Some(secs) = self.timeout else {
+            return Ok(None);
+        };
+        match Duration::try_from_secs_f64(secs) {
+            Ok(timeout) if !timeout.is_zero() => Ok(Some(timeout)),
+            _ => Err(format!(
+                "timeout must be a positive number of seconds, got {}",
+                secs
+            )),
+        }
+    }
+
+    /// Check if input is passed as a file rather than on stdin.
+    pub fn input_is_file(&self) -> bool {
+        self.command
+            .iter()
+            .any(|arg| arg.contains(INPUT_PLACEHOLDER))
+    }
+
+    /// Check if output is read from a file rather than from stdout.
+    pub fn output_is_file(&self) -> bool {
+        self.command
+            .iter()
+            .any(|arg| arg.contains(OUTPUT_PLACEHOLDER))
+    }
+}
+
+/// A converter that runs an external command.
+pub struct CommandConverter {
+    command: CommandDecl,
+    decl: ConverterDecl,
+}
+
+impl CommandConverter {
+    /// Create a converter from its declaration.
+    pub fn new(command: CommandDecl) -> Self {
+        let mut decl = ConverterDecl::simple(
+            command.id.clone(),
+            command.input.clone(),
+            command.output.clone(),
+        )
+        .description(if command.description.is_empty() {
+            format!("Run {}", command.command.first().map_or("", String::as_str))
+        } else {
+            command.description.clone()
+        });
+        decl.costs = command.costs.clone();
+        Self { command, decl }
+    }
+
+    /// The command declaration.
+    pub fn command(&self) -> &CommandDecl {
+        &self.command
+    }
+
+    fn failed(&self, message: impl std::fmt::Display) -> ConvertError {
+        ConvertError::Failed(format!("{}: {}", self.command.id, message))
+    }
+
+    fn timed_out(&self, program: &str, timeout: Option<Duration>) -> ConvertError {
+        self.failed(format!(
+            "{} timed out after {:?}",
+            program,
+            timeout.unwrap_or_default()
+        ))
+    }
+
+    fn run(&self, input: &[u8], props: &Properties) -> Result<Vec<u8>, ConvertError> {
+        let Some((program, _)) = self.command.command.split_first() else {
+            return Err(self.failed("empty command"));
+        };
+        let timeout = self
+            .command
+            .timeout_duration()
+            .map_err(|e| self.failed(e))?;
+
+        let dir = tempfile::Builder::new()
+            .prefix("paraphase-cmd-")
+            .tempdir()
+            .map_err(|e| self.failed(e))?;
+        let input_path = dir.path().join(file_name("input", props.get("format")));
+        let output_path = dir.path().join(file_name(
+            "output",
+            match self.command.output.predicates.get("format") {
+                Some(Predicate::Eq(format)) => Some(format),
+                _ => None,
+            },
+        ));
+        let input_is_file = self.command.input_is_file();
+        let output_is_file = self.command.output_is_file();
+        // Piped input is fed from the file too, so no writer thread can block
+        // on a pipe nobody reads
+        std::fs::write(&input_path, input).map_err(|e| self.failed(e))?;
+        let stdin = if input_is_file {
+            Stdio::null()
+        } else {
+            std::fs::File::open(&input_path)
+                .map_err(|e| self.failed(e))?
+                .into()
+        };
+
+        let args: Vec<String> = self.command.command[1..]
+            .iter()
+            .map(|arg| {
+                arg.replace(INPUT_PLACEHOLDER, &input_path.to_string_lossy())
+                    .replace(OUTPUT_PLACEHOLDER, &output_path.to_string_lossy())
+            })
+            .collect();
+
+        let mut child = Command::new(program)
+            .args(&args)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| self.failed(format!("failed to run {}: {}", program, e)))?;
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let stdout = read_all(child.stdout.take());
+        let stderr = read_all(child.stderr.take());
+        let status = match wait(&mut child, deadline) {
+            Ok(Some(status)) => status,
+            Ok(None) => return Err(self.timed_out(program, timeout)),
+            Err(e) => return Err(self.failed(e)),
+        };
+        // Processes the command started can keep its pipes open after it
+        // exits, so stop reading at the deadline, or shortly after exit
+        // without one
+        let drain = deadline.unwrap_or_else(|| Instant::now() + DRAIN_TIMEOUT);
+        let (Some(stdout), Some(stderr)) = (collect(&stdout, drain), collect(&stderr, drain))
+        else {
+            return Err(match timeout {
+                Some(_) => self.timed_out(program, timeout),
+                None => self.failed(format!(
+                    "{} exited but its output was still open after {:?}",
+                    program, DRAIN_TIMEOUT
+                )),
+            });
+        };
+        if !status.success() {
+            let stderr = String::from_utf8_lossy(&stderr);
+            return Err(self.failed(format!(
+                "{} exited with {}: {}",
+                program,
+                status,
+                stderr.trim()
+            )));
+        }
+
+        if output_is_file {
+            std::fs::read(&output_path)
+                .map_err(|e| self.failed(format!("reading {}: {}", output_path.display(), e)))
+        } else {
+            Ok(stdout)
+        }
+    }
+}
+
+impl Converter for CommandConverter {
+    fn decl(&self) -> &ConverterDecl {
+        &self.decl
+    }
+
+    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+        let data = self.run(input, props)?;
+
+        // Commands produce bytes only: take output properties from the
+        // values the output pattern pins down.
+        let mut out_props = props.clone();
+        for (key, predicate) in &self.command.output.predicates {
+            if let Predicate::Eq(value) = predicate {
+                out_props.insert(key.clone(), value.clone());
+            }
+        }
+        Ok(ConvertOutput::Single(data, out_props))
+    }
+}
+
+/// Wait for `child`, killing it at `deadline`. Returns `None` on timeout.
+fn wait(
+    child: &mut std::process::Child,
+    deadline: Option<Instant>,
+) -> std::io::Result<Option<ExitStatus>> {
+    let Some(deadline) = deadline else {
+        return child.wait().map(Some);
+    };
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Read `reader` to the end on a detached thread, which is abandoned if the
+/// pipe never closes.
+fn read_all(reader: Option<impl Read + Send + 'static>) -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut reader) = reader {
+            let _ = reader.read_to_end(&mut buf);
+        }
+        let _ = tx.send(buf);
+    });
+    rx
+}
+
+/// Wait for what `read_all` read, up to `deadline`. Returns `None` on
+/// timeout.
+fn collect(output: &mpsc::Receiver<Vec<u8>>, deadline: Instant) -> Option<Vec<u8>> {
+    output
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .ok()
+}
+
+/// `input.png` when the format is known, else `input`. Tools often pick
+/// their parser from the extension.
+fn file_name(stem: &str, format: Option<&Value>) -> String {
+    match format.and_then(|f| f.as_str()) {
+        Some(ext) if !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            format!("{}.{}", stem, ext)
+        }
+        _ => stem.to_string(),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::properties::PropertiesExt;
+
+    fn decl(command: &[&str]) -> CommandDecl {
+        CommandDecl::new(
+            "test.upper",
+            PropertyPattern::new().eq("format", "txt"),
+            PropertyPattern::new().eq("format", "shout"),
+            command.iter().copied(),
+        )
+    }
+
+    fn run(decl: CommandDecl) -> Result<(Vec<u8>, Properties), ConvertError> {
+        let props = Properties::new().with("format", "txt");
+        match CommandConverter::new(decl).convert(b"hello", &props)? {
+            ConvertOutput::Single(data, props) => Ok((data, props)),
+            ConvertOutput::Multiple(_) => panic!("expected single output"),
+        }
+    }
+
+    #[test]
+    fn test_stdin_stdout() {
+        let (data, props) = run(decl(&["tr", "a-z", "A-Z"])).unwrap();
+        assert_eq!(data, b"HELLO");
+        assert_eq!(props.get("format").and_then(|v| v.as_str()), Some("shout"));
+    }
+
+    #[test]
+    fn test_file_placeholders() {
+        let decl = decl(&[
+            "sh",
+            "-c",
+            "tr a-z A-Z < \"$0\" > \"$1\"",
+            "{input}",
+            "{output}",
+        ]);
+        assert!(decl.input_is_file() && decl.output_is_file());
+        let (data, _) = run(decl).unwrap();
+        assert_eq!(data, b"HELLO");
+
+        // The input file carries the format as its extension
+        let (data, _) = run(self::decl(&["echo", "{input}"])).unwrap();
+        assert!(
+            String::from_utf8(data)
+                .unwrap()
+                .trim()
+                .ends_with("input.txt")
+        );
+    }
+
+    #[test]
+    fn test_failure_and_timeout() {
+        let err = run(decl(&["sh", "-c", "echo oops >&2; exit 3"]))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("oops"));
+
+        let err = run(decl(&["definitely-not-a-real-command"])).err().unwrap();
+        assert!(err.to_string().contains("failed to run"));
+
+        let start = Instant::now();
+        let err = run(decl(&["sleep", "5"]).timeout(Duration::from_millis(100)))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        // A background process keeps stdout open after the command exits
+        let start = Instant::now();
+        let err =
+            run(decl(&["sh", "-c", "sleep 5 & echo started"]).timeout(Duration::from_millis(200)))
+                .err()
+                .unwrap();
+        assert!(err.to_string().contains("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        // Without a timeout, reading still stops shortly after exit
+        let start = Instant::now();
+        let err = run(decl(&["sh", "-c", "sleep 5 & echo started"]))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("still open"));
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_invalid_timeout() {
+        for timeout in [-1.0, 0.0, f64::NAN, f64::INFINITY] {
+            let mut decl = decl(&["true"]);
+            decl.timeout = Some(timeout);
+            assert!(decl.timeout_duration().is_err());
+            let err = run(decl).err().unwrap();
+            assert!(err.to_string().contains("timeout must be"));
+        }
+    }
+
+    #[test]
+    fn test_decl_from_toml() {
+        let decl: CommandDecl = toml::from_str(
+            r#"
+            id = "acme.foo-to-bar"
+            input = { format = { eq = "foo" } }
+            output = { format = { eq = "bar" } }
+            command = ["foo2bar", "{input}"]
+            costs = { quality_loss = 0.0 }
+            timeout = 2.5
+            "#,
+        )
+        .unwrap();
+        let converter = CommandConverter::new(decl);
+        let decl = converter.decl();
+        assert_eq!(decl.id, "acme.foo-to-bar");
+        assert_eq!(decl.description, "Run foo2bar");
+        assert!(
+            decl.matches_input(&Properties::new().with("format", "foo"))
+                .is_some()
+        );
+        assert_eq!(converter.command().timeout, Some(2.5));
+    }
+}
@@ -1,7 +1,10 @@
 //! Converter declarations and traits.
 
+use crate::bundle::BUNDLE_FORMAT;
+use crate::option::{self, OptionDecl, OptionError};
 use crate::pattern::PropertyPattern;
 use crate::properties::Properties;
+use crate::resource::{Formula, ResourceModel};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +17,13 @@ pub struct PortDecl {
     /// If false, this port handles single items.
     #[serde(default)]
     pub list: bool,
+    /// If true, items on this port are encoded [`Bundle`](crate::Bundle)s.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub bundle: bool,
+}
+
+pub(crate) fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 impl PortDecl {
@@ -22,6 +32,7 @@ impl PortDecl {
         Self {
             pattern,
             list: false,
+            bundle: false,
         }
     }
 
@@ -30,6 +41,19 @@ impl PortDecl {
         Self {
             pattern,
             list: true,
+            bundle: false,
+        }
+    }
+
+    /// Create a port taking a bundle whose primary member has the given
+    /// format.
+    pub fn bundle(primary_format: &str) -> Self {
+        Self {
+            pattern: PropertyPattern::new()
+                .eq("format", BUNDLE_FORMAT)
+                .eq("bundle_format", primary_format),
+            list: false,
+            bundle: true,
         }
     }
 }
@@ -52,6 +76,26 @@ pub struct ConverterDecl {
     /// Cost metrics for path optimization.
     #[serde(default)]
     pub costs: Properties,
+    /// Resource models as functions of the input.
+    #[serde(default, skip_serializing_if = "ResourceModel::is_empty")]
+    pub resources: ResourceModel,
+    /// Options accepted alongside the input, by name.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub options: IndexMap<String, OptionDecl>,
+    /// Crate or plugin providing the converter.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub provider: String,
+    /// Version of the implementation.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version: String,
+    /// Preference among converters covering the same edge at equal cost;
+    /// higher wins.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
 }
 
 impl ConverterDecl {
@@ -63,6 +107,11 @@ impl ConverterDecl {
             inputs: IndexMap::new(),
             outputs: IndexMap::new(),
             costs: Properties::new(),
+            resources: ResourceModel::default(),
+            options: IndexMap::new(),
+            provider: String::new(),
+            version: String::new(),
+            priority: 0,
         }
     }
 
@@ -108,6 +157,65 @@ impl ConverterDecl {
         self
     }
 
+    /// Declare peak memory (bytes, excluding the input buffer) as a function of the input.
+    pub fn memory_model(mut self, formula: Formula) -> Self {
+        self.resources.memory = Some(formula);
+        self
+    }
+
+    /// Declare processing time as a function of the input.
+    ///
+    /// Evaluates on the same scale as the `speed` cost and replaces it when
+    /// the planner knows the input size.
+    pub fn time_model(mut self, formula: Formula) -> Self {
+        self.resources.time = Some(formula);
+        self
+    }
+
+    /// Declare output size (bytes) as a function of the input.
+    pub fn output_size_model(mut self, formula: Formula) -> Self {
+        self.resources.output_size = Some(formula);
+        self
+    }
+
+    /// Declare an option the converter accepts.
+    pub fn option(mut self, name: impl Into<String>, decl: OptionDecl) -> Self {
+        self.options.insert(name.into(), decl);
+        self
+    }
+
+    /// Set the provider (crate or plugin name).
+    pub fn provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = provider.into();
+        self
+    }
+
+    /// Set the implementation version.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Set the priority used to break ties between equal-cost paths.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Namespace of the ID: the part before the first `.`, e.g. `serde`
+    /// for `serde.json-to-yaml`. Empty for IDs without one.
+    pub fn namespace(&self) -> &str {
+        self.id.split_once('.').map_or("", |(ns, _)| ns)
+    }
+
+    /// Validate options against the declared schema.
+    ///
+    /// Rejects unknown names and ill-typed or out-of-range values. A
+    /// converter that declares no options accepts anything.
+    pub fn validate_options(&self, options: &Properties) -> Result<(), OptionError> {
+        option::validate(&self.id, &self.options, options)
+    }
+
     /// Check if this converter can handle the given input properties.
     ///
     /// For single-input converters, checks the "in" port.
@@ -199,6 +307,121 @@ pub trait Converter: Send + Sync {
         let _ = inputs;
         Err(ConvertError::BatchNotSupported)
     }
+
+    /// Convert a single input with step options.
+    ///
+    /// Options (`max_width`, `quality`…) tune the conversion and are kept
+    /// apart from `props`, which describe only the data. The default
+    /// implementation serves converters that read options from their input
+    /// properties: it overlays the options for the call and removes them from
+    /// the output properties again.
+    fn convert_with_options(
+        &self,
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        if options.is_empty() {
+            return self.convert(input, props);
+        }
+        let output = self.convert(input, &overlay(props, options))?;
+        Ok(strip(output, options, &[props]))
+    }
+
+    /// Convert multiple named inputs with step options.
+    ///
+    /// See [`convert_with_options`](Self::convert_with_options); the default
+    /// overlays the options onto every input.
+    fn convert_multi_with_options(
+        &self,
+        inputs: &IndexMap<String, NamedInput<'_>>,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        if options.is_empty() {
+            return self.convert_multi(inputs);
+        }
+        let props: Vec<_> = inputs
+            .values()
+            .map(|input| overlay(input.props, options))
+            .collect();
+        let overlaid = inputs
+            .iter()
+            .zip(&props)
+            .map(|((name, input), props)| {
+                let input = NamedInput {
+                    data: input.data,
+                    props,
+                };
+                (name.clone(), input)
+            })
+            .collect();
+        let output = self.convert_multi(&overlaid)?;
+        let originals: Vec<_> = inputs.values().map(|input| input.props).collect();
+        Ok(strip(output, options, &originals))
+    }
+
+    /// Convert a batch of inputs with step options.
+    ///
+    /// See [`convert_with_options`](Self::convert_with_options); the default
+    /// overlays the options onto every input.
+    fn convert_batch_with_options(
+        &self,
+        inputs: &[(&[u8], &Properties)],
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        if options.is_empty() {
+            return self.convert_batch(inputs);
+        }
+        let props: Vec<_> = inputs
+            .iter()
+            .map(|(_, props)| overlay(props, options))
+            .collect();
+        let overlaid: Vec<_> = inputs
+            .iter()
+            .zip(&props)
+            .map(|((data, _), props)| (*data, props))
+            .collect();
+        let output = self.convert_batch(&overlaid)?;
+        let originals: Vec<_> = inputs.iter().map(|(_, props)| *props).collect();
+        Ok(strip(output, options, &originals))
+    }
+}
+
+/// Input properties with options layered on top.
+fn overlay(props: &Properties, options: &Properties) -> Properties {
+    let mut props = props.clone();
+    props.extend(options.iter().map(|(k, v)| (k.clone(), v.clone())));
+    props
+}
+
+/// Undo the overlay on output properties: options passed through unchanged
+/// are removed, or reset to the input's value if the data had that key.
+fn strip(output: ConvertOutput, options: &Properties, inputs: &[&Properties]) -> ConvertOutput {
+    let strip_props = |mut props: Properties| {
+        for (key, value) in options {
+            if props.get(key) != Some(value) {
+                continue;
+            }
+            match inputs.iter().find_map(|input| input.get(key)) {
+                Some(original) => {
+                    props.insert(key.clone(), original.clone());
+                }
+                None => {
+                    props.shift_remove(key);
+                }
+            }
+        }
+        props
+    };
+    match output {
+        ConvertOutput::Single(data, props) => ConvertOutput::Single(data, strip_props(props)),
+        ConvertOutput::Multiple(items) => ConvertOutput::Multiple(
+            items
+                .into_iter()
+                .map(|(data, props)| (data, strip_props(props)))
+                .collect(),
+        ),
+    }
 }
 
 /// Errors that can occur during conversion.
@@ -222,6 +445,10 @@ pub enum ConvertError {
     #[error("missing required property: {0}")]
     MissingProperty(String),
 
+    /// The input would exceed a resource limit (see [`Limits`](crate::Limits)).
+    #[error("resource limit exceeded: {0}")]
+    LimitExceeded(String),
+
     #[error(transparent)]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
@@ -316,4 +543,55 @@ mod tests {
         assert_eq!(decl.matches_input(&png_props), Some("in"));
         assert_eq!(decl.matches_input(&jpg_props), None);
     }
+
+    /// Legacy converter that reads `quality` from its input properties.
+    struct PropsQuality(ConverterDecl);
+
+    impl Converter for PropsQuality {
+        fn decl(&self) -> &ConverterDecl {
+            &self.0
+        }
+
+        fn convert(
+            &self,
+            _input: &[u8],
+            props: &Properties,
+        ) -> Result<ConvertOutput, ConvertError> {
+            let quality = props.get("quality").and_then(|v| v.as_i64()).unwrap_or(0);
+            Ok(ConvertOutput::Single(
+                quality.to_string().into_bytes(),
+                props.clone(),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_default_options_channel() {
+        use crate::properties::PropertiesExt;
+
+        let converter = PropsQuality(ConverterDecl::new("quality"));
+        let props = Properties::new().with("format", "png");
+        let options = Properties::new().with("quality", 80i64);
+
+        let ConvertOutput::Single(data, out) = converter
+            .convert_with_options(b"", &props, &options)
+            .unwrap()
+        else {
+            panic!("expected single output");
+        };
+        // The option reaches the converter but does not leak into the output
+        assert_eq!(data, b"80");
+        assert_eq!(out, props);
+
+        // Keys that were part of the input data keep their value
+        let props = props.with("quality", 50i64);
+        let ConvertOutput::Single(data, out) = converter
+            .convert_with_options(b"", &props, &options)
+            .unwrap()
+        else {
+            panic!("expected single output");
+        };
+        assert_eq!(data, b"80");
+        assert_eq!(out, props);
+    }
 }
@@ -0,0 +1,61 @@
+//! Deterministic output mode.
+//!
+//! Archives embed timestamps and some encoders record the time or the order
+//! things happened to finish in. With
+//! `ExecutionContext::with_deterministic`, the executor runs every step in
+//! deterministic mode; converters check [`is_deterministic`] and then fix
+//! timestamps to [`FIXED_MTIME`], sort entries, pin encoder settings and
+//! strip volatile metadata, so the same input yields byte-identical output.
+
+use std::cell::Cell;
+
+/// Timestamp written in deterministic mode, in seconds since the Unix epoch.
+///
+/// Formats that cannot represent it (zip starts in 1980) use their earliest
+/// representable time instead.
+pub const FIXED_MTIME: u64 = 0;
+
+thread_local! {
+    static DETERMINISTIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the current conversion should produce deterministic output.
+pub fn is_deterministic() -> bool {
+    DETERMINISTIC.with(Cell::get)
+}
+
+/// Run `f` with deterministic mode set to `enabled` on this thread.
+pub fn with_determinism<T>(enabled: bool, f: impl FnOnce() -> T) -> T {
+    /// Restores the enclosing mode, even if `f` panics.
+    struct Scope(bool);
+
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            DETERMINISTIC.with(|d| d.set(self.0));
+        }
+    }
+
+    let _scope = Scope(DETERMINISTIC.with(|d| d.replace(enabled)));
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope() {
+        assert!(!is_deterministic());
+        let inner = with_determinism(true, || {
+            let nested = with_determinism(false, is_deterministic);
+            assert!(is_deterministic());
+            nested
+        });
+        assert!(!inner);
+        assert!(!is_deterministic());
+
+        let result = std::panic::catch_unwind(|| with_determinism(true, || panic!("boom")));
+        assert!(result.is_err());
+        assert!(!is_deterministic());
+    }
+}
@@ -6,10 +6,21 @@
 //!
 //! See ADR-0006 for design rationale.
 
-use crate::converter::ConvertError;
-use crate::planner::Plan;
+use crate::converter::{ConvertError, ConvertOutput, Converter, ConverterDecl, NamedInput};
+use crate::determinism::with_determinism;
+use crate::fidelity::{LossNote, collect_losses};
+use crate::isolation::{IsolatedConverter, WorkerPool};
+use crate::limits::{Limits, with_limits};
+use crate::option::OptionError;
+use crate::planner::{Plan, PlanStep};
 use crate::properties::Properties;
 use crate::registry::Registry;
+use crate::resource::PlanEstimate;
+use crate::roundtrip::RoundTripReport;
+use crate::vocabulary::PropertyIssue;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "spill")]
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
@@ -23,6 +34,18 @@ pub struct ExecutionContext {
     pub memory_limit: Option<usize>,
     /// Optional parallelism limit (max concurrent jobs).
     pub parallelism: Option<usize>,
+    /// Directory for spilling intermediates that exceed the memory limit.
+    #[cfg(feature = "spill")]
+    pub spill_dir: Option<PathBuf>,
+    /// Worker processes that isolated converters run in.
+    pub isolation: Option<Arc<WorkerPool>>,
+    /// Check every step's output properties against the registry's
+    /// vocabulary (debug mode).
+    pub check_properties: bool,
+    /// Ask converters for byte-identical output across runs.
+    pub deterministic: bool,
+    /// Safety limits converters enforce on untrusted input.
+    pub limits: Limits,
 }
 
 impl ExecutionContext {
@@ -32,6 +55,12 @@ impl ExecutionContext {
             registry,
             memory_limit: None,
             parallelism: None,
+            #[cfg(feature = "spill")]
+            spill_dir: None,
+            isolation: None,
+            check_properties: false,
+            deterministic: false,
+            limits: Limits::default(),
         }
     }
 
@@ -46,6 +75,153 @@ impl ExecutionContext {
         self.parallelism = Some(jobs);
         self
     }
+
+    /// Spill intermediates that exceed the memory limit to files in `dir`.
+    ///
+    /// Only takes effect together with a memory limit.
+    #[cfg(feature = "spill")]
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.spill_dir = Some(dir.into());
+        self
+    }
+
+    /// Run the converters selected by the pool's policy in its worker
+    /// processes, so crashes surface as conversion errors.
+    pub fn with_isolation(mut self, pool: Arc<WorkerPool>) -> Self {
+        self.isolation = Some(pool);
+        self
+    }
+
+    /// Fail steps whose output properties use undeclared keys or values of
+    /// the wrong type. Meant for debugging converters.
+    pub fn with_property_checks(mut self) -> Self {
+        self.check_properties = true;
+        self
+    }
+
+    /// Run converters in deterministic mode: fixed timestamps, sorted
+    /// entries, pinned encoder settings and no volatile metadata.
+    pub fn with_deterministic(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
+    /// Enforce safety limits on output size, expansion, image size, archive
+    /// entries and nesting depth.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Look up a converter, routed through a worker process if isolated.
+    pub fn converter(&self, id: &str) -> Option<Arc<dyn Converter>> {
+        let converter = self.registry.get(id)?;
+        match &self.isolation {
+            Some(pool) if pool.isolates(id) => Some(Arc::new(IsolatedConverter {
+                inner: converter,
+                pool: Arc::clone(pool),
+            })),
+            _ => Some(converter),
+        }
+    }
+
+    /// Look up the converter for a plan step, applying the step's options.
+    ///
+    /// The options are validated against the converter's schema and passed
+    /// through the options channel of every call, separate from the data
+    /// properties.
+    pub fn step_converter(&self, step: &PlanStep) -> Result<Arc<dyn Converter>, ExecuteError> {
+        let converter = self
+            .converter(&step.converter_id)
+            .ok_or_else(|| ExecuteError::ConverterNotFound(step.converter_id.clone()))?;
+        if step.options.is_empty() {
+            return Ok(converter);
+        }
+        converter.decl().validate_options(&step.options)?;
+        Ok(Arc::new(OptionsConverter {
+            inner: converter,
+            options: step.options.clone(),
+        }))
+    }
+}
+
+/// A converter bound to the options of a plan step.
+struct OptionsConverter {
+    inner: Arc<dyn Converter>,
+    options: Properties,
+}
+
+impl Converter for OptionsConverter {
+    fn decl(&self) -> &ConverterDecl {
+        self.inner.decl()
+    }
+
+    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+        self.inner.convert_with_options(input, props, &self.options)
+    }
+
+    fn convert_multi(
+        &self,
+        inputs: &indexmap::IndexMap<String, NamedInput<'_>>,
+    ) -> Result<ConvertOutput, ConvertError> {
+        self.inner.convert_multi_with_options(inputs, &self.options)
+    }
+
+    fn convert_batch(
+        &self,
+        inputs: &[(&[u8], &Properties)],
+    ) -> Result<ConvertOutput, ConvertError> {
+        self.inner.convert_batch_with_options(inputs, &self.options)
+    }
+}
+
+/// Run one conversion of a plan step, collecting its fidelity losses.
+///
+/// The conversion runs under the context's limits, and in deterministic mode
+/// if the context asks for it. Its output is checked against the output
+/// limits whether or not the converter enforces them itself.
+///
+/// With property checks enabled, also checks the output properties against
+/// the vocabulary.
+fn run_step(
+    ctx: &ExecutionContext,
+    losses: &mut Vec<LossNote>,
+    converter_id: &str,
+    step: usize,
+    input_len: usize,
+    convert: impl FnOnce() -> Result<ConvertOutput, ConvertError>,
+) -> Result<ConvertOutput, ExecuteError> {
+    let (output, notes) = collect_losses(converter_id, || {
+        with_determinism(ctx.deterministic, || with_limits(ctx.limits, convert))
+    });
+    losses.extend(notes);
+    let output = output.and_then(|output| {
+        let output_len: usize = match &output {
+            ConvertOutput::Single(data, _) => data.len(),
+            ConvertOutput::Multiple(items) => items.iter().map(|(data, _)| data.len()).sum(),
+        };
+        ctx.limits.check_output(input_len, output_len as u64)?;
+        Ok(output)
+    });
+    let output = output.map_err(|source| ExecuteError::ConversionFailed { step, source })?;
+    if ctx.check_properties {
+        let vocabulary = ctx.registry.vocabulary();
+        let issues: Vec<PropertyIssue> = match &output {
+            ConvertOutput::Single(_, props) => vocabulary.check(props),
+            ConvertOutput::Multiple(items) => items
+                .iter()
+                .flat_map(|(_, p)| vocabulary.check(p))
+                .collect(),
+        };
+        if !issues.is_empty() {
+            return Err(ExecuteError::PropertyCheckFailed {
+                step,
+                converter: converter_id.to_string(),
+                issues,
+            });
+        }
+    }
+    Ok(output)
 }
 
 /// Result of executing a conversion plan.
@@ -57,10 +233,12 @@ pub struct ExecutionResult {
     pub props: Properties,
     /// Execution statistics.
     pub stats: ExecutionStats,
+    /// Fidelity losses reported by the converters, in execution order.
+    pub losses: Vec<LossNote>,
 }
 
 /// Statistics from plan execution.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExecutionStats {
     /// Total execution duration.
     pub duration: Duration,
@@ -68,6 +246,50 @@ pub struct ExecutionStats {
     pub peak_memory: usize,
     /// Number of converter steps executed.
     pub steps_executed: usize,
+    /// Bytes of intermediate data spilled to disk.
+    pub spilled_bytes: usize,
+    /// Per-step statistics, in execution order.
+    pub steps: Vec<StepStats>,
+}
+
+/// Statistics for a single plan step.
+///
+/// When a step runs over several items (after an expanding step), sizes and
+/// durations are summed across all of them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StepStats {
+    /// Converter ID.
+    pub converter_id: String,
+    /// `format` property of the step's (first) input.
+    pub input_format: Option<String>,
+    /// Total input size in bytes.
+    pub input_size: usize,
+    /// Total output size in bytes.
+    pub output_size: usize,
+    /// Total time spent in the converter.
+    pub duration: Duration,
+}
+
+impl StepStats {
+    fn new(converter_id: &str, props: Option<&Properties>) -> Self {
+        Self {
+            converter_id: converter_id.to_string(),
+            input_format: props
+                .and_then(|p| p.get("format"))
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            ..Self::default()
+        }
+    }
+
+    fn observe(&mut self, input_size: usize, output: &crate::ConvertOutput, elapsed: Duration) {
+        self.input_size += input_size;
+        self.output_size += match output {
+            crate::ConvertOutput::Single(data, _) => data.len(),
+            crate::ConvertOutput::Multiple(outputs) => outputs.iter().map(|(d, _)| d.len()).sum(),
+        };
+        self.duration += elapsed;
+    }
 }
 
 /// A conversion job for batch processing.
@@ -100,11 +322,42 @@ pub enum ExecuteError {
     #[error("converter not found: {0}")]
     ConverterNotFound(String),
 
+    #[error(transparent)]
+    InvalidOption(#[from] OptionError),
+
     #[error("memory limit exceeded: need {needed} bytes, limit {limit} bytes")]
     MemoryLimitExceeded { needed: usize, limit: usize },
 
+    #[error("failed to spill intermediate to disk: {0}")]
+    SpillFailed(#[source] std::io::Error),
+
     #[error("empty plan")]
     EmptyPlan,
+
+    #[error(
+        "step {step} ({converter}) produced unexpected properties: {}",
+        join(issues)
+    )]
+    PropertyCheckFailed {
+        step: usize,
+        converter: String,
+        issues: Vec<PropertyIssue>,
+    },
+
+    #[error("round-trip comparison by {comparator} failed: {source}")]
+    ComparisonFailed {
+        comparator: String,
+        #[source]
+        source: ConvertError,
+    },
+}
+
+fn join(issues: &[PropertyIssue]) -> String {
+    issues
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 /// Executor determines HOW a plan runs.
@@ -142,6 +395,22 @@ pub trait Executor: Send + Sync {
         self.execute(ctx, plan, input, props).map(|r| vec![r])
     }
 
+    /// Verify that a finished conversion can be undone without loss.
+    ///
+    /// Plans the reverse path from the result back to the format of
+    /// `original_props`, executes it, and compares the outcome with
+    /// `original` using the registry's comparator for that format (bytes if
+    /// none is registered).
+    fn verify_roundtrip(
+        &self,
+        ctx: &ExecutionContext,
+        original: &[u8],
+        original_props: &Properties,
+        result: &ExecutionResult,
+    ) -> Result<RoundTripReport, ExecuteError> {
+        crate::roundtrip::verify(self, ctx, original, original_props, result)
+    }
+
     /// Execute a batch of independent conversion jobs.
     ///
     /// Default implementation runs sequentially.
@@ -177,6 +446,7 @@ pub trait Executor: Send + Sync {
 
         let start = Instant::now();
         let mut peak_memory = inputs.iter().map(|(d, _)| d.len()).sum::<usize>();
+        let mut losses = Vec::new();
 
         // Find the aggregating step (converter that implements convert_batch)
         // For now, we assume it's specified by the caller via aggregate_step_index
@@ -187,6 +457,12 @@ pub trait Executor: Send + Sync {
         let aggregate_step = &plan.steps[aggregate_idx];
         let post_aggregate_steps = &plan.steps[aggregate_idx + 1..];
 
+        let first_props = inputs.first().map(|(_, p)| p);
+        let mut step_stats: Vec<StepStats> = pre_aggregate_steps
+            .iter()
+            .map(|step| StepStats::new(&step.converter_id, first_props))
+            .collect();
+
         // Phase 1: Process each input through pre-aggregation steps
         let mut processed: Vec<(Vec<u8>, Properties)> = Vec::new();
 
@@ -195,17 +471,18 @@ pub trait Executor: Send + Sync {
             let mut current_props = props;
 
             for (step_idx, step) in pre_aggregate_steps.iter().enumerate() {
-                let converter = ctx
-                    .registry
-                    .get(&step.converter_id)
-                    .ok_or_else(|| ExecuteError::ConverterNotFound(step.converter_id.clone()))?;
-
-                let output = converter
-                    .convert(&current_data, &current_props)
-                    .map_err(|e| ExecuteError::ConversionFailed {
-                        step: step_idx,
-                        source: e,
-                    })?;
+                let converter = ctx.step_converter(step)?;
+
+                let step_start = Instant::now();
+                let output = run_step(
+                    ctx,
+                    &mut losses,
+                    &step.converter_id,
+                    step_idx,
+                    current_data.len(),
+                    || converter.convert(&current_data, &current_props),
+                )?;
+                step_stats[step_idx].observe(current_data.len(), &output, step_start.elapsed());
 
                 match output {
                     crate::ConvertOutput::Single(data, props) => {
@@ -228,21 +505,27 @@ pub trait Executor: Send + Sync {
         }
 
         // Phase 2: Run the aggregating step
-        let aggregator = ctx
-            .registry
-            .get(&aggregate_step.converter_id)
-            .ok_or_else(|| ExecuteError::ConverterNotFound(aggregate_step.converter_id.clone()))?;
+        let aggregator = ctx.step_converter(aggregate_step)?;
 
         let batch_input: Vec<(&[u8], &Properties)> =
             processed.iter().map(|(d, p)| (d.as_slice(), p)).collect();
 
-        let output =
-            aggregator
-                .convert_batch(&batch_input)
-                .map_err(|e| ExecuteError::ConversionFailed {
-                    step: aggregate_idx,
-                    source: e,
-                })?;
+        let mut aggregate_stats = StepStats::new(
+            &aggregate_step.converter_id,
+            processed.first().map(|(_, p)| p),
+        );
+        let batch_size = batch_input.iter().map(|(d, _)| d.len()).sum();
+        let step_start = Instant::now();
+        let output = run_step(
+            ctx,
+            &mut losses,
+            &aggregate_step.converter_id,
+            aggregate_idx,
+            batch_size,
+            || aggregator.convert_batch(&batch_input),
+        )?;
+        aggregate_stats.observe(batch_size, &output, step_start.elapsed());
+        step_stats.push(aggregate_stats);
 
         let (mut current_data, mut current_props) = match output {
             crate::ConvertOutput::Single(data, props) => (data, props),
@@ -256,17 +539,20 @@ pub trait Executor: Send + Sync {
         // Phase 3: Process aggregated output through post-aggregation steps
         for (rel_idx, step) in post_aggregate_steps.iter().enumerate() {
             let step_idx = aggregate_idx + 1 + rel_idx;
-            let converter = ctx
-                .registry
-                .get(&step.converter_id)
-                .ok_or_else(|| ExecuteError::ConverterNotFound(step.converter_id.clone()))?;
-
-            let output = converter
-                .convert(&current_data, &current_props)
-                .map_err(|e| ExecuteError::ConversionFailed {
-                    step: step_idx,
-                    source: e,
-                })?;
+            let converter = ctx.step_converter(step)?;
+
+            let mut stats = StepStats::new(&step.converter_id, Some(&current_props));
+            let step_start = Instant::now();
+            let output = run_step(
+                ctx,
+                &mut losses,
+                &step.converter_id,
+                step_idx,
+                current_data.len(),
+                || converter.convert(&current_data, &current_props),
+            )?;
+            stats.observe(current_data.len(), &output, step_start.elapsed());
+            step_stats.push(stats);
 
             match output {
                 crate::ConvertOutput::Single(data, props) => {
@@ -291,7 +577,10 @@ pub trait Executor: Send + Sync {
                 duration: start.elapsed(),
                 peak_memory,
                 steps_executed: plan.steps.len(),
+                spilled_bytes: 0,
+                steps: step_stats,
             },
+            losses,
         })
     }
 }
@@ -346,26 +635,30 @@ impl Executor for SimpleExecutor {
     ) -> Result<Vec<ExecutionResult>, ExecuteError> {
         let start = Instant::now();
         let mut peak_memory = input.len();
+        let mut losses = Vec::new();
 
         // Track all items flowing through the pipeline
         // Each item is (data, props)
         let mut items: Vec<(Vec<u8>, Properties)> = vec![(input, props)];
+        let mut step_stats = Vec::with_capacity(plan.steps.len());
 
         for (step_idx, step) in plan.steps.iter().enumerate() {
-            let converter = ctx
-                .registry
-                .get(&step.converter_id)
-                .ok_or_else(|| ExecuteError::ConverterNotFound(step.converter_id.clone()))?;
+            let converter = ctx.step_converter(step)?;
 
+            let mut stats = StepStats::new(&step.converter_id, items.first().map(|(_, p)| p));
             let mut next_items = Vec::new();
 
             for (data, props) in items {
-                let output = converter.convert(&data, &props).map_err(|e| {
-                    ExecuteError::ConversionFailed {
-                        step: step_idx,
-                        source: e,
-                    }
-                })?;
+                let step_start = Instant::now();
+                let output = run_step(
+                    ctx,
+                    &mut losses,
+                    &step.converter_id,
+                    step_idx,
+                    data.len(),
+                    || converter.convert(&data, &props),
+                )?;
+                stats.observe(data.len(), &output, step_start.elapsed());
 
                 match output {
                     crate::ConvertOutput::Single(out_data, out_props) => {
@@ -385,6 +678,7 @@ impl Executor for SimpleExecutor {
                 return Err(ExecuteError::EmptyPlan);
             }
 
+            step_stats.push(stats);
             items = next_items;
         }
 
@@ -400,7 +694,10 @@ impl Executor for SimpleExecutor {
                     duration,
                     peak_memory,
                     steps_executed,
+                    spilled_bytes: 0,
+                    steps: step_stats.clone(),
                 },
+                losses: losses.clone(),
             })
             .collect())
     }
@@ -534,6 +831,16 @@ impl Drop for MemoryPermit<'_> {
 ///
 /// Checks estimated memory usage before execution and fails fast if it
 /// would exceed the configured limit. Useful for preventing OOM on large files.
+/// The check uses [`PlanEstimate::peak_memory`], which counts the input buffer
+/// alongside the first step's output, so it is stricter than comparing
+/// [`estimate_memory`] with the limit.
+///
+/// With the `spill` feature and a spill directory on the context, it instead
+/// runs the plan and moves intermediates to disk whenever resident data would
+/// exceed half the limit (the other half is left for the next step's output).
+/// The estimate is then checked without the intermediates that will be
+/// spilled. Aggregating plans spill their inputs and intermediates the same
+/// way.
 #[derive(Debug, Clone, Default)]
 pub struct BoundedExecutor;
 
@@ -566,7 +873,21 @@ impl Executor for BoundedExecutor {
     ) -> Result<Vec<ExecutionResult>, ExecuteError> {
         // Check memory limit before starting
         if let Some(limit) = ctx.memory_limit {
-            let estimated = estimate_memory(input.len(), plan);
+            let estimate = PlanEstimate::new(&ctx.registry, input.len(), &props, plan);
+
+            #[cfg(feature = "spill")]
+            if let Some(dir) = &ctx.spill_dir {
+                let estimated = estimate.resident_memory(limit / 2);
+                if estimated > limit {
+                    return Err(ExecuteError::MemoryLimitExceeded {
+                        needed: estimated,
+                        limit,
+                    });
+                }
+                return execute_spilling(ctx, plan, input, props, limit, dir);
+            }
+
+            let estimated = estimate.peak_memory();
             if estimated > limit {
                 return Err(ExecuteError::MemoryLimitExceeded {
                     needed: estimated,
@@ -578,6 +899,307 @@ impl Executor for BoundedExecutor {
         // Delegate to SimpleExecutor for actual execution with expansion
         SimpleExecutor::new().execute_expanding(ctx, plan, input, props)
     }
+
+    fn execute_aggregating(
+        &self,
+        ctx: &ExecutionContext,
+        plan: &Plan,
+        inputs: Vec<(Vec<u8>, Properties)>,
+    ) -> Result<ExecutionResult, ExecuteError> {
+        #[cfg(feature = "spill")]
+        if let (Some(limit), Some(dir)) = (ctx.memory_limit, &ctx.spill_dir) {
+            return execute_aggregating_spilling(ctx, plan, inputs, limit, dir);
+        }
+
+        SimpleExecutor::new().execute_aggregating(ctx, plan, inputs)
+    }
+}
+
+/// Tracks resident intermediate bytes and spills data that would push them
+/// past a threshold.
+#[cfg(feature = "spill")]
+struct Spiller<'a> {
+    dir: &'a Path,
+    threshold: usize,
+    resident: usize,
+    peak_memory: usize,
+    spilled_bytes: usize,
+}
+
+#[cfg(feature = "spill")]
+impl<'a> Spiller<'a> {
+    fn new(dir: &'a Path, limit: usize) -> Self {
+        Self {
+            dir,
+            threshold: limit / 2,
+            resident: 0,
+            peak_memory: 0,
+            spilled_bytes: 0,
+        }
+    }
+
+    /// Take ownership of `data`, spilling it if `may_spill` and resident
+    /// data would exceed the threshold.
+    fn hold(
+        &mut self,
+        data: Vec<u8>,
+        may_spill: bool,
+    ) -> Result<crate::spill::Intermediate, ExecuteError> {
+        use crate::spill::Intermediate;
+
+        if may_spill && self.resident + data.len() > self.threshold {
+            self.spilled_bytes += data.len();
+            return Intermediate::spill(data, self.dir).map_err(ExecuteError::SpillFailed);
+        }
+        self.resident += data.len();
+        self.peak_memory = self.peak_memory.max(self.resident);
+        Ok(Intermediate::Resident(data))
+    }
+
+    /// Note a step output of `bytes` produced while current data is resident.
+    fn produced(&mut self, bytes: usize) {
+        self.peak_memory = self.peak_memory.max(self.resident + bytes);
+    }
+
+    /// Drop data that is no longer needed.
+    fn release(&mut self, data: crate::spill::Intermediate) {
+        self.resident -= data.resident_len();
+    }
+}
+
+/// Run a plan, spilling intermediates to `dir` to stay under `limit`.
+///
+/// Resident bytes are tracked from actual output sizes rather than estimates.
+/// Outputs of the final step are never spilled since they are returned in memory.
+#[cfg(feature = "spill")]
+fn execute_spilling(
+    ctx: &ExecutionContext,
+    plan: &Plan,
+    input: Vec<u8>,
+    props: Properties,
+    limit: usize,
+    dir: &Path,
+) -> Result<Vec<ExecutionResult>, ExecuteError> {
+    let start = Instant::now();
+    let mut spiller = Spiller::new(dir, limit);
+    spiller.peak_memory = input.len();
+
+    let first = spiller.hold(input, !plan.steps.is_empty())?;
+    let mut items = vec![(first, props)];
+    let mut step_stats = Vec::with_capacity(plan.steps.len());
+    let mut losses = Vec::new();
+
+    for (step_idx, step) in plan.steps.iter().enumerate() {
+        let converter = ctx.step_converter(step)?;
+        let is_last = step_idx + 1 == plan.steps.len();
+
+        let mut stats = StepStats::new(&step.converter_id, items.first().map(|(_, p)| p));
+        let mut next_items = Vec::new();
+
+        for (data, props) in items {
+            let step_start = Instant::now();
+            let output = run_step(
+                ctx,
+                &mut losses,
+                &step.converter_id,
+                step_idx,
+                data.len(),
+                || converter.convert(data.as_slice(), &props),
+            )?;
+            stats.observe(data.len(), &output, step_start.elapsed());
+
+            let outputs = match output {
+                crate::ConvertOutput::Single(out_data, out_props) => vec![(out_data, out_props)],
+                crate::ConvertOutput::Multiple(outputs) => outputs,
+            };
+
+            spiller.produced(outputs.iter().map(|(d, _)| d.len()).sum());
+            spiller.release(data);
+
+            for (out_data, out_props) in outputs {
+                next_items.push((spiller.hold(out_data, !is_last)?, out_props));
+            }
+        }
+
+        if next_items.is_empty() {
+            return Err(ExecuteError::EmptyPlan);
+        }
+
+        step_stats.push(stats);
+        items = next_items;
+    }
+
+    let duration = start.elapsed();
+    let steps_executed = plan.steps.len();
+
+    Ok(items
+        .into_iter()
+        .map(|(data, props)| ExecutionResult {
+            data: data.into_vec(),
+            props,
+            stats: ExecutionStats {
+                duration,
+                peak_memory: spiller.peak_memory,
+                steps_executed,
+                spilled_bytes: spiller.spilled_bytes,
+                steps: step_stats.clone(),
+            },
+            losses: losses.clone(),
+        })
+        .collect())
+}
+
+/// Run an aggregating plan, spilling inputs and intermediates to `dir` to
+/// stay under `limit`.
+///
+/// Follows [`Executor::execute_aggregating`]: each input runs through the
+/// steps before the aggregator, the aggregator reads them all (spilled ones
+/// as mapped files), and its output runs through the remaining steps.
+#[cfg(feature = "spill")]
+fn execute_aggregating_spilling(
+    ctx: &ExecutionContext,
+    plan: &Plan,
+    inputs: Vec<(Vec<u8>, Properties)>,
+    limit: usize,
+    dir: &Path,
+) -> Result<ExecutionResult, ExecuteError> {
+    if plan.steps.is_empty() {
+        return Err(ExecuteError::EmptyPlan);
+    }
+
+    let start = Instant::now();
+    let mut spiller = Spiller::new(dir, limit);
+    spiller.peak_memory = inputs.iter().map(|(d, _)| d.len()).sum();
+    let mut losses = Vec::new();
+
+    let aggregate_idx = find_aggregate_step_index(ctx, plan).unwrap_or(plan.steps.len() - 1);
+    let pre_aggregate_steps = &plan.steps[..aggregate_idx];
+    let aggregate_step = &plan.steps[aggregate_idx];
+
+    let first_props = inputs.first().map(|(_, p)| p);
+    let mut step_stats: Vec<StepStats> = pre_aggregate_steps
+        .iter()
+        .map(|step| StepStats::new(&step.converter_id, first_props))
+        .collect();
+
+    // Inputs are held until the aggregator has read them all
+    let mut held = Vec::with_capacity(inputs.len());
+    for (data, props) in inputs {
+        held.push((spiller.hold(data, true)?, props));
+    }
+
+    // Phase 1: Process each input through pre-aggregation steps
+    let mut processed = Vec::with_capacity(held.len());
+    for (mut current_data, mut current_props) in held {
+        for (step_idx, step) in pre_aggregate_steps.iter().enumerate() {
+            let converter = ctx.step_converter(step)?;
+
+            let step_start = Instant::now();
+            let output = run_step(
+                ctx,
+                &mut losses,
+                &step.converter_id,
+                step_idx,
+                current_data.len(),
+                || converter.convert(current_data.as_slice(), &current_props),
+            )?;
+            step_stats[step_idx].observe(current_data.len(), &output, step_start.elapsed());
+
+            // For aggregation, take just the last output from expansion
+            let (data, props) = match output {
+                crate::ConvertOutput::Single(data, props) => (data, props),
+                crate::ConvertOutput::Multiple(mut outputs) => match outputs.pop() {
+                    Some(output) => output,
+                    None => continue,
+                },
+            };
+            spiller.produced(data.len());
+            spiller.release(current_data);
+            current_data = spiller.hold(data, true)?;
+            current_props = props;
+        }
+        processed.push((current_data, current_props));
+    }
+
+    // Phase 2: Run the aggregating step
+    let aggregator = ctx.step_converter(aggregate_step)?;
+    let batch_input: Vec<(&[u8], &Properties)> =
+        processed.iter().map(|(d, p)| (d.as_slice(), p)).collect();
+
+    let mut aggregate_stats = StepStats::new(
+        &aggregate_step.converter_id,
+        processed.first().map(|(_, p)| p),
+    );
+    let batch_size = batch_input.iter().map(|(d, _)| d.len()).sum();
+    let step_start = Instant::now();
+    let output = run_step(
+        ctx,
+        &mut losses,
+        &aggregate_step.converter_id,
+        aggregate_idx,
+        batch_size,
+        || aggregator.convert_batch(&batch_input),
+    )?;
+    aggregate_stats.observe(batch_size, &output, step_start.elapsed());
+    step_stats.push(aggregate_stats);
+    drop(batch_input);
+
+    let (data, props) = match output {
+        crate::ConvertOutput::Single(data, props) => (data, props),
+        crate::ConvertOutput::Multiple(mut outputs) => {
+            outputs.pop().ok_or(ExecuteError::EmptyPlan)?
+        }
+    };
+    spiller.produced(data.len());
+    for (data, _) in processed {
+        spiller.release(data);
+    }
+
+    // Phase 3: Process aggregated output through post-aggregation steps
+    let mut current_data = spiller.hold(data, aggregate_idx + 1 < plan.steps.len())?;
+    let mut current_props = props;
+    for (step_idx, step) in plan.steps.iter().enumerate().skip(aggregate_idx + 1) {
+        let converter = ctx.step_converter(step)?;
+        let is_last = step_idx + 1 == plan.steps.len();
+
+        let mut stats = StepStats::new(&step.converter_id, Some(&current_props));
+        let step_start = Instant::now();
+        let output = run_step(
+            ctx,
+            &mut losses,
+            &step.converter_id,
+            step_idx,
+            current_data.len(),
+            || converter.convert(current_data.as_slice(), &current_props),
+        )?;
+        stats.observe(current_data.len(), &output, step_start.elapsed());
+        step_stats.push(stats);
+
+        let (data, props) = match output {
+            crate::ConvertOutput::Single(data, props) => (data, props),
+            crate::ConvertOutput::Multiple(mut outputs) => match outputs.pop() {
+                Some(output) => output,
+                None => continue,
+            },
+        };
+        spiller.produced(data.len());
+        spiller.release(current_data);
+        current_data = spiller.hold(data, !is_last)?;
+        current_props = props;
+    }
+
+    Ok(ExecutionResult {
+        data: current_data.into_vec(),
+        props: current_props,
+        stats: ExecutionStats {
+            duration: start.elapsed(),
+            peak_memory: spiller.peak_memory,
+            steps_executed: plan.steps.len(),
+            spilled_bytes: spiller.spilled_bytes,
+            steps: step_stats,
+        },
+        losses,
+    })
 }
 
 // ============================================================================
@@ -588,6 +1210,7 @@ impl Executor for BoundedExecutor {
 ///
 /// Uses rayon for parallel execution with backpressure based on memory budget.
 /// Jobs that would exceed the memory limit are skipped (returned as errors).
+/// Jobs are started largest-estimate first; results keep submission order.
 #[cfg(feature = "parallel")]
 #[derive(Debug, Clone, Default)]
 pub struct ParallelExecutor;
@@ -613,6 +1236,15 @@ impl Executor for ParallelExecutor {
         BoundedExecutor.execute(ctx, plan, input, props)
     }
 
+    fn execute_aggregating(
+        &self,
+        ctx: &ExecutionContext,
+        plan: &Plan,
+        inputs: Vec<(Vec<u8>, Properties)>,
+    ) -> Result<ExecutionResult, ExecuteError> {
+        BoundedExecutor.execute_aggregating(ctx, plan, inputs)
+    }
+
     fn execute_batch(
         &self,
         ctx: &ExecutionContext,
@@ -633,9 +1265,29 @@ impl Executor for ParallelExecutor {
             None
         };
 
-        let execute_job = |job: Job| {
-            let estimated = estimate_memory(job.input.len(), &job.plan);
+        let estimate_job = |job: &Job| {
+            let estimate = PlanEstimate::new(&ctx.registry, job.input.len(), &job.props, &job.plan);
+
+            // Spilling jobs only need what stays resident
+            #[cfg(feature = "spill")]
+            if ctx.spill_dir.is_some() {
+                return estimate.resident_memory(memory_limit / 2);
+            }
+
+            estimate.peak_memory()
+        };
+
+        // Schedule the largest jobs first so small ones fill the gaps
+        // instead of leaving a large job to run alone at the end. Workers
+        // pull from the sorted list in order, unlike a split parallel iterator.
+        let mut scheduled: Vec<(usize, usize, Job)> = jobs
+            .into_iter()
+            .enumerate()
+            .map(|(idx, job)| (idx, estimate_job(&job), job))
+            .collect();
+        scheduled.sort_by_key(|(_, estimated, _)| std::cmp::Reverse(*estimated));
 
+        let run_job = |estimated: usize, job: Job| {
             // Block until memory is available (backpressure)
             // Only fails if single job exceeds total budget
             let _permit = match budget.reserve(estimated) {
@@ -652,12 +1304,28 @@ impl Executor for ParallelExecutor {
             // Execute with permit held (released on drop)
             BoundedExecutor.execute(ctx, &job.plan, job.input, job.props)
         };
-
-        if let Some(pool) = pool {
-            pool.install(|| jobs.into_par_iter().map(execute_job).collect())
+        let execute_job =
+            |(idx, estimated, job): (usize, usize, Job)| (idx, run_job(estimated, job));
+
+        let mut results: Vec<_> = if let Some(pool) = pool {
+            pool.install(|| {
+                scheduled
+                    .into_iter()
+                    .par_bridge()
+                    .map(execute_job)
+                    .collect()
+            })
         } else {
-            jobs.into_par_iter().map(execute_job).collect()
-        }
+            scheduled
+                .into_iter()
+                .par_bridge()
+                .map(execute_job)
+                .collect()
+        };
+
+        // Restore submission order
+        results.sort_by_key(|(idx, _)| *idx);
+        results.into_iter().map(|(_, result)| result).collect()
     }
 }
 
@@ -672,19 +1340,22 @@ impl Executor for ParallelExecutor {
 /// - Images: ~4x (compressed to RGBA)
 /// - Video: ~100x (compressed to raw frames)
 /// - Serde: ~1x (roughly same size)
+///
+/// Executors use [`PlanEstimate::peak_memory`] instead, which prefers the
+/// resource models declared by converters and also counts the input buffer
+/// held while the first step runs.
 pub fn estimate_memory(input_size: usize, plan: &Plan) -> usize {
-    let mut estimate = input_size;
-
-    for step in &plan.steps {
-        estimate = match step.converter_id.as_str() {
-            s if s.starts_with("audio.") => estimate.saturating_mul(10),
-            s if s.starts_with("image.") => estimate.saturating_mul(4),
-            s if s.starts_with("video.") => estimate.saturating_mul(100),
-            _ => estimate,
-        };
-    }
+    PlanEstimate::heuristic(input_size, plan).output_size()
+}
 
-    estimate
+/// Estimate peak resident memory for a plan when intermediates may be spilled.
+///
+/// Uses the same expansion factors as `estimate_memory`, but assumes any
+/// intermediate larger than `spill_threshold` is moved to disk before the next
+/// step runs. Each step still holds its whole output in memory, so the peak is
+/// the largest resident input plus output of a single step.
+pub fn estimate_resident_memory(input_size: usize, plan: &Plan, spill_threshold: usize) -> usize {
+    PlanEstimate::heuristic(input_size, plan).resident_memory(spill_threshold)
 }
 
 #[cfg(test)]
@@ -749,12 +1420,14 @@ mod tests {
                     input_port: "in".into(),
                     output_port: "out".into(),
                     output_properties: Properties::new().with("format", "b"),
+                    options: Properties::new(),
                 },
                 crate::PlanStep {
                     converter_id: "test.b-to-c".into(),
                     input_port: "in".into(),
                     output_port: "out".into(),
                     output_properties: Properties::new().with("format", "c"),
+                    options: Properties::new(),
                 },
             ],
             cost: 2.0,
@@ -774,6 +1447,42 @@ mod tests {
         assert_eq!(result.stats.steps_executed, 2);
     }
 
+    #[test]
+    fn test_output_limit() {
+        let mut registry = Registry::new();
+        registry.register(IdentityConverter::new("a", "b"));
+        let plan = Plan {
+            steps: vec![crate::PlanStep {
+                converter_id: "test.a-to-b".into(),
+                input_port: "in".into(),
+                output_port: "out".into(),
+                output_properties: Properties::new().with("format", "b"),
+                options: Properties::new(),
+            }],
+            cost: 1.0,
+        };
+        let registry = Arc::new(registry);
+        let run = |limits: Limits| {
+            let ctx = ExecutionContext::new(Arc::clone(&registry)).with_limits(limits);
+            SimpleExecutor::new().execute(
+                &ctx,
+                &plan,
+                b"test data".to_vec(),
+                Properties::new().with("format", "a"),
+            )
+        };
+
+        // The identity converter knows nothing about limits
+        assert!(run(Limits::new().max_output_bytes(9)).is_ok());
+        assert!(matches!(
+            run(Limits::new().max_output_bytes(8)),
+            Err(ExecuteError::ConversionFailed {
+                step: 0,
+                source: ConvertError::LimitExceeded(_),
+            })
+        ));
+    }
+
     #[test]
     fn test_execute_empty_plan() {
         let registry = Registry::new();
@@ -798,6 +1507,220 @@ mod tests {
         assert_eq!(result.stats.steps_executed, 0);
     }
 
+    #[test]
+    fn test_step_options() {
+        let mut converter = IdentityConverter::new("a", "b");
+        converter.decl = converter
+            .decl
+            .option("level", crate::OptionDecl::int("Level").range(0.0, 9.0));
+        let mut registry = Registry::new();
+        registry.register(converter);
+        let ctx = ExecutionContext::new(Arc::new(registry));
+
+        let plan = |options: Properties| Plan {
+            steps: vec![crate::PlanStep {
+                converter_id: "test.a-to-b".into(),
+                input_port: "in".into(),
+                output_port: "out".into(),
+                output_properties: Properties::new().with("format", "b"),
+                options,
+            }],
+            cost: 1.0,
+        };
+        let executor = SimpleExecutor::new();
+        let props = Properties::new().with("format", "a");
+
+        // Options reach the converter but do not leak into the data.
+        let result = executor
+            .execute(
+                &ctx,
+                &plan(Properties::new().with("level", 3i64)),
+                b"x".to_vec(),
+                props.clone(),
+            )
+            .unwrap();
+        assert_eq!(result.props, Properties::new().with("format", "b"));
+
+        let err = executor
+            .execute(
+                &ctx,
+                &plan(Properties::new().with("levle", 3i64)),
+                b"x".to_vec(),
+                props.clone(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExecuteError::InvalidOption(crate::OptionError::Unknown { .. })
+        ));
+
+        let err = executor
+            .execute(
+                &ctx,
+                &plan(Properties::new().with("level", 12i64)),
+                b"x".to_vec(),
+                props,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExecuteError::InvalidOption(crate::OptionError::Invalid { .. })
+        ));
+    }
+
+    /// Passes data through, reporting the loss of `comments`.
+    struct LossyConverter(ConverterDecl);
+
+    impl Converter for LossyConverter {
+        fn decl(&self) -> &ConverterDecl {
+            &self.0
+        }
+
+        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+            crate::report_loss(crate::LossNote::new("comments", crate::Severity::Minor).count(2));
+            Ok(ConvertOutput::Single(input.to_vec(), props.clone()))
+        }
+    }
+
+    #[test]
+    fn test_losses_collected() {
+        let mut registry = Registry::new();
+        registry.register(IdentityConverter::new("a", "b"));
+        registry.register(LossyConverter(ConverterDecl::simple(
+            "test.lossy",
+            PropertyPattern::new().eq("format", "b"),
+            PropertyPattern::new().eq("format", "b"),
+        )));
+        let ctx = ExecutionContext::new(Arc::new(registry));
+
+        let step = |id: &str| crate::PlanStep {
+            converter_id: id.into(),
+            input_port: "in".into(),
+            output_port: "out".into(),
+            output_properties: Properties::new(),
+            options: Properties::new(),
+        };
+        let plan = Plan {
+            steps: vec![step("test.a-to-b"), step("test.lossy")],
+            cost: 2.0,
+        };
+
+        let result = SimpleExecutor::new()
+            .execute(
+                &ctx,
+                &plan,
+                b"x".to_vec(),
+                Properties::new().with("format", "a"),
+            )
+            .unwrap();
+        assert_eq!(result.losses.len(), 1);
+        assert_eq!(result.losses[0].converter, "test.lossy");
+        assert_eq!(result.losses[0].count, 2);
+    }
+
+    #[test]
+    fn test_property_checks() {
+        let mut registry = Registry::new();
+        registry.register(IdentityConverter::new("a", "b"));
+        let plan = Plan {
+            steps: vec![crate::PlanStep {
+                converter_id: "test.a-to-b".into(),
+                input_port: "in".into(),
+                output_port: "out".into(),
+                output_properties: Properties::new().with("format", "b"),
+                options: Properties::new(),
+            }],
+            cost: 1.0,
+        };
+        let props = Properties::new()
+            .with("format", "a")
+            .with("width", "wide")
+            .with("img.depth", 8i64);
+
+        // Off by default
+        let ctx = ExecutionContext::new(Arc::new(registry.clone()));
+        assert!(
+            SimpleExecutor::new()
+                .execute(&ctx, &plan, b"x".to_vec(), props.clone())
+                .is_ok()
+        );
+
+        let ctx = ExecutionContext::new(Arc::new(registry)).with_property_checks();
+        let err = SimpleExecutor::new()
+            .execute(&ctx, &plan, b"x".to_vec(), props)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "step 0 (test.a-to-b) produced unexpected properties: \
+             property `width` should be int, got String(\"wide\"); \
+             undeclared property `img.depth`"
+        );
+    }
+
+    /// Stamps its output with the current time unless deterministic.
+    struct StampConverter(ConverterDecl);
+
+    impl Converter for StampConverter {
+        fn decl(&self) -> &ConverterDecl {
+            &self.0
+        }
+
+        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+            let stamp = if crate::is_deterministic() {
+                crate::FIXED_MTIME as u128
+            } else {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_nanos()
+            };
+            let mut output = input.to_vec();
+            output.extend_from_slice(&stamp.to_le_bytes());
+            Ok(ConvertOutput::Single(output, props.clone()))
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let mut registry = Registry::new();
+        registry.register(StampConverter(ConverterDecl::simple(
+            "test.stamp",
+            PropertyPattern::new().eq("format", "a"),
+            PropertyPattern::new().eq("format", "a"),
+        )));
+        let registry = Arc::new(registry);
+        let plan = Plan {
+            steps: vec![crate::PlanStep {
+                converter_id: "test.stamp".into(),
+                input_port: "in".into(),
+                output_port: "out".into(),
+                output_properties: Properties::new(),
+                options: Properties::new(),
+            }],
+            cost: 1.0,
+        };
+        let run = |ctx: &ExecutionContext| {
+            SimpleExecutor::new()
+                .execute(
+                    ctx,
+                    &plan,
+                    b"x".to_vec(),
+                    Properties::new().with("format", "a"),
+                )
+                .unwrap()
+                .data
+        };
+
+        let ctx = ExecutionContext::new(Arc::clone(&registry)).with_deterministic();
+        let first = run(&ctx);
+        assert_eq!(first, run(&ctx));
+        assert_eq!(&first[1..], &0u128.to_le_bytes());
+        assert!(!crate::is_deterministic());
+
+        let ctx = ExecutionContext::new(registry);
+        assert_ne!(run(&ctx), first);
+    }
+
     #[test]
     fn test_estimate_memory() {
         let plan = Plan {
@@ -806,6 +1729,7 @@ mod tests {
                 input_port: "in".into(),
                 output_port: "out".into(),
                 output_properties: Properties::new(),
+                options: Properties::new(),
             }],
             cost: 1.0,
         };
@@ -814,6 +1738,25 @@ mod tests {
         assert_eq!(estimate, 10000); // 10x for audio
     }
 
+    #[test]
+    fn test_plan_estimate_counts_input() {
+        let plan = Plan {
+            steps: vec![crate::PlanStep {
+                converter_id: "audio.mp3-to-wav".into(),
+                input_port: "in".into(),
+                output_port: "out".into(),
+                output_properties: Properties::new(),
+                options: Properties::new(),
+            }],
+            cost: 1.0,
+        };
+
+        // The input stays resident while the step produces its output
+        let estimate = PlanEstimate::heuristic(1000, &plan);
+        assert_eq!(estimate.peak_memory(), 11000);
+        assert_eq!(estimate.output_size(), estimate_memory(1000, &plan));
+    }
+
     #[test]
     fn test_memory_budget_try_reserve() {
         let budget = MemoryBudget::new(100);
@@ -896,6 +1839,7 @@ mod tests {
                 input_port: "in".into(),
                 output_port: "out".into(),
                 output_properties: Properties::new().with("format", "b"),
+                options: Properties::new(),
             }],
             cost: 1.0,
         };
@@ -922,6 +1866,7 @@ mod tests {
                 input_port: "in".into(),
                 output_port: "out".into(),
                 output_properties: Properties::new().with("format", "b"),
+                options: Properties::new(),
             }],
             cost: 1.0,
         };
@@ -937,6 +1882,122 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_estimate_resident_memory() {
+        let plan = Plan {
+            steps: vec![
+                crate::PlanStep {
+                    converter_id: "image.png-to-rgba".into(),
+                    input_port: "in".into(),
+                    output_port: "out".into(),
+                    output_properties: Properties::new(),
+                    options: Properties::new(),
+                },
+                crate::PlanStep {
+                    converter_id: "serde.rgba-to-json".into(),
+                    input_port: "in".into(),
+                    output_port: "out".into(),
+                    output_properties: Properties::new(),
+                    options: Properties::new(),
+                },
+            ],
+            cost: 2.0,
+        };
+
+        // Without spilling: 4000-byte intermediate resident alongside its copy
+        assert_eq!(estimate_resident_memory(1000, &plan, usize::MAX), 8000);
+        // Intermediate spilled: peak is the first step's input plus output
+        assert_eq!(estimate_resident_memory(1000, &plan, 2000), 5000);
+        // Everything spilled: each step only holds its output
+        assert_eq!(estimate_resident_memory(1000, &plan, 500), 4000);
+    }
+
+    #[cfg(feature = "spill")]
+    #[test]
+    fn test_bounded_executor_spills_instead_of_failing() {
+        let mut registry = Registry::new();
+        registry.register(IdentityConverter::new("a", "b"));
+        registry.register(IdentityConverter::new("b", "c"));
+
+        // Input and intermediate together exceed the limit; each alone fits
+        let ctx = ExecutionContext::new(Arc::new(registry))
+            .with_memory_limit(30)
+            .with_spill_dir(std::env::temp_dir());
+
+        let plan = Plan {
+            steps: vec![
+                crate::PlanStep {
+                    converter_id: "test.a-to-b".into(),
+                    input_port: "in".into(),
+                    output_port: "out".into(),
+                    output_properties: Properties::new().with("format", "b"),
+                    options: Properties::new(),
+                },
+                crate::PlanStep {
+                    converter_id: "test.b-to-c".into(),
+                    input_port: "in".into(),
+                    output_port: "out".into(),
+                    output_properties: Properties::new().with("format", "c"),
+                    options: Properties::new(),
+                },
+            ],
+            cost: 2.0,
+        };
+
+        let input = b"this is too large".to_vec();
+        let props = Properties::new().with("format", "a");
+
+        let result = BoundedExecutor::new()
+            .execute(&ctx, &plan, input.clone(), props)
+            .expect("should spill rather than fail");
+
+        assert_eq!(result.data, input);
+        assert_eq!(
+            result.props.get("format").and_then(|v| v.as_str()),
+            Some("c")
+        );
+        // Input and the intermediate were spilled; the final output was not.
+        assert_eq!(result.stats.spilled_bytes, input.len() * 2);
+        assert!(result.stats.peak_memory <= 30);
+    }
+
+    #[cfg(feature = "spill")]
+    #[test]
+    fn test_bounded_executor_spilling_checks_resident_memory() {
+        let mut registry = Registry::new();
+        registry.register(IdentityConverter::new("a", "b"));
+
+        // Spilling cannot help when one step's output exceeds the limit
+        let ctx = ExecutionContext::new(Arc::new(registry))
+            .with_memory_limit(10)
+            .with_spill_dir(std::env::temp_dir());
+
+        let plan = Plan {
+            steps: vec![crate::PlanStep {
+                converter_id: "test.a-to-b".into(),
+                input_port: "in".into(),
+                output_port: "out".into(),
+                output_properties: Properties::new().with("format", "b"),
+                options: Properties::new(),
+            }],
+            cost: 1.0,
+        };
+
+        let result = BoundedExecutor::new().execute(
+            &ctx,
+            &plan,
+            b"this is too large".to_vec(),
+            Properties::new().with("format", "a"),
+        );
+        assert!(matches!(
+            result,
+            Err(ExecuteError::MemoryLimitExceeded {
+                needed: 17,
+                limit: 10
+            })
+        ));
+    }
+
     #[test]
     fn test_execute_batch() {
         let mut registry = Registry::new();
@@ -950,6 +2011,7 @@ mod tests {
                 input_port: "in".into(),
                 output_port: "out".into(),
                 output_properties: Properties::new().with("format", "b"),
+                options: Properties::new(),
             }],
             cost: 1.0,
         };
@@ -1039,6 +2101,7 @@ mod tests {
                 input_port: "in".into(),
                 output_port: "out".into(),
                 output_properties: Properties::new().with("format", "file"),
+                options: Properties::new(),
             }],
             cost: 1.0,
         };
@@ -1078,12 +2141,14 @@ mod tests {
                     input_port: "in".into(),
                     output_port: "out".into(),
                     output_properties: Properties::new().with("format", "raw"),
+                    options: Properties::new(),
                 },
                 crate::PlanStep {
                     converter_id: "test.raw-to-processed".into(),
                     input_port: "in".into(),
                     output_port: "out".into(),
                     output_properties: Properties::new().with("format", "processed"),
+                    options: Properties::new(),
                 },
             ],
             cost: 2.0,
@@ -1123,6 +2188,7 @@ mod tests {
                 input_port: "in".into(),
                 output_port: "out".into(),
                 output_properties: Properties::new().with("format", "file"),
+                options: Properties::new(),
             }],
             cost: 1.0,
         };
@@ -1202,6 +2268,7 @@ mod tests {
                 input_port: "in".into(),
                 output_port: "out".into(),
                 output_properties: Properties::new().with("format", "bundle"),
+                options: Properties::new(),
             }],
             cost: 1.0,
         };
@@ -1241,12 +2308,14 @@ mod tests {
                     input_port: "in".into(),
                     output_port: "out".into(),
                     output_properties: Properties::new().with("format", "item"),
+                    options: Properties::new(),
                 },
                 crate::PlanStep {
                     converter_id: "test.aggregator".into(),
                     input_port: "in".into(),
                     output_port: "out".into(),
                     output_properties: Properties::new().with("format", "bundle"),
+                    options: Properties::new(),
                 },
             ],
             cost: 2.0,
@@ -1268,6 +2337,55 @@ mod tests {
         assert_eq!(result.props.get("count").unwrap().as_i64(), Some(2));
     }
 
+    #[cfg(feature = "spill")]
+    #[test]
+    fn test_bounded_executor_aggregating_spills() {
+        let mut registry = Registry::new();
+        registry.register(IdentityConverter::new("raw", "item"));
+        registry.register(AggregatorConverter::new());
+
+        // The inputs and their intermediates (48 bytes) exceed the limit
+        let ctx = ExecutionContext::new(Arc::new(registry))
+            .with_memory_limit(20)
+            .with_spill_dir(std::env::temp_dir());
+
+        let plan = Plan {
+            steps: vec![
+                crate::PlanStep {
+                    converter_id: "test.raw-to-item".into(),
+                    input_port: "in".into(),
+                    output_port: "out".into(),
+                    output_properties: Properties::new().with("format", "item"),
+                    options: Properties::new(),
+                },
+                crate::PlanStep {
+                    converter_id: "test.aggregator".into(),
+                    input_port: "in".into(),
+                    output_port: "out".into(),
+                    output_properties: Properties::new().with("format", "bundle"),
+                    options: Properties::new(),
+                },
+            ],
+            cost: 2.0,
+        };
+
+        let inputs: Vec<_> = [b"aaaaaaaa", b"bbbbbbbb", b"cccccccc"]
+            .iter()
+            .map(|data| (data.to_vec(), Properties::new().with("format", "raw")))
+            .collect();
+
+        let result = BoundedExecutor::new()
+            .execute_aggregating(&ctx, &plan, inputs)
+            .expect("should spill rather than hold everything");
+
+        assert_eq!(result.data, b"aaaaaaaa|bbbbbbbb|cccccccc");
+        assert_eq!(result.props.get("count").unwrap().as_i64(), Some(3));
+        // The first input stays resident; the others and their
+        // intermediates go to disk
+        assert_eq!(result.stats.spilled_bytes, 32);
+        assert_eq!(result.stats.steps.len(), 2);
+    }
+
     #[test]
     fn test_execute_aggregating_empty_plan() {
         let registry = Registry::new();
@@ -0,0 +1,196 @@
+//! Fidelity tracking: notes about data a conversion could not carry over.
+//!
+//! Converters call [`report_loss`] while converting, e.g. when YAML comments
+//! have no place in JSON. The executor collects the notes of each step into
+//! `ExecutionResult::losses`; outside an executor, notes are discarded.
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+
+/// How much a loss matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Presentation that does not change meaning (formatting, key order).
+    Info,
+    /// Auxiliary content (comments, styling, metadata).
+    Minor,
+    /// Primary content or structure (materials, texture coordinates).
+    Major,
+}
+
+impl Severity {
+    /// Parse a severity name.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "minor" => Some(Severity::Minor),
+            "major" => Some(Severity::Major),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Minor => "minor",
+            Severity::Major => "major",
+        })
+    }
+}
+
+/// A field or feature dropped by a conversion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LossNote {
+    /// Converter that reported the loss (filled in by the executor).
+    #[serde(default)]
+    pub converter: String,
+    /// Field or feature lost, e.g. `comments` or `materials`.
+    pub feature: String,
+    /// How much the loss matters.
+    pub severity: Severity,
+    /// Number of occurrences lost.
+    pub count: u64,
+    /// Optional human-readable detail.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+}
+
+impl LossNote {
+    /// A single lost occurrence of `feature`.
+    pub fn new(feature: impl Into<String>, severity: Severity) -> Self {
+        Self {
+            converter: String::new(),
+            feature: feature.into(),
+            severity,
+            count: 1,
+            detail: String::new(),
+        }
+    }
+
+    /// Set the number of occurrences lost.
+    pub fn count(mut self, count: u64) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Set a human-readable detail.
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+}
+
+impl fmt::Display for LossNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.severity)?;
+        if !self.converter.is_empty() {
+            write!(f, "{}: ", self.converter)?;
+        }
+        write!(f, "{}", self.feature)?;
+        if self.count != 1 {
+            write!(f, " (x{})", self.count)?;
+        }
+        if !self.detail.is_empty() {
+            write!(f, " - {}", self.detail)?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static COLLECTOR: RefCell<Option<Vec<LossNote>>> = const { RefCell::new(None) };
+}
+
+/// Report a loss from inside a conversion.
+///
+/// Does nothing when no executor is collecting notes on this thread.
+pub fn report_loss(note: LossNote) {
+    COLLECTOR.with(|c| {
+        if let Some(notes) = c.borrow_mut().as_mut() {
+            notes.push(note);
+        }
+    });
+}
+
+/// Run `f`, returning the notes it reported, attributed to `converter`.
+///
+/// Notes that already name a converter keep it. Nested calls collect their
+/// own notes.
+pub fn collect_losses<T>(converter: &str, f: impl FnOnce() -> T) -> (T, Vec<LossNote>) {
+    /// Restores the enclosing collector, even if `f` panics.
+    struct Scope(Option<Vec<LossNote>>);
+
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            let outer = self.0.take();
+            COLLECTOR.with(|c| *c.borrow_mut() = outer);
+        }
+    }
+
+    let scope = Scope(COLLECTOR.with(|c| c.borrow_mut().replace(Vec::new())));
+    let value = f();
+    let mut notes = COLLECTOR
+        .with(|c| c.borrow_mut().take())
+        .unwrap_or_default();
+    drop(scope);
+
+    for note in &mut notes {
+        if note.converter.is_empty() {
+            note.converter = converter.to_string();
+        }
+    }
+    (value, notes)
+}
+
+/// Highest severity among the notes.
+pub fn worst_loss(notes: &[LossNote]) -> Option<Severity> {
+    notes.iter().map(|n| n.severity).max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_losses() {
+        // Without a collector, reports are dropped.
+        report_loss(LossNote::new("comments", Severity::Minor));
+
+        let (value, notes) = collect_losses("serde.yaml-to-json", || {
+            report_loss(LossNote::new("comments", Severity::Minor).count(3));
+            let ((), inner) = collect_losses("inner", || {
+                report_loss(LossNote::new("anchors", Severity::Info));
+            });
+            assert_eq!(inner[0].converter, "inner");
+            42
+        });
+        assert_eq!(value, 42);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].converter, "serde.yaml-to-json");
+        assert_eq!(
+            notes[0].to_string(),
+            "[minor] serde.yaml-to-json: comments (x3)"
+        );
+
+        // The collector is gone again.
+        let ((), notes) = collect_losses("x", || {});
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_severity_order() {
+        assert!(Severity::Info < Severity::Minor && Severity::Minor < Severity::Major);
+        assert_eq!(Severity::parse("MAJOR"), Some(Severity::Major));
+
+        let notes = [
+            LossNote::new("a", Severity::Info),
+            LossNote::new("b", Severity::Major),
+        ];
+        assert_eq!(worst_loss(&notes), Some(Severity::Major));
+        assert_eq!(worst_loss(&[]), None);
+    }
+}
@@ -0,0 +1,411 @@
+//! Format descriptors: extensions, MIME types, magic bytes and aliases.
+//!
+//! Properties name formats by ID (`format = "jpg"`), but files arrive with
+//! extensions (`.jpeg`), MIME types (`image/jpeg`) or just their bytes.
+//! Domain crates describe each format they handle with a [`FormatDecl`] when
+//! they register; detection, output naming and error suggestions all look
+//! formats up through the resulting [`Formats`] table.
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Bytes expected at a fixed offset at the start of a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Magic {
+    /// Offset of the signature from the start of the data.
+    #[serde(default)]
+    pub offset: usize,
+    /// Expected bytes.
+    pub bytes: Vec<u8>,
+}
+
+impl Magic {
+    /// Whether `data` carries this signature.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.get(self.offset..self.offset + self.bytes.len()) == Some(self.bytes.as_slice())
+    }
+
+    /// Whether the signature is too short to trust over a file extension:
+    /// fewer than four non-zero bytes also turn up in ordinary data.
+    fn is_weak(&self) -> bool {
+        self.bytes.iter().filter(|&&b| b != 0).count() < 4
+    }
+}
+
+/// Description of a data format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FormatDecl {
+    /// Format ID, as used in the `format` property.
+    pub id: String,
+    /// File extensions, without the dot. The first one is used to name
+    /// output files.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// MIME types. The first one is the canonical type.
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+    /// Magic-byte signatures; any one of them identifies the format.
+    #[serde(default)]
+    pub magic: Vec<Magic>,
+    /// Other names accepted for the format, e.g. `jpeg` for `jpg`.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Extensions of sidecar files that belong with a file of this format,
+    /// e.g. `mtl` for `obj`. See [`Bundle::gather`](crate::Bundle::gather).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecars: Vec<String>,
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: String,
+}
+
+impl FormatDecl {
+    /// Describe a format by ID.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            extensions: Vec::new(),
+            mime_types: Vec::new(),
+            magic: Vec::new(),
+            aliases: Vec::new(),
+            sidecars: Vec::new(),
+            description: String::new(),
+        }
+    }
+
+    /// Add a file extension.
+    pub fn extension(mut self, ext: impl Into<String>) -> Self {
+        self.extensions.push(ext.into());
+        self
+    }
+
+    /// Add several file extensions.
+    pub fn extensions<I, S>(mut self, exts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extensions.extend(exts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add a MIME type.
+    pub fn mime(mut self, mime: impl Into<String>) -> Self {
+        self.mime_types.push(mime.into());
+        self
+    }
+
+    /// Add a signature at the start of the data.
+    pub fn magic(self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.magic_at(0, bytes)
+    }
+
+    /// Add a signature at a fixed offset.
+    pub fn magic_at(mut self, offset: usize, bytes: impl Into<Vec<u8>>) -> Self {
+        self.magic.push(Magic {
+            offset,
+            bytes: bytes.into(),
+        });
+        self
+    }
+
+    /// Add an alias.
+    pub fn alias(mut self, alias: impl Into<String>) -> Self {
+        self.aliases.push(alias.into());
+        self
+    }
+
+    /// Add a sidecar extension.
+    pub fn sidecar(mut self, ext: impl Into<String>) -> Self {
+        self.sidecars.push(ext.into());
+        self
+    }
+
+    /// Set the description.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Extension used when naming output files (the ID if none is declared).
+    pub fn primary_extension(&self) -> &str {
+        self.extensions.first().unwrap_or(&self.id)
+    }
+
+    /// Top-level media type of the canonical MIME type, e.g. `image`.
+    pub fn media_type(&self) -> Option<&str> {
+        self.mime_types.first()?.split('/').next()
+    }
+
+    /// Merge another description of the same format into this one.
+    fn merge(&mut self, other: FormatDecl) {
+        fn extend<T: PartialEq>(into: &mut Vec<T>, from: Vec<T>) {
+            for item in from {
+                if !into.contains(&item) {
+                    into.push(item);
+                }
+            }
+        }
+        extend(&mut self.extensions, other.extensions);
+        extend(&mut self.mime_types, other.mime_types);
+        extend(&mut self.magic, other.magic);
+        extend(&mut self.aliases, other.aliases);
+        extend(&mut self.sidecars, other.sidecars);
+        if self.description.is_empty() {
+            self.description = other.description;
+        }
+    }
+}
+
+/// Table of known formats.
+#[derive(Debug, Clone, Default)]
+pub struct Formats {
+    formats: IndexMap<String, FormatDecl>,
+    /// Lowercase extension -> format ID.
+    by_extension: HashMap<String, String>,
+    /// Lowercase MIME type -> format ID.
+    by_mime: HashMap<String, String>,
+    /// Lowercase alias -> format ID.
+    by_alias: HashMap<String, String>,
+}
+
+impl Formats {
+    /// An empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The formats core reads itself (workflow files).
+    pub fn well_known() -> Self {
+        let mut formats = Self::new();
+        for decl in [
+            FormatDecl::new("json")
+                .extension("json")
+                .mime("application/json")
+                .description("JSON"),
+            FormatDecl::new("yaml")
+                .extensions(["yaml", "yml"])
+                .mime("application/yaml")
+                .mime("application/x-yaml")
+                .mime("text/yaml")
+                .alias("yml")
+                .description("YAML"),
+            FormatDecl::new("toml")
+                .extension("toml")
+                .mime("application/toml")
+                .mime("text/x-toml")
+                .description("TOML"),
+        ] {
+            formats.register(decl);
+        }
+        formats
+    }
+
+    /// Register a format.
+    ///
+    /// Registering an ID twice merges the descriptions, so two crates can
+    /// both handle (say) `gif`. An extension, MIME type or alias claimed by
+    /// an earlier format keeps resolving to it.
+    pub fn register(&mut self, decl: FormatDecl) {
+        let id = decl.id.clone();
+        for ext in &decl.extensions {
+            self.by_extension
+                .entry(ext.to_lowercase())
+                .or_insert_with(|| id.clone());
+        }
+        for mime in &decl.mime_types {
+            self.by_mime
+                .entry(mime.to_lowercase())
+                .or_insert_with(|| id.clone());
+        }
+        for alias in &decl.aliases {
+            self.by_alias
+                .entry(alias.to_lowercase())
+                .or_insert_with(|| id.clone());
+        }
+        match self.formats.get_mut(&id) {
+            Some(existing) => existing.merge(decl),
+            None => {
+                self.formats.insert(id, decl);
+            }
+        }
+    }
+
+    /// Look up a format by ID.
+    pub fn get(&self, id: &str) -> Option<&FormatDecl> {
+        self.formats.get(id)
+    }
+
+    /// Iterate over all formats, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &FormatDecl> {
+        self.formats.values()
+    }
+
+    /// Number of registered formats.
+    pub fn len(&self) -> usize {
+        self.formats.len()
+    }
+
+    /// Whether no format is registered.
+    pub fn is_empty(&self) -> bool {
+        self.formats.is_empty()
+    }
+
+    /// Resolve a user-supplied name (ID, alias or extension) to a format ID.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        if let Some(decl) = self.formats.get(name) {
+            return Some(&decl.id);
+        }
+        let name = name.to_lowercase();
+        self.formats
+            .get(&name)
+            .map(|decl| &decl.id)
+            .or_else(|| self.by_alias.get(&name))
+            .or_else(|| self.by_extension.get(&name))
+            .map(String::as_str)
+    }
+
+    /// Format with the given file extension.
+    pub fn from_extension(&self, ext: &str) -> Option<&str> {
+        self.by_extension
+            .get(&ext.to_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Format of a path, from its extension.
+    pub fn from_path(&self, path: &str) -> Option<&str> {
+        let (_, ext) = path.rsplit_once('.')?;
+        self.from_extension(ext)
+    }
+
+    /// Format with the given MIME type. Parameters such as `; charset=...`
+    /// are ignored.
+    pub fn from_mime(&self, mime: &str) -> Option<&str> {
+        let mime = mime.split(';').next().unwrap_or_default().trim();
+        self.by_mime.get(&mime.to_lowercase()).map(String::as_str)
+    }
+
+    /// Format whose magic bytes `data` starts with.
+    ///
+    /// When several signatures match, the one reaching furthest into the
+    /// data wins, so `RIFF....WAVE` beats a bare `RIFF`.
+    pub fn from_magic(&self, data: &[u8]) -> Option<&str> {
+        self.best_magic(data).map(|(id, _)| id)
+    }
+
+    /// The most specific signature matching `data`, with its format ID.
+    fn best_magic(&self, data: &[u8]) -> Option<(&str, &Magic)> {
+        self.formats
+            .values()
+            .flat_map(|decl| decl.magic.iter().map(move |magic| (decl, magic)))
+            .filter(|(_, magic)| magic.matches(data))
+            .max_by_key(|(_, magic)| magic.offset + magic.bytes.len())
+            .map(|(decl, magic)| (decl.id.as_str(), magic))
+    }
+
+    /// Detect the format of a file from its magic bytes and extension.
+    ///
+    /// Magic bytes win over a contradicting extension, unless the signature
+    /// has fewer than four non-zero bytes (`ply`, ICO's `00 00 01 00`):
+    /// those also start ordinary files, so a known extension wins.
+    pub fn detect(&self, path: Option<&str>, data: &[u8]) -> Option<&str> {
+        let by_extension = path.and_then(|p| self.from_path(p));
+        match self.best_magic(data) {
+            Some((id, magic)) if by_extension.is_none() || !magic.is_weak() => Some(id),
+            _ => by_extension,
+        }
+    }
+
+    /// Extension for output files of the given format.
+    pub fn extension_for(&self, id: &str) -> Option<&str> {
+        self.resolve(id)
+            .and_then(|id| self.formats.get(id))
+            .map(FormatDecl::primary_extension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formats() -> Formats {
+        let mut formats = Formats::well_known();
+        formats.register(
+            FormatDecl::new("jpg")
+                .extensions(["jpg", "jpeg"])
+                .mime("image/jpeg")
+                .magic(b"\xFF\xD8\xFF")
+                .alias("jpeg"),
+        );
+        formats.register(FormatDecl::new("riff").magic(b"RIFF"));
+        formats.register(
+            FormatDecl::new("wav")
+                .extensions(["wav", "wave"])
+                .mime("audio/wav")
+                .magic_at(8, b"WAVE"),
+        );
+        formats
+    }
+
+    #[test]
+    fn test_lookups() {
+        let formats = formats();
+        assert_eq!(formats.from_path("photo.JPEG"), Some("jpg"));
+        assert_eq!(formats.from_path("config.yml"), Some("yaml"));
+        assert_eq!(formats.from_path("README"), None);
+        assert_eq!(formats.from_mime("image/jpeg"), Some("jpg"));
+        assert_eq!(
+            formats.from_mime("application/json; charset=utf-8"),
+            Some("json")
+        );
+        assert_eq!(formats.resolve("JPEG"), Some("jpg"));
+        assert_eq!(formats.resolve("yml"), Some("yaml"));
+        assert_eq!(formats.resolve("wave"), Some("wav"));
+        assert_eq!(formats.resolve("nope"), None);
+        assert_eq!(formats.extension_for("yaml"), Some("yaml"));
+        assert_eq!(formats.extension_for("riff"), Some("riff"));
+    }
+
+    #[test]
+    fn test_magic() {
+        let formats = formats();
+        assert_eq!(formats.from_magic(b"\xFF\xD8\xFF\xE0rest"), Some("jpg"));
+        assert_eq!(formats.from_magic(b"RIFF\0\0\0\0WAVEfmt "), Some("wav"));
+        assert_eq!(formats.from_magic(b"RIFF\0\0\0\0AVI "), Some("riff"));
+        assert_eq!(formats.from_magic(b"RI"), None);
+        // Magic bytes win over a misleading extension...
+        assert_eq!(
+            formats.detect(Some("photo.jpg"), b"RIFF\0\0\0\0WAVEfmt "),
+            Some("wav")
+        );
+        assert_eq!(formats.detect(Some("data.json"), b"{}"), Some("json"));
+        // ...unless the signature is too short to be sure of
+        assert_eq!(
+            formats.detect(Some("sound.wav"), b"\xFF\xD8\xFF\xE0"),
+            Some("wav")
+        );
+        assert_eq!(formats.detect(None, b"\xFF\xD8\xFF\xE0"), Some("jpg"));
+        assert_eq!(
+            formats.detect(Some("notes.txt"), b"\xFF\xD8\xFF\xE0"),
+            Some("jpg")
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut formats = formats();
+        formats.register(
+            FormatDecl::new("jpg")
+                .extension("jpe")
+                .extension("jpg")
+                .description("JPEG image"),
+        );
+        assert_eq!(formats.len(), 6);
+        let jpg = formats.get("jpg").unwrap();
+        assert_eq!(jpg.extensions, ["jpg", "jpeg", "jpe"]);
+        assert_eq!(jpg.description, "JPEG image");
+        assert_eq!(jpg.media_type(), Some("image"));
+        assert_eq!(formats.from_extension("jpe"), Some("jpg"));
+    }
+}
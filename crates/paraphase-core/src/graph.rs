@@ -0,0 +1,306 @@
+//! Converter graph: formats as nodes, converters as edges.
+//!
+//! Built from the registry's declarations for inspection and documentation.
+//! Exported as Graphviz DOT, Mermaid, or JSON (via serde).
+
+use crate::pattern::{Predicate, PropertyPattern};
+use crate::planner::{OptimizeTarget, Plan, static_cost};
+use crate::registry::Registry;
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Node for converters that accept any format (or constrain other
+/// properties only, like image transforms).
+pub const ANY_FORMAT: &str = "*";
+
+/// A converter edge between two formats.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    /// Converter ID.
+    pub converter: String,
+    /// Input format.
+    pub from: String,
+    /// Output format.
+    pub to: String,
+    /// Declared cost for the graph's optimization target.
+    pub cost: f64,
+    /// Whether the edge is part of a highlighted plan.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub highlighted: bool,
+}
+
+/// The format/converter graph of a registry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConverterGraph {
+    /// Formats, in order of first appearance.
+    pub nodes: Vec<String>,
+    /// Converter edges.
+    pub edges: Vec<GraphEdge>,
+}
+
+impl ConverterGraph {
+    /// Build the graph of all declared converters.
+    ///
+    /// A converter contributes one edge per (input format, output format)
+    /// pair its ports admit. An output port without a `format` constraint
+    /// keeps the input format.
+    pub fn from_registry(registry: &Registry, target: OptimizeTarget) -> Self {
+        let mut edges = Vec::new();
+        for decl in registry.declarations() {
+            let cost = static_cost(decl, target);
+            let inputs: IndexSet<String> = decl
+                .inputs
+                .values()
+                .flat_map(|port| formats(&port.pattern))
+                .collect();
+            let outputs: IndexSet<Option<String>> = decl
+                .outputs
+                .values()
+                .flat_map(|port| {
+                    let constrained = port.pattern.predicates.contains_key("format");
+                    formats(&port.pattern)
+                        .into_iter()
+                        .map(move |f| constrained.then_some(f))
+                })
+                .collect();
+
+            for from in &inputs {
+                for to in &outputs {
+                    edges.push(GraphEdge {
+                        converter: decl.id.clone(),
+                        from: from.clone(),
+                        to: to.clone().unwrap_or_else(|| from.clone()),
+                        cost,
+                        highlighted: false,
+                    });
+                }
+            }
+        }
+
+        let mut graph = Self {
+            nodes: Vec::new(),
+            edges,
+        };
+        graph.collect_nodes();
+        graph
+    }
+
+    /// Keep only converters whose ID starts with one of `prefixes`.
+    ///
+    /// An empty list keeps everything.
+    pub fn retain_prefixes<S: AsRef<str>>(&mut self, prefixes: &[S]) {
+        if prefixes.is_empty() {
+            return;
+        }
+        self.edges
+            .retain(|e| prefixes.iter().any(|p| e.converter.starts_with(p.as_ref())));
+        self.collect_nodes();
+    }
+
+    /// Mark the edges a plan takes, starting from `source_format`.
+    pub fn highlight(&mut self, plan: &Plan, source_format: &str) {
+        let mut current = source_format.to_string();
+        for step in &plan.steps {
+            let next = step
+                .output_properties
+                .get("format")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&current)
+                .to_string();
+            for edge in &mut self.edges {
+                if edge.converter == step.converter_id
+                    && (edge.from == current || edge.from == ANY_FORMAT)
+                    && (edge.to == next || edge.to == ANY_FORMAT)
+                {
+                    edge.highlighted = true;
+                }
+            }
+            current = next;
+        }
+    }
+
+    /// Render as a Graphviz DOT digraph.
+    pub fn to_dot(&self, show_costs: bool) -> String {
+        let mut out =
+            String::from("digraph converters {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let _ = writeln!(out, "    \"{}\";", escape(node));
+        }
+        for edge in &self.edges {
+            let mut attrs = format!("label=\"{}\"", escape(&self.label(edge, show_costs)));
+            if edge.highlighted {
+                attrs.push_str(", color=red, penwidth=2");
+            }
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [{}];",
+                escape(&edge.from),
+                escape(&edge.to),
+                attrs
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as a Mermaid flowchart.
+    pub fn to_mermaid(&self, show_costs: bool) -> String {
+        let mut out = String::from("flowchart LR\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let _ = writeln!(out, "    n{}[\"{}\"]", i, mermaid_text(node));
+        }
+        let mut highlighted = Vec::new();
+        for (i, edge) in self.edges.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    n{} -->|\"{}\"| n{}",
+                self.node_index(&edge.from),
+                mermaid_text(&self.label(edge, show_costs)),
+                self.node_index(&edge.to)
+            );
+            if edge.highlighted {
+                highlighted.push(i.to_string());
+            }
+        }
+        if !highlighted.is_empty() {
+            let _ = writeln!(
+                out,
+                "    linkStyle {} stroke:#d00,stroke-width:3px",
+                highlighted.join(",")
+            );
+        }
+        out
+    }
+
+    fn label(&self, edge: &GraphEdge, show_costs: bool) -> String {
+        if show_costs {
+            format!("{} ({})", edge.converter, edge.cost)
+        } else {
+            edge.converter.clone()
+        }
+    }
+
+    fn node_index(&self, format: &str) -> usize {
+        self.nodes.iter().position(|n| n == format).unwrap_or(0)
+    }
+
+    fn collect_nodes(&mut self) {
+        let nodes: IndexSet<&String> = self.edges.iter().flat_map(|e| [&e.from, &e.to]).collect();
+        self.nodes = nodes.into_iter().cloned().collect();
+    }
+}
+
+/// Formats a port pattern admits, or [`ANY_FORMAT`].
+fn formats(pattern: &PropertyPattern) -> Vec<String> {
+    let values = match pattern.predicates.get("format") {
+        Some(Predicate::Eq(v)) => vec![v],
+        Some(Predicate::OneOf(vs)) => vs.iter().collect(),
+        _ => vec![],
+    };
+    let names: Vec<String> = values
+        .into_iter()
+        .filter_map(|v| v.as_str().map(String::from))
+        .collect();
+    if names.is_empty() {
+        vec![ANY_FORMAT.to_string()]
+    } else {
+        names
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_text(s: &str) -> String {
+    s.replace('"', "#quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cardinality, ConverterDecl, Planner, Properties, PropertiesExt};
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.register_decl(ConverterDecl::simple(
+            "serde.json-to-yaml",
+            PropertyPattern::new().eq("format", "json"),
+            PropertyPattern::new().eq("format", "yaml"),
+        ));
+        registry.register_decl(
+            ConverterDecl::simple(
+                "serde.yaml-to-toml",
+                PropertyPattern::new().eq("format", "yaml"),
+                PropertyPattern::new().eq("format", "toml"),
+            )
+            .cost("cost", 2.0),
+        );
+        registry.register_decl(ConverterDecl::simple(
+            "3d.mesh-to-stl",
+            PropertyPattern::new().with(
+                "format",
+                Predicate::OneOf(vec!["gltf".into(), "glb".into()]),
+            ),
+            PropertyPattern::new().eq("format", "stl"),
+        ));
+        registry.register_decl(ConverterDecl::simple(
+            "image.resize",
+            PropertyPattern::new().with("width", Predicate::Any),
+            PropertyPattern::new().with("width", Predicate::Any),
+        ));
+        registry
+    }
+
+    #[test]
+    fn test_graph_edges() {
+        let graph = ConverterGraph::from_registry(&registry(), OptimizeTarget::Speed);
+        assert_eq!(
+            graph.nodes,
+            vec!["json", "yaml", "toml", "gltf", "stl", "glb", "*"]
+        );
+        assert_eq!(graph.edges.len(), 5);
+        assert_eq!(graph.edges[1].cost, 2.0);
+        // Transforms keep their input format
+        assert_eq!(graph.edges[4].from, "*");
+        assert_eq!(graph.edges[4].to, "*");
+
+        let mut serde_only = graph.clone();
+        serde_only.retain_prefixes(&["serde."]);
+        assert_eq!(serde_only.edges.len(), 2);
+        assert_eq!(serde_only.nodes, vec!["json", "yaml", "toml"]);
+    }
+
+    #[test]
+    fn test_highlight_and_render() {
+        let registry = registry();
+        let mut graph = ConverterGraph::from_registry(&registry, OptimizeTarget::Speed);
+        graph.retain_prefixes(&["serde."]);
+
+        let source = Properties::new().with("format", "json");
+        let plan = Planner::new(&registry)
+            .plan(
+                &source,
+                &PropertyPattern::new().eq("format", "toml"),
+                Cardinality::One,
+                Cardinality::One,
+            )
+            .unwrap();
+        graph.highlight(&plan, "json");
+        assert!(graph.edges.iter().all(|e| e.highlighted));
+
+        let dot = graph.to_dot(true);
+        assert!(dot.contains(
+            "\"json\" -> \"yaml\" [label=\"serde.json-to-yaml (1)\", color=red, penwidth=2];"
+        ));
+
+        let mermaid = graph.to_mermaid(false);
+        assert!(mermaid.starts_with("flowchart LR\n    n0[\"json\"]\n"));
+        assert!(mermaid.contains("    n0 -->|\"serde.json-to-yaml\"| n1\n"));
+        assert!(mermaid.ends_with("    linkStyle 0,1 stroke:#d00,stroke-width:3px\n"));
+
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(json["edges"][0]["highlighted"], true);
+    }
+}
@@ -0,0 +1,97 @@
+//! Lookup index over converter declarations.
+//!
+//! Matching every declaration against every search node is linear in the
+//! number of converters. Most input ports pin a property to a fixed string
+//! (`format = "png"`, `format in [json, yaml]`), so declarations are indexed
+//! by one such equality per port. Lookups only evaluate the declarations
+//! indexed under the properties' values, plus the ports that pin nothing.
+
+use crate::converter::ConverterDecl;
+use crate::pattern::{Predicate, PropertyPattern};
+use crate::properties::{Properties, Value};
+use std::collections::HashMap;
+
+/// Property preferred as index key when a port pins several.
+const PREFERRED_KEY: &str = "format";
+
+/// Declaration positions keyed on equality predicates of their input ports.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DeclIndex {
+    /// Property name -> value -> declaration positions.
+    by_value: HashMap<String, HashMap<String, Vec<usize>>>,
+    /// Declarations with an input port that cannot be indexed.
+    unindexed: Vec<usize>,
+}
+
+impl DeclIndex {
+    /// Index the declaration at `position`.
+    pub(crate) fn insert(&mut self, position: usize, decl: &ConverterDecl) {
+        for port in decl.inputs.values() {
+            match index_key(&port.pattern) {
+                Some((key, values)) => {
+                    let by_value = self.by_value.entry(key.to_string()).or_default();
+                    for value in values {
+                        let positions = by_value.entry(value.to_string()).or_default();
+                        if positions.last() != Some(&position) {
+                            positions.push(position);
+                        }
+                    }
+                }
+                None => {
+                    if self.unindexed.last() != Some(&position) {
+                        self.unindexed.push(position);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rebuild the index from scratch.
+    pub(crate) fn rebuild<'a>(decls: impl Iterator<Item = &'a ConverterDecl>) -> Self {
+        let mut index = Self::default();
+        for (position, decl) in decls.enumerate() {
+            index.insert(position, decl);
+        }
+        index
+    }
+
+    /// Positions of the declarations that may accept `props`, in order.
+    ///
+    /// A superset of the matching declarations: callers still check the
+    /// patterns.
+    pub(crate) fn candidates(&self, props: &Properties) -> Vec<usize> {
+        let mut positions = self.unindexed.clone();
+        for (key, by_value) in &self.by_value {
+            if let Some(found) = props
+                .get(key)
+                .and_then(Value::as_str)
+                .and_then(|v| by_value.get(v))
+            {
+                positions.extend_from_slice(found);
+            }
+        }
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+}
+
+/// Property and string values a pattern requires, if any.
+fn index_key(pattern: &PropertyPattern) -> Option<(&str, Vec<&str>)> {
+    if let Some(values) = pattern.predicates.get(PREFERRED_KEY).and_then(required) {
+        return Some((PREFERRED_KEY, values));
+    }
+    pattern
+        .predicates
+        .iter()
+        .find_map(|(key, predicate)| Some((key.as_str(), required(predicate)?)))
+}
+
+/// String values an equality predicate admits.
+fn required(predicate: &Predicate) -> Option<Vec<&str>> {
+    match predicate {
+        Predicate::Eq(v) => Some(vec![v.as_str()?]),
+        Predicate::OneOf(vs) => vs.iter().map(Value::as_str).collect(),
+        _ => None,
+    }
+}
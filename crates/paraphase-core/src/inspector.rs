@@ -0,0 +1,65 @@
+//! Content inspectors: derive properties from raw bytes.
+//!
+//! Format detection only yields `format`. Inspectors look inside the data to
+//! fill in properties such as `width`/`height` for images or `sample_rate`
+//! for audio, so the planner can route on them (and resource models can use
+//! them) before any converter has run.
+
+use crate::converter::ConvertError;
+use crate::pattern::PropertyPattern;
+use crate::properties::Properties;
+use serde::{Deserialize, Serialize};
+
+/// Declaration of an inspector's interface.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InspectorDecl {
+    /// Unique identifier for this inspector.
+    pub id: String,
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: String,
+    /// Pattern the input properties must match for the inspector to run.
+    pub pattern: PropertyPattern,
+    /// Property keys this inspector may produce.
+    #[serde(default)]
+    pub provides: Vec<String>,
+}
+
+impl InspectorDecl {
+    /// Create a new inspector declaration.
+    pub fn new(id: impl Into<String>, pattern: PropertyPattern) -> Self {
+        Self {
+            id: id.into(),
+            description: String::new(),
+            pattern,
+            provides: Vec::new(),
+        }
+    }
+
+    /// Set the description.
+    pub fn description(mut self, desc: impl Into<String>) -> Self {
+        self.description = desc.into();
+        self
+    }
+
+    /// Declare a property key this inspector produces.
+    pub fn provides(mut self, key: impl Into<String>) -> Self {
+        self.provides.push(key.into());
+        self
+    }
+}
+
+/// Trait for implementing inspectors.
+///
+/// Inspectors should read only as much of the input as they need (headers,
+/// tables) rather than decoding it fully.
+pub trait Inspector: Send + Sync {
+    /// Get the declaration for this inspector.
+    fn decl(&self) -> &InspectorDecl;
+
+    /// Inspect the input and return the properties found.
+    ///
+    /// Only newly discovered properties need to be returned; they are merged
+    /// into the input properties by the caller.
+    fn inspect(&self, input: &[u8], props: &Properties) -> Result<Properties, ConvertError>;
+}
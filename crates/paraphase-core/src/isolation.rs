@@ -0,0 +1,895 @@
+//! Run converters in reusable worker subprocesses.
+//!
+//! Native-backed converters (FFmpeg, codec libraries) can segfault or abort
+//! on hostile input, taking the whole process down with them. A
+//! [`WorkerPool`] runs selected converters in child processes instead: a
+//! crash fails that one conversion with a [`ConvertError`], and the worker
+//! is replaced. Healthy workers are kept and reused across jobs. With a
+//! [`WorkerPool::timeout`], a worker that hangs is killed and replaced the
+//! same way. A worker that hangs while starting is killed after
+//! [`WorkerPool::startup_timeout`].
+//!
+//! The worker is normally the host executable re-run in a worker mode that
+//! builds the same registry and calls [`serve_worker`] on stdin and stdout.
+//!
+//! # Protocol
+//!
+//! On start-up the worker writes a handshake line; anything the process
+//! prints before it is skipped. Requests and responses are frames: a
+//! little-endian `u64` header length, a JSON header, then the payloads
+//! listed in the header, concatenated. Workers log to stderr only.
+
+use crate::converter::{
+    ConvertError, ConvertOutput, Converter, ConverterDecl, NamedInput, is_false,
+};
+use crate::determinism::{is_deterministic, with_determinism};
+use crate::fidelity::{LossNote, collect_losses, report_loss};
+use crate::limits::{Limits, current_limits, with_limits};
+use crate::properties::Properties;
+use crate::registry::Registry;
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Environment variable set for worker processes.
+pub const WORKER_ENV: &str = "PARAPHASE_WORKER";
+
+/// Written by a worker once it is ready for requests.
+const HANDSHAKE: &[u8] = b"PARAPHASE-WORKER/1\n";
+
+/// Output skipped while waiting for the handshake before giving up.
+const MAX_PREAMBLE: usize = 64 * 1024;
+
+/// Default time a worker has to send its handshake.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound on a frame header, to reject corrupt streams early.
+const MAX_HEADER: u64 = 64 * 1024 * 1024;
+
+/// Which converters run in worker processes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum IsolationPolicy {
+    /// Every converter.
+    #[default]
+    All,
+    /// Converters whose ID matches one of these: an exact ID, or a prefix
+    /// followed by `*` (e.g. `video.*`).
+    Only(Vec<String>),
+}
+
+impl IsolationPolicy {
+    /// Check if a converter should run in a worker.
+    pub fn applies_to(&self, converter_id: &str) -> bool {
+        match self {
+            IsolationPolicy::All => true,
+            IsolationPolicy::Only(ids) => ids.iter().any(|id| match id.strip_suffix('*') {
+                Some(prefix) => converter_id.starts_with(prefix),
+                None => converter_id == id,
+            }),
+        }
+    }
+}
+
+/// How the converter is invoked.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    Single,
+    Batch,
+    Multi,
+}
+
+/// Properties and payload length of one input or output.
+#[derive(Debug, Serialize, Deserialize)]
+struct Item {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    port: Option<String>,
+    props: Properties,
+    len: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    converter: String,
+    mode: Mode,
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    options: Properties,
+    #[serde(default, skip_serializing_if = "is_false")]
+    deterministic: bool,
+    #[serde(default, skip_serializing_if = "Limits::is_unlimited")]
+    limits: Limits,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Response {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<WireError>,
+    #[serde(default)]
+    multiple: bool,
+    #[serde(default)]
+    items: Vec<Item>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    losses: Vec<LossNote>,
+}
+
+/// A `ConvertError` in transit.
+#[derive(Debug, Serialize, Deserialize)]
+struct WireError {
+    kind: String,
+    message: String,
+}
+
+impl From<&ConvertError> for WireError {
+    fn from(e: &ConvertError) -> Self {
+        let (kind, message) = match e {
+            ConvertError::Failed(m) => ("failed", m.clone()),
+            ConvertError::BatchNotSupported => ("batch_not_supported", String::new()),
+            ConvertError::MultiInputNotSupported => ("multi_input_not_supported", String::new()),
+            ConvertError::MissingInput(m) => ("missing_input", m.clone()),
+            ConvertError::InvalidInput(m) => ("invalid_input", m.clone()),
+            ConvertError::MissingProperty(m) => ("missing_property", m.clone()),
+            ConvertError::LimitExceeded(m) => ("limit_exceeded", m.clone()),
+            other => ("failed", other.to_string()),
+        };
+        Self {
+            kind: kind.into(),
+            message,
+        }
+    }
+}
+
+impl From<WireError> for ConvertError {
+    fn from(e: WireError) -> Self {
+        match e.kind.as_str() {
+            "batch_not_supported" => ConvertError::BatchNotSupported,
+            "multi_input_not_supported" => ConvertError::MultiInputNotSupported,
+            "missing_input" => ConvertError::MissingInput(e.message),
+            "invalid_input" => ConvertError::InvalidInput(e.message),
+            "missing_property" => ConvertError::MissingProperty(e.message),
+            "limit_exceeded" => ConvertError::LimitExceeded(e.message),
+            _ => ConvertError::Failed(e.message),
+        }
+    }
+}
+
+fn write_frame(w: &mut impl Write, header: &impl Serialize, payloads: &[&[u8]]) -> io::Result<()> {
+    let header = serde_json::to_vec(header)?;
+    w.write_all(&(header.len() as u64).to_le_bytes())?;
+    w.write_all(&header)?;
+    for payload in payloads {
+        w.write_all(payload)?;
+    }
+    w.flush()
+}
+
+/// Read a frame header; `None` on a clean end of stream.
+fn read_header<T: DeserializeOwned>(r: &mut impl Read) -> io::Result<Option<T>> {
+    let mut len = [0u8; 8];
+    match r.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u64::from_le_bytes(len);
+    if len > MAX_HEADER {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame header of {} bytes", len),
+        ));
+    }
+    let mut header = vec![0u8; len as usize];
+    r.read_exact(&mut header)?;
+    Ok(Some(serde_json::from_slice(&header)?))
+}
+
+fn read_payloads(r: &mut impl Read, items: &[Item]) -> io::Result<Vec<Vec<u8>>> {
+    items
+        .iter()
+        .map(|item| {
+            let mut buf = Vec::new();
+            r.by_ref().take(item.len).read_to_end(&mut buf)?;
+            if buf.len() as u64 != item.len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            Ok(buf)
+        })
+        .collect()
+}
+
+/// Serve conversion requests from `input` until it is closed.
+///
+/// This is the worker side of the protocol. Converter errors and panics are
+/// reported back to the host; only I/O failures end the loop.
+pub fn serve_worker(registry: &Registry, input: impl Read, output: impl Write) -> io::Result<()> {
+    let mut input = BufReader::new(input);
+    let mut output = BufWriter::new(output);
+    output.write_all(HANDSHAKE)?;
+    output.flush()?;
+
+    while let Some(request) = read_header::<Request>(&mut input)? {
+        let payloads = read_payloads(&mut input, &request.items)?;
+        let (result, losses) = collect_losses(&request.converter, || {
+            catch_unwind(AssertUnwindSafe(|| {
+                with_determinism(request.deterministic, || {
+                    with_limits(request.limits, || handle(registry, &request, &payloads))
+                })
+            }))
+            .unwrap_or_else(|_| {
+                Err(ConvertError::Failed(format!(
+                    "converter {} panicked",
+                    request.converter
+                )))
+            })
+        });
+
+        let (response, data) = match result {
+            Ok(ConvertOutput::Single(data, props)) => (
+                Response {
+                    items: vec![item(None, props, &data)],
+                    ..Default::default()
+                },
+                vec![data],
+            ),
+            Ok(ConvertOutput::Multiple(outputs)) => {
+                let (items, data) = outputs
+                    .into_iter()
+                    .map(|(data, props)| (item(None, props, &data), data))
+                    .unzip();
+                (
+                    Response {
+                        multiple: true,
+                        items,
+                        ..Default::default()
+                    },
+                    data,
+                )
+            }
+            Err(e) => (
+                Response {
+                    error: Some(WireError::from(&e)),
+                    ..Default::default()
+                },
+                Vec::new(),
+            ),
+        };
+        let response = Response { losses, ..response };
+        let payloads: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+        write_frame(&mut output, &response, &payloads)?;
+    }
+    Ok(())
+}
+
+fn item(port: Option<String>, props: Properties, data: &[u8]) -> Item {
+    Item {
+        port,
+        props,
+        len: data.len() as u64,
+    }
+}
+
+fn handle(
+    registry: &Registry,
+    request: &Request,
+    payloads: &[Vec<u8>],
+) -> Result<ConvertOutput, ConvertError> {
+    let converter = registry
+        .get(&request.converter)
+        .ok_or_else(|| ConvertError::Failed(format!("unknown converter: {}", request.converter)))?;
+    let inputs = request.items.iter().zip(payloads);
+
+    match request.mode {
+        Mode::Single => {
+            let (item, data) = inputs
+                .into_iter()
+                .next()
+                .ok_or_else(|| ConvertError::MissingInput("in".into()))?;
+            converter.convert_with_options(data, &item.props, &request.options)
+        }
+        Mode::Batch => {
+            let batch: Vec<(&[u8], &Properties)> = inputs
+                .map(|(item, data)| (data.as_slice(), &item.props))
+                .collect();
+            converter.convert_batch_with_options(&batch, &request.options)
+        }
+        Mode::Multi => {
+            let named: IndexMap<String, NamedInput<'_>> = inputs
+                .map(|(item, data)| {
+                    (
+                        item.port.clone().unwrap_or_default(),
+                        NamedInput {
+                            data,
+                            props: &item.props,
+                        },
+                    )
+                })
+                .collect();
+            converter.convert_multi_with_options(&named, &request.options)
+        }
+    }
+}
+
+/// A running worker process.
+struct Worker {
+    child: Child,
+    stdin: BufWriter<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Worker {
+    fn spawn(program: &PathBuf, args: &[OsString], startup: Duration) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .env(WORKER_ENV, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let stdin = BufWriter::new(child.stdin.take().expect("piped stdin"));
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+        let mut worker = Self {
+            child,
+            stdin,
+            stdout,
+        };
+        let Self { child, stdout, .. } = &mut worker;
+        watched(child, startup, || Self::await_handshake(stdout))?;
+        Ok(worker)
+    }
+
+    /// Skip start-up noise until the handshake arrives.
+    fn await_handshake(stdout: &mut BufReader<ChildStdout>) -> io::Result<()> {
+        let mut window = Vec::with_capacity(HANDSHAKE.len());
+        let mut byte = [0u8; 1];
+        for _ in 0..MAX_PREAMBLE {
+            stdout.read_exact(&mut byte)?;
+            window.push(byte[0]);
+            if window.len() > HANDSHAKE.len() {
+                window.remove(0);
+            }
+            if window == HANDSHAKE {
+                return Ok(());
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "worker did not send a handshake",
+        ))
+    }
+
+    /// Send a request. The outer error means the worker is unusable.
+    ///
+    /// With a `timeout`, a worker that has not answered in time is killed
+    /// and the call fails with [`io::ErrorKind::TimedOut`].
+    fn call(
+        &mut self,
+        request: &Request,
+        payloads: &[&[u8]],
+        timeout: Option<Duration>,
+    ) -> io::Result<Result<ConvertOutput, ConvertError>> {
+        let Some(timeout) = timeout else {
+            return Self::exchange(&mut self.stdin, &mut self.stdout, request, payloads);
+        };
+
+        let Self {
+            child,
+            stdin,
+            stdout,
+        } = self;
+        watched(child, timeout, || {
+            Self::exchange(stdin, stdout, request, payloads)
+        })
+    }
+
+    fn exchange(
+        stdin: &mut BufWriter<ChildStdin>,
+        stdout: &mut BufReader<ChildStdout>,
+        request: &Request,
+        payloads: &[&[u8]],
+    ) -> io::Result<Result<ConvertOutput, ConvertError>> {
+        write_frame(stdin, request, payloads)?;
+        let response: Response =
+            read_header(stdout)?.ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        // Hand losses on to the host's collector
+        for note in response.losses {
+            report_loss(note);
+        }
+        if let Some(error) = response.error {
+            return Ok(Err(error.into()));
+        }
+
+        let data = read_payloads(stdout, &response.items)?;
+        let mut outputs: Vec<(Vec<u8>, Properties)> = data
+            .into_iter()
+            .zip(response.items)
+            .map(|(data, item)| (data, item.props))
+            .collect();
+        Ok(Ok(if response.multiple {
+            ConvertOutput::Multiple(outputs)
+        } else {
+            let (data, props) = outputs
+                .pop()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty response"))?;
+            ConvertOutput::Single(data, props)
+        }))
+    }
+
+    /// Reap a failed worker and describe how it ended.
+    fn reap(mut self) -> String {
+        let _ = self.child.kill();
+        match self.child.wait() {
+            Ok(status) => status.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+}
+
+/// Run `f`, which talks to `child`, killing the child if it takes longer
+/// than `timeout`. Killing it closes the pipes, which unblocks `f`; the
+/// result is then an [`io::ErrorKind::TimedOut`] error.
+fn watched<T>(
+    child: &mut Child,
+    timeout: Duration,
+    f: impl FnOnce() -> io::Result<T>,
+) -> io::Result<T> {
+    let (done, finished) = mpsc::channel::<()>();
+    std::thread::scope(|scope| {
+        let watchdog = scope.spawn(move || {
+            // Disconnected means `f` finished in time
+            let expired = finished.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout);
+            if expired {
+                let _ = child.kill();
+            }
+            expired
+        });
+        let result = f();
+        drop(done);
+        if watchdog.join().unwrap_or(true) {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("no response within {:?}", timeout),
+            ));
+        }
+        result
+    })
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // Workers exit when their stdin closes; don't wait on a stuck one
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Spawns worker processes and reuses them across conversions.
+pub struct WorkerPool {
+    program: PathBuf,
+    args: Vec<OsString>,
+    policy: IsolationPolicy,
+    max_idle: usize,
+    timeout: Option<Duration>,
+    startup_timeout: Duration,
+    idle: Mutex<Vec<Worker>>,
+}
+
+impl WorkerPool {
+    /// Create a pool running `program` as the worker, isolating every
+    /// converter.
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            policy: IsolationPolicy::All,
+            max_idle: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            timeout: None,
+            startup_timeout: STARTUP_TIMEOUT,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create a pool that re-runs the current executable with `args`.
+    pub fn current_exe(args: impl IntoIterator<Item = impl Into<OsString>>) -> io::Result<Self> {
+        Ok(Self::new(std::env::current_exe()?).args(args))
+    }
+
+    /// Append arguments for the worker command.
+    pub fn args(mut self, args: impl IntoIterator<Item = impl Into<OsString>>) -> Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Choose which converters run in workers.
+    pub fn policy(mut self, policy: IsolationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Number of idle workers kept for reuse.
+    pub fn max_idle(mut self, workers: usize) -> Self {
+        self.max_idle = workers;
+        self
+    }
+
+    /// Kill a worker whose conversion runs longer than `timeout`. The call
+    /// fails and a fresh worker takes over.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Kill a worker that has not finished starting after `timeout`
+    /// (30 seconds by default). The call that needed it fails.
+    pub fn startup_timeout(mut self, timeout: Duration) -> Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// Check if a converter runs in a worker under this pool's policy.
+    pub fn isolates(&self, converter_id: &str) -> bool {
+        self.policy.applies_to(converter_id)
+    }
+
+    /// Number of idle workers.
+    pub fn idle_workers(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+
+    /// Run a single conversion in a worker.
+    pub fn convert(
+        &self,
+        converter_id: &str,
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        let items = vec![item(None, props.clone(), input)];
+        self.call(converter_id, Mode::Single, options, items, &[input])
+    }
+
+    /// Run a batch conversion in a worker.
+    pub fn convert_batch(
+        &self,
+        converter_id: &str,
+        inputs: &[(&[u8], &Properties)],
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        let items = inputs
+            .iter()
+            .map(|(data, props)| item(None, (*props).clone(), data))
+            .collect();
+        let payloads: Vec<&[u8]> = inputs.iter().map(|(data, _)| *data).collect();
+        self.call(converter_id, Mode::Batch, options, items, &payloads)
+    }
+
+    /// Run a multi-input conversion in a worker.
+    pub fn convert_multi(
+        &self,
+        converter_id: &str,
+        inputs: &IndexMap<String, NamedInput<'_>>,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        let items = inputs
+            .iter()
+            .map(|(port, input)| item(Some(port.clone()), input.props.clone(), input.data))
+            .collect();
+        let payloads: Vec<&[u8]> = inputs.values().map(|input| input.data).collect();
+        self.call(converter_id, Mode::Multi, options, items, &payloads)
+    }
+
+    fn call(
+        &self,
+        converter_id: &str,
+        mode: Mode,
+        options: &Properties,
+        items: Vec<Item>,
+        payloads: &[&[u8]],
+    ) -> Result<ConvertOutput, ConvertError> {
+        let idle = self.idle.lock().unwrap().pop();
+        let mut worker = match idle {
+            Some(worker) => worker,
+            None => {
+                Worker::spawn(&self.program, &self.args, self.startup_timeout).map_err(|e| {
+                    ConvertError::Failed(format!(
+                        "failed to start worker {}: {}",
+                        self.program.display(),
+                        e
+                    ))
+                })?
+            }
+        };
+
+        let request = Request {
+            converter: converter_id.to_string(),
+            mode,
+            options: options.clone(),
+            deterministic: is_deterministic(),
+            limits: current_limits(),
+            items,
+        };
+        match worker.call(&request, payloads, self.timeout) {
+            Ok(result) => {
+                let mut idle = self.idle.lock().unwrap();
+                if idle.len() < self.max_idle {
+                    idle.push(worker);
+                }
+                result
+            }
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                worker.reap();
+                Err(ConvertError::Failed(format!(
+                    "{} timed out in worker process: {}",
+                    converter_id, e
+                )))
+            }
+            Err(e) => {
+                let status = worker.reap();
+                Err(ConvertError::Failed(format!(
+                    "{} crashed in worker process ({}): {}",
+                    converter_id, status, e
+                )))
+            }
+        }
+    }
+}
+
+/// A registry converter whose calls are forwarded to a worker pool.
+pub(crate) struct IsolatedConverter {
+    pub(crate) inner: Arc<dyn Converter>,
+    pub(crate) pool: Arc<WorkerPool>,
+}
+
+impl Converter for IsolatedConverter {
+    fn decl(&self) -> &ConverterDecl {
+        self.inner.decl()
+    }
+
+    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+        self.convert_with_options(input, props, &Properties::new())
+    }
+
+    fn convert_multi(
+        &self,
+        inputs: &IndexMap<String, NamedInput<'_>>,
+    ) -> Result<ConvertOutput, ConvertError> {
+        self.convert_multi_with_options(inputs, &Properties::new())
+    }
+
+    fn convert_batch(
+        &self,
+        inputs: &[(&[u8], &Properties)],
+    ) -> Result<ConvertOutput, ConvertError> {
+        self.convert_batch_with_options(inputs, &Properties::new())
+    }
+
+    fn convert_with_options(
+        &self,
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        self.pool.convert(&self.decl().id, input, props, options)
+    }
+
+    fn convert_multi_with_options(
+        &self,
+        inputs: &IndexMap<String, NamedInput<'_>>,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        self.pool.convert_multi(&self.decl().id, inputs, options)
+    }
+
+    fn convert_batch_with_options(
+        &self,
+        inputs: &[(&[u8], &Properties)],
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        self.pool.convert_batch(&self.decl().id, inputs, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::PropertyPattern;
+    use crate::properties::PropertiesExt;
+
+    struct TestConverter(ConverterDecl, fn(&[u8]) -> Result<Vec<u8>, ConvertError>);
+
+    impl Converter for TestConverter {
+        fn decl(&self) -> &ConverterDecl {
+            &self.0
+        }
+
+        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+            let data = (self.1)(input)?;
+            Ok(ConvertOutput::Single(
+                data,
+                props.clone().with("worker", true),
+            ))
+        }
+    }
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        let mut add = |id: &str, f: fn(&[u8]) -> Result<Vec<u8>, ConvertError>| {
+            let decl = ConverterDecl::simple(
+                id,
+                PropertyPattern::new().eq("format", "a"),
+                PropertyPattern::new().eq("format", "b"),
+            );
+            registry.register(TestConverter(decl, f));
+        };
+        add("test.upper", |input| Ok(input.to_ascii_uppercase()));
+        add("test.pid", |_| {
+            Ok(std::process::id().to_string().into_bytes())
+        });
+        add("test.reject", |_| {
+            Err(ConvertError::InvalidInput("rejected".into()))
+        });
+        add("test.crash", |_| std::process::abort());
+        add("test.hang", |_| {
+            std::thread::sleep(Duration::from_secs(60));
+            Ok(Vec::new())
+        });
+        registry
+    }
+
+    /// Worker entry point: the test binary re-runs itself with only this
+    /// test selected and `WORKER_ENV` set.
+    #[test]
+    #[ignore]
+    fn worker_main() {
+        if std::env::var_os(WORKER_ENV).is_none() {
+            return;
+        }
+        serve_worker(&registry(), io::stdin().lock(), io::stdout().lock()).unwrap();
+        std::process::exit(0);
+    }
+
+    fn pool() -> WorkerPool {
+        WorkerPool::current_exe([
+            "--ignored",
+            "--exact",
+            "isolation::tests::worker_main",
+            "--test-threads=1",
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = IsolationPolicy::Only(vec!["video.*".into(), "image.png-to-jpg".into()]);
+        assert!(policy.applies_to("video.resize"));
+        assert!(policy.applies_to("image.png-to-jpg"));
+        assert!(!policy.applies_to("image.png-to-webp"));
+        assert!(IsolationPolicy::All.applies_to("anything"));
+    }
+
+    #[test]
+    fn test_serve_in_process() {
+        let mut input = Vec::new();
+        let request = Request {
+            converter: "test.upper".into(),
+            mode: Mode::Single,
+            options: Properties::new(),
+            deterministic: false,
+            limits: Limits::new(),
+            items: vec![item(None, Properties::new(), b"abc")],
+        };
+        write_frame(&mut input, &request, &[b"abc"]).unwrap();
+
+        let mut output = Vec::new();
+        serve_worker(&registry(), input.as_slice(), &mut output).unwrap();
+
+        let mut reader = &output[HANDSHAKE.len()..];
+        let response: Response = read_header(&mut reader).unwrap().unwrap();
+        assert!(response.error.is_none());
+        assert_eq!(
+            read_payloads(&mut reader, &response.items).unwrap(),
+            [b"ABC"]
+        );
+    }
+
+    #[test]
+    fn test_worker_reuse() {
+        let pool = pool();
+        let props = Properties::new().with("format", "a");
+        let none = Properties::new();
+
+        let ConvertOutput::Single(data, out) =
+            pool.convert("test.upper", b"abc", &props, &none).unwrap()
+        else {
+            panic!("expected single output");
+        };
+        assert_eq!(data, b"ABC");
+        assert_eq!(out.get("worker"), Some(&true.into()));
+
+        let pid = |pool: &WorkerPool| match pool.convert("test.pid", b"", &props, &none).unwrap() {
+            ConvertOutput::Single(data, _) => String::from_utf8(data).unwrap(),
+            ConvertOutput::Multiple(_) => panic!("expected single output"),
+        };
+        let first = pid(&pool);
+        assert_ne!(first, std::process::id().to_string());
+        assert_eq!(pid(&pool), first);
+        assert_eq!(pool.idle_workers(), 1);
+
+        // Converter errors keep their kind and leave the worker running
+        let err = pool
+            .convert("test.reject", b"", &props, &none)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ConvertError::InvalidInput(_)));
+        assert_eq!(pid(&pool), first);
+    }
+
+    #[test]
+    fn test_worker_timeout() {
+        let pool = pool().timeout(Duration::from_millis(500));
+        let props = Properties::new().with("format", "a");
+        let none = Properties::new();
+
+        let start = std::time::Instant::now();
+        let err = pool.convert("test.hang", b"", &props, &none).err().unwrap();
+        assert!(err.to_string().contains("timed out in worker process"));
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert_eq!(pool.idle_workers(), 0);
+
+        // A fresh worker takes over
+        assert!(pool.convert("test.upper", b"abc", &props, &none).is_ok());
+        assert_eq!(pool.idle_workers(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_worker_startup_timeout() {
+        // Never sends the handshake
+        let pool = WorkerPool::new("sleep")
+            .args(["60"])
+            .startup_timeout(Duration::from_millis(200));
+        let props = Properties::new().with("format", "a");
+
+        let start = std::time::Instant::now();
+        let err = pool
+            .convert("test.upper", b"abc", &props, &Properties::new())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("failed to start worker"));
+        assert!(err.to_string().contains("no response within"));
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert_eq!(pool.idle_workers(), 0);
+    }
+
+    #[test]
+    fn test_worker_crash() {
+        let pool = pool();
+        let props = Properties::new().with("format", "a");
+        let none = Properties::new();
+        let before = match pool.convert("test.pid", b"", &props, &none).unwrap() {
+            ConvertOutput::Single(data, _) => data,
+            ConvertOutput::Multiple(_) => panic!("expected single output"),
+        };
+
+        let err = pool
+            .convert("test.crash", b"", &props, &none)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("crashed in worker process"));
+        assert_eq!(pool.idle_workers(), 0);
+
+        // A fresh worker takes over
+        let ConvertOutput::Single(after, _) = pool.convert("test.pid", b"", &props, &none).unwrap()
+        else {
+            panic!("expected single output");
+        };
+        assert_ne!(after, before);
+    }
+}
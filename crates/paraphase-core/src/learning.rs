@@ -0,0 +1,277 @@
+//! Adaptive costs learned from execution statistics.
+//!
+//! The static `speed`/`size` costs on a `ConverterDecl` are guesses. A
+//! `CostStore` records how converters actually behave on this machine and
+//! workload, bucketed by converter, input format and input size class, and
+//! the planner prefers these observations over the declared costs once a
+//! bucket has enough samples.
+
+use crate::executor::{ExecutionStats, StepStats};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// Observations needed before a bucket overrides declared costs.
+pub const MIN_SAMPLES: u64 = 3;
+
+/// Number of recent samples the running averages effectively cover,
+/// so costs follow changes in the workload instead of freezing.
+const WINDOW: u64 = 20;
+
+/// Coarse input size class used for bucketing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeClass {
+    /// Under 64 KiB.
+    Tiny,
+    /// Under 1 MiB.
+    Small,
+    /// Under 16 MiB.
+    Medium,
+    /// Under 256 MiB.
+    Large,
+    /// 256 MiB and up.
+    Huge,
+}
+
+impl SizeClass {
+    /// Classify a size in bytes.
+    pub fn of(bytes: usize) -> Self {
+        match bytes {
+            b if b < 64 << 10 => SizeClass::Tiny,
+            b if b < 1 << 20 => SizeClass::Small,
+            b if b < 16 << 20 => SizeClass::Medium,
+            b if b < 256 << 20 => SizeClass::Large,
+            _ => SizeClass::Huge,
+        }
+    }
+
+    /// Name used in store keys.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SizeClass::Tiny => "tiny",
+            SizeClass::Small => "small",
+            SizeClass::Medium => "medium",
+            SizeClass::Large => "large",
+            SizeClass::Huge => "huge",
+        }
+    }
+}
+
+/// Learned behaviour of a converter within one bucket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LearnedCost {
+    /// Number of observations.
+    pub samples: u64,
+    /// Average step duration in seconds.
+    pub seconds: f64,
+    /// Average output/input size ratio.
+    pub size_ratio: f64,
+}
+
+impl LearnedCost {
+    fn observe(&mut self, seconds: f64, size_ratio: f64) {
+        self.samples += 1;
+        let weight = 1.0 / self.samples.min(WINDOW) as f64;
+        self.seconds += (seconds - self.seconds) * weight;
+        self.size_ratio += (size_ratio - self.size_ratio) * weight;
+    }
+}
+
+/// Persistent store of learned converter costs.
+///
+/// Keys have the form `converter|format|size-class`; inputs without a
+/// `format` property are recorded under `*`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostStore {
+    entries: IndexMap<String, LearnedCost>,
+}
+
+impl CostStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a store from a JSON file. A missing file yields an empty store.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match std::fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Save the store as JSON, creating parent directories as needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, data)
+    }
+
+    /// Record the per-step statistics of an execution.
+    pub fn record(&mut self, stats: &ExecutionStats) {
+        for step in &stats.steps {
+            self.record_step(step);
+        }
+    }
+
+    /// Record a single step observation.
+    pub fn record_step(&mut self, step: &StepStats) {
+        if step.input_size == 0 {
+            return;
+        }
+        let key = Self::key(
+            &step.converter_id,
+            step.input_format.as_deref(),
+            SizeClass::of(step.input_size),
+        );
+        self.entries.entry(key).or_default().observe(
+            step.duration.as_secs_f64(),
+            step.output_size as f64 / step.input_size as f64,
+        );
+    }
+
+    /// Look up learned costs for a converter.
+    ///
+    /// With a known input size only the matching size class is consulted;
+    /// otherwise all size classes for the format are combined. Returns `None`
+    /// until the bucket has at least [`MIN_SAMPLES`] observations.
+    pub fn lookup(
+        &self,
+        converter_id: &str,
+        format: Option<&str>,
+        input_size: Option<usize>,
+    ) -> Option<LearnedCost> {
+        let learned = match input_size {
+            Some(size) => self
+                .entries
+                .get(&Self::key(converter_id, format, SizeClass::of(size)))
+                .copied()?,
+            None => {
+                let prefix = Self::key_prefix(converter_id, format);
+                self.entries
+                    .iter()
+                    .filter(|(key, _)| key.starts_with(&prefix))
+                    .fold(LearnedCost::default(), |acc, (_, cost)| {
+                        let samples = acc.samples + cost.samples;
+                        let mix = |a: f64, b: f64| {
+                            (a * acc.samples as f64 + b * cost.samples as f64) / samples as f64
+                        };
+                        LearnedCost {
+                            samples,
+                            seconds: mix(acc.seconds, cost.seconds),
+                            size_ratio: mix(acc.size_ratio, cost.size_ratio),
+                        }
+                    })
+            }
+        };
+
+        (learned.samples >= MIN_SAMPLES).then_some(learned)
+    }
+
+    /// Number of buckets in the store.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn key(converter_id: &str, format: Option<&str>, class: SizeClass) -> String {
+        format!(
+            "{}{}",
+            Self::key_prefix(converter_id, format),
+            class.as_str()
+        )
+    }
+
+    fn key_prefix(converter_id: &str, format: Option<&str>) -> String {
+        format!("{}|{}|", converter_id, format.unwrap_or("*"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn step(input_size: usize, output_size: usize, millis: u64) -> StepStats {
+        StepStats {
+            converter_id: "serde.json-to-yaml".into(),
+            input_format: Some("json".into()),
+            input_size,
+            output_size,
+            duration: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn test_lookup_requires_min_samples() {
+        let mut store = CostStore::new();
+        for _ in 0..MIN_SAMPLES - 1 {
+            store.record_step(&step(1000, 500, 10));
+        }
+        assert!(
+            store
+                .lookup("serde.json-to-yaml", Some("json"), None)
+                .is_none()
+        );
+
+        store.record_step(&step(1000, 500, 10));
+        let learned = store
+            .lookup("serde.json-to-yaml", Some("json"), Some(2000))
+            .unwrap();
+        assert_eq!(learned.samples, MIN_SAMPLES);
+        assert!((learned.seconds - 0.01).abs() < 1e-9);
+        assert!((learned.size_ratio - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_buckets_by_size_class() {
+        let mut store = CostStore::new();
+        for _ in 0..MIN_SAMPLES {
+            store.record_step(&step(1000, 1000, 10));
+            store.record_step(&step(10 << 20, 10 << 20, 1000));
+        }
+
+        let small = store.lookup("serde.json-to-yaml", Some("json"), Some(100));
+        let medium = store.lookup("serde.json-to-yaml", Some("json"), Some(2 << 20));
+        assert!(small.unwrap().seconds < medium.unwrap().seconds);
+
+        // Unknown size combines all classes
+        let any = store
+            .lookup("serde.json-to-yaml", Some("json"), None)
+            .unwrap();
+        assert_eq!(any.samples, MIN_SAMPLES * 2);
+
+        // Other formats are separate buckets
+        assert!(
+            store
+                .lookup("serde.json-to-yaml", Some("yaml"), None)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let mut store = CostStore::new();
+        store.record_step(&step(1000, 500, 10));
+
+        let path =
+            std::env::temp_dir().join(format!("paraphase-costs-{}.json", std::process::id()));
+        store.save(&path).unwrap();
+        let loaded = CostStore::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, store);
+        assert!(CostStore::load(&path).unwrap().is_empty());
+    }
+}
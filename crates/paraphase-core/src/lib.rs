@@ -3,23 +3,64 @@
 //! Paraphase is a route planner for data conversion. Given source and target
 //! properties, it finds a path through available converters.
 
+mod bundle;
+#[cfg(feature = "command")]
+mod command;
 mod converter;
+mod determinism;
 mod executor;
+mod fidelity;
+mod format;
+mod graph;
+mod index;
+mod inspector;
+mod isolation;
+mod learning;
+mod limits;
+mod option;
 mod pattern;
 mod planner;
 mod properties;
 mod registry;
+mod resource;
+mod roundtrip;
+mod scalar;
+#[cfg(feature = "spill")]
+mod spill;
+mod vocabulary;
 mod workflow;
 
+pub use bundle::{BUNDLE_FORMAT, BUNDLE_MAGIC, Bundle, BundleMember};
+#[cfg(feature = "command")]
+pub use command::{CommandConverter, CommandDecl, INPUT_PLACEHOLDER, OUTPUT_PLACEHOLDER};
 pub use converter::{ConvertError, ConvertOutput, Converter, ConverterDecl, NamedInput, PortDecl};
+pub use determinism::{FIXED_MTIME, is_deterministic, with_determinism};
 #[cfg(feature = "parallel")]
 pub use executor::ParallelExecutor;
 pub use executor::{
     BoundedExecutor, ExecuteError, ExecutionContext, ExecutionResult, ExecutionStats, Executor,
-    Job, MemoryBudget, MemoryPermit, SimpleExecutor, estimate_memory,
+    Job, MemoryBudget, MemoryPermit, SimpleExecutor, StepStats, estimate_memory,
+    estimate_resident_memory,
 };
+pub use fidelity::{LossNote, Severity, collect_losses, report_loss, worst_loss};
+pub use format::{FormatDecl, Formats, Magic};
+pub use graph::{ANY_FORMAT, ConverterGraph, GraphEdge};
+pub use inspector::{Inspector, InspectorDecl};
+pub use isolation::{IsolationPolicy, WORKER_ENV, WorkerPool, serve_worker};
+pub use learning::{CostStore, LearnedCost, MIN_SAMPLES, SizeClass};
+pub use limits::{Limits, current_limits, with_limits};
+pub use option::{OptionDecl, OptionError, OptionType};
 pub use pattern::{Predicate, PropertyPattern};
 pub use planner::{Cardinality, OptimizeTarget, Plan, PlanStep, Planner};
 pub use properties::{Properties, PropertiesExt, Value};
-pub use registry::Registry;
+pub use registry::{ConflictPolicy, Registry, RegistryError};
+pub use resource::{Formula, PlanEstimate, ResourceModel, StepEstimate, speed_unit_seconds};
+pub use roundtrip::{Comparator, ComparatorDecl, RoundTripReport, RoundTripStatus};
+pub use scalar::{Decimal, Timestamp, format_duration, parse_duration};
+#[cfg(feature = "spill")]
+pub use spill::{Intermediate, SpillFile};
+pub use vocabulary::{PropertyDecl, PropertyIssue, PropertyType, Vocabulary};
 pub use workflow::{Sink, Source, Step, Workflow, WorkflowError};
+
+/// Version of this crate.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
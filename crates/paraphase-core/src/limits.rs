@@ -0,0 +1,251 @@
+//! Resource limits for untrusted input.
+//!
+//! A small gzip stream can inflate to gigabytes, an image header can claim
+//! billions of pixels and an archive can hold millions of entries. With
+//! `ExecutionContext::with_limits`, the executor runs every step under a set
+//! of [`Limits`]; decoders read them with [`current_limits`] and fail with
+//! [`ConvertError::LimitExceeded`] before doing the expensive work.
+
+use crate::converter::ConvertError;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::io::Read;
+
+/// Safety limits on what a conversion may produce. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Limits {
+    /// Most bytes a single conversion may output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_bytes: Option<u64>,
+    /// Most output bytes per input byte.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_expansion_ratio: Option<f64>,
+    /// Largest image, in millions of pixels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_megapixels: Option<f64>,
+    /// Most entries in an archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_entries: Option<u64>,
+    /// Deepest nesting of structured input, e.g. SVG groups.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+}
+
+impl Limits {
+    /// No limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the bytes a single conversion may output.
+    pub fn max_output_bytes(mut self, bytes: u64) -> Self {
+        self.max_output_bytes = Some(bytes);
+        self
+    }
+
+    /// Limit the output bytes per input byte.
+    pub fn max_expansion_ratio(mut self, ratio: f64) -> Self {
+        self.max_expansion_ratio = Some(ratio);
+        self
+    }
+
+    /// Limit image size, in millions of pixels.
+    pub fn max_megapixels(mut self, megapixels: f64) -> Self {
+        self.max_megapixels = Some(megapixels);
+        self
+    }
+
+    /// Limit the number of archive entries.
+    pub fn max_entries(mut self, entries: u64) -> Self {
+        self.max_entries = Some(entries);
+        self
+    }
+
+    /// Limit nesting depth.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Whether no limit is set.
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Most bytes a conversion of `input_len` bytes may output.
+    pub fn output_budget(&self, input_len: usize) -> Option<u64> {
+        let by_ratio = self
+            .max_expansion_ratio
+            .map(|ratio| (input_len.max(1) as f64 * ratio) as u64);
+        match (self.max_output_bytes, by_ratio) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Fail if `output_len` bytes is too much output for `input_len` bytes
+    /// of input.
+    pub fn check_output(&self, input_len: usize, output_len: u64) -> Result<(), ConvertError> {
+        if let Some(max) = self.max_output_bytes
+            && output_len > max
+        {
+            return Err(ConvertError::LimitExceeded(format!(
+                "output exceeds {} bytes",
+                max
+            )));
+        }
+        if let Some(ratio) = self.max_expansion_ratio
+            && output_len as f64 > input_len.max(1) as f64 * ratio
+        {
+            return Err(ConvertError::LimitExceeded(format!(
+                "output is more than {}x the {}-byte input",
+                ratio, input_len
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read `reader` to the end, stopping as soon as the output budget for
+    /// `input_len` bytes of input is exceeded.
+    ///
+    /// Read errors become `InvalidInput` errors prefixed with `what`, e.g.
+    /// "Gzip decompression".
+    pub fn read_to_end(
+        &self,
+        reader: impl Read,
+        input_len: usize,
+        what: &str,
+    ) -> Result<Vec<u8>, ConvertError> {
+        // One byte past the budget tells a full read from an overflow
+        let cap = self
+            .output_budget(input_len)
+            .map_or(u64::MAX, |b| b.saturating_add(1));
+        let mut output = Vec::new();
+        reader
+            .take(cap)
+            .read_to_end(&mut output)
+            .map_err(|e| ConvertError::InvalidInput(format!("{} failed: {}", what, e)))?;
+        self.check_output(input_len, output.len() as u64)?;
+        Ok(output)
+    }
+
+    /// Fail if a `width` x `height` image is too large.
+    pub fn check_pixels(&self, width: u64, height: u64) -> Result<(), ConvertError> {
+        match self.max_megapixels {
+            Some(max) if width.saturating_mul(height) as f64 > max * 1e6 => {
+                Err(ConvertError::LimitExceeded(format!(
+                    "image of {}x{} pixels exceeds {} megapixels",
+                    width, height, max
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if an archive has more than the allowed number of entries.
+    pub fn check_entries(&self, entries: u64) -> Result<(), ConvertError> {
+        match self.max_entries {
+            Some(max) if entries > max => Err(ConvertError::LimitExceeded(format!(
+                "archive has more than {} entries",
+                max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if nesting reaches deeper than allowed.
+    pub fn check_depth(&self, depth: usize) -> Result<(), ConvertError> {
+        match self.max_depth {
+            Some(max) if depth > max => Err(ConvertError::LimitExceeded(format!(
+                "nesting deeper than {} levels",
+                max
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT: Cell<Limits> = const {
+        Cell::new(Limits {
+            max_output_bytes: None,
+            max_expansion_ratio: None,
+            max_megapixels: None,
+            max_entries: None,
+            max_depth: None,
+        })
+    };
+}
+
+/// The limits the current conversion runs under.
+pub fn current_limits() -> Limits {
+    CURRENT.with(Cell::get)
+}
+
+/// Run `f` under `limits` on this thread.
+pub fn with_limits<T>(limits: Limits, f: impl FnOnce() -> T) -> T {
+    /// Restores the enclosing limits, even if `f` panics.
+    struct Scope(Limits);
+
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            CURRENT.with(|c| c.set(self.0));
+        }
+    }
+
+    let _scope = Scope(CURRENT.with(|c| c.replace(limits)));
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_limits() {
+        let limits = Limits::new()
+            .max_output_bytes(1000)
+            .max_expansion_ratio(10.0);
+        assert_eq!(limits.output_budget(50), Some(500));
+        assert_eq!(limits.output_budget(500), Some(1000));
+        assert!(limits.check_output(50, 500).is_ok());
+        assert!(matches!(
+            limits.check_output(50, 501),
+            Err(ConvertError::LimitExceeded(_))
+        ));
+        assert!(Limits::new().check_output(1, u64::MAX).is_ok());
+
+        let data = vec![0u8; 2000];
+        let err = limits
+            .read_to_end(data.as_slice(), 100, "Copy")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "resource limit exceeded: output exceeds 1000 bytes"
+        );
+        let out = limits.read_to_end(&data[..800], 100, "Copy").unwrap();
+        assert_eq!(out.len(), 800);
+    }
+
+    #[test]
+    fn test_checks() {
+        let limits = Limits::new()
+            .max_megapixels(1.0)
+            .max_entries(2)
+            .max_depth(3);
+        assert!(limits.check_pixels(1000, 1000).is_ok());
+        assert!(limits.check_pixels(1001, 1000).is_err());
+        assert!(limits.check_entries(2).is_ok());
+        assert!(limits.check_entries(3).is_err());
+        assert!(limits.check_depth(3).is_ok());
+        assert!(limits.check_depth(4).is_err());
+    }
+
+    #[test]
+    fn test_scope() {
+        assert!(current_limits().is_unlimited());
+        let inner = with_limits(Limits::new().max_entries(5), current_limits);
+        assert_eq!(inner.max_entries, Some(5));
+        assert!(current_limits().is_unlimited());
+    }
+}
@@ -0,0 +1,332 @@
+//! Declared converter options.
+//!
+//! Converters take tuning knobs (`max_width`, `quality`, `pem_label`…) as
+//! options, separate from the properties describing the data. An option
+//! schema on the `ConverterDecl` names those knobs so typos are reported
+//! instead of silently ignored, and so tools can list what a converter
+//! accepts.
+
+use crate::properties::{Properties, Value};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Type of an option value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionType {
+    Bool,
+    Int,
+    /// Any number; integers are accepted.
+    Float,
+    String,
+    /// Any value (validated by the converter itself).
+    Any,
+}
+
+impl OptionType {
+    /// Lowercase name, as used in the serialized schema.
+    pub fn name(self) -> &'static str {
+        match self {
+            OptionType::Bool => "bool",
+            OptionType::Int => "int",
+            OptionType::Float => "float",
+            OptionType::String => "string",
+            OptionType::Any => "any",
+        }
+    }
+
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            OptionType::Bool => value.as_bool().is_some(),
+            OptionType::Int => value.as_i64().is_some(),
+            OptionType::Float => value.as_f64().is_some(),
+            OptionType::String => value.as_str().is_some(),
+            OptionType::Any => true,
+        }
+    }
+}
+
+/// Declaration of a single converter option.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptionDecl {
+    /// Value type.
+    #[serde(rename = "type")]
+    pub ty: OptionType,
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: String,
+    /// Value the converter uses when the option is absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    /// Inclusive lower bound for numeric options.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Inclusive upper bound for numeric options.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Allowed values for string options (empty = any string).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+}
+
+impl OptionDecl {
+    /// Create an option of the given type.
+    pub fn new(ty: OptionType, description: impl Into<String>) -> Self {
+        Self {
+            ty,
+            description: description.into(),
+            default: None,
+            min: None,
+            max: None,
+            choices: Vec::new(),
+        }
+    }
+
+    /// Boolean option.
+    pub fn bool(description: impl Into<String>) -> Self {
+        Self::new(OptionType::Bool, description)
+    }
+
+    /// Integer option.
+    pub fn int(description: impl Into<String>) -> Self {
+        Self::new(OptionType::Int, description)
+    }
+
+    /// Numeric option.
+    pub fn float(description: impl Into<String>) -> Self {
+        Self::new(OptionType::Float, description)
+    }
+
+    /// String option.
+    pub fn string(description: impl Into<String>) -> Self {
+        Self::new(OptionType::String, description)
+    }
+
+    /// Set the default value.
+    pub fn default_value(mut self, value: impl Into<Value>) -> Self {
+        self.default = Some(value.into());
+        self
+    }
+
+    /// Set the inclusive numeric range.
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Set the inclusive lower bound.
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Restrict a string option to the given values.
+    pub fn choices<I, S>(mut self, choices: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.choices = choices.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Check a value against this declaration.
+    ///
+    /// Returns a description of the problem on failure.
+    pub fn check(&self, value: &Value) -> Result<(), String> {
+        if !self.ty.accepts(value) {
+            return Err(format!("expected {}, got {}", self.ty.name(), kind(value)));
+        }
+        if let Some(n) = value.as_f64() {
+            if let Some(min) = self.min
+                && n < min
+            {
+                return Err(format!("{} is below the minimum {}", n, min));
+            }
+            if let Some(max) = self.max
+                && n > max
+            {
+                return Err(format!("{} is above the maximum {}", n, max));
+            }
+        }
+        if let Some(s) = value.as_str()
+            && !self.choices.is_empty()
+            && !self.choices.iter().any(|c| c == s)
+        {
+            return Err(format!("`{}` is not one of {}", s, self.choices.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+/// An option that does not match a converter's schema.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum OptionError {
+    #[error("unknown option `{name}` for {converter}{}", hint(suggestion))]
+    Unknown {
+        converter: String,
+        name: String,
+        suggestion: Option<String>,
+    },
+
+    #[error("invalid value for option `{name}` of {converter}: {message}")]
+    Invalid {
+        converter: String,
+        name: String,
+        message: String,
+    },
+}
+
+fn hint(suggestion: &Option<String>) -> String {
+    suggestion
+        .as_ref()
+        .map(|s| format!(" (did you mean `{}`?)", s))
+        .unwrap_or_default()
+}
+
+/// Validate options against a schema.
+///
+/// An empty schema means the converter declares nothing, so every key is
+/// accepted.
+pub(crate) fn validate(
+    converter: &str,
+    schema: &IndexMap<String, OptionDecl>,
+    options: &Properties,
+) -> Result<(), OptionError> {
+    if schema.is_empty() {
+        return Ok(());
+    }
+    for (name, value) in options {
+        match schema.get(name) {
+            Some(decl) => decl.check(value).map_err(|message| OptionError::Invalid {
+                converter: converter.to_string(),
+                name: name.clone(),
+                message,
+            })?,
+            None => {
+                return Err(OptionError::Unknown {
+                    converter: converter.to_string(),
+                    name: name.clone(),
+                    suggestion: suggest(name, schema.keys()),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Closest declared name within a small edit distance.
+fn suggest<'a>(name: &str, known: impl Iterator<Item = &'a String>) -> Option<String> {
+    known
+        .map(|k| (edit_distance(name, k), k))
+        .filter(|(d, k)| *d <= 2.max(k.len() / 4))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k.clone())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(cur)
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Int(_) | Value::UInt(_) => "int",
+        Value::Float(_) | Value::Decimal(_) => "float",
+        Value::String(_) => "string",
+        Value::Timestamp(_) => "timestamp",
+        Value::Duration(_) => "duration",
+        Value::Bytes(_) => "bytes",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PropertiesExt;
+
+    fn schema() -> IndexMap<String, OptionDecl> {
+        let mut schema = IndexMap::new();
+        schema.insert(
+            "max_width".into(),
+            OptionDecl::int("Maximum width").min(1.0),
+        );
+        schema.insert(
+            "quality".into(),
+            OptionDecl::float("Quality").range(0.0, 100.0),
+        );
+        schema.insert(
+            "gravity".into(),
+            OptionDecl::string("Anchor").choices(["center", "north"]),
+        );
+        schema
+    }
+
+    #[test]
+    fn test_check_types_and_ranges() {
+        let schema = schema();
+        let ok = Properties::new()
+            .with("max_width", 800i64)
+            .with("quality", 85i64)
+            .with("gravity", "north");
+        assert!(validate("img", &schema, &ok).is_ok());
+
+        let bad_type = Properties::new().with("max_width", "800");
+        let err = validate("img", &schema, &bad_type).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value for option `max_width` of img: expected int, got string"
+        );
+
+        let out_of_range = Properties::new().with("quality", 120.0);
+        assert!(validate("img", &schema, &out_of_range).is_err());
+
+        let bad_choice = Properties::new().with("gravity", "up");
+        assert!(validate("img", &schema, &bad_choice).is_err());
+    }
+
+    #[test]
+    fn test_unknown_options() {
+        let schema = schema();
+        let typo = Properties::new().with("max_widht", 800i64);
+        let err = validate("img", &schema, &typo).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown option `max_widht` for img (did you mean `max_width`?)"
+        );
+
+        // Undeclared converters accept anything.
+        assert!(validate("img", &IndexMap::new(), &typo).is_ok());
+    }
+
+    #[test]
+    fn test_schema_serde() {
+        let decl = OptionDecl::int("Maximum width").default_value(0i64);
+        let json = serde_json::to_string(&decl).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"int","description":"Maximum width","default":0}"#
+        );
+        let back: OptionDecl = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, decl);
+    }
+}
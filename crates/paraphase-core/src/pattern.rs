@@ -1,6 +1,7 @@
 //! Property patterns for matching and routing.
 
 use crate::properties::{Properties, Value};
+use crate::scalar::Timestamp;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
@@ -14,7 +15,7 @@ pub enum Predicate {
     Eq(Value),
     /// Matches if not equal.
     Ne(Value),
-    /// Numeric greater than.
+    /// Numeric greater than. Durations compare in seconds.
     Gt(f64),
     /// Numeric greater than or equal.
     Gte(f64),
@@ -30,6 +31,10 @@ pub enum Predicate {
     Contains(String),
     /// Value is one of these.
     OneOf(Vec<Value>),
+    /// Timestamp strictly before.
+    Before(Timestamp),
+    /// Timestamp strictly after.
+    After(Timestamp),
 }
 
 impl Predicate {
@@ -39,18 +44,27 @@ impl Predicate {
             Predicate::Any => true,
             Predicate::Eq(expected) => value == expected,
             Predicate::Ne(expected) => value != expected,
-            Predicate::Gt(n) => value.as_f64().is_some_and(|v| v > *n),
-            Predicate::Gte(n) => value.as_f64().is_some_and(|v| v >= *n),
-            Predicate::Lt(n) => value.as_f64().is_some_and(|v| v < *n),
-            Predicate::Lte(n) => value.as_f64().is_some_and(|v| v <= *n),
+            Predicate::Gt(n) => numeric(value).is_some_and(|v| v > *n),
+            Predicate::Gte(n) => numeric(value).is_some_and(|v| v >= *n),
+            Predicate::Lt(n) => numeric(value).is_some_and(|v| v < *n),
+            Predicate::Lte(n) => numeric(value).is_some_and(|v| v <= *n),
             Predicate::StartsWith(prefix) => value.as_str().is_some_and(|s| s.starts_with(prefix)),
             Predicate::EndsWith(suffix) => value.as_str().is_some_and(|s| s.ends_with(suffix)),
             Predicate::Contains(substr) => value.as_str().is_some_and(|s| s.contains(substr)),
             Predicate::OneOf(values) => values.contains(value),
+            Predicate::Before(t) => value.as_timestamp().is_some_and(|v| v < *t),
+            Predicate::After(t) => value.as_timestamp().is_some_and(|v| v > *t),
         }
     }
 }
 
+/// Number, or duration in seconds, for the numeric predicates.
+fn numeric(value: &Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_duration().map(|d| d.as_secs_f64()))
+}
+
 /// A pattern for matching property bags.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct PropertyPattern {
@@ -173,4 +187,42 @@ mod tests {
         assert!(pattern.matches(&Properties::new().with("format", "jpg")));
         assert!(!pattern.matches(&Properties::new().with("format", "webp")));
     }
+
+    #[test]
+    fn test_duration_and_timestamp_predicates() {
+        use std::time::Duration;
+
+        let long = PropertyPattern::new().with("duration", Predicate::Gte(60.0));
+        let props = Properties::new().with("duration", Duration::from_secs(90));
+        assert!(long.matches(&props));
+        let props = Properties::new().with("duration", Duration::from_millis(59_999));
+        assert!(!long.matches(&props));
+
+        let cutoff = Timestamp::parse("2024-01-01T00:00:00Z").unwrap();
+        let recent = PropertyPattern::new().with("taken", Predicate::After(cutoff));
+        let props = Properties::new().with(
+            "taken",
+            Timestamp::parse("2024-06-01T08:00:00+02:00").unwrap(),
+        );
+        assert!(recent.matches(&props));
+        assert!(
+            !PropertyPattern::new()
+                .with("taken", Predicate::Before(cutoff))
+                .matches(&props)
+        );
+
+        // Strings are not timestamps.
+        let props = Properties::new().with("taken", "2024-06-01T08:00:00Z");
+        assert!(!recent.matches(&props));
+
+        let json = serde_json::to_string(&recent).unwrap();
+        assert_eq!(
+            json,
+            r#"{"taken":{"after":{"$timestamp":"2024-01-01T00:00:00Z"}}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<PropertyPattern>(&json).unwrap(),
+            recent
+        );
+    }
 }
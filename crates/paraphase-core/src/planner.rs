@@ -4,9 +4,12 @@
 //! sequence of converters that transforms the source to the target.
 
 use crate::converter::ConverterDecl;
+use crate::learning::CostStore;
 use crate::pattern::PropertyPattern;
 use crate::properties::Properties;
 use crate::registry::Registry;
+use crate::resource::{StepEstimate, speed_unit_seconds};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashSet};
 
@@ -23,7 +26,7 @@ pub enum OptimizeTarget {
 }
 
 /// A planned conversion path.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Plan {
     /// Steps in the plan, in execution order.
     pub steps: Vec<PlanStep>,
@@ -32,7 +35,7 @@ pub struct Plan {
 }
 
 /// A single step in a conversion plan.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanStep {
     /// Converter ID.
     pub converter_id: String,
@@ -42,6 +45,9 @@ pub struct PlanStep {
     pub output_port: String,
     /// Expected output properties after this step.
     pub output_properties: Properties,
+    /// Options for the converter, checked against its schema when executed.
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    pub options: Properties,
 }
 
 /// Cardinality of the data flowing through the plan.
@@ -62,15 +68,19 @@ struct SearchNode {
     cardinality: Cardinality,
     /// Steps taken to reach this node.
     steps: Vec<PlanStep>,
+    /// Estimated data size in bytes, if the input size is known.
+    size: Option<usize>,
     /// Cost so far (g in A*).
     cost: f64,
     /// Estimated total cost (f = g + h in A*).
     estimated_total: f64,
+    /// Sum of the converter priorities along the path (tie-breaker).
+    priority: i64,
 }
 
 impl PartialEq for SearchNode {
     fn eq(&self, other: &Self) -> bool {
-        self.estimated_total == other.estimated_total
+        self.estimated_total == other.estimated_total && self.priority == other.priority
     }
 }
 
@@ -84,11 +94,13 @@ impl PartialOrd for SearchNode {
 
 impl Ord for SearchNode {
     fn cmp(&self, other: &Self) -> Ordering {
-        // Reverse order for min-heap (lower cost = higher priority)
+        // Reverse order for min-heap (lower cost first); at equal cost,
+        // paths through higher-priority converters first
         other
             .estimated_total
             .partial_cmp(&self.estimated_total)
             .unwrap_or(Ordering::Equal)
+            .then(self.priority.cmp(&other.priority))
     }
 }
 
@@ -97,6 +109,8 @@ pub struct Planner<'a> {
     registry: &'a Registry,
     max_depth: usize,
     optimize: OptimizeTarget,
+    input_size: Option<usize>,
+    learned: Option<&'a CostStore>,
 }
 
 impl<'a> Planner<'a> {
@@ -106,6 +120,8 @@ impl<'a> Planner<'a> {
             registry,
             max_depth: 10,
             optimize: OptimizeTarget::default(),
+            input_size: None,
+            learned: None,
         }
     }
 
@@ -121,6 +137,46 @@ impl<'a> Planner<'a> {
         self
     }
 
+    /// Set the source size in bytes.
+    ///
+    /// Enables converter time and output-size models in the cost function;
+    /// without it only static `costs` are used.
+    pub fn input_size(mut self, bytes: usize) -> Self {
+        self.input_size = Some(bytes);
+        self
+    }
+
+    /// Use costs learned from previous executions.
+    ///
+    /// Learned timings and size ratios take precedence over declared models
+    /// and static costs for buckets with enough samples.
+    pub fn learned_costs(mut self, store: &'a CostStore) -> Self {
+        self.learned = Some(store);
+        self
+    }
+
+    /// Inspect the input, then find a conversion path for it.
+    ///
+    /// Runs the registry's inspectors on `input` to complete `source`, and
+    /// uses the input length as the source size unless one was set. Returns
+    /// the plan together with the inspected source properties.
+    pub fn plan_input(
+        &self,
+        input: &[u8],
+        source: &Properties,
+        target: &PropertyPattern,
+        source_cardinality: Cardinality,
+        target_cardinality: Cardinality,
+    ) -> Option<(Plan, Properties)> {
+        let source = self.registry.inspect(input, source);
+        let planner = Planner {
+            input_size: self.input_size.or(Some(input.len())),
+            ..*self
+        };
+        let plan = planner.plan(&source, target, source_cardinality, target_cardinality)?;
+        Some((plan, source))
+    }
+
     /// Find a conversion path from source to target properties.
     ///
     /// Uses A* search to find the lowest-cost path.
@@ -141,14 +197,17 @@ impl<'a> Planner<'a> {
 
         let mut frontier = BinaryHeap::new();
         let mut visited = HashSet::new();
+        let min_step_cost = self.min_step_cost();
 
         // Create initial node
         let initial = SearchNode {
             properties: source.clone(),
             cardinality: source_cardinality,
             steps: vec![],
+            size: self.input_size,
             cost: 0.0,
-            estimated_total: self.heuristic(source, target),
+            estimated_total: self.heuristic(source, target, min_step_cost),
+            priority: 0,
         };
         frontier.push(initial);
 
@@ -174,8 +233,10 @@ impl<'a> Planner<'a> {
             }
 
             // Expand neighbors
-            for decl in self.registry.declarations() {
-                if let Some(neighbor) = self.try_apply(decl, &current, target, target_cardinality) {
+            for decl in self.registry.candidates(&current.properties) {
+                if let Some(neighbor) =
+                    self.try_apply(decl, &current, target, target_cardinality, min_step_cost)
+                {
                     let neighbor_key = self.state_key(&neighbor.properties, neighbor.cardinality);
                     if !visited.contains(&neighbor_key) {
                         frontier.push(neighbor);
@@ -194,6 +255,7 @@ impl<'a> Planner<'a> {
         current: &SearchNode,
         target: &PropertyPattern,
         target_cardinality: Cardinality,
+        min_step_cost: f64,
     ) -> Option<SearchNode> {
         // Find matching input port
         let (input_port, input_decl) = decl
@@ -253,16 +315,22 @@ impl<'a> Planner<'a> {
         }
 
         // Calculate step cost based on optimization target
-        let step_cost = self.cost_for_converter(decl);
+        let estimate = current
+            .size
+            .map(|size| decl.resources.estimate(&decl.id, size, &current.properties));
+        let step_cost = self
+            .learned_cost(decl, current)
+            .unwrap_or_else(|| self.cost_for_converter(decl, estimate.as_ref()));
 
         let new_cost = current.cost + step_cost;
-        let heuristic = self.heuristic(&output_props, target);
+        let heuristic = self.heuristic(&output_props, target, min_step_cost);
 
         let step = PlanStep {
             converter_id: decl.id.clone(),
             input_port: input_port.clone(),
             output_port: output_port.clone(),
             output_properties: output_props.clone(),
+            options: Properties::new(),
         };
 
         let mut new_steps = current.steps.clone();
@@ -272,22 +340,39 @@ impl<'a> Planner<'a> {
             properties: output_props,
             cardinality: new_cardinality,
             steps: new_steps,
+            size: estimate.map(|e| e.output_size),
             cost: new_cost,
             estimated_total: new_cost + heuristic,
+            priority: current.priority + i64::from(decl.priority),
         })
     }
 
     /// Heuristic: estimate remaining cost to goal.
     ///
-    /// Currently just counts mismatched properties.
-    fn heuristic(&self, current: &Properties, target: &PropertyPattern) -> f64 {
-        let mut mismatches = 0;
-        for (key, predicate) in &target.predicates {
-            if !current.get(key).is_some_and(|v| predicate.matches(v)) {
-                mismatches += 1;
-            }
+    /// Any mismatched property needs at least one more step. A single step
+    /// can fix several properties, so that is all A* may assume without
+    /// overestimating.
+    fn heuristic(&self, current: &Properties, target: &PropertyPattern, min_step_cost: f64) -> f64 {
+        let mismatched = target
+            .predicates
+            .iter()
+            .any(|(key, predicate)| !current.get(key).is_some_and(|v| predicate.matches(v)));
+        if mismatched { min_step_cost } else { 0.0 }
+    }
+
+    /// Lowest cost any single step can have.
+    ///
+    /// Modelled and learned costs can be arbitrarily small, so with either
+    /// in play the only safe bound is zero.
+    fn min_step_cost(&self) -> f64 {
+        if self.learned.is_some() || self.input_size.is_some() {
+            return 0.0;
         }
-        mismatches as f64
+        self.registry
+            .declarations()
+            .map(|decl| static_cost(decl, self.optimize))
+            .reduce(f64::min)
+            .map_or(0.0, |cost| cost.max(0.0))
     }
 
     /// Create a state key for visited tracking.
@@ -301,30 +386,73 @@ impl<'a> Planner<'a> {
         format!("{}:{:?}", format, cardinality)
     }
 
+    /// Get the learned cost for a converter, if the store has enough samples.
+    ///
+    /// Speed uses observed seconds per step in `speed` units, Size the
+    /// observed output/input ratio. Quality loss cannot be observed and is
+    /// never learned.
+    fn learned_cost(&self, decl: &ConverterDecl, current: &SearchNode) -> Option<f64> {
+        let format = current.properties.get("format").and_then(|v| v.as_str());
+        let learned = self.learned?.lookup(&decl.id, format, current.size)?;
+        match self.optimize {
+            OptimizeTarget::Speed => {
+                Some(learned.seconds / speed_unit_seconds(current.size.unwrap_or(0)))
+            }
+            OptimizeTarget::Size => Some(learned.size_ratio),
+            OptimizeTarget::Quality => None,
+        }
+    }
+
     /// Get the cost for a converter based on optimization target.
     ///
     /// Cost properties:
     /// - `quality_loss`: higher = more quality degradation (used for Quality optimization)
     /// - `speed`: higher = slower (used for Speed optimization)
-    /// - `size`: higher = larger output (used for Size optimization)
+    /// - `size`: output/input size ratio (used for Size optimization)
     ///
     /// Falls back to generic `cost` property, then to 1.0.
-    fn cost_for_converter(&self, decl: &ConverterDecl) -> f64 {
-        let cost_key = match self.optimize {
-            OptimizeTarget::Quality => "quality_loss",
-            OptimizeTarget::Speed => "speed",
-            OptimizeTarget::Size => "size",
-        };
+    ///
+    /// When the step's input size is known, declared resource models take
+    /// precedence: the time model for Speed, converted to `speed` units with
+    /// [`speed_unit_seconds`], and the output/input size ratio for Size.
+    fn cost_for_converter(&self, decl: &ConverterDecl, estimate: Option<&StepEstimate>) -> f64 {
+        if let Some(estimate) = estimate {
+            let modelled = match self.optimize {
+                OptimizeTarget::Speed => estimate
+                    .time
+                    .map(|seconds| seconds / speed_unit_seconds(estimate.input_size)),
+                OptimizeTarget::Size if decl.resources.output_size.is_some() => {
+                    Some(estimate.output_size as f64 / estimate.input_size.max(1) as f64)
+                }
+                _ => None,
+            };
+            if let Some(cost) = modelled {
+                return cost;
+            }
+        }
 
-        // Try optimization-specific cost, fall back to generic "cost", then 1.0
-        decl.costs
-            .get(cost_key)
-            .and_then(|v| v.as_f64())
-            .or_else(|| decl.costs.get("cost").and_then(|v| v.as_f64()))
-            .unwrap_or(1.0)
+        static_cost(decl, self.optimize)
     }
 }
 
+/// Declared cost of a converter for an optimization target.
+///
+/// Tries the target-specific cost property, then the generic `cost`, then
+/// falls back to 1.0.
+pub(crate) fn static_cost(decl: &ConverterDecl, target: OptimizeTarget) -> f64 {
+    let cost_key = match target {
+        OptimizeTarget::Quality => "quality_loss",
+        OptimizeTarget::Speed => "speed",
+        OptimizeTarget::Size => "size",
+    };
+
+    decl.costs
+        .get(cost_key)
+        .and_then(|v| v.as_f64())
+        .or_else(|| decl.costs.get("cost").and_then(|v| v.as_f64()))
+        .unwrap_or(1.0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -512,4 +640,217 @@ mod tests {
         assert_eq!(quality_plan.steps.len(), 1);
         assert_eq!(quality_plan.steps[0].converter_id, "a-to-c-slow");
     }
+
+    #[test]
+    fn test_time_model_depends_on_input_size() {
+        use crate::Formula;
+
+        // Direct path has a large fixed overhead but scales well;
+        // the 2-hop path is cheap to start but scales poorly.
+        let mut registry = Registry::new();
+        registry.register_decl(
+            ConverterDecl::simple(
+                "a-to-c",
+                PropertyPattern::new().eq("format", "a"),
+                PropertyPattern::new().eq("format", "c"),
+            )
+            .time_model(Formula::constant(10.0) + Formula::input_size() * 0.001),
+        );
+        for (id, from, to) in [("a-to-b", "a", "b"), ("b-to-c", "b", "c")] {
+            registry.register_decl(
+                ConverterDecl::simple(
+                    id,
+                    PropertyPattern::new().eq("format", from),
+                    PropertyPattern::new().eq("format", to),
+                )
+                .time_model(Formula::input_size() * 0.01),
+            );
+        }
+
+        let source = Properties::new().with("format", "a");
+        let target = PropertyPattern::new().eq("format", "c");
+        let plan_for = |size| {
+            Planner::new(&registry)
+                .input_size(size)
+                .plan(&source, &target, Cardinality::One, Cardinality::One)
+                .expect("should find plan")
+        };
+
+        assert_eq!(plan_for(100).steps.len(), 2);
+        assert_eq!(plan_for(100_000).steps.len(), 1);
+    }
+    #[test]
+    fn test_heuristic_admissible_for_small_costs() {
+        // One cheap hop of 0.5 against two of 0.01 each
+        let mut registry = Registry::new();
+        registry.register_decl(
+            ConverterDecl::simple(
+                "a-to-c",
+                PropertyPattern::new().eq("format", "a"),
+                PropertyPattern::new().eq("format", "c"),
+            )
+            .cost("speed", 0.5),
+        );
+        for (id, from, to) in [("a-to-b", "a", "b"), ("b-to-c", "b", "c")] {
+            registry.register_decl(
+                ConverterDecl::simple(
+                    id,
+                    PropertyPattern::new().eq("format", from),
+                    PropertyPattern::new().eq("format", to),
+                )
+                .cost("speed", 0.01),
+            );
+        }
+
+        let source = Properties::new().with("format", "a");
+        let target = PropertyPattern::new().eq("format", "c");
+        let plan = Planner::new(&registry)
+            .plan(&source, &target, Cardinality::One, Cardinality::One)
+            .unwrap();
+        assert_eq!(plan.steps.len(), 2);
+        assert!((plan.cost - 0.02).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_learned_costs_override_static() {
+        use crate::{MIN_SAMPLES, StepStats};
+        use std::time::Duration;
+
+        // Static costs favour the direct path...
+        let mut registry = Registry::new();
+        registry.register_decl(
+            ConverterDecl::simple(
+                "a-to-c",
+                PropertyPattern::new().eq("format", "a"),
+                PropertyPattern::new().eq("format", "c"),
+            )
+            .cost("speed", 0.1),
+        );
+        for (id, from, to) in [("a-to-b", "a", "b"), ("b-to-c", "b", "c")] {
+            registry.register_decl(
+                ConverterDecl::simple(
+                    id,
+                    PropertyPattern::new().eq("format", from),
+                    PropertyPattern::new().eq("format", to),
+                )
+                .cost("speed", 1.0),
+            );
+        }
+
+        let source = Properties::new().with("format", "a");
+        let target = PropertyPattern::new().eq("format", "c");
+        let plan = Planner::new(&registry)
+            .plan(&source, &target, Cardinality::One, Cardinality::One)
+            .unwrap();
+        assert_eq!(plan.steps.len(), 1);
+
+        // ...but it turned out to be slow in practice
+        let mut store = CostStore::new();
+        for (id, format, secs) in [
+            ("a-to-c", "a", 5.0),
+            ("a-to-b", "a", 0.01),
+            ("b-to-c", "b", 0.01),
+        ] {
+            for _ in 0..MIN_SAMPLES {
+                store.record_step(&StepStats {
+                    converter_id: id.into(),
+                    input_format: Some(format.into()),
+                    input_size: 1000,
+                    output_size: 1000,
+                    duration: Duration::from_secs_f64(secs),
+                });
+            }
+        }
+
+        let plan = Planner::new(&registry)
+            .learned_costs(&store)
+            .plan(&source, &target, Cardinality::One, Cardinality::One)
+            .unwrap();
+        assert_eq!(plan.steps.len(), 2);
+    }
+    #[test]
+    fn test_plan_input_runs_inspectors() {
+        use crate::{ConvertError, Inspector, InspectorDecl};
+
+        struct WidthInspector(InspectorDecl);
+
+        impl Inspector for WidthInspector {
+            fn decl(&self) -> &InspectorDecl {
+                &self.0
+            }
+
+            fn inspect(
+                &self,
+                input: &[u8],
+                _props: &Properties,
+            ) -> Result<Properties, ConvertError> {
+                Ok(Properties::new().with("width", input.len() as i64))
+            }
+        }
+
+        // Converter only applies once the width is known
+        let mut registry = Registry::new();
+        registry.register_decl(ConverterDecl::simple(
+            "a-to-b",
+            PropertyPattern::new().eq("format", "a").exists("width"),
+            PropertyPattern::new().eq("format", "b"),
+        ));
+        registry.register_inspector(WidthInspector(InspectorDecl::new(
+            "a-width",
+            PropertyPattern::new().eq("format", "a"),
+        )));
+
+        let source = Properties::new().with("format", "a");
+        let target = PropertyPattern::new().eq("format", "b");
+        let planner = Planner::new(&registry);
+
+        assert!(
+            planner
+                .plan(&source, &target, Cardinality::One, Cardinality::One)
+                .is_none()
+        );
+
+        let (plan, props) = planner
+            .plan_input(b"abc", &source, &target, Cardinality::One, Cardinality::One)
+            .expect("should find plan");
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(props.get("width").and_then(|v| v.as_i64()), Some(3));
+    }
+
+    #[test]
+    fn test_priority_breaks_ties() {
+        let mut registry = Registry::new();
+        for (id, priority) in [("builtin.png-to-webp", 0), ("plugin.png-to-webp", 10)] {
+            registry.register_decl(
+                ConverterDecl::simple(
+                    id,
+                    PropertyPattern::new().eq("format", "png"),
+                    PropertyPattern::new().eq("format", "webp"),
+                )
+                .priority(priority),
+            );
+        }
+        let source = Properties::new().with("format", "png");
+        let target = PropertyPattern::new().eq("format", "webp");
+
+        let plan = Planner::new(&registry)
+            .plan(&source, &target, Cardinality::One, Cardinality::One)
+            .unwrap();
+        assert_eq!(plan.steps[0].converter_id, "plugin.png-to-webp");
+
+        // Cost still comes first.
+        registry.register_decl(
+            ConverterDecl::simple(
+                "plugin.png-to-webp",
+                PropertyPattern::new().eq("format", "png"),
+                PropertyPattern::new().eq("format", "webp"),
+            )
+            .priority(10)
+            .cost("speed", 5.0),
+        );
+        let plan = Planner::new(&registry)
+            .plan(&source, &target, Cardinality::One, Cardinality::One)
+            .unwrap();
+        assert_eq!(plan.steps[0].converter_id, "builtin.png-to-webp");
+    }
 }
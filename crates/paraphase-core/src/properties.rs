@@ -3,18 +3,37 @@
 //! Data is described by property bags, not hierarchical types.
 //! Format is just another property.
 
+use crate::scalar::{self, Decimal, Timestamp};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// A JSON-like value that can represent any property.
+///
+/// Beyond JSON, values can hold unsigned 64-bit integers, exact decimals,
+/// timestamps, durations and raw bytes. The non-JSON variants serialize as
+/// single-key objects (`{"$duration": "PT90.5S"}`), so property bags keep
+/// round-tripping through JSON, YAML and TOML.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Value {
     Null,
     Bool(bool),
     Int(i64),
+    /// Integers above `i64::MAX` (sizes, hashes, counters).
+    UInt(u64),
     Float(f64),
     String(String),
+    /// Exact decimal number.
+    Decimal(Decimal),
+    /// Point in time (UTC).
+    Timestamp(Timestamp),
+    /// Length of time.
+    #[serde(with = "scalar::duration")]
+    Duration(Duration),
+    /// Binary data (ICC profiles, thumbnails).
+    #[serde(with = "scalar::bytes")]
+    Bytes(Vec<u8>),
     Array(Vec<Value>),
     Object(IndexMap<String, Value>),
 }
@@ -34,14 +53,54 @@ impl Value {
     pub fn as_i64(&self) -> Option<i64> {
         match self {
             Value::Int(n) => Some(*n),
+            Value::UInt(n) => i64::try_from(*n).ok(),
             _ => None,
         }
     }
 
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::UInt(n) => Some(*n),
+            Value::Int(n) => u64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+
+    /// Any number as `f64` (decimals and large integers may be rounded).
     pub fn as_f64(&self) -> Option<f64> {
         match self {
             Value::Float(n) => Some(*n),
             Value::Int(n) => Some(*n as f64),
+            Value::UInt(n) => Some(*n as f64),
+            Value::Decimal(d) => Some(d.to_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_decimal(&self) -> Option<&Decimal> {
+        match self {
+            Value::Decimal(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Value::Timestamp(t) => Some(*t),
+            _ => None,
+        }
+    }
+
+    pub fn as_duration(&self) -> Option<Duration> {
+        match self {
+            Value::Duration(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(b) => Some(b),
             _ => None,
         }
     }
@@ -86,6 +145,13 @@ impl From<i32> for Value {
     }
 }
 
+impl From<u64> for Value {
+    /// Values that fit stay `Int`, so equality with small literals holds.
+    fn from(n: u64) -> Self {
+        i64::try_from(n).map_or(Value::UInt(n), Value::Int)
+    }
+}
+
 impl From<f64> for Value {
     fn from(n: f64) -> Self {
         Value::Float(n)
@@ -104,6 +170,30 @@ impl From<&str> for Value {
     }
 }
 
+impl From<Decimal> for Value {
+    fn from(d: Decimal) -> Self {
+        Value::Decimal(d)
+    }
+}
+
+impl From<Timestamp> for Value {
+    fn from(t: Timestamp) -> Self {
+        Value::Timestamp(t)
+    }
+}
+
+impl From<Duration> for Value {
+    fn from(d: Duration) -> Self {
+        Value::Duration(d)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(b: &[u8]) -> Self {
+        Value::Bytes(b.to_vec())
+    }
+}
+
 impl<T: Into<Value>> From<Vec<T>> for Value {
     fn from(arr: Vec<T>) -> Self {
         Value::Array(arr.into_iter().map(Into::into).collect())
@@ -151,11 +241,53 @@ mod tests {
         assert_eq!(props.get("width").and_then(Value::as_i64), Some(1024));
     }
 
+    #[test]
+    fn test_extended_serde_roundtrip() {
+        let props = Properties::new()
+            .with("size", u64::MAX)
+            .with("small", 5u64)
+            .with("price", Decimal::parse("19.90").unwrap())
+            .with("taken", Timestamp::parse("2024-05-01T12:30:00Z").unwrap())
+            .with("duration", Duration::from_millis(90_500))
+            .with("icc", &b"\x00\x01icc"[..])
+            .with("meta", Value::Object(IndexMap::new()));
+
+        let json = serde_json::to_string(&props).unwrap();
+        assert_eq!(
+            json,
+            r#"{"size":18446744073709551615,"small":5,"price":{"$decimal":"19.9"},"taken":{"$timestamp":"2024-05-01T12:30:00Z"},"duration":{"$duration":"PT90.5S"},"icc":{"$bytes":"AAFpY2M="},"meta":{}}"#
+        );
+        let back: Properties = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, props);
+        assert_eq!(back["small"], Value::Int(5));
+
+        let yaml = serde_yaml::to_string(&props).unwrap();
+        let back: Properties = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(back, props);
+
+        // Ordinary objects that merely look similar stay objects.
+        let obj: Value = serde_json::from_str(r#"{"$duration": "soon"}"#).unwrap();
+        assert!(obj.as_object().is_some());
+    }
+
     #[test]
     fn test_value_accessors() {
         let v = Value::Int(42);
         assert_eq!(v.as_i64(), Some(42));
         assert_eq!(v.as_f64(), Some(42.0));
         assert_eq!(v.as_str(), None);
+
+        assert_eq!(Value::UInt(u64::MAX).as_i64(), None);
+        assert_eq!(Value::Int(-1).as_u64(), None);
+        assert_eq!(Value::Int(7).as_u64(), Some(7));
+        assert_eq!(
+            Value::from(Decimal::parse("2.5").unwrap()).as_f64(),
+            Some(2.5)
+        );
+        assert_eq!(
+            Value::from(Duration::from_secs(3)).as_duration(),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(Value::from(&b"ab"[..]).as_bytes(), Some(&b"ab"[..]));
     }
 }
@@ -1,20 +1,68 @@
 //! Registry for converters.
 
 use crate::converter::{Converter, ConverterDecl};
+use crate::format::{FormatDecl, Formats};
+use crate::graph::ConverterGraph;
+use crate::index::DeclIndex;
+use crate::inspector::{Inspector, InspectorDecl};
+use crate::planner::OptimizeTarget;
 use crate::properties::Properties;
+use crate::roundtrip::{Comparator, ComparatorDecl};
+use crate::vocabulary::{PropertyDecl, Vocabulary};
 use indexmap::IndexMap;
 use std::sync::Arc;
 
+/// What registering a converter with an existing ID does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// The new converter replaces the existing one.
+    #[default]
+    Replace,
+    /// Registration fails; the existing converter stays.
+    Reject,
+}
+
+/// Errors from registering converters.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RegistryError {
+    #[error("converter `{id}` is already registered{}", provided_by(provider))]
+    Conflict { id: String, provider: String },
+}
+
+fn provided_by(provider: &str) -> String {
+    if provider.is_empty() {
+        String::new()
+    } else {
+        format!(" by {}", provider)
+    }
+}
+
 /// Registry of available converters.
 ///
 /// The registry holds converter declarations and (optionally) their implementations.
 /// It provides methods for querying which converters can handle given properties.
+/// Declarations are indexed on the equality predicates of their input ports,
+/// so lookups don't scan every converter.
 #[derive(Clone)]
 pub struct Registry {
     /// Converter declarations indexed by ID.
     declarations: IndexMap<String, ConverterDecl>,
+    /// Lookup index over `declarations`.
+    index: DeclIndex,
     /// Converter implementations indexed by ID.
     implementations: IndexMap<String, Arc<dyn Converter>>,
+    /// Content inspectors indexed by ID.
+    inspectors: IndexMap<String, Arc<dyn Inspector>>,
+    /// Round-trip comparators indexed by ID.
+    comparators: IndexMap<String, Arc<dyn Comparator>>,
+    /// What to do when an ID is registered twice.
+    policy: ConflictPolicy,
+    /// Provider recorded on converters registered without one.
+    provider: String,
+    /// Declared property keys.
+    vocabulary: Vocabulary,
+    /// Known formats.
+    formats: Formats,
 }
 
 impl Default for Registry {
@@ -28,23 +76,119 @@ impl Registry {
     pub fn new() -> Self {
         Self {
             declarations: IndexMap::new(),
+            index: DeclIndex::default(),
             implementations: IndexMap::new(),
+            inspectors: IndexMap::new(),
+            comparators: IndexMap::new(),
+            policy: ConflictPolicy::default(),
+            provider: String::new(),
+            vocabulary: Vocabulary::well_known(),
+            formats: Formats::well_known(),
         }
     }
 
+    /// Set what registering an existing ID does.
+    pub fn conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
     /// Register a converter declaration (without implementation).
     ///
     /// Useful for planning when the actual converter isn't available yet.
+    ///
+    /// # Panics
+    ///
+    /// Under [`ConflictPolicy::Reject`], if the ID is already registered.
     pub fn register_decl(&mut self, decl: ConverterDecl) {
-        self.declarations.insert(decl.id.clone(), decl);
+        if let Err(e) = self.try_register_decl(decl) {
+            panic!("{}", e);
+        }
     }
 
     /// Register a converter with its implementation.
+    ///
+    /// # Panics
+    ///
+    /// Under [`ConflictPolicy::Reject`], if the ID is already registered.
     pub fn register(&mut self, converter: impl Converter + 'static) {
-        let decl = converter.decl().clone();
-        let id = decl.id.clone();
-        self.declarations.insert(id.clone(), decl);
+        if let Err(e) = self.try_register(converter) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Register a converter declaration, following the conflict policy.
+    pub fn try_register_decl(&mut self, decl: ConverterDecl) -> Result<(), RegistryError> {
+        self.check_conflict(&decl.id)?;
+        self.insert_decl(decl);
+        Ok(())
+    }
+
+    /// Register a converter with its implementation, following the conflict
+    /// policy.
+    pub fn try_register(
+        &mut self,
+        converter: impl Converter + 'static,
+    ) -> Result<(), RegistryError> {
+        let id = converter.decl().id.clone();
+        self.check_conflict(&id)?;
+        self.insert_decl(converter.decl().clone());
         self.implementations.insert(id, Arc::new(converter));
+        Ok(())
+    }
+
+    /// Run `register` with `provider` recorded on every converter it
+    /// registers that does not name a provider itself.
+    ///
+    /// Domain crates wrap their `register_all` in this, so converters list
+    /// the crate they come from.
+    pub fn with_provider(&mut self, provider: &str, register: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.provider, provider.to_string());
+        register(self);
+        self.provider = outer;
+    }
+
+    /// Remove a converter. Returns its declaration if it was registered.
+    pub fn unregister(&mut self, id: &str) -> Option<ConverterDecl> {
+        let decl = self.declarations.shift_remove(id)?;
+        self.implementations.shift_remove(id);
+        self.index = DeclIndex::rebuild(self.declarations.values());
+        Some(decl)
+    }
+
+    /// Remove every converter in a namespace. Returns how many were removed.
+    pub fn unregister_namespace(&mut self, namespace: &str) -> usize {
+        let before = self.declarations.len();
+        self.declarations.retain(|_, d| d.namespace() != namespace);
+        let declarations = &self.declarations;
+        self.implementations
+            .retain(|id, _| declarations.contains_key(id));
+        self.index = DeclIndex::rebuild(self.declarations.values());
+        before - self.declarations.len()
+    }
+
+    fn check_conflict(&self, id: &str) -> Result<(), RegistryError> {
+        match (self.policy, self.declarations.get(id)) {
+            (ConflictPolicy::Reject, Some(existing)) => Err(RegistryError::Conflict {
+                id: id.to_string(),
+                provider: existing.provider.clone(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Insert a declaration and keep the index in sync.
+    fn insert_decl(&mut self, mut decl: ConverterDecl) {
+        if decl.provider.is_empty() {
+            decl.provider.clone_from(&self.provider);
+        }
+        let (position, replaced) = self.declarations.insert_full(decl.id.clone(), decl);
+        if replaced.is_some() {
+            // The old ports may be indexed under other values.
+            self.index = DeclIndex::rebuild(self.declarations.values());
+        } else {
+            self.index.insert(position, &self.declarations[position]);
+        }
     }
 
     /// Get a converter declaration by ID.
@@ -62,24 +206,127 @@ impl Registry {
         self.declarations.values()
     }
 
+    /// Declarations in a namespace (see [`ConverterDecl::namespace`]).
+    pub fn namespace<'a>(&'a self, namespace: &'a str) -> impl Iterator<Item = &'a ConverterDecl> {
+        self.declarations
+            .values()
+            .filter(move |d| d.namespace() == namespace)
+    }
+
+    /// Declarations that may accept `props`, in registration order.
+    ///
+    /// Narrowed down by the index; callers still match the input patterns.
+    pub(crate) fn candidates(&self, props: &Properties) -> impl Iterator<Item = &ConverterDecl> {
+        self.index
+            .candidates(props)
+            .into_iter()
+            .map(|position| &self.declarations[position])
+    }
+
     /// Find all converters that can handle the given input properties.
     ///
     /// Returns converter IDs and the name of the matching input port.
     pub fn find_matching(&self, props: &Properties) -> Vec<(&str, &str)> {
-        self.declarations
-            .iter()
-            .filter_map(|(id, decl)| decl.matches_input(props).map(|port| (id.as_str(), port)))
+        self.candidates(props)
+            .filter_map(|decl| {
+                decl.matches_input(props)
+                    .map(|port| (decl.id.as_str(), port))
+            })
             .collect()
     }
 
     /// Find simple (1→1) converters that can handle the given input properties.
     pub fn find_simple_matching(&self, props: &Properties) -> Vec<&ConverterDecl> {
-        self.declarations
-            .values()
+        self.candidates(props)
             .filter(|decl| decl.is_simple() && decl.matches_input(props).is_some())
             .collect()
     }
 
+    /// Format/converter graph of all declarations, with edge costs for
+    /// `target`.
+    pub fn graph(&self, target: OptimizeTarget) -> ConverterGraph {
+        ConverterGraph::from_registry(self, target)
+    }
+
+    /// Register a content inspector.
+    pub fn register_inspector(&mut self, inspector: impl Inspector + 'static) {
+        let id = inspector.decl().id.clone();
+        self.inspectors.insert(id, Arc::new(inspector));
+    }
+
+    /// Get an inspector by ID.
+    pub fn get_inspector(&self, id: &str) -> Option<Arc<dyn Inspector>> {
+        self.inspectors.get(id).cloned()
+    }
+
+    /// Iterate over all inspector declarations.
+    pub fn inspectors(&self) -> impl Iterator<Item = &InspectorDecl> {
+        self.inspectors.values().map(|i| i.decl())
+    }
+
+    /// Run all inspectors matching `props` against the input.
+    ///
+    /// Inspectors run in registration order, each seeing the properties found
+    /// by the ones before it. Properties already present are never replaced,
+    /// so explicit properties win over inspected ones. Inspection is
+    /// best-effort: an inspector that fails contributes nothing.
+    pub fn inspect(&self, input: &[u8], props: &Properties) -> Properties {
+        let mut result = props.clone();
+        for inspector in self.inspectors.values() {
+            if !inspector.decl().pattern.matches(&result) {
+                continue;
+            }
+            if let Ok(found) = inspector.inspect(input, &result) {
+                for (key, value) in found {
+                    result.entry(key).or_insert(value);
+                }
+            }
+        }
+        result
+    }
+
+    /// Register a round-trip comparator.
+    pub fn register_comparator(&mut self, comparator: impl Comparator + 'static) {
+        let id = comparator.decl().id.clone();
+        self.comparators.insert(id, Arc::new(comparator));
+    }
+
+    /// Iterate over all comparator declarations.
+    pub fn comparators(&self) -> impl Iterator<Item = &ComparatorDecl> {
+        self.comparators.values().map(|c| c.decl())
+    }
+
+    /// First comparator (in registration order) whose pattern matches `props`.
+    pub fn find_comparator(&self, props: &Properties) -> Option<Arc<dyn Comparator>> {
+        self.comparators
+            .values()
+            .find(|c| c.decl().pattern.matches(props))
+            .cloned()
+    }
+
+    /// Declare a property key produced by registered converters or
+    /// inspectors.
+    pub fn declare_property(&mut self, decl: PropertyDecl) {
+        self.vocabulary.declare(decl);
+    }
+
+    /// Declared property keys: the well-known core keys plus those declared
+    /// by domain crates.
+    pub fn vocabulary(&self) -> &Vocabulary {
+        &self.vocabulary
+    }
+
+    /// Describe a format handled by registered converters.
+    pub fn register_format(&mut self, decl: FormatDecl) {
+        self.formats.register(decl);
+    }
+
+    /// Known formats: the workflow formats core reads plus those registered
+    /// by domain crates.
+    pub fn formats(&self) -> &Formats {
+        &self.formats
+    }
+
     /// Number of registered converters.
     pub fn len(&self) -> usize {
         self.declarations.len()
@@ -176,4 +423,174 @@ mod tests {
         assert!(registry.get_decl("png-to-webp").is_some());
         assert!(registry.get_decl("nonexistent").is_none());
     }
+
+    #[test]
+    fn test_index_candidates() {
+        let mut registry = make_test_registry();
+        registry.register_decl(ConverterDecl::simple(
+            "resize",
+            PropertyPattern::new().with("width", crate::Predicate::Gt(0.0)),
+            PropertyPattern::new().with("width", crate::Predicate::Any),
+        ));
+        registry.register_decl(ConverterDecl::simple(
+            "text-to-utf8",
+            PropertyPattern::new().eq("encoding", "latin1"),
+            PropertyPattern::new().eq("encoding", "utf8"),
+        ));
+
+        let ids = |props: &Properties| -> Vec<String> {
+            registry.candidates(props).map(|d| d.id.clone()).collect()
+        };
+
+        // Only png converters and the unindexable resize are candidates.
+        let png = Properties::new().with("format", "png");
+        assert_eq!(
+            ids(&png),
+            ["png-to-webp", "png-to-jpg", "frames-to-gif", "resize"]
+        );
+
+        // Ports without a format are indexed on another equality.
+        let latin1 = Properties::new().with("encoding", "latin1");
+        assert_eq!(ids(&latin1), ["resize", "text-to-utf8"]);
+        assert_eq!(registry.find_matching(&latin1), [("text-to-utf8", "in")]);
+    }
+
+    #[test]
+    fn test_index_follows_replacement() {
+        let mut registry = make_test_registry();
+        registry.register_decl(ConverterDecl::simple(
+            "png-to-webp",
+            PropertyPattern::new().eq("format", "bmp"),
+            PropertyPattern::new().eq("format", "webp"),
+        ));
+
+        let png = Properties::new().with("format", "png");
+        assert!(
+            !registry
+                .find_matching(&png)
+                .iter()
+                .any(|(id, _)| *id == "png-to-webp")
+        );
+        let bmp = Properties::new().with("format", "bmp");
+        assert_eq!(registry.find_matching(&bmp), [("png-to-webp", "in")]);
+        assert_eq!(registry.len(), 4);
+    }
+
+    #[test]
+    fn test_conflict_policy() {
+        let decl = || {
+            ConverterDecl::simple(
+                "image.png-to-webp",
+                PropertyPattern::new().eq("format", "png"),
+                PropertyPattern::new().eq("format", "webp"),
+            )
+        };
+
+        let mut registry = Registry::new();
+        registry.register_decl(decl().provider("paraphase-image"));
+        registry.register_decl(decl().provider("my-plugin").version("2.0"));
+        assert_eq!(registry.len(), 1);
+        assert_eq!(
+            registry.get_decl("image.png-to-webp").unwrap().provider,
+            "my-plugin"
+        );
+
+        let mut registry = Registry::new().conflict_policy(ConflictPolicy::Reject);
+        registry.register_decl(decl().provider("paraphase-image"));
+        let err = registry
+            .try_register_decl(decl().provider("my-plugin"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "converter `image.png-to-webp` is already registered by paraphase-image"
+        );
+        assert_eq!(
+            registry.get_decl("image.png-to-webp").unwrap().provider,
+            "paraphase-image"
+        );
+
+        // Unregistering frees the ID.
+        assert!(registry.unregister("image.png-to-webp").is_some());
+        assert!(registry.unregister("image.png-to-webp").is_none());
+        assert!(registry.try_register_decl(decl()).is_ok());
+
+        // A provider scope attributes converters that don't name a provider.
+        let mut registry = Registry::new();
+        registry.with_provider("paraphase-image", |registry| {
+            registry.register_decl(decl());
+        });
+        registry.register_decl(ConverterDecl::simple(
+            "image.png-to-gif",
+            PropertyPattern::new().eq("format", "png"),
+            PropertyPattern::new().eq("format", "gif"),
+        ));
+        assert_eq!(
+            registry.get_decl("image.png-to-webp").unwrap().provider,
+            "paraphase-image"
+        );
+        assert_eq!(registry.get_decl("image.png-to-gif").unwrap().provider, "");
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let mut registry = make_test_registry();
+        registry.register_decl(ConverterDecl::simple(
+            "serde.json-to-yaml",
+            PropertyPattern::new().eq("format", "json"),
+            PropertyPattern::new().eq("format", "yaml"),
+        ));
+        assert_eq!(registry.namespace("serde").count(), 1);
+        assert_eq!(registry.namespace("").count(), 4);
+
+        assert_eq!(registry.unregister_namespace(""), 4);
+        assert_eq!(registry.len(), 1);
+        let png = Properties::new().with("format", "png");
+        assert!(registry.find_matching(&png).is_empty());
+    }
+
+    struct DimensionsInspector(InspectorDecl);
+
+    impl Inspector for DimensionsInspector {
+        fn decl(&self) -> &InspectorDecl {
+            &self.0
+        }
+
+        fn inspect(
+            &self,
+            input: &[u8],
+            _props: &Properties,
+        ) -> Result<Properties, crate::ConvertError> {
+            if input.len() < 2 {
+                return Err(crate::ConvertError::InvalidInput("too short".into()));
+            }
+            Ok(Properties::new()
+                .with("width", input[0] as i64)
+                .with("height", input[1] as i64))
+        }
+    }
+
+    #[test]
+    fn test_inspect() {
+        let mut registry = make_test_registry();
+        registry.register_inspector(DimensionsInspector(
+            InspectorDecl::new("png-dimensions", PropertyPattern::new().eq("format", "png"))
+                .provides("width")
+                .provides("height"),
+        ));
+        assert_eq!(registry.inspectors().count(), 1);
+
+        let png = Properties::new().with("format", "png");
+        let props = registry.inspect(&[3, 4], &png);
+        assert_eq!(props.get("width").and_then(|v| v.as_i64()), Some(3));
+        assert_eq!(props.get("height").and_then(|v| v.as_i64()), Some(4));
+
+        // Explicit properties win
+        let props = registry.inspect(&[3, 4], &png.clone().with("width", 100i64));
+        assert_eq!(props.get("width").and_then(|v| v.as_i64()), Some(100));
+
+        // Non-matching inputs and failures leave properties untouched
+        let jpg = Properties::new().with("format", "jpg");
+        assert_eq!(registry.inspect(&[3, 4], &jpg), jpg);
+        assert_eq!(registry.inspect(&[], &png), png);
+    }
 }
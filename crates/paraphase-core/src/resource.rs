@@ -0,0 +1,362 @@
+//! Resource models: converter-declared estimates of memory, time and output size.
+//!
+//! A converter can describe how expensive it is as a function of its input,
+//! e.g. "decoding needs width * height * 4 bytes" or "gzip output is at most
+//! 1.1x the input". Executors use the memory model to reserve budget, and the
+//! planner uses the time and output-size models to rank paths.
+//!
+//! Steps without a model (or whose model references properties that are not
+//! known yet) fall back to a heuristic keyed on the converter ID prefix.
+
+use crate::planner::Plan;
+use crate::properties::Properties;
+use crate::registry::Registry;
+use serde::{Deserialize, Serialize};
+
+/// An arithmetic expression over a step's input.
+///
+/// Serializes as e.g. `{"mul": [{"prop": "width"}, {"prop": "height"}, {"const": 4.0}]}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Formula {
+    /// A constant.
+    Const(f64),
+    /// Size of the step's input in bytes.
+    InputSize,
+    /// A numeric input property (e.g. `width`, `sample_rate`).
+    Prop(String),
+    /// Sum of sub-expressions.
+    Add(Vec<Formula>),
+    /// Product of sub-expressions.
+    Mul(Vec<Formula>),
+    /// Maximum of sub-expressions.
+    Max(Vec<Formula>),
+}
+
+impl Formula {
+    /// A constant value.
+    pub fn constant(value: f64) -> Self {
+        Formula::Const(value)
+    }
+
+    /// The input size in bytes.
+    pub fn input_size() -> Self {
+        Formula::InputSize
+    }
+
+    /// A numeric input property.
+    pub fn prop(key: impl Into<String>) -> Self {
+        Formula::Prop(key.into())
+    }
+
+    /// Maximum of two expressions.
+    pub fn max(self, other: impl Into<Formula>) -> Self {
+        match self {
+            Formula::Max(mut terms) => {
+                terms.push(other.into());
+                Formula::Max(terms)
+            }
+            f => Formula::Max(vec![f, other.into()]),
+        }
+    }
+
+    /// Evaluate against a step's input.
+    ///
+    /// Returns `None` if a referenced property is missing or not numeric.
+    pub fn eval(&self, input_size: usize, props: &Properties) -> Option<f64> {
+        match self {
+            Formula::Const(value) => Some(*value),
+            Formula::InputSize => Some(input_size as f64),
+            Formula::Prop(key) => props.get(key).and_then(|v| v.as_f64()),
+            Formula::Add(terms) => terms.iter().map(|t| t.eval(input_size, props)).sum(),
+            Formula::Mul(terms) => terms.iter().map(|t| t.eval(input_size, props)).product(),
+            Formula::Max(terms) => terms
+                .iter()
+                .map(|t| t.eval(input_size, props))
+                .try_fold(f64::NEG_INFINITY, |acc, v| v.map(|v| acc.max(v))),
+        }
+    }
+}
+
+impl From<f64> for Formula {
+    fn from(value: f64) -> Self {
+        Formula::Const(value)
+    }
+}
+
+impl<T: Into<Formula>> std::ops::Add<T> for Formula {
+    type Output = Formula;
+
+    fn add(self, rhs: T) -> Formula {
+        match self {
+            Formula::Add(mut terms) => {
+                terms.push(rhs.into());
+                Formula::Add(terms)
+            }
+            f => Formula::Add(vec![f, rhs.into()]),
+        }
+    }
+}
+
+impl<T: Into<Formula>> std::ops::Mul<T> for Formula {
+    type Output = Formula;
+
+    fn mul(self, rhs: T) -> Formula {
+        match self {
+            Formula::Mul(mut terms) => {
+                terms.push(rhs.into());
+                Formula::Mul(terms)
+            }
+            f => Formula::Mul(vec![f, rhs.into()]),
+        }
+    }
+}
+
+/// Resource models declared by a converter.
+///
+/// All models are evaluated against the step's input size and properties.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceModel {
+    /// Peak memory in bytes allocated by the step, including its output
+    /// but not its input buffer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Formula>,
+    /// Processing time in seconds.
+    ///
+    /// The planner compares it with static `speed` costs through
+    /// [`speed_unit_seconds`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<Formula>,
+    /// Output size in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_size: Option<Formula>,
+}
+
+impl ResourceModel {
+    /// Check if no models are declared.
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_none() && self.time.is_none() && self.output_size.is_none()
+    }
+
+    /// Estimate a single step, falling back to the ID heuristic where needed.
+    pub fn estimate(
+        &self,
+        converter_id: &str,
+        input_size: usize,
+        props: &Properties,
+    ) -> StepEstimate {
+        let eval = |f: &Option<Formula>| {
+            f.as_ref()
+                .and_then(|f| f.eval(input_size, props))
+                .map(to_bytes)
+        };
+
+        let output_size = eval(&self.output_size)
+            .unwrap_or_else(|| input_size.saturating_mul(expansion_factor(converter_id)));
+        let memory = eval(&self.memory).unwrap_or(output_size);
+        let time = self.time.as_ref().and_then(|f| f.eval(input_size, props));
+
+        StepEstimate {
+            input_size,
+            output_size,
+            memory,
+            time,
+        }
+    }
+}
+
+/// Estimated resource usage of one plan step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepEstimate {
+    /// Input size in bytes.
+    pub input_size: usize,
+    /// Output size in bytes.
+    pub output_size: usize,
+    /// Memory allocated by the step, excluding its input.
+    pub memory: usize,
+    /// Processing time, if the converter declares a time model.
+    pub time: Option<f64>,
+}
+
+/// Estimated resource usage of a whole plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanEstimate {
+    /// Size of the plan's input in bytes.
+    pub input_size: usize,
+    /// Per-step estimates, in execution order.
+    pub steps: Vec<StepEstimate>,
+}
+
+impl PlanEstimate {
+    /// Estimate a plan using the resource models declared in `registry`.
+    ///
+    /// Each step is evaluated against the input properties merged with the
+    /// output properties the planner predicted for the preceding steps.
+    pub fn new(registry: &Registry, input_size: usize, props: &Properties, plan: &Plan) -> Self {
+        Self::build(Some(registry), input_size, props, plan)
+    }
+
+    /// Estimate a plan using only the ID-prefix heuristic.
+    pub fn heuristic(input_size: usize, plan: &Plan) -> Self {
+        Self::build(None, input_size, &Properties::new(), plan)
+    }
+
+    fn build(
+        registry: Option<&Registry>,
+        input_size: usize,
+        props: &Properties,
+        plan: &Plan,
+    ) -> Self {
+        let default_model = ResourceModel::default();
+        let mut props = props.clone();
+        let mut current = input_size;
+        let mut steps = Vec::with_capacity(plan.steps.len());
+
+        for step in &plan.steps {
+            let model = registry
+                .and_then(|r| r.get_decl(&step.converter_id))
+                .map_or(&default_model, |decl| &decl.resources);
+            let estimate = model.estimate(&step.converter_id, current, &props);
+            current = estimate.output_size;
+            steps.push(estimate);
+            props.extend(
+                step.output_properties
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone())),
+            );
+        }
+
+        Self { input_size, steps }
+    }
+
+    /// Peak memory of the plan: the largest input plus working memory of a
+    /// single step, assuming each step's input is released once it has run.
+    pub fn peak_memory(&self) -> usize {
+        self.resident_memory(usize::MAX)
+    }
+
+    /// Peak resident memory when intermediates larger than `spill_threshold`
+    /// are moved to disk before the next step runs.
+    pub fn resident_memory(&self, spill_threshold: usize) -> usize {
+        let resident = |size: usize| if size > spill_threshold { 0 } else { size };
+        self.steps
+            .iter()
+            .map(|s| resident(s.input_size).saturating_add(s.memory))
+            .fold(self.input_size, usize::max)
+    }
+
+    /// Estimated size of the final output in bytes.
+    pub fn output_size(&self) -> usize {
+        self.steps.last().map_or(self.input_size, |s| s.output_size)
+    }
+}
+
+/// Seconds that one unit of the static `speed` cost stands for on an input
+/// of `input_size` bytes: 10 ms of overhead plus 10 ms per MiB.
+///
+/// Time models and learned timings are divided by this, so a converter with
+/// `speed` 2.0 ranks like one whose time model predicts twice the reference.
+pub fn speed_unit_seconds(input_size: usize) -> f64 {
+    0.01 + input_size as f64 / (1 << 20) as f64 * 0.01
+}
+
+/// Heuristic expansion factor for a converter, keyed on its ID prefix.
+///
+/// - Audio: ~10x (compressed to PCM)
+/// - Images: ~4x (compressed to RGBA)
+/// - Video: ~100x (compressed to raw frames)
+/// - Everything else: ~1x
+pub(crate) fn expansion_factor(converter_id: &str) -> usize {
+    match converter_id {
+        s if s.starts_with("audio.") => 10,
+        s if s.starts_with("image.") => 4,
+        s if s.starts_with("video.") => 100,
+        _ => 1,
+    }
+}
+
+fn to_bytes(value: f64) -> usize {
+    if value.is_finite() && value > 0.0 {
+        value.ceil() as usize
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConverterDecl, PlanStep, PropertiesExt, PropertyPattern};
+
+    fn step(id: &str) -> PlanStep {
+        PlanStep {
+            converter_id: id.into(),
+            input_port: "in".into(),
+            output_port: "out".into(),
+            output_properties: Properties::new(),
+            options: Properties::new(),
+        }
+    }
+
+    #[test]
+    fn test_formula_eval() {
+        let f = Formula::prop("width") * Formula::prop("height") * 4.0;
+        let props = Properties::new().with("width", 10i64).with("height", 20i64);
+
+        assert_eq!(f.eval(0, &props), Some(800.0));
+        assert_eq!(f.eval(0, &Properties::new()), None);
+
+        let f = Formula::input_size() * 1.1 + 64.0;
+        assert_eq!(f.eval(1000, &props), Some(1164.0));
+
+        let f = Formula::input_size().max(4096.0);
+        assert_eq!(f.eval(1000, &props), Some(4096.0));
+    }
+
+    #[test]
+    fn test_formula_serde() {
+        let f = Formula::prop("width") * Formula::input_size();
+        let json = serde_json::to_string(&f).unwrap();
+        assert_eq!(json, r#"{"mul":[{"prop":"width"},"input_size"]}"#);
+        assert_eq!(serde_json::from_str::<Formula>(&json).unwrap(), f);
+    }
+
+    #[test]
+    fn test_plan_estimate_uses_models() {
+        let mut registry = Registry::new();
+        registry.register_decl(
+            ConverterDecl::simple(
+                "video.decode",
+                PropertyPattern::new().eq("format", "mp4"),
+                PropertyPattern::new().eq("format", "raw"),
+            )
+            .memory_model(
+                Formula::prop("width") * Formula::prop("height") * 4.0 * Formula::prop("frames"),
+            )
+            .output_size_model(Formula::input_size() * 2.0),
+        );
+
+        let plan = Plan {
+            steps: vec![step("video.decode")],
+            cost: 1.0,
+        };
+
+        // Properties known: model applies
+        let props = Properties::new()
+            .with("width", 10i64)
+            .with("height", 10i64)
+            .with("frames", 50i64);
+        let estimate = PlanEstimate::new(&registry, 1000, &props, &plan);
+        assert_eq!(estimate.steps[0].memory, 20_000);
+        assert_eq!(estimate.output_size(), 2000);
+        assert_eq!(estimate.peak_memory(), 21_000);
+
+        // Properties unknown: memory falls back to the output size
+        let estimate = PlanEstimate::new(&registry, 1000, &Properties::new(), &plan);
+        assert_eq!(estimate.steps[0].memory, 2000);
+
+        // No declaration: heuristic 100x for video
+        let estimate = PlanEstimate::heuristic(1000, &plan);
+        assert_eq!(estimate.peak_memory(), 101_000);
+    }
+}
@@ -0,0 +1,308 @@
+//! Round-trip verification: convert back and compare with the original.
+//!
+//! After a conversion, the reverse path (output format back to the input
+//! format) is planned and executed, and the result is compared with the
+//! original input. Byte equality is too strict for most formats (key order,
+//! whitespace, encoder choices), so domain crates register [`Comparator`]s
+//! that compare decoded content: serde values, pixels, samples, geometry.
+
+use crate::converter::ConvertError;
+use crate::executor::{ExecuteError, ExecutionContext, ExecutionResult, Executor};
+use crate::pattern::PropertyPattern;
+use crate::planner::{Cardinality, Plan, Planner};
+use crate::properties::Properties;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Declaration of a comparator's interface.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComparatorDecl {
+    /// Unique identifier for this comparator.
+    pub id: String,
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: String,
+    /// Pattern the original properties must match for the comparator to run.
+    pub pattern: PropertyPattern,
+}
+
+impl ComparatorDecl {
+    /// Create a new comparator declaration.
+    pub fn new(id: impl Into<String>, pattern: PropertyPattern) -> Self {
+        Self {
+            id: id.into(),
+            description: String::new(),
+            pattern,
+        }
+    }
+
+    /// Set the description.
+    pub fn description(mut self, desc: impl Into<String>) -> Self {
+        self.description = desc.into();
+        self
+    }
+}
+
+/// Trait for implementing semantic comparisons.
+pub trait Comparator: Send + Sync {
+    /// Get the declaration for this comparator.
+    fn decl(&self) -> &ComparatorDecl;
+
+    /// Compare two encodings of the same format.
+    ///
+    /// Returns the differences found; an empty list means the contents are
+    /// equivalent. Errors are for inputs that cannot be decoded.
+    fn compare(
+        &self,
+        original: &[u8],
+        roundtrip: &[u8],
+        props: &Properties,
+    ) -> Result<Vec<String>, ConvertError>;
+}
+
+/// Outcome of a round-trip verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundTripStatus {
+    /// The converted-back data matches the original.
+    Passed,
+    /// The converted-back data differs from the original.
+    Failed,
+    /// No path leads from the output back to the input format.
+    NoReversePath,
+}
+
+/// Report of a round-trip verification.
+#[derive(Debug, Clone)]
+pub struct RoundTripReport {
+    /// Outcome.
+    pub status: RoundTripStatus,
+    /// The reverse plan that was executed.
+    pub reverse: Option<Plan>,
+    /// Comparator used, or `None` for byte comparison.
+    pub comparator: Option<String>,
+    /// Differences found (empty unless the verification failed).
+    pub diffs: Vec<String>,
+}
+
+impl RoundTripReport {
+    /// Whether the round trip preserved the content.
+    pub fn passed(&self) -> bool {
+        self.status == RoundTripStatus::Passed
+    }
+}
+
+impl fmt::Display for RoundTripReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let via = self.comparator.as_deref().unwrap_or("bytes");
+        match self.status {
+            RoundTripStatus::Passed => write!(f, "round trip passed (compared {})", via),
+            RoundTripStatus::NoReversePath => {
+                write!(f, "round trip not verified: no reverse path")
+            }
+            RoundTripStatus::Failed => {
+                write!(
+                    f,
+                    "round trip failed (compared {}, {} difference{})",
+                    via,
+                    self.diffs.len(),
+                    if self.diffs.len() == 1 { "" } else { "s" }
+                )?;
+                for diff in &self.diffs {
+                    write!(f, "\n  {}", diff)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Plan the reverse of a conversion, run it, and compare with the original.
+pub(crate) fn verify<E: Executor + ?Sized>(
+    executor: &E,
+    ctx: &ExecutionContext,
+    original: &[u8],
+    original_props: &Properties,
+    result: &ExecutionResult,
+) -> Result<RoundTripReport, ExecuteError> {
+    let no_path = RoundTripReport {
+        status: RoundTripStatus::NoReversePath,
+        reverse: None,
+        comparator: None,
+        diffs: Vec::new(),
+    };
+    let Some(format) = original_props.get("format") else {
+        return Ok(no_path);
+    };
+    let target = PropertyPattern::new().eq("format", format.clone());
+    let Some(reverse) = Planner::new(&ctx.registry)
+        .input_size(result.data.len())
+        .plan(&result.props, &target, Cardinality::One, Cardinality::One)
+    else {
+        return Ok(no_path);
+    };
+
+    let back = executor.execute(ctx, &reverse, result.data.clone(), result.props.clone())?;
+
+    let comparator = ctx.registry.find_comparator(original_props);
+    let diffs = match &comparator {
+        Some(c) => c
+            .compare(original, &back.data, original_props)
+            .map_err(|source| ExecuteError::ComparisonFailed {
+                comparator: c.decl().id.clone(),
+                source,
+            })?,
+        None => compare_bytes(original, &back.data),
+    };
+
+    Ok(RoundTripReport {
+        status: if diffs.is_empty() {
+            RoundTripStatus::Passed
+        } else {
+            RoundTripStatus::Failed
+        },
+        reverse: Some(reverse),
+        comparator: comparator.map(|c| c.decl().id.clone()),
+        diffs,
+    })
+}
+
+/// Byte-exact comparison, for formats without a comparator.
+fn compare_bytes(original: &[u8], roundtrip: &[u8]) -> Vec<String> {
+    if original == roundtrip {
+        return Vec::new();
+    }
+    let mut diffs = Vec::new();
+    if original.len() != roundtrip.len() {
+        diffs.push(format!(
+            "length: {} bytes, got {}",
+            original.len(),
+            roundtrip.len()
+        ));
+    }
+    if let Some(offset) = original.iter().zip(roundtrip).position(|(a, b)| a != b) {
+        diffs.push(format!("first differing byte at offset {}", offset));
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConvertOutput, Converter, ConverterDecl, PropertiesExt, Registry, SimpleExecutor};
+    use std::sync::Arc;
+
+    /// Converts between `upper` and `lower` by changing ASCII case.
+    struct CaseConverter(ConverterDecl, bool);
+
+    impl CaseConverter {
+        fn new(from: &str, to: &str) -> Self {
+            let decl = ConverterDecl::simple(
+                format!("test.{}-to-{}", from, to),
+                PropertyPattern::new().eq("format", from),
+                PropertyPattern::new().eq("format", to),
+            );
+            Self(decl, to == "upper")
+        }
+    }
+
+    impl Converter for CaseConverter {
+        fn decl(&self) -> &ConverterDecl {
+            &self.0
+        }
+
+        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+            let (data, format) = if self.1 {
+                (input.to_ascii_uppercase(), "upper")
+            } else {
+                (input.to_ascii_lowercase(), "lower")
+            };
+            Ok(ConvertOutput::Single(
+                data,
+                props.clone().with("format", format),
+            ))
+        }
+    }
+
+    struct CaseInsensitive(ComparatorDecl);
+
+    impl Comparator for CaseInsensitive {
+        fn decl(&self) -> &ComparatorDecl {
+            &self.0
+        }
+
+        fn compare(
+            &self,
+            original: &[u8],
+            roundtrip: &[u8],
+            _props: &Properties,
+        ) -> Result<Vec<String>, ConvertError> {
+            Ok(if original.eq_ignore_ascii_case(roundtrip) {
+                vec![]
+            } else {
+                vec!["text differs".into()]
+            })
+        }
+    }
+
+    fn run(registry: Registry, input: &[u8]) -> RoundTripReport {
+        let ctx = ExecutionContext::new(Arc::new(registry));
+        let props = Properties::new().with("format", "lower");
+        let target = PropertyPattern::new().eq("format", "upper");
+        let plan = Planner::new(&ctx.registry)
+            .plan(&props, &target, Cardinality::One, Cardinality::One)
+            .unwrap();
+        let executor = SimpleExecutor::new();
+        let result = executor
+            .execute(&ctx, &plan, input.to_vec(), props.clone())
+            .unwrap();
+        executor
+            .verify_roundtrip(&ctx, input, &props, &result)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_bytes() {
+        let mut registry = Registry::new();
+        registry.register(CaseConverter::new("lower", "upper"));
+        registry.register(CaseConverter::new("upper", "lower"));
+
+        let report = run(registry.clone(), b"hello");
+        assert!(report.passed());
+        assert_eq!(report.reverse.unwrap().steps.len(), 1);
+        assert_eq!(report.comparator, None);
+
+        // Mixed case does not survive byte-for-byte.
+        let report = run(registry, b"Hello");
+        assert_eq!(report.status, RoundTripStatus::Failed);
+        assert_eq!(report.diffs, vec!["first differing byte at offset 0"]);
+    }
+
+    #[test]
+    fn test_roundtrip_comparator() {
+        let mut registry = Registry::new();
+        registry.register(CaseConverter::new("lower", "upper"));
+        registry.register(CaseConverter::new("upper", "lower"));
+        registry.register_comparator(CaseInsensitive(ComparatorDecl::new(
+            "test.case-insensitive",
+            PropertyPattern::new().eq("format", "lower"),
+        )));
+
+        let report = run(registry, b"Hello");
+        assert!(report.passed());
+        assert_eq!(report.comparator.as_deref(), Some("test.case-insensitive"));
+    }
+
+    #[test]
+    fn test_no_reverse_path() {
+        let mut registry = Registry::new();
+        registry.register(CaseConverter::new("lower", "upper"));
+
+        let report = run(registry, b"hello");
+        assert_eq!(report.status, RoundTripStatus::NoReversePath);
+        assert_eq!(
+            report.to_string(),
+            "round trip not verified: no reverse path"
+        );
+    }
+}
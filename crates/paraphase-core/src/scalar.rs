@@ -0,0 +1,490 @@
+//! Scalar property values beyond JSON: timestamps, durations, decimals and
+//! bytes.
+//!
+//! These serialize as single-key objects (`{"$timestamp": "..."}`) so that
+//! property bags stay readable JSON/YAML/TOML and deserialize back into the
+//! same `Value` variant.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::time::Duration;
+
+/// A point in time (UTC), with nanosecond precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Timestamp {
+    /// Timestamp from seconds and nanoseconds since the Unix epoch.
+    pub fn from_unix(secs: i64, nanos: u32) -> Self {
+        Self {
+            secs: secs + i64::from(nanos / 1_000_000_000),
+            nanos: nanos % 1_000_000_000,
+        }
+    }
+
+    /// Whole seconds since the Unix epoch.
+    pub fn unix_seconds(&self) -> i64 {
+        self.secs
+    }
+
+    /// Nanoseconds past the whole second.
+    pub fn subsec_nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// Timestamp of a UTC calendar date and time; `None` if out of range.
+    pub fn from_civil(
+        year: i64,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Option<Self> {
+        let (month, day) = (i64::from(month), i64::from(day));
+        if !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return None;
+        }
+        let days = days_from_civil(year, month, day);
+        let time = i64::from(hour * 3600 + minute * 60 + second);
+        Some(Self::from_unix(days * 86_400 + time, 0))
+    }
+
+    /// UTC calendar date and time: `(year, month, day, hour, minute, second)`.
+    pub fn to_civil(&self) -> (i64, u32, u32, u32, u32, u32) {
+        let days = self.secs.div_euclid(86_400);
+        let time = self.secs.rem_euclid(86_400) as u32;
+        let (year, month, day) = civil_from_days(days);
+        (
+            year,
+            month as u32,
+            day as u32,
+            time / 3600,
+            time / 60 % 60,
+            time % 60,
+        )
+    }
+
+    /// Parse an RFC 3339 timestamp, e.g. `2024-05-01T12:30:00.5+02:00`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let b = s.as_bytes();
+        let digits = |range: std::ops::Range<usize>| -> Option<i64> {
+            let part = s.get(range)?;
+            if part.is_empty() || !part.bytes().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            part.parse().ok()
+        };
+        if b.len() < 20
+            || b[4] != b'-'
+            || b[7] != b'-'
+            || !matches!(b[10], b'T' | b't' | b' ')
+            || b[13] != b':'
+            || b[16] != b':'
+        {
+            return None;
+        }
+        let (year, month, day) = (digits(0..4)?, digits(5..7)?, digits(8..10)?);
+        let (hour, minute, second) = (digits(11..13)?, digits(14..16)?, digits(17..19)?);
+        if !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+
+        let mut rest = &s[19..];
+        let mut nanos = 0u32;
+        if let Some(frac) = rest.strip_prefix('.') {
+            let len = frac.bytes().take_while(u8::is_ascii_digit).count();
+            if len == 0 {
+                return None;
+            }
+            for (i, c) in frac[..len].bytes().take(9).enumerate() {
+                nanos += u32::from(c - b'0') * 10u32.pow(8 - i as u32);
+            }
+            rest = &frac[len..];
+        }
+
+        let offset = match rest {
+            "Z" | "z" => 0,
+            _ => {
+                let sign = match rest.as_bytes().first()? {
+                    b'+' => 1,
+                    b'-' => -1,
+                    _ => return None,
+                };
+                if rest.len() != 6 || rest.as_bytes()[3] != b':' {
+                    return None;
+                }
+                let hours: i64 = rest[1..3].parse().ok()?;
+                let minutes: i64 = rest[4..6].parse().ok()?;
+                sign * (hours * 3600 + minutes * 60)
+            }
+        };
+
+        let days = days_from_civil(year, month, day);
+        let secs = days * 86_400 + hour * 3600 + minute * 60 + second - offset;
+        Some(Self::from_unix(secs, nanos))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    /// RFC 3339 in UTC, with as many fractional digits as needed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day, hour, minute, second) = self.to_civil();
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year, month, day, hour, minute, second
+        )?;
+        write_fraction(f, self.nanos)?;
+        f.write_str("Z")
+    }
+}
+
+impl From<std::time::SystemTime> for Timestamp {
+    fn from(time: std::time::SystemTime) -> Self {
+        match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(d) => Self::from_unix(d.as_secs() as i64, d.subsec_nanos()),
+            Err(e) => {
+                let d = e.duration();
+                let secs = -(d.as_secs() as i64);
+                match d.subsec_nanos() {
+                    0 => Self::from_unix(secs, 0),
+                    n => Self::from_unix(secs - 1, 1_000_000_000 - n),
+                }
+            }
+        }
+    }
+}
+
+/// A decimal number kept as text, for values that must not be rounded
+/// through `f64` (prices, coordinates, fixed-point fields).
+///
+/// Stored in canonical form (no leading zeros, no trailing fractional
+/// zeros), so equal numbers compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Decimal(String);
+
+impl Decimal {
+    /// Parse a decimal such as `-12.50`. Exponents are not accepted.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        if (int.is_empty() && frac.is_empty())
+            || !int.bytes().all(|c| c.is_ascii_digit())
+            || !frac.bytes().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let int = int.trim_start_matches('0');
+        let frac = frac.trim_end_matches('0');
+        let mut out = String::new();
+        if negative && !(int.is_empty() && frac.is_empty()) {
+            out.push('-');
+        }
+        out.push_str(if int.is_empty() { "0" } else { int });
+        if !frac.is_empty() {
+            out.push('.');
+            out.push_str(frac);
+        }
+        Some(Self(out))
+    }
+
+    /// Canonical text.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Nearest `f64` (may lose precision).
+    pub fn to_f64(&self) -> f64 {
+        self.0.parse().unwrap_or(f64::NAN)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Format a duration as ISO 8601 seconds, e.g. `PT90.5S`.
+pub fn format_duration(d: Duration) -> String {
+    struct Iso(Duration);
+
+    impl fmt::Display for Iso {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "PT{}", self.0.as_secs())?;
+            write_fraction(f, self.0.subsec_nanos())?;
+            f.write_str("S")
+        }
+    }
+
+    Iso(d).to_string()
+}
+
+/// Parse an ISO 8601 time duration: `PT1H2M3.5S`, `PT90S`, `PT0S`.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let mut rest = s.strip_prefix("PT")?;
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total = Duration::ZERO;
+    for (unit, scale) in [('H', 3600), ('M', 60)] {
+        if let Some(pos) = rest.find(unit) {
+            let n: u64 = rest[..pos].parse().ok()?;
+            total += Duration::from_secs(n.checked_mul(scale)?);
+            rest = &rest[pos + 1..];
+        }
+    }
+    if let Some(secs) = rest.strip_suffix('S') {
+        let (whole, frac) = secs.split_once('.').unwrap_or((secs, ""));
+        if !frac.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut nanos = 0u32;
+        for (i, c) in frac.bytes().take(9).enumerate() {
+            nanos += u32::from(c - b'0') * 10u32.pow(8 - i as u32);
+        }
+        total += Duration::new(whole.parse().ok()?, nanos);
+    } else if !rest.is_empty() {
+        return None;
+    }
+    Some(total)
+}
+
+fn write_fraction(f: &mut fmt::Formatter<'_>, nanos: u32) -> fmt::Result {
+    if nanos == 0 {
+        return Ok(());
+    }
+    let digits = format!("{:09}", nanos);
+    write!(f, ".{}", digits.trim_end_matches('0'))
+}
+
+fn is_leap(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian date of a day count since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (u32::from(b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[((n >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim_end_matches('=');
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let v = BASE64.iter().position(|&b| b == c)? as u32;
+        acc = (acc << 6) | v;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+/// Serialize `value` as `{"<tag>": value}`.
+fn tagged<S: Serializer>(serializer: S, tag: &str, value: &str) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeMap;
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(tag, value)?;
+    map.end()
+}
+
+/// Deserialize `{"<tag>": "..."}` and parse the string.
+fn untag<'de, D, T>(
+    deserializer: D,
+    tag: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let map = std::collections::BTreeMap::<String, String>::deserialize(deserializer)?;
+    match map.get(tag) {
+        Some(s) if map.len() == 1 => {
+            parse(s).ok_or_else(|| D::Error::custom(format!("invalid {}: {}", tag, s)))
+        }
+        _ => Err(D::Error::custom(format!("expected {{\"{}\": ...}}", tag))),
+    }
+}
+
+pub(crate) mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(data: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        tagged(s, "$bytes", &encode_base64(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        untag(d, "$bytes", decode_base64)
+    }
+}
+
+pub(crate) mod duration {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Duration, s: S) -> Result<S::Ok, S::Error> {
+        tagged(s, "$duration", &format_duration(*value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+        untag(d, "$duration", parse_duration)
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        tagged(s, "$timestamp", &self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        untag(d, "$timestamp", Timestamp::parse)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        tagged(s, "$decimal", &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        untag(d, "$decimal", Decimal::parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_rfc3339() {
+        let ts = Timestamp::parse("2024-02-29T23:30:00.25+02:00").unwrap();
+        assert_eq!(ts.to_string(), "2024-02-29T21:30:00.25Z");
+        assert_eq!(ts.unix_seconds(), 1_709_242_200);
+        assert_eq!(
+            Timestamp::from_unix(0, 0).to_string(),
+            "1970-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            Timestamp::from_unix(-1, 0).to_string(),
+            "1969-12-31T23:59:59Z"
+        );
+        assert_eq!(ts.to_civil(), (2024, 2, 29, 21, 30, 0));
+        assert_eq!(
+            Timestamp::from_civil(2024, 2, 29, 21, 30, 0).unwrap(),
+            Timestamp::from_unix(1_709_242_200, 0)
+        );
+        assert!(Timestamp::from_civil(2023, 2, 29, 0, 0, 0).is_none());
+        assert!(Timestamp::parse("2023-02-29T00:00:00Z").is_none());
+        assert!(Timestamp::parse("2024-01-01 00:00:00").is_none());
+        assert!(
+            Timestamp::parse("2024-01-01T00:00:00Z").unwrap()
+                < Timestamp::parse("2024-01-01T00:00:00.001Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_duration_iso() {
+        assert_eq!(format_duration(Duration::from_millis(90_500)), "PT90.5S");
+        assert_eq!(format_duration(Duration::ZERO), "PT0S");
+        assert_eq!(
+            parse_duration("PT1H2M3.5S"),
+            Some(Duration::from_millis(3_723_500))
+        );
+        assert_eq!(parse_duration("PT5M"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("P1D"), None);
+        assert_eq!(parse_duration("PT"), None);
+    }
+
+    #[test]
+    fn test_decimal_canonical() {
+        assert_eq!(Decimal::parse("0012.500").unwrap().as_str(), "12.5");
+        assert_eq!(Decimal::parse("-0.0").unwrap().as_str(), "0");
+        assert_eq!(Decimal::parse(".5").unwrap().as_str(), "0.5");
+        assert_eq!(Decimal::parse("1.50"), Decimal::parse("+1.5"));
+        assert!(Decimal::parse("1e5").is_none());
+        assert!(Decimal::parse("-").is_none());
+    }
+
+    #[test]
+    fn test_base64() {
+        for data in [&b""[..], b"f", b"fo", b"foo", b"\x00\xff\x10\x80"] {
+            assert_eq!(decode_base64(&encode_base64(data)).unwrap(), data);
+        }
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert!(decode_base64("Zm8*").is_none());
+    }
+}
@@ -0,0 +1,121 @@
+//! Spill-to-disk storage for intermediates that exceed the memory budget.
+//!
+//! When a `BoundedExecutor` runs under a memory limit with a spill directory
+//! configured, intermediates that would push resident memory past the budget
+//! are written to anonymous temporary files and memory-mapped. Converters
+//! still receive a plain `&[u8]`; the OS pages the data in on demand and can
+//! evict it again under pressure.
+
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Data flowing between plan steps, either held in memory or spilled to disk.
+pub enum Intermediate {
+    /// Data held in memory.
+    Resident(Vec<u8>),
+    /// Data written to a temporary file and memory-mapped.
+    Spilled(SpillFile),
+}
+
+impl Intermediate {
+    /// Write data to an anonymous temporary file in `dir` and map it.
+    ///
+    /// Empty data is kept resident (zero-length files cannot be mapped).
+    pub fn spill(data: Vec<u8>, dir: &Path) -> io::Result<Self> {
+        if data.is_empty() {
+            return Ok(Intermediate::Resident(data));
+        }
+        SpillFile::write(&data, dir).map(Intermediate::Spilled)
+    }
+
+    /// Borrow the data as a byte slice.
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            Intermediate::Resident(data) => data,
+            Intermediate::Spilled(file) => file.as_slice(),
+        }
+    }
+
+    /// Length of the data in bytes.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Check if the data is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes held in memory (zero for spilled data).
+    pub fn resident_len(&self) -> usize {
+        match self {
+            Intermediate::Resident(data) => data.len(),
+            Intermediate::Spilled(_) => 0,
+        }
+    }
+
+    /// Check if the data lives on disk.
+    pub fn is_spilled(&self) -> bool {
+        matches!(self, Intermediate::Spilled(_))
+    }
+
+    /// Take the data as an owned buffer, reading it back from disk if spilled.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self {
+            Intermediate::Resident(data) => data,
+            Intermediate::Spilled(file) => file.as_slice().to_vec(),
+        }
+    }
+}
+
+/// A memory-mapped temporary file.
+///
+/// The file is created with `tempfile::tempfile_in`, so it has no name on
+/// disk and is removed by the OS once the mapping and handle are dropped.
+pub struct SpillFile {
+    map: Mmap,
+    _file: File,
+}
+
+impl SpillFile {
+    fn write(data: &[u8], dir: &Path) -> io::Result<Self> {
+        let mut file = tempfile::tempfile_in(dir)?;
+        file.write_all(data)?;
+        file.flush()?;
+        // SAFETY: the file is anonymous and owned by this struct, so nothing
+        // else can truncate or modify it while the mapping is alive.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Self { map, _file: file })
+    }
+
+    /// Borrow the mapped bytes.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spill_roundtrip() {
+        let dir = std::env::temp_dir();
+        let data = b"spilled intermediate".to_vec();
+
+        let spilled = Intermediate::spill(data.clone(), &dir).unwrap();
+        assert!(spilled.is_spilled());
+        assert_eq!(spilled.resident_len(), 0);
+        assert_eq!(spilled.as_slice(), data.as_slice());
+        assert_eq!(spilled.into_vec(), data);
+    }
+
+    #[test]
+    fn test_spill_empty_stays_resident() {
+        let spilled = Intermediate::spill(Vec::new(), &std::env::temp_dir()).unwrap();
+        assert!(!spilled.is_spilled());
+        assert!(spilled.is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+//! Vocabulary of well-known property keys.
+//!
+//! Property bags are free-form, so nothing stops one crate from writing
+//! `width` and another `image.width`. The vocabulary names the keys the
+//! ecosystem agrees on, with their value type, unit and meaning. Core keys
+//! are built in; domain crates declare the keys their converters and
+//! inspectors produce when they register. With
+//! `ExecutionContext::with_property_checks`, the executor flags undeclared
+//! keys and ill-typed values as they flow through a plan.
+
+use crate::properties::{Properties, Value};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Type of a property value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyType {
+    Bool,
+    /// Signed or unsigned integer.
+    Int,
+    /// Any number; integers and decimals are accepted.
+    Float,
+    String,
+    Timestamp,
+    /// A duration, or a number of seconds.
+    Duration,
+    Bytes,
+    Array,
+    Object,
+    /// Any value.
+    Any,
+}
+
+impl PropertyType {
+    /// Lowercase name, as used in the serialized vocabulary.
+    pub fn name(self) -> &'static str {
+        match self {
+            PropertyType::Bool => "bool",
+            PropertyType::Int => "int",
+            PropertyType::Float => "float",
+            PropertyType::String => "string",
+            PropertyType::Timestamp => "timestamp",
+            PropertyType::Duration => "duration",
+            PropertyType::Bytes => "bytes",
+            PropertyType::Array => "array",
+            PropertyType::Object => "object",
+            PropertyType::Any => "any",
+        }
+    }
+
+    /// Whether `value` has this type.
+    pub fn accepts(self, value: &Value) -> bool {
+        match self {
+            PropertyType::Bool => value.as_bool().is_some(),
+            PropertyType::Int => matches!(value, Value::Int(_) | Value::UInt(_)),
+            PropertyType::Float => value.as_f64().is_some(),
+            PropertyType::String => value.as_str().is_some(),
+            PropertyType::Timestamp => value.as_timestamp().is_some(),
+            PropertyType::Duration => value.as_duration().is_some() || value.as_f64().is_some(),
+            PropertyType::Bytes => value.as_bytes().is_some(),
+            PropertyType::Array => value.as_array().is_some(),
+            PropertyType::Object => value.as_object().is_some(),
+            PropertyType::Any => true,
+        }
+    }
+}
+
+impl fmt::Display for PropertyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Declaration of a property key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyDecl {
+    /// Property key, e.g. `sample_rate`.
+    pub key: String,
+    /// Value type.
+    #[serde(rename = "type")]
+    pub ty: PropertyType,
+    /// Unit of numeric values, e.g. `Hz` or `px`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub unit: String,
+    /// Human-readable description.
+    #[serde(default)]
+    pub description: String,
+}
+
+impl PropertyDecl {
+    /// Declare a key of the given type.
+    pub fn new(key: impl Into<String>, ty: PropertyType, description: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            ty,
+            unit: String::new(),
+            description: description.into(),
+        }
+    }
+
+    /// Set the unit.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = unit.into();
+        self
+    }
+}
+
+/// A property that does not match the vocabulary.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PropertyIssue {
+    #[error("undeclared property `{0}`")]
+    Undeclared(String),
+
+    #[error("property `{key}` should be {expected}, got {found:?}")]
+    WrongType {
+        key: String,
+        expected: PropertyType,
+        found: Value,
+    },
+}
+
+/// The set of declared property keys.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vocabulary {
+    properties: IndexMap<String, PropertyDecl>,
+}
+
+impl Vocabulary {
+    /// An empty vocabulary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The core keys shared by all domains.
+    pub fn well_known() -> Self {
+        let mut vocabulary = Self::new();
+        for decl in [
+            PropertyDecl::new("format", PropertyType::String, "Data format, e.g. `png`"),
+            PropertyDecl::new("path", PropertyType::String, "Source file path"),
+            PropertyDecl::new("glob", PropertyType::String, "Source file glob pattern"),
+            PropertyDecl::new("name", PropertyType::String, "Name of the item"),
+            PropertyDecl::new(
+                "description",
+                PropertyType::String,
+                "Description of the item",
+            ),
+            PropertyDecl::new("size", PropertyType::Int, "Size of the data").unit("bytes"),
+            PropertyDecl::new("width", PropertyType::Int, "Width").unit("px"),
+            PropertyDecl::new("height", PropertyType::Int, "Height").unit("px"),
+            PropertyDecl::new("duration", PropertyType::Duration, "Playing time").unit("s"),
+            PropertyDecl::new(
+                "bundle_format",
+                PropertyType::String,
+                "Format of a bundle's primary member",
+            ),
+            PropertyDecl::new("members", PropertyType::Array, "Member names of a bundle"),
+        ] {
+            vocabulary.declare(decl);
+        }
+        vocabulary
+    }
+
+    /// Declare a key, replacing any earlier declaration.
+    pub fn declare(&mut self, decl: PropertyDecl) {
+        self.properties.insert(decl.key.clone(), decl);
+    }
+
+    /// Look up a key.
+    pub fn get(&self, key: &str) -> Option<&PropertyDecl> {
+        self.properties.get(key)
+    }
+
+    /// Iterate over all declarations, in declaration order.
+    pub fn iter(&self) -> impl Iterator<Item = &PropertyDecl> {
+        self.properties.values()
+    }
+
+    /// Check a property bag against the vocabulary.
+    pub fn check(&self, props: &Properties) -> Vec<PropertyIssue> {
+        props
+            .iter()
+            .filter_map(|(key, value)| match self.properties.get(key) {
+                None => Some(PropertyIssue::Undeclared(key.clone())),
+                Some(decl) if !decl.ty.accepts(value) => Some(PropertyIssue::WrongType {
+                    key: key.clone(),
+                    expected: decl.ty,
+                    found: value.clone(),
+                }),
+                Some(_) => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PropertiesExt;
+    use std::time::Duration;
+
+    #[test]
+    fn test_check() {
+        let mut vocabulary = Vocabulary::well_known();
+        vocabulary.declare(
+            PropertyDecl::new("sample_rate", PropertyType::Int, "Samples per second").unit("Hz"),
+        );
+        assert_eq!(vocabulary.get("sample_rate").unwrap().unit, "Hz");
+
+        let ok = Properties::new()
+            .with("format", "wav")
+            .with("sample_rate", 44_100i64)
+            .with("duration", 1.5)
+            .with("size", u64::MAX);
+        assert!(vocabulary.check(&ok).is_empty());
+        let ok = Properties::new().with("duration", Duration::from_secs(2));
+        assert!(vocabulary.check(&ok).is_empty());
+
+        let bad = Properties::new()
+            .with("width", "wide")
+            .with("image.width", 10i64);
+        let issues = vocabulary.check(&bad);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(
+            issues[0].to_string(),
+            "property `width` should be int, got String(\"wide\")"
+        );
+        assert_eq!(issues[1], PropertyIssue::Undeclared("image.width".into()));
+    }
+}
@@ -7,10 +7,17 @@
 //!
 //! Incomplete workflows (missing steps) trigger auto-planning.
 
+use crate::bundle::Bundle;
+use crate::converter::ConverterDecl;
+use crate::format::{FormatDecl, Formats};
+use crate::option::OptionError;
 use crate::pattern::PropertyPattern;
+use crate::planner::Plan;
 use crate::properties::{Properties, Value};
+use crate::registry::Registry;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 /// A workflow definition.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -50,13 +57,23 @@ pub enum Source {
 
 impl Source {
     /// Get source properties for planning.
+    ///
+    /// Only common data and image formats are detected; use
+    /// [`Source::to_properties_with`] with [`Registry::formats`] to detect
+    /// everything registered.
     pub fn to_properties(&self) -> Properties {
+        self.to_properties_with(&default_formats())
+    }
+
+    /// Get source properties for planning, detecting the format from the
+    /// path's extension in `formats`.
+    pub fn to_properties_with(&self, formats: &Formats) -> Properties {
         match self {
             Source::File { path } => {
                 let mut props = Properties::new();
                 props.insert("path".into(), Value::String(path.clone()));
-                if let Some(format) = detect_format(path) {
-                    props.insert("format".into(), Value::String(format));
+                if let Some(format) = formats.from_path(path) {
+                    props.insert("format".into(), Value::String(format.into()));
                 }
                 props
             }
@@ -64,8 +81,8 @@ impl Source {
                 let mut props = Properties::new();
                 props.insert("glob".into(), Value::String(glob.clone()));
                 // Try to detect format from glob pattern
-                if let Some(format) = detect_format(glob) {
-                    props.insert("format".into(), Value::String(format));
+                if let Some(format) = formats.from_path(glob) {
+                    props.insert("format".into(), Value::String(format.into()));
                 }
                 props
             }
@@ -73,6 +90,16 @@ impl Source {
         }
     }
 
+    /// Sidecar files of a file source, without reading them.
+    ///
+    /// See [`Bundle::sidecars`]. Empty for other sources.
+    pub fn sidecars(&self, formats: &Formats) -> Vec<PathBuf> {
+        match self {
+            Source::File { path } => Bundle::sidecars(Path::new(path), formats),
+            _ => Vec::new(),
+        }
+    }
+
     /// Check if this source represents multiple files.
     pub fn is_batch(&self) -> bool {
         matches!(self, Source::Glob { .. })
@@ -93,11 +120,21 @@ pub enum Sink {
 
 impl Sink {
     /// Get target pattern for planning.
+    ///
+    /// Only common data and image formats are detected; use
+    /// [`Sink::to_pattern_with`] with [`Registry::formats`] to detect
+    /// everything registered.
     pub fn to_pattern(&self) -> PropertyPattern {
+        self.to_pattern_with(&default_formats())
+    }
+
+    /// Get target pattern for planning, detecting the format from the
+    /// path's extension in `formats`.
+    pub fn to_pattern_with(&self, formats: &Formats) -> PropertyPattern {
         match self {
             Sink::File { path } => {
                 let mut pattern = PropertyPattern::new();
-                if let Some(format) = detect_format(path) {
+                if let Some(format) = formats.from_path(path) {
                     pattern = pattern.eq("format", format);
                 }
                 pattern
@@ -196,13 +233,80 @@ impl Workflow {
         self.source.is_some() && self.sink.is_some() && self.steps.is_empty()
     }
 
+    /// Check explicit steps against the converters in a registry.
+    ///
+    /// Reports unknown converters, options a step's converter does not
+    /// declare (or whose values do not fit the schema), and global options
+    /// that no step accepts.
+    pub fn validate(&self, registry: &Registry) -> Result<(), WorkflowError> {
+        let decls = self
+            .steps
+            .iter()
+            .map(|step| {
+                registry
+                    .get_decl(&step.converter)
+                    .ok_or_else(|| WorkflowError::UnknownConverter(step.converter.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (step, decl) in self.steps.iter().zip(&decls) {
+            decl.validate_options(&step.options)?;
+        }
+        self.global_options(&decls).map(|_| ())
+    }
+
+    /// Attach the workflow's options to a plan built for it.
+    ///
+    /// Explicit steps pair up with plan steps in order. Global options go to
+    /// every step whose converter declares them, or declares no options at
+    /// all; step options take precedence.
+    pub fn apply_options(&self, plan: &mut Plan, registry: &Registry) -> Result<(), WorkflowError> {
+        let decls = plan
+            .steps
+            .iter()
+            .map(|step| {
+                registry
+                    .get_decl(&step.converter_id)
+                    .ok_or_else(|| WorkflowError::UnknownConverter(step.converter_id.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let global = self.global_options(&decls)?;
+
+        for (i, (plan_step, decl)) in plan.steps.iter_mut().zip(&decls).enumerate() {
+            let mut options: Properties = global
+                .iter()
+                .filter(|(name, _)| accepts(decl, name))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            if let Some(step) = self.steps.get(i) {
+                options.extend(step.options.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            decl.validate_options(&options)?;
+            plan_step.options = options;
+        }
+        Ok(())
+    }
+
+    /// Global options, checked against the converters they will reach.
+    fn global_options(&self, decls: &[&ConverterDecl]) -> Result<&Properties, WorkflowError> {
+        for (name, value) in &self.options {
+            let targets: Vec<_> = decls.iter().filter(|d| accepts(d, name)).collect();
+            if targets.is_empty() && !decls.is_empty() {
+                return Err(WorkflowError::UnknownOption(name.clone()));
+            }
+            let single = Properties::from_iter([(name.clone(), value.clone())]);
+            for decl in targets {
+                decl.validate_options(&single)?;
+            }
+        }
+        Ok(&self.options)
+    }
+
     /// Parse workflow from bytes, auto-detecting format.
     pub fn from_bytes(data: &[u8], path: Option<&str>) -> Result<Self, WorkflowError> {
-        let format = path
-            .and_then(detect_format)
-            .unwrap_or_else(|| "yaml".to_string());
+        let formats = Formats::well_known();
+        let format = path.and_then(|p| formats.from_path(p)).unwrap_or("yaml");
 
-        Self::from_bytes_format(data, &format)
+        Self::from_bytes_format(data, format)
     }
 
     /// Parse workflow from bytes with explicit format.
@@ -255,30 +359,45 @@ pub enum WorkflowError {
 
     #[error("workflow execution failed: {0}")]
     Execution(String),
+
+    #[error("unknown converter: {0}")]
+    UnknownConverter(String),
+
+    #[error("option `{0}` is not accepted by any step")]
+    UnknownOption(String),
+
+    #[error(transparent)]
+    InvalidOption(#[from] OptionError),
+}
+
+/// Whether a converter takes an option (undeclared converters take any).
+fn accepts(decl: &ConverterDecl, name: &str) -> bool {
+    decl.options.is_empty() || decl.options.contains_key(name)
 }
 
-/// Detect format from file path extension.
-fn detect_format(path: &str) -> Option<String> {
-    let ext = path.rsplit('.').next()?;
-    match ext.to_lowercase().as_str() {
-        "json" => Some("json".into()),
-        "yaml" | "yml" => Some("yaml".into()),
-        "toml" => Some("toml".into()),
-        "ron" => Some("ron".into()),
-        "msgpack" | "mp" => Some("msgpack".into()),
-        "cbor" => Some("cbor".into()),
-        "csv" => Some("csv".into()),
-        "png" => Some("png".into()),
-        "jpg" | "jpeg" => Some("jpg".into()),
-        "webp" => Some("webp".into()),
-        "gif" => Some("gif".into()),
-        _ => None,
+/// Formats detected without a registry: core's well-known ones plus
+/// common data and image formats.
+fn default_formats() -> Formats {
+    let mut formats = Formats::well_known();
+    for decl in [
+        FormatDecl::new("ron").extension("ron"),
+        FormatDecl::new("msgpack").extensions(["msgpack", "mp"]),
+        FormatDecl::new("cbor").extension("cbor"),
+        FormatDecl::new("csv").extension("csv"),
+        FormatDecl::new("png").extension("png"),
+        FormatDecl::new("jpg").extensions(["jpg", "jpeg"]),
+        FormatDecl::new("webp").extension("webp"),
+        FormatDecl::new("gif").extension("gif"),
+    ] {
+        formats.register(decl);
     }
+    formats
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::option::OptionDecl;
     use crate::properties::PropertiesExt;
 
     #[test]
@@ -314,6 +433,31 @@ mod tests {
         assert_eq!(props.get("format").unwrap().as_str(), Some("json"));
     }
 
+    #[test]
+    fn test_registered_formats() {
+        let mut registry = Registry::new();
+        registry.register_format(crate::FormatDecl::new("stl").extension("stl"));
+
+        let source = Source::File {
+            path: "photo.JPEG".into(),
+        };
+        let props = source.to_properties();
+        assert_eq!(props.get("format").unwrap().as_str(), Some("jpg"));
+
+        let source = Source::File {
+            path: "model.stl".into(),
+        };
+        assert!(source.to_properties().get("format").is_none());
+        let props = source.to_properties_with(registry.formats());
+        assert_eq!(props.get("format").unwrap().as_str(), Some("stl"));
+
+        let sink = Sink::File {
+            path: "out.stl".into(),
+        };
+        let pattern = sink.to_pattern_with(registry.formats());
+        assert!(pattern.matches(&Properties::new().with("format", "stl")));
+    }
+
     #[test]
     fn test_sink_pattern() {
         let sink = Sink::File {
@@ -328,6 +472,94 @@ mod tests {
         assert!(!pattern.matches(&props));
     }
 
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.register_decl(
+            ConverterDecl::simple(
+                "image.resize",
+                PropertyPattern::new().eq("format", "png"),
+                PropertyPattern::new().eq("format", "png"),
+            )
+            .option("max_width", OptionDecl::int("Maximum width").min(1.0)),
+        );
+        registry.register_decl(ConverterDecl::simple(
+            "image.png-to-webp",
+            PropertyPattern::new().eq("format", "png"),
+            PropertyPattern::new().eq("format", "webp"),
+        ));
+        registry
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let registry = registry();
+        let mut workflow = Workflow::new().step("image.resize");
+        workflow.steps[0]
+            .options
+            .insert("max_width".into(), Value::Int(800));
+        assert!(workflow.validate(&registry).is_ok());
+
+        workflow.steps[0]
+            .options
+            .insert("max_widht".into(), Value::Int(800));
+        let err = workflow.validate(&registry).unwrap_err();
+        assert!(err.to_string().contains("did you mean `max_width`?"));
+
+        let workflow = Workflow::new().step("image.missing");
+        assert!(matches!(
+            workflow.validate(&registry),
+            Err(WorkflowError::UnknownConverter(_))
+        ));
+
+        // Global options must fit at least one step.
+        let mut workflow = Workflow::new().step("image.resize");
+        workflow.options.insert("quality".into(), Value::Int(80));
+        assert!(matches!(
+            workflow.validate(&registry),
+            Err(WorkflowError::UnknownOption(name)) if name == "quality"
+        ));
+    }
+
+    #[test]
+    fn test_apply_options() {
+        let registry = registry();
+        let mut workflow = Workflow::new()
+            .step("image.resize")
+            .step("image.png-to-webp");
+        workflow.options.insert("max_width".into(), Value::Int(640));
+        workflow
+            .options
+            .insert("lossless".into(), Value::Bool(true));
+        workflow.steps[0]
+            .options
+            .insert("max_width".into(), Value::Int(800));
+
+        let step = |id: &str| crate::PlanStep {
+            converter_id: id.into(),
+            input_port: "in".into(),
+            output_port: "out".into(),
+            output_properties: Properties::new(),
+            options: Properties::new(),
+        };
+        let mut plan = Plan {
+            steps: vec![step("image.resize"), step("image.png-to-webp")],
+            cost: 2.0,
+        };
+        // `lossless` is not declared by the resize step.
+        assert!(workflow.apply_options(&mut plan, &registry).is_ok());
+
+        assert_eq!(
+            plan.steps[0].options,
+            Properties::new().with("max_width", 800i64)
+        );
+        assert_eq!(
+            plan.steps[1].options,
+            Properties::new()
+                .with("max_width", 640i64)
+                .with("lossless", true)
+        );
+    }
+
     #[test]
     fn test_workflow_json_roundtrip() {
         let workflow = Workflow::new()
@@ -6,19 +6,192 @@
 //!
 //! # Features
 //! - `woff` (default) — TTF/OTF ↔ WOFF1
+//!
+//! The TTF/OTF inspector (family and style names) is always available.
 
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    ConvertError, FormatDecl, Inspector, InspectorDecl, Properties, PropertyDecl, PropertyPattern,
+    PropertyType, Registry,
 };
 
 /// Register all enabled font converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Property keys produced by the converters and inspectors below
+    registry.declare_property(PropertyDecl::new(
+        "font_family",
+        PropertyType::String,
+        "Font family name",
+    ));
+    registry.declare_property(PropertyDecl::new(
+        "font_style",
+        PropertyType::String,
+        "Font subfamily, e.g. `Bold Italic`",
+    ));
+
+    // Formats handled by the converters and inspectors below
+    registry.register_format(
+        FormatDecl::new("ttf")
+            .extension("ttf")
+            .mime("font/ttf")
+            .magic(b"\0\x01\0\0")
+            .description("TrueType font"),
+    );
+    registry.register_format(
+        FormatDecl::new("otf")
+            .extension("otf")
+            .mime("font/otf")
+            .magic(b"OTTO")
+            .description("OpenType font"),
+    );
+    #[cfg(feature = "woff")]
+    registry.register_format(
+        FormatDecl::new("woff")
+            .extension("woff")
+            .mime("font/woff")
+            .magic(b"wOFF")
+            .description("Web Open Font Format"),
+    );
+
     #[cfg(feature = "woff")]
     {
         registry.register(TtfToWoff);
         registry.register(OtfToWoff);
         registry.register(WoffToTtf);
     }
+
+    registry.register_inspector(SfntInspector::new("ttf"));
+    registry.register_inspector(SfntInspector::new("otf"));
+}
+
+// ============================================
+// sfnt 'name' table inspection
+//
+// Spec: https://learn.microsoft.com/en-us/typography/opentype/spec/name
+//
+// name header (6 bytes):
+//   format:        uint16
+//   count:         uint16
+//   stringOffset:  uint16 (from start of table)
+//
+// name record (12 bytes each):
+//   platformID, encodingID, languageID, nameID, length, offset: uint16
+// ============================================
+
+/// Name IDs read by the inspector.
+const NAME_FAMILY: u16 = 1;
+const NAME_SUBFAMILY: u16 = 2;
+const NAME_TYPOGRAPHIC_FAMILY: u16 = 16;
+const NAME_TYPOGRAPHIC_SUBFAMILY: u16 = 17;
+
+/// Inspector reading family and style names from a TTF/OTF.
+pub struct SfntInspector {
+    decl: InspectorDecl,
+}
+
+impl SfntInspector {
+    /// Create an inspector for `format` (`ttf` or `otf`).
+    pub fn new(format: &str) -> Self {
+        let decl = InspectorDecl::new(
+            format!("font.inspect-{}", format),
+            PropertyPattern::new().eq("format", format),
+        )
+        .description(format!(
+            "Read {} family and style names",
+            format.to_uppercase()
+        ))
+        .provides("font_family")
+        .provides("font_style");
+
+        Self { decl }
+    }
+}
+
+impl Inspector for SfntInspector {
+    fn decl(&self) -> &InspectorDecl {
+        &self.decl
+    }
+
+    fn inspect(&self, input: &[u8], _props: &Properties) -> Result<Properties, ConvertError> {
+        let names = read_names(input)?;
+        let pick = |preferred: u16, fallback: u16| {
+            names
+                .iter()
+                .find(|(id, _)| *id == preferred)
+                .or_else(|| names.iter().find(|(id, _)| *id == fallback))
+                .map(|(_, name)| name.clone())
+        };
+
+        let mut props = Properties::new();
+        if let Some(family) = pick(NAME_TYPOGRAPHIC_FAMILY, NAME_FAMILY) {
+            props.insert("font_family".into(), family.into());
+        }
+        if let Some(style) = pick(NAME_TYPOGRAPHIC_SUBFAMILY, NAME_SUBFAMILY) {
+            props.insert("font_style".into(), style.into());
+        }
+        Ok(props)
+    }
+}
+
+/// Read the decodable records of the 'name' table as `(nameID, string)`.
+///
+/// Unicode and Windows records (UTF-16BE) come before Macintosh Roman ones,
+/// and English records before other languages.
+fn read_names(data: &[u8]) -> Result<Vec<(u16, String)>, ConvertError> {
+    let u16_at = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+    let u32_at = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let truncated = || ConvertError::InvalidInput("Font file truncated".into());
+
+    let num_tables = u16_at(4).ok_or_else(truncated)? as usize;
+    let table = (0..num_tables)
+        .map(|i| 12 + i * 16)
+        .find(|&entry| data.get(entry..entry + 4) == Some(b"name"))
+        .ok_or_else(|| ConvertError::InvalidInput("Font has no 'name' table".into()))?;
+    let base = u32_at(table + 8).ok_or_else(truncated)? as usize;
+
+    let count = u16_at(base + 2).ok_or_else(truncated)? as usize;
+    let strings = base + u16_at(base + 4).ok_or_else(truncated)? as usize;
+
+    let mut records = Vec::with_capacity(count);
+    for i in 0..count {
+        let record = base + 6 + i * 12;
+        let field = |n: usize| u16_at(record + n * 2).ok_or_else(truncated);
+        let (platform, encoding, language, name_id) = (field(0)?, field(1)?, field(2)?, field(3)?);
+        let (length, offset) = (field(4)? as usize, field(5)? as usize);
+        let Some(raw) = data.get(strings + offset..strings + offset + length) else {
+            continue;
+        };
+
+        let (rank, name) = match (platform, encoding) {
+            (0, _) | (3, 0 | 1 | 10) => {
+                let units: Vec<u16> = raw
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                (0, String::from_utf16_lossy(&units))
+            }
+            // Macintosh Roman: decode the ASCII subset
+            (1, 0) => (1, raw.iter().map(|&b| b as char).collect()),
+            _ => continue,
+        };
+        let english = matches!((platform, language), (0, _) | (1, 0) | (3, 0x0409));
+        records.push(((rank, !english), name_id, name));
+    }
+
+    records.sort_by_key(|(rank, _, _)| *rank);
+    Ok(records
+        .into_iter()
+        .map(|(_, name_id, name)| (name_id, name))
+        .collect())
 }
 
 // ============================================
@@ -68,6 +241,7 @@ mod woff_impl {
     use flate2::Compression;
     use flate2::read::DeflateDecoder;
     use flate2::write::DeflateEncoder;
+    use paraphase_macros::paraphase_converter;
     use std::io::{Read, Write};
 
     const WOFF_SIGNATURE: u32 = 0x774F4646;
@@ -371,78 +545,24 @@ mod woff_impl {
         Ok(buf)
     }
 
-    /// Convert TTF to WOFF1.
-    pub struct TtfToWoff;
-
-    impl Converter for TtfToWoff {
-        fn decl(&self) -> &ConverterDecl {
-            static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-            DECL.get_or_init(|| {
-                ConverterDecl::simple(
-                    "font.ttf-to-woff",
-                    PropertyPattern::new().eq("format", "ttf"),
-                    PropertyPattern::new().eq("format", "woff"),
-                )
-                .description("Convert TTF font to WOFF1 container")
-            })
-        }
-
-        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-            let (sf_version, tables) = parse_sfnt_tables(input)?;
-            let woff = build_woff(sf_version, &tables)?;
-            let mut out_props = props.clone();
-            out_props.insert("format".into(), "woff".into());
-            Ok(ConvertOutput::Single(woff, out_props))
-        }
+    /// Convert TTF font to WOFF1 container
+    #[paraphase_converter(id = "font.ttf-to-woff", from = "ttf", to = "woff")]
+    pub fn ttf_to_woff(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+        let (sf_version, tables) = parse_sfnt_tables(input)?;
+        build_woff(sf_version, &tables)
     }
 
-    /// Convert OTF to WOFF1.
-    pub struct OtfToWoff;
-
-    impl Converter for OtfToWoff {
-        fn decl(&self) -> &ConverterDecl {
-            static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-            DECL.get_or_init(|| {
-                ConverterDecl::simple(
-                    "font.otf-to-woff",
-                    PropertyPattern::new().eq("format", "otf"),
-                    PropertyPattern::new().eq("format", "woff"),
-                )
-                .description("Convert OTF font to WOFF1 container")
-            })
-        }
-
-        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-            let (sf_version, tables) = parse_sfnt_tables(input)?;
-            let woff = build_woff(sf_version, &tables)?;
-            let mut out_props = props.clone();
-            out_props.insert("format".into(), "woff".into());
-            Ok(ConvertOutput::Single(woff, out_props))
-        }
+    /// Convert OTF font to WOFF1 container
+    #[paraphase_converter(id = "font.otf-to-woff", from = "otf", to = "woff")]
+    pub fn otf_to_woff(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+        let (sf_version, tables) = parse_sfnt_tables(input)?;
+        build_woff(sf_version, &tables)
     }
 
-    /// Convert WOFF1 back to TTF.
-    pub struct WoffToTtf;
-
-    impl Converter for WoffToTtf {
-        fn decl(&self) -> &ConverterDecl {
-            static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-            DECL.get_or_init(|| {
-                ConverterDecl::simple(
-                    "font.woff-to-ttf",
-                    PropertyPattern::new().eq("format", "woff"),
-                    PropertyPattern::new().eq("format", "ttf"),
-                )
-                .description("Extract TTF from WOFF1 container")
-            })
-        }
-
-        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-            let ttf = woff_to_sfnt(input)?;
-            let mut out_props = props.clone();
-            out_props.insert("format".into(), "ttf".into());
-            Ok(ConvertOutput::Single(ttf, out_props))
-        }
+    /// Extract TTF from WOFF1 container
+    #[paraphase_converter(id = "font.woff-to-ttf", from = "woff", to = "ttf")]
+    pub fn woff_to_ttf(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+        woff_to_sfnt(input)
     }
 }
 
@@ -455,7 +575,7 @@ mod tests {
     #[cfg(feature = "woff")]
     fn test_woff_bad_magic() {
         use super::*;
-        use paraphase_core::PropertiesExt;
+        use paraphase_core::{Converter, PropertiesExt};
         let bad_data = b"not a font";
         let props = Properties::new().with("format", "ttf");
         let result = TtfToWoff.convert(bad_data, &props);
@@ -466,10 +586,63 @@ mod tests {
     #[cfg(feature = "woff")]
     fn test_woff_to_ttf_bad_magic() {
         use super::*;
-        use paraphase_core::PropertiesExt;
+        use paraphase_core::{Converter, PropertiesExt};
         let bad_data = b"not a woff file at all really";
         let props = Properties::new().with("format", "woff");
         let result = WoffToTtf.convert(bad_data, &props);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_inspect_family_name() {
+        use super::*;
+
+        // Offset table with a single 'name' table holding one Windows
+        // record (nameID 1, UTF-16BE) and one Mac record (nameID 2)
+        let family: Vec<u8> = "Test Sans"
+            .encode_utf16()
+            .flat_map(|u| u.to_be_bytes())
+            .collect();
+        let style = b"Bold";
+
+        let mut name = Vec::new();
+        for v in [0u16, 2, 6 + 2 * 12] {
+            name.extend_from_slice(&v.to_be_bytes());
+        }
+        for v in [3u16, 1, 0x0409, 1, family.len() as u16, 0] {
+            name.extend_from_slice(&v.to_be_bytes());
+        }
+        for v in [1u16, 0, 0, 2, style.len() as u16, family.len() as u16] {
+            name.extend_from_slice(&v.to_be_bytes());
+        }
+        name.extend_from_slice(&family);
+        name.extend_from_slice(style);
+
+        let mut font = Vec::new();
+        font.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        font.extend_from_slice(&[0, 1, 0, 16, 0, 0, 0, 0]);
+        font.extend_from_slice(b"name");
+        font.extend_from_slice(&0u32.to_be_bytes());
+        font.extend_from_slice(&28u32.to_be_bytes());
+        font.extend_from_slice(&(name.len() as u32).to_be_bytes());
+        font.extend_from_slice(&name);
+
+        let props = SfntInspector::new("ttf")
+            .inspect(&font, &Properties::new())
+            .unwrap();
+        assert_eq!(
+            props.get("font_family").and_then(|v| v.as_str()),
+            Some("Test Sans")
+        );
+        assert_eq!(
+            props.get("font_style").and_then(|v| v.as_str()),
+            Some("Bold")
+        );
+
+        assert!(
+            SfntInspector::new("ttf")
+                .inspect(b"not a font", &Properties::new())
+                .is_err()
+        );
+    }
 }
@@ -6,11 +6,38 @@
 //! - `gpx` (default) — GPX ↔ GeoJSON via the gpx crate
 
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    ConvertError, ConvertOutput, Converter, ConverterDecl, FormatDecl, Properties, PropertyPattern,
+    Registry,
 };
 
 /// Register all enabled geo converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Formats handled by the converters below
+    #[cfg(feature = "gpx")]
+    registry.register_format(
+        FormatDecl::new("gpx")
+            .extension("gpx")
+            .mime("application/gpx+xml")
+            .description("GPS Exchange Format"),
+    );
+    #[cfg(any(feature = "gpx", feature = "wkt"))]
+    registry.register_format(
+        FormatDecl::new("geojson")
+            .extension("geojson")
+            .mime("application/geo+json")
+            .description("GeoJSON"),
+    );
+    #[cfg(feature = "wkt")]
+    registry.register_format(
+        FormatDecl::new("wkt")
+            .extension("wkt")
+            .description("Well-known text geometry"),
+    );
+
     #[cfg(feature = "gpx")]
     {
         registry.register(GpxToGeoJson);
@@ -31,15 +31,32 @@
 use image::{DynamicImage, GenericImageView, ImageFormat, Rgba};
 use indexmap::IndexMap;
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, NamedInput, PortDecl, Predicate,
-    Properties, PropertyPattern, Registry,
+    Comparator, ComparatorDecl, ConvertError, ConvertOutput, Converter, ConverterDecl, FormatDecl,
+    Formula, Inspector, InspectorDecl, NamedInput, OptionDecl, OptionType, PortDecl, Predicate,
+    Properties, PropertiesExt, PropertyDecl, PropertyPattern, PropertyType, Registry,
 };
 use std::io::Cursor;
 
 /// Register all enabled image converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Property keys produced by the converters and inspectors below
+    registry.declare_property(PropertyDecl::new(
+        "color_type",
+        PropertyType::String,
+        "Pixel layout, e.g. `rgba8`",
+    ));
+
     let formats = enabled_formats();
 
+    // Formats handled by the converters below
+    for (name, format) in &formats {
+        registry.register_format(format_decl(name, *format));
+    }
+
     // Register converters between all pairs of enabled formats
     for (from_name, from_fmt) in &formats {
         for (to_name, to_fmt) in &formats {
@@ -47,7 +64,9 @@ pub fn register_all(registry: &mut Registry) {
                 registry.register(ImageConverter::new(from_name, *from_fmt, to_name, *to_fmt));
             }
         }
+        registry.register_inspector(ImageInspector::new(from_name, *from_fmt));
     }
+    registry.register_comparator(PixelComparator::new(PixelComparator::DEFAULT_TOLERANCE));
 
     // Register transform converters
     registry.register(ResizeConverter::new());
@@ -90,6 +109,88 @@ pub fn enabled_formats() -> Vec<(&'static str, ImageFormat)> {
     ]
 }
 
+/// Format descriptor for an image format, for the registry.
+pub fn format_decl(name: &str, format: ImageFormat) -> FormatDecl {
+    let decl = FormatDecl::new(name);
+    match format {
+        ImageFormat::Png => decl
+            .extension("png")
+            .mime("image/png")
+            .magic(b"\x89PNG\r\n\x1a\n")
+            .description("Portable Network Graphics"),
+        ImageFormat::Jpeg => decl
+            .extensions(["jpg", "jpeg", "jpe"])
+            .mime("image/jpeg")
+            .magic(b"\xFF\xD8\xFF")
+            .alias("jpeg")
+            .description("JPEG image"),
+        ImageFormat::WebP => decl
+            .extension("webp")
+            .mime("image/webp")
+            .magic_at(8, b"WEBP")
+            .description("WebP image"),
+        ImageFormat::Gif => decl
+            .extension("gif")
+            .mime("image/gif")
+            .magic(b"GIF87a")
+            .magic(b"GIF89a")
+            .description("Graphics Interchange Format"),
+        ImageFormat::Bmp => decl
+            .extensions(["bmp", "dib"])
+            .mime("image/bmp")
+            .mime("image/x-ms-bmp")
+            .description("Windows bitmap"),
+        ImageFormat::Ico => decl
+            .extension("ico")
+            .mime("image/x-icon")
+            .mime("image/vnd.microsoft.icon")
+            .magic(b"\0\0\x01\0")
+            .description("Windows icon"),
+        ImageFormat::Tiff => decl
+            .extensions(["tiff", "tif"])
+            .mime("image/tiff")
+            .magic(b"II*\0")
+            .magic(b"MM\0*")
+            .alias("tif")
+            .description("Tagged Image File Format"),
+        ImageFormat::Tga => decl
+            .extension("tga")
+            .mime("image/x-tga")
+            .description("Truevision TGA"),
+        ImageFormat::Pnm => decl
+            .extensions(["pnm", "pbm", "pgm", "ppm", "pam"])
+            .mime("image/x-portable-anymap")
+            .description("Netpbm image"),
+        ImageFormat::Farbfeld => decl
+            .extensions(["ff", "farbfeld"])
+            .mime("image/x-farbfeld")
+            .magic(b"farbfeld")
+            .description("Farbfeld image"),
+        ImageFormat::Qoi => decl
+            .extension("qoi")
+            .mime("image/qoi")
+            .magic(b"qoif")
+            .description("Quite OK Image"),
+        ImageFormat::Avif => decl
+            .extension("avif")
+            .mime("image/avif")
+            .magic_at(4, b"ftypavif")
+            .description("AV1 image"),
+        ImageFormat::OpenExr => decl
+            .extension("exr")
+            .mime("image/x-exr")
+            .magic(b"\x76\x2F\x31\x01")
+            .alias("openexr")
+            .description("OpenEXR image"),
+        ImageFormat::Hdr => decl
+            .extension("hdr")
+            .mime("image/vnd.radiance")
+            .magic(b"#?RADIANCE")
+            .description("Radiance HDR image"),
+        _ => decl.extension(name),
+    }
+}
+
 /// A converter between two image formats.
 pub struct ImageConverter {
     decl: ConverterDecl,
@@ -115,7 +216,11 @@ impl ImageConverter {
             "Convert {} to {} via image crate",
             from_name.to_uppercase(),
             to_name.to_uppercase()
-        ));
+        ))
+        // Decoded RGBA buffer plus the encoded output
+        .memory_model(
+            Formula::prop("width") * Formula::prop("height") * 4.0 + Formula::input_size(),
+        );
 
         Self {
             decl,
@@ -133,8 +238,7 @@ impl Converter for ImageConverter {
 
     fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
         // Decode image
-        let img = image::load_from_memory_with_format(input, self.from_format)
-            .map_err(|e| ConvertError::InvalidInput(format!("Failed to decode image: {}", e)))?;
+        let img = decode_image(input, Some(self.from_format), "image")?;
 
         // Encode to target format
         let output = encode_image(&img, self.to_format)?;
@@ -151,6 +255,162 @@ impl Converter for ImageConverter {
     }
 }
 
+/// Inspector reading dimensions and color type from an image header.
+pub struct ImageInspector {
+    decl: InspectorDecl,
+    format: ImageFormat,
+}
+
+impl ImageInspector {
+    pub fn new(name: &'static str, format: ImageFormat) -> Self {
+        let decl = InspectorDecl::new(
+            format!("image.inspect-{}", name),
+            PropertyPattern::new().eq("format", name),
+        )
+        .description(format!(
+            "Read {} dimensions and color type",
+            name.to_uppercase()
+        ))
+        .provides("width")
+        .provides("height")
+        .provides("color_type");
+
+        Self { decl, format }
+    }
+}
+
+impl Inspector for ImageInspector {
+    fn decl(&self) -> &InspectorDecl {
+        &self.decl
+    }
+
+    fn inspect(&self, input: &[u8], _props: &Properties) -> Result<Properties, ConvertError> {
+        use image::ImageDecoder;
+
+        let decoder = image::ImageReader::with_format(Cursor::new(input), self.format)
+            .into_decoder()
+            .map_err(|e| {
+                ConvertError::InvalidInput(format!("Failed to read image header: {}", e))
+            })?;
+        let (width, height) = decoder.dimensions();
+        // e.g. "rgba8", "l16", "rgb32f"
+        let color_type = format!("{:?}", decoder.color_type()).to_lowercase();
+
+        Ok(Properties::new()
+            .with("width", width as i64)
+            .with("height", height as i64)
+            .with("color_type", color_type))
+    }
+}
+
+/// Compares two images by their decoded RGBA pixels.
+pub struct PixelComparator {
+    decl: ComparatorDecl,
+    tolerance: u8,
+}
+
+impl PixelComparator {
+    /// Default per-channel tolerance, enough to absorb rounding in color
+    /// conversions but not lossy re-encoding.
+    pub const DEFAULT_TOLERANCE: u8 = 2;
+
+    /// Create a comparator accepting channel differences up to `tolerance`.
+    pub fn new(tolerance: u8) -> Self {
+        let formats = enabled_formats()
+            .into_iter()
+            .map(|(name, _)| name.into())
+            .collect();
+        let decl = ComparatorDecl::new(
+            "image.pixels",
+            PropertyPattern::new().with("format", Predicate::OneOf(formats)),
+        )
+        .description(format!(
+            "Compare decoded pixels (tolerance {} per channel)",
+            tolerance
+        ));
+        Self { decl, tolerance }
+    }
+}
+
+impl Comparator for PixelComparator {
+    fn decl(&self) -> &ComparatorDecl {
+        &self.decl
+    }
+
+    fn compare(
+        &self,
+        original: &[u8],
+        roundtrip: &[u8],
+        _props: &Properties,
+    ) -> Result<Vec<String>, ConvertError> {
+        let decode = |data: &[u8]| decode_image(data, None, "image").map(|img| img.to_rgba8());
+        let a = decode(original)?;
+        let b = decode(roundtrip)?;
+
+        if a.dimensions() != b.dimensions() {
+            return Ok(vec![format!(
+                "dimensions: {}x{}, got {}x{}",
+                a.width(),
+                a.height(),
+                b.width(),
+                b.height()
+            )]);
+        }
+
+        let mut differing = 0u64;
+        let mut worst = (0u8, 0, 0);
+        for ((x, y, pa), pb) in a.enumerate_pixels().zip(b.pixels()) {
+            let delta = pa.0.iter().zip(pb.0).map(|(ca, cb)| ca.abs_diff(cb)).max();
+            let delta = delta.unwrap_or(0);
+            if delta > self.tolerance {
+                differing += 1;
+            }
+            if delta > worst.0 {
+                worst = (delta, x, y);
+            }
+        }
+        if differing == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(vec![format!(
+            "{} of {} pixels differ by more than {} (max {} at {},{})",
+            differing,
+            a.width() as u64 * a.height() as u64,
+            self.tolerance,
+            worst.0,
+            worst.1,
+            worst.2
+        )])
+    }
+}
+
+/// Decode an image, checking its size against the current limits before
+/// decoding any pixels. `what` names the image in error messages.
+fn decode_image(
+    input: &[u8],
+    format: Option<ImageFormat>,
+    what: &str,
+) -> Result<DynamicImage, ConvertError> {
+    use image::ImageDecoder;
+
+    let invalid = |e: image::ImageError| {
+        ConvertError::InvalidInput(format!("Failed to decode {}: {}", what, e))
+    };
+    let mut reader = image::ImageReader::new(Cursor::new(input));
+    match format {
+        Some(format) => reader.set_format(format),
+        None => {
+            reader = reader
+                .with_guessed_format()
+                .map_err(|e| invalid(e.into()))?
+        }
+    }
+    let decoder = reader.into_decoder().map_err(invalid)?;
+    let (width, height) = decoder.dimensions();
+    paraphase_core::current_limits().check_pixels(width as u64, height as u64)?;
+    DynamicImage::from_decoder(decoder).map_err(invalid)
+}
+
 /// Encode a DynamicImage to bytes in the specified format.
 fn encode_image(img: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ConvertError> {
     let mut buf = Cursor::new(Vec::new());
@@ -215,7 +475,7 @@ impl Gravity {
 
 /// Resize images to fit within bounds or scale by factor.
 ///
-/// Options (via properties):
+/// Options:
 /// - `max_width`: fit within this width (preserves aspect ratio)
 /// - `max_height`: fit within this height (preserves aspect ratio)
 /// - `target_width`: exact target width
@@ -237,7 +497,27 @@ impl ResizeConverter {
                 .with("width", Predicate::Any)
                 .with("height", Predicate::Any),
         )
-        .description("Resize image to target dimensions or within bounds");
+        .description("Resize image to target dimensions or within bounds")
+        .option(
+            "max_width",
+            OptionDecl::int("Fit within this width, preserving aspect ratio").min(1.0),
+        )
+        .option(
+            "max_height",
+            OptionDecl::int("Fit within this height, preserving aspect ratio").min(1.0),
+        )
+        .option(
+            "target_width",
+            OptionDecl::int("Exact target width").min(1.0),
+        )
+        .option(
+            "target_height",
+            OptionDecl::int("Exact target height").min(1.0),
+        )
+        .option(
+            "scale",
+            OptionDecl::float("Scale factor (e.g. 0.5 for half size)").min(0.0),
+        );
 
         Self { decl }
     }
@@ -255,14 +535,22 @@ impl Converter for ResizeConverter {
     }
 
     fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+        self.convert_with_options(input, props, &Properties::new())
+    }
+
+    fn convert_with_options(
+        &self,
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
         // Decode image (auto-detect format)
-        let img = image::load_from_memory(input)
-            .map_err(|e| ConvertError::InvalidInput(format!("Failed to decode image: {}", e)))?;
+        let img = decode_image(input, None, "image")?;
 
         let (orig_w, orig_h) = img.dimensions();
 
         // Determine target dimensions from options
-        let (new_w, new_h) = compute_resize_dimensions(orig_w, orig_h, props)?;
+        let (new_w, new_h) = compute_resize_dimensions(orig_w, orig_h, options)?;
 
         // Skip resize if dimensions unchanged
         let resized = if new_w == orig_w && new_h == orig_h {
@@ -295,13 +583,13 @@ impl Converter for ResizeConverter {
 fn compute_resize_dimensions(
     orig_w: u32,
     orig_h: u32,
-    props: &Properties,
+    options: &Properties,
 ) -> Result<(u32, u32), ConvertError> {
     let orig_w_f = orig_w as f64;
     let orig_h_f = orig_h as f64;
 
     // Check for scale factor first
-    if let Some(scale) = props.get("scale").and_then(|v| v.as_f64()) {
+    if let Some(scale) = options.get("scale").and_then(|v| v.as_f64()) {
         if scale <= 0.0 {
             return Err(ConvertError::InvalidInput("Scale must be positive".into()));
         }
@@ -312,11 +600,11 @@ fn compute_resize_dimensions(
     }
 
     // Check for exact dimensions
-    let target_w = props
+    let target_w = options
         .get("target_width")
         .and_then(|v| v.as_i64())
         .map(|v| v as u32);
-    let target_h = props
+    let target_h = options
         .get("target_height")
         .and_then(|v| v.as_i64())
         .map(|v| v as u32);
@@ -326,11 +614,11 @@ fn compute_resize_dimensions(
     }
 
     // Check for max bounds (fit within)
-    let max_w = props
+    let max_w = options
         .get("max_width")
         .and_then(|v| v.as_i64())
         .map(|v| v as u32);
-    let max_h = props
+    let max_h = options
         .get("max_height")
         .and_then(|v| v.as_i64())
         .map(|v| v as u32);
@@ -380,7 +668,7 @@ fn compute_resize_dimensions(
 
 /// Crop image to target aspect ratio with gravity anchor.
 ///
-/// Options (via properties):
+/// Options:
 /// - `aspect`: target aspect ratio as "W:H" string (e.g., "16:9") or float
 /// - `gravity`: anchor point for crop (default: "center")
 pub struct CropAspectConverter {
@@ -398,7 +686,15 @@ impl CropAspectConverter {
                 .with("width", Predicate::Any)
                 .with("height", Predicate::Any),
         )
-        .description("Crop image to target aspect ratio");
+        .description("Crop image to target aspect ratio")
+        .option(
+            "aspect",
+            OptionDecl::new(OptionType::Any, "Aspect ratio as \"W:H\" or a number"),
+        )
+        .option(
+            "gravity",
+            OptionDecl::string("Anchor point for the crop").default_value("center"),
+        );
 
         Self { decl }
     }
@@ -416,16 +712,24 @@ impl Converter for CropAspectConverter {
     }
 
     fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        let img = image::load_from_memory(input)
-            .map_err(|e| ConvertError::InvalidInput(format!("Failed to decode image: {}", e)))?;
+        self.convert_with_options(input, props, &Properties::new())
+    }
+
+    fn convert_with_options(
+        &self,
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        let img = decode_image(input, None, "image")?;
 
         let (orig_w, orig_h) = img.dimensions();
 
         // Parse target aspect ratio
-        let target_aspect = parse_aspect_ratio(props)?;
+        let target_aspect = parse_aspect_ratio(options)?;
 
         // Parse gravity
-        let gravity = props
+        let gravity = options
             .get("gravity")
             .and_then(|v| v.as_str())
             .and_then(Gravity::parse)
@@ -458,9 +762,9 @@ impl Converter for CropAspectConverter {
     }
 }
 
-/// Parse aspect ratio from properties.
-fn parse_aspect_ratio(props: &Properties) -> Result<f64, ConvertError> {
-    let aspect_val = props
+/// Parse aspect ratio from options.
+fn parse_aspect_ratio(options: &Properties) -> Result<f64, ConvertError> {
+    let aspect_val = options
         .get("aspect")
         .ok_or_else(|| ConvertError::MissingProperty("aspect".into()))?;
 
@@ -539,7 +843,7 @@ fn compute_crop_region(
 /// - `image`: the base image
 /// - `watermark`: the watermark/overlay image
 ///
-/// Options (via properties on the "image" input):
+/// Options:
 /// - `position`: gravity preset for watermark placement (default: "bottom-right")
 /// - `opacity`: watermark opacity 0.0-1.0 (default: 0.5)
 /// - `margin`: margin from edge in pixels (default: 10)
@@ -551,6 +855,22 @@ impl WatermarkConverter {
     pub fn new() -> Self {
         let decl = ConverterDecl::new("image.watermark")
             .description("Composite watermark onto image")
+            .option(
+                "position",
+                OptionDecl::string("Gravity preset for placement").default_value("bottom-right"),
+            )
+            .option(
+                "opacity",
+                OptionDecl::float("Watermark opacity")
+                    .range(0.0, 1.0)
+                    .default_value(0.5),
+            )
+            .option(
+                "margin",
+                OptionDecl::int("Margin from the edge in pixels")
+                    .min(0.0)
+                    .default_value(10i64),
+            )
             .input(
                 "image",
                 PortDecl::single(
@@ -599,6 +919,14 @@ impl Converter for WatermarkConverter {
     fn convert_multi(
         &self,
         inputs: &IndexMap<String, NamedInput<'_>>,
+    ) -> Result<ConvertOutput, ConvertError> {
+        self.convert_multi_with_options(inputs, &Properties::new())
+    }
+
+    fn convert_multi_with_options(
+        &self,
+        inputs: &IndexMap<String, NamedInput<'_>>,
+        options: &Properties,
     ) -> Result<ConvertOutput, ConvertError> {
         // Get both inputs
         let image_input = inputs
@@ -609,32 +937,25 @@ impl Converter for WatermarkConverter {
             .ok_or_else(|| ConvertError::MissingInput("watermark".into()))?;
 
         // Decode images
-        let mut base_img = image::load_from_memory(image_input.data)
-            .map_err(|e| ConvertError::InvalidInput(format!("Failed to decode base image: {}", e)))?
-            .to_rgba8();
+        let mut base_img = decode_image(image_input.data, None, "base image")?.to_rgba8();
 
-        let watermark_img = image::load_from_memory(watermark_input.data)
-            .map_err(|e| {
-                ConvertError::InvalidInput(format!("Failed to decode watermark image: {}", e))
-            })?
-            .to_rgba8();
+        let watermark_img = decode_image(watermark_input.data, None, "watermark image")?.to_rgba8();
 
-        // Get options from base image properties
         let props = image_input.props;
 
-        let position = props
+        let position = options
             .get("position")
             .and_then(|v| v.as_str())
             .and_then(Gravity::parse)
             .unwrap_or(Gravity::BottomRight);
 
-        let opacity = props
+        let opacity = options
             .get("opacity")
             .and_then(|v| v.as_f64())
             .unwrap_or(0.5)
             .clamp(0.0, 1.0);
 
-        let margin = props.get("margin").and_then(|v| v.as_i64()).unwrap_or(10) as u32;
+        let margin = options.get("margin").and_then(|v| v.as_i64()).unwrap_or(10) as u32;
 
         // Calculate watermark position
         let (base_w, base_h) = base_img.dimensions();
@@ -794,7 +1115,6 @@ pub fn detect_format(path: &str) -> Option<(&'static str, ImageFormat)> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use paraphase_core::PropertiesExt;
 
     #[test]
     fn test_register_all() {
@@ -840,6 +1160,40 @@ mod tests {
         buf.into_inner()
     }
 
+    #[test]
+    #[cfg(all(feature = "png", feature = "jpeg"))]
+    fn test_pixel_limit() {
+        use image::{ImageBuffer, Rgba};
+        use paraphase_core::{Limits, with_limits};
+
+        let img: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(2000, 1000);
+        let mut buf = Cursor::new(Vec::new());
+        img.write_to(&mut buf, ImageFormat::Png).unwrap();
+        let png_data = buf.into_inner();
+
+        let converter = ImageConverter::new("png", ImageFormat::Png, "jpg", ImageFormat::Jpeg);
+        let props = Properties::new().with("format", "png");
+        let convert = |megapixels| {
+            with_limits(Limits::new().max_megapixels(megapixels), || {
+                converter.convert(&png_data, &props)
+            })
+        };
+        assert!(convert(2.0).is_ok());
+        assert!(matches!(convert(1.5), Err(ConvertError::LimitExceeded(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "png")]
+    fn test_inspect_png() {
+        let mut registry = Registry::new();
+        register_all(&mut registry);
+
+        let props = registry.inspect(&create_test_png(), &Properties::new().with("format", "png"));
+        assert_eq!(props.get("width").unwrap().as_i64(), Some(1));
+        assert_eq!(props.get("height").unwrap().as_i64(), Some(1));
+        assert_eq!(props.get("color_type").unwrap().as_str(), Some("rgba8"));
+    }
+
     #[test]
     #[cfg(all(feature = "png", feature = "webp"))]
     fn test_png_to_webp() {
@@ -861,6 +1215,44 @@ mod tests {
     }
 
     #[cfg(feature = "png")]
+    #[test]
+    #[cfg(feature = "png")]
+    fn test_pixel_comparator() {
+        use image::{ImageBuffer, Rgba};
+
+        let encode = |color: [u8; 4]| {
+            let mut img: ImageBuffer<Rgba<u8>, Vec<u8>> =
+                ImageBuffer::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+            img.put_pixel(1, 2, Rgba(color));
+            let mut buf = Cursor::new(Vec::new());
+            img.write_to(&mut buf, ImageFormat::Png).unwrap();
+            buf.into_inner()
+        };
+        let comparator = PixelComparator::new(2);
+        let props = Properties::new().with("format", "png");
+        let original = encode([255, 0, 0, 255]);
+
+        let close = encode([253, 1, 0, 255]);
+        assert!(
+            comparator
+                .compare(&original, &close, &props)
+                .unwrap()
+                .is_empty()
+        );
+
+        let far = encode([200, 0, 0, 255]);
+        assert_eq!(
+            comparator.compare(&original, &far, &props).unwrap(),
+            vec!["1 of 16 pixels differ by more than 2 (max 55 at 1,2)"]
+        );
+
+        let resized = create_test_png_sized(4, 3);
+        assert_eq!(
+            comparator.compare(&original, &resized, &props).unwrap(),
+            vec!["dimensions: 4x4, got 4x3"]
+        );
+    }
+
     fn create_test_png_sized(width: u32, height: u32) -> Vec<u8> {
         use image::{ImageBuffer, Rgba};
 
@@ -880,10 +1272,12 @@ mod tests {
         let props = Properties::new()
             .with("format", "png")
             .with("width", 100i64)
-            .with("height", 50i64)
-            .with("scale", 0.5f64);
+            .with("height", 50i64);
+        let options = Properties::new().with("scale", 0.5f64);
 
-        let result = converter.convert(&png_data, &props).unwrap();
+        let result = converter
+            .convert_with_options(&png_data, &props, &options)
+            .unwrap();
 
         match result {
             ConvertOutput::Single(_, out_props) => {
@@ -903,10 +1297,12 @@ mod tests {
         let props = Properties::new()
             .with("format", "png")
             .with("width", 200i64)
-            .with("height", 100i64)
-            .with("max_width", 100i64);
+            .with("height", 100i64);
+        let options = Properties::new().with("max_width", 100i64);
 
-        let result = converter.convert(&png_data, &props).unwrap();
+        let result = converter
+            .convert_with_options(&png_data, &props, &options)
+            .unwrap();
 
         match result {
             ConvertOutput::Single(_, out_props) => {
@@ -927,10 +1323,12 @@ mod tests {
         let props = Properties::new()
             .with("format", "png")
             .with("width", 50i64)
-            .with("height", 25i64)
-            .with("max_width", 100i64);
+            .with("height", 25i64);
+        let options = Properties::new().with("max_width", 100i64);
 
-        let result = converter.convert(&png_data, &props).unwrap();
+        let result = converter
+            .convert_with_options(&png_data, &props, &options)
+            .unwrap();
 
         match result {
             ConvertOutput::Single(_, out_props) => {
@@ -952,10 +1350,12 @@ mod tests {
         let props = Properties::new()
             .with("format", "png")
             .with("width", 100i64)
-            .with("height", 100i64)
-            .with("aspect", "16:9");
+            .with("height", 100i64);
+        let options = Properties::new().with("aspect", "16:9");
 
-        let result = converter.convert(&png_data, &props).unwrap();
+        let result = converter
+            .convert_with_options(&png_data, &props, &options)
+            .unwrap();
 
         match result {
             ConvertOutput::Single(_, out_props) => {
@@ -979,10 +1379,12 @@ mod tests {
         let props = Properties::new()
             .with("format", "png")
             .with("width", 200i64)
-            .with("height", 100i64)
-            .with("aspect", "1:1");
+            .with("height", 100i64);
+        let options = Properties::new().with("aspect", "1:1");
 
-        let result = converter.convert(&png_data, &props).unwrap();
+        let result = converter
+            .convert_with_options(&png_data, &props, &options)
+            .unwrap();
 
         match result {
             ConvertOutput::Single(_, out_props) => {
@@ -0,0 +1,508 @@
+//! Procedural macros for Paraphase converters.
+//!
+//! `#[paraphase_converter]` turns a plain conversion function into a unit
+//! struct implementing `Converter`, generating the `ConverterDecl` (ports,
+//! costs, description) that would otherwise be written by hand:
+//!
+//! ```ignore
+//! use paraphase_macros::paraphase_converter;
+//!
+//! /// Convert TTF font to WOFF1 container
+//! #[paraphase_converter(id = "font.ttf-to-woff", from = "ttf", to = "woff", lossless)]
+//! pub fn ttf_to_woff(input: &[u8], props: &Properties) -> Result<Vec<u8>, ConvertError> {
+//!     // ...
+//! }
+//!
+//! registry.register(TtfToWoff);
+//! ```
+//!
+//! # Arguments
+//! - `from = "..."`, `to = "..."` (required) — `format` of the input and output;
+//!   `from = ["...", ...]` accepts any of several input formats
+//! - `id = "..."` — converter ID (default: the function name with `-` for `_`)
+//! - `description = "..."` — description (default: the function's doc comment)
+//! - `name = Ident` — name of the generated struct (default: the function
+//!   name in CamelCase)
+//! - `lossless` — shorthand for `cost(quality_loss = 0.0)`
+//! - `cost(key = value, ...)` — cost properties for path optimization
+//! - `option(name = expr, ...)` — options the converter accepts, each an
+//!   `OptionDecl` expression
+//! - `memory = expr`, `time = expr`, `output_size = expr` — resource models,
+//!   each a `Formula` expression
+//! - `provider = "..."`, `version = "..."`, `priority = expr` — the
+//!   corresponding `ConverterDecl` fields
+//!
+//! # Function signature
+//! The function takes `input: &[u8]`, optionally `props: &Properties`, and
+//! optionally `options: &Properties` after that (options are then kept
+//! apart from the properties, as in `Converter::convert_with_options`).
+//! It returns `Result<Vec<u8>, E>` (output properties are the input
+//! properties with `format` set to `to`) or `Result<ConvertOutput, E>`,
+//! where `E: Into<ConvertError>`.
+//!
+//! Plugins depending only on `paraphase-plugin` use the re-export
+//! `paraphase_plugin::paraphase_converter`, which generates paths into that
+//! crate instead of `paraphase_core`.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::meta::ParseNestedMeta;
+use syn::{
+    Attribute, Expr, ExprLit, FnArg, GenericArgument, Ident, ItemFn, Lit, LitStr, Meta,
+    PathArguments, ReturnType, Type, parse_macro_input,
+};
+
+/// Generate a `Converter` from a conversion function. See the crate docs.
+#[proc_macro_attribute]
+pub fn paraphase_converter(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand(attr, item, quote!(::paraphase_core))
+}
+
+/// Variant of [`paraphase_converter`] re-exported by `paraphase-plugin`.
+#[doc(hidden)]
+#[proc_macro_attribute]
+pub fn plugin_converter(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand(attr, item, quote!(::paraphase_plugin))
+}
+
+fn expand(attr: TokenStream, item: TokenStream, krate: TokenStream2) -> TokenStream {
+    let mut args = Args::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    parse_macro_input!(attr with parser);
+    let func = parse_macro_input!(item as ItemFn);
+
+    match generate(args, &func, krate) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Arguments passed straight to a `ConverterDecl` builder method.
+const SETTERS: &[(&str, &str)] = &[
+    ("memory", "memory_model"),
+    ("time", "time_model"),
+    ("output_size", "output_size_model"),
+    ("provider", "provider"),
+    ("version", "version"),
+    ("priority", "priority"),
+];
+
+/// Parsed attribute arguments.
+#[derive(Default)]
+struct Args {
+    id: Option<LitStr>,
+    from: Vec<LitStr>,
+    to: Option<LitStr>,
+    description: Option<LitStr>,
+    name: Option<Ident>,
+    lossless: bool,
+    costs: Vec<(String, f64)>,
+    options: Vec<(String, Expr)>,
+    /// Builder method and argument, e.g. `memory_model` and a formula.
+    setters: Vec<(Ident, Expr)>,
+}
+
+impl Args {
+    fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("id") {
+            self.id = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("from") {
+            self.from = match meta.value()?.parse::<Expr>()? {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(format),
+                    ..
+                }) => vec![format],
+                Expr::Array(formats) => formats
+                    .elems
+                    .into_iter()
+                    .map(|format| match format {
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(format),
+                            ..
+                        }) => Ok(format),
+                        other => Err(syn::Error::new_spanned(other, "expected a string")),
+                    })
+                    .collect::<syn::Result<_>>()?,
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "expected a string or an array of strings",
+                    ));
+                }
+            };
+        } else if meta.path.is_ident("to") {
+            self.to = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("description") {
+            self.description = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("lossless") {
+            self.lossless = true;
+        } else if meta.path.is_ident("cost") {
+            meta.parse_nested_meta(|cost| {
+                let key = cost
+                    .path
+                    .get_ident()
+                    .ok_or_else(|| cost.error("expected a cost name"))?
+                    .to_string();
+                let value = match cost.value()?.parse::<Lit>()? {
+                    Lit::Float(f) => f.base10_parse::<f64>()?,
+                    Lit::Int(i) => i.base10_parse::<f64>()?,
+                    lit => return Err(syn::Error::new(lit.span(), "expected a number")),
+                };
+                self.costs.push((key, value));
+                Ok(())
+            })?;
+        } else if meta.path.is_ident("option") {
+            meta.parse_nested_meta(|option| {
+                let name = option
+                    .path
+                    .get_ident()
+                    .ok_or_else(|| option.error("expected an option name"))?
+                    .to_string();
+                self.options.push((name, option.value()?.parse()?));
+                Ok(())
+            })?;
+        } else if let Some(setter) = SETTERS
+            .iter()
+            .find(|(arg, _)| meta.path.is_ident(arg))
+            .map(|(_, method)| Ident::new(method, Span::call_site()))
+        {
+            self.setters.push((setter, meta.value()?.parse()?));
+        } else {
+            return Err(meta.error(
+                "unsupported argument; expected `id`, `from`, `to`, `description`, `name`, `lossless`, \
+                 `cost(...)`, `option(...)`, `memory`, `time`, `output_size`, `provider`, `version` or `priority`",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn generate(args: Args, func: &ItemFn, krate: TokenStream2) -> syn::Result<TokenStream2> {
+    let sig = &func.sig;
+    let missing = |arg: &str| {
+        syn::Error::new(
+            Span::call_site(),
+            format!("missing `{} = \"...\"` argument", arg),
+        )
+    };
+    if args.from.is_empty() {
+        return Err(missing("from"));
+    }
+    let from = &args.from;
+    let to = args.to.ok_or_else(|| missing("to"))?;
+
+    if !sig.generics.params.is_empty() || sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            sig,
+            "converter functions cannot be generic or async",
+        ));
+    }
+    let arity = sig.inputs.len();
+    if !(1..=3).contains(&arity) {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            "expected `(input: &[u8])`, `(input: &[u8], props: &Properties)` or \
+             `(input: &[u8], props: &Properties, options: &Properties)`",
+        ));
+    }
+    if let Some(FnArg::Receiver(receiver)) = sig.inputs.first() {
+        return Err(syn::Error::new_spanned(
+            receiver,
+            "converter functions cannot take `self`",
+        ));
+    }
+
+    let fn_name = &sig.ident;
+    let vis = &func.vis;
+    let struct_name = args
+        .name
+        .unwrap_or_else(|| format_ident!("{}", camel_case(&fn_name.to_string())));
+    let id = args
+        .id
+        .map(|id| id.value())
+        .unwrap_or_else(|| default_id(&fn_name.to_string()));
+    let description = args
+        .description
+        .map(|d| d.value())
+        .or_else(|| doc_text(&func.attrs))
+        .unwrap_or_else(|| {
+            format!(
+                "Convert {} to {}",
+                from.iter()
+                    .map(|f| f.value().to_uppercase())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                to.value().to_uppercase()
+            )
+        });
+
+    let mut costs = args.costs;
+    if args.lossless && !costs.iter().any(|(k, _)| k == "quality_loss") {
+        costs.push(("quality_loss".into(), 0.0));
+    }
+    let cost_calls = costs.iter().map(|(key, value)| quote!(.cost(#key, #value)));
+    let option_calls = args
+        .options
+        .iter()
+        .map(|(name, decl)| quote!(.option(#name, #decl)));
+    let setter_calls = args
+        .setters
+        .iter()
+        .map(|(method, value)| quote!(.#method(#value)));
+
+    let call = match arity {
+        1 => quote!(#fn_name(input)),
+        2 => quote!(#fn_name(input, props)),
+        _ => quote!(#fn_name(input, props, options)),
+    };
+    let body = if returns_convert_output(&sig.output) {
+        quote! {
+            let _ = props;
+            #call.map_err(::core::convert::Into::<#krate::ConvertError>::into)
+        }
+    } else {
+        quote! {
+            let data = #call.map_err(::core::convert::Into::<#krate::ConvertError>::into)?;
+            let mut out_props = props.clone();
+            out_props.insert("format".into(), #to.into());
+            ::core::result::Result::Ok(#krate::ConvertOutput::Single(data, out_props))
+        }
+    };
+    let input_pattern = match from.as_slice() {
+        [format] => quote!(#krate::PropertyPattern::new().eq("format", #format)),
+        formats => quote! {
+            #krate::PropertyPattern::new().with(
+                "format",
+                #krate::Predicate::OneOf(::std::vec![#(#krate::Value::from(#formats)),*]),
+            )
+        },
+    };
+    // Functions taking options get them apart from the properties
+    let convert = if arity == 3 {
+        quote! {
+            fn convert(
+                &self,
+                input: &[u8],
+                props: &#krate::Properties,
+            ) -> ::core::result::Result<#krate::ConvertOutput, #krate::ConvertError> {
+                self.convert_with_options(input, props, &#krate::Properties::new())
+            }
+
+            fn convert_with_options(
+                &self,
+                input: &[u8],
+                props: &#krate::Properties,
+                options: &#krate::Properties,
+            ) -> ::core::result::Result<#krate::ConvertOutput, #krate::ConvertError> {
+                #body
+            }
+        }
+    } else {
+        quote! {
+            fn convert(
+                &self,
+                input: &[u8],
+                props: &#krate::Properties,
+            ) -> ::core::result::Result<#krate::ConvertOutput, #krate::ConvertError> {
+                #body
+            }
+        }
+    };
+
+    Ok(quote! {
+        #func
+
+        #[doc = #description]
+        #[derive(Debug, Clone, Copy, Default)]
+        #vis struct #struct_name;
+
+        impl #krate::Converter for #struct_name {
+            fn decl(&self) -> &#krate::ConverterDecl {
+                static DECL: ::std::sync::OnceLock<#krate::ConverterDecl> =
+                    ::std::sync::OnceLock::new();
+                DECL.get_or_init(|| {
+                    #krate::ConverterDecl::simple(
+                        #id,
+                        #input_pattern,
+                        #krate::PropertyPattern::new().eq("format", #to),
+                    )
+                    .description(#description)
+                    #(#cost_calls)*
+                    #(#option_calls)*
+                    #(#setter_calls)*
+                })
+            }
+
+            #convert
+        }
+    })
+}
+
+/// Check if a return type is `Result<ConvertOutput, _>`.
+fn returns_convert_output(output: &ReturnType) -> bool {
+    let ReturnType::Type(_, ty) = output else {
+        return false;
+    };
+    let Type::Path(path) = ty.as_ref() else {
+        return false;
+    };
+    let Some(result) = path.path.segments.last() else {
+        return false;
+    };
+    let PathArguments::AngleBracketed(args) = &result.arguments else {
+        return false;
+    };
+    matches!(
+        args.args.first(),
+        Some(GenericArgument::Type(Type::Path(ok)))
+            if ok.path.segments.last().is_some_and(|s| s.ident == "ConvertOutput")
+    )
+}
+
+/// Doc comment text, lines joined with spaces.
+fn doc_text(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|a| match &a.meta {
+            Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect();
+    (!lines.is_empty()).then(|| lines.join(" "))
+}
+
+/// `json_to_yaml` -> `JsonToYaml`.
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// `json_to_yaml` -> `json-to-yaml`.
+fn default_id(name: &str) -> String {
+    name.trim_matches('_').replace('_', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(camel_case("json_to_yaml"), "JsonToYaml");
+        assert_eq!(camel_case("ttf_to_woff2"), "TtfToWoff2");
+        assert_eq!(default_id("json_to_yaml"), "json-to-yaml");
+    }
+
+    #[test]
+    fn test_generate() {
+        let func: ItemFn = syn::parse_quote! {
+            /// Shout the input
+            pub fn txt_to_shout(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+                Ok(input.to_ascii_uppercase())
+            }
+        };
+        let args = Args {
+            from: vec![syn::parse_quote!("txt")],
+            to: Some(syn::parse_quote!("shout")),
+            lossless: true,
+            ..Default::default()
+        };
+        let out = generate(args, &func, quote!(::paraphase_core))
+            .unwrap()
+            .to_string();
+
+        assert!(out.contains("pub struct TxtToShout"));
+        assert!(out.contains("\"txt-to-shout\""));
+        assert!(out.contains("\"Shout the input\""));
+        assert!(out.contains("\"quality_loss\""));
+
+        let missing = Args::default();
+        assert!(generate(missing, &func, quote!(::paraphase_core)).is_err());
+    }
+
+    #[test]
+    fn test_generate_decl_fields() {
+        let func: ItemFn = syn::parse_quote! {
+            fn png_to_jpg(
+                input: &[u8],
+                props: &Properties,
+                options: &Properties,
+            ) -> Result<Vec<u8>, ConvertError> {
+                todo!()
+            }
+        };
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| args.parse(meta));
+        syn::parse::Parser::parse2(
+            parser,
+            quote! {
+                from = "png",
+                to = "jpg",
+                option(quality = OptionDecl::int("JPEG quality")),
+                memory = Formula::input_size(),
+                provider = "acme",
+                priority = -1,
+            },
+        )
+        .unwrap();
+        let out = generate(args, &func, quote!(::paraphase_core))
+            .unwrap()
+            .to_string();
+
+        assert!(out.contains(". option (\"quality\" , OptionDecl :: int (\"JPEG quality\"))"));
+        assert!(out.contains(". memory_model (Formula :: input_size ())"));
+        assert!(out.contains(". provider (\"acme\")"));
+        assert!(out.contains(". priority (- 1)"));
+        assert!(out.contains("fn convert_with_options"));
+        assert!(out.contains("png_to_jpg (input , props , options)"));
+    }
+
+    #[test]
+    fn test_generate_several_inputs() {
+        let func: ItemFn = syn::parse_quote! {
+            fn gltf_to_stl(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+                todo!()
+            }
+        };
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| args.parse(meta));
+        syn::parse::Parser::parse2(parser, quote!(from = ["gltf", "glb"], to = "stl")).unwrap();
+        let out = generate(args, &func, quote!(::paraphase_core))
+            .unwrap()
+            .to_string();
+
+        assert!(out.contains("Predicate :: OneOf"));
+        assert!(out.contains("\"Convert GLTF/GLB to STL\""));
+    }
+
+    #[test]
+    fn test_returns_convert_output() {
+        let func: ItemFn = syn::parse_quote! {
+            fn f(input: &[u8]) -> Result<ConvertOutput, ConvertError> { todo!() }
+        };
+        assert!(returns_convert_output(&func.sig.output));
+
+        let func: ItemFn = syn::parse_quote! {
+            fn f(input: &[u8]) -> Result<Vec<u8>, ConvertError> { todo!() }
+        };
+        assert!(!returns_convert_output(&func.sig.output));
+    }
+}
@@ -6,13 +6,41 @@
 //! - `pem` (default) — PEM encode/decode via pem-rfc7468
 
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    ConvertError, ConvertOutput, FormatDecl, OptionDecl, Properties, PropertyDecl, PropertyType,
+    Registry,
 };
 
 /// Register all enabled PKI converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Formats handled by the converters below
+    #[cfg(feature = "pem")]
+    {
+        registry.register_format(
+            FormatDecl::new("pem")
+                .extensions(["pem", "crt", "cer", "key"])
+                .mime("application/x-pem-file")
+                .magic(b"-----BEGIN ")
+                .description("PEM-encoded DER"),
+        );
+        registry.register_format(
+            FormatDecl::new("der")
+                .extension("der")
+                .description("DER-encoded ASN.1"),
+        );
+    }
+
     #[cfg(feature = "pem")]
     {
+        // Property keys produced by the converters below
+        registry.declare_property(PropertyDecl::new(
+            "pem_label",
+            PropertyType::String,
+            "PEM block label, e.g. `CERTIFICATE`",
+        ));
         registry.register(PemToDer);
         registry.register(DerToPem);
     }
@@ -25,72 +53,56 @@ pub fn register_all(registry: &mut Registry) {
 #[cfg(feature = "pem")]
 mod pem_impl {
     use super::*;
+    use paraphase_macros::paraphase_converter;
 
     /// Decode PEM-encoded data to raw DER bytes.
     ///
     /// Input: PEM text with `-----BEGIN {label}-----` header.
     /// Output: raw DER bytes, `format = "der"`, `pem_label = "{label}"`.
-    pub struct PemToDer;
-
-    impl Converter for PemToDer {
-        fn decl(&self) -> &ConverterDecl {
-            static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-            DECL.get_or_init(|| {
-                ConverterDecl::simple(
-                    "pki.pem-to-der",
-                    PropertyPattern::new().eq("format", "pem"),
-                    PropertyPattern::new().eq("format", "der"),
-                )
-                .description("Decode PEM to raw DER bytes")
-            })
-        }
-
-        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-            let (label, der_data) = pem_rfc7468::decode_vec(input)
-                .map_err(|e| ConvertError::InvalidInput(format!("Invalid PEM: {}", e)))?;
-
-            let mut out_props = props.clone();
-            out_props.insert("format".into(), "der".into());
-            out_props.insert("pem_label".into(), label.to_string().into());
-            Ok(ConvertOutput::Single(der_data, out_props))
-        }
+    #[paraphase_converter(
+        id = "pki.pem-to-der",
+        from = "pem",
+        to = "der",
+        description = "Decode PEM to raw DER bytes"
+    )]
+    pub fn pem_to_der(input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+        let (label, der_data) = pem_rfc7468::decode_vec(input)
+            .map_err(|e| ConvertError::InvalidInput(format!("Invalid PEM: {}", e)))?;
+
+        let mut out_props = props.clone();
+        out_props.insert("format".into(), "der".into());
+        out_props.insert("pem_label".into(), label.to_string().into());
+        Ok(ConvertOutput::Single(der_data, out_props))
     }
 
     /// Encode raw DER bytes as PEM.
     ///
     /// Input: DER bytes, `format = "der"`.
-    /// Props: optional `pem_label` (default: "CERTIFICATE").
+    /// Label: the `pem_label` option, else the `pem_label` property (default: "CERTIFICATE").
     /// Output: PEM text, `format = "pem"`.
-    pub struct DerToPem;
-
-    impl Converter for DerToPem {
-        fn decl(&self) -> &ConverterDecl {
-            static DECL: std::sync::OnceLock<ConverterDecl> = std::sync::OnceLock::new();
-            DECL.get_or_init(|| {
-                ConverterDecl::simple(
-                    "pki.der-to-pem",
-                    PropertyPattern::new().eq("format", "der"),
-                    PropertyPattern::new().eq("format", "pem"),
-                )
-                .description(
-                    "Encode DER bytes as PEM (requires pem_label property, default: CERTIFICATE)",
-                )
-            })
-        }
-
-        fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-            let label = props
-                .get("pem_label")
-                .and_then(|v| v.as_str().map(|s| s.to_string()))
-                .unwrap_or_else(|| "CERTIFICATE".to_string());
-
-            let pem = pem_rfc7468::encode_string(&label, pem_rfc7468::LineEnding::LF, input)
-                .map_err(|e| ConvertError::Failed(format!("PEM encode failed: {}", e)))?;
-
-            let mut out_props = props.clone();
-            out_props.insert("format".into(), "pem".into());
-            Ok(ConvertOutput::Single(pem.into_bytes(), out_props))
-        }
+    #[paraphase_converter(
+        id = "pki.der-to-pem",
+        from = "der",
+        to = "pem",
+        description = "Encode DER bytes as PEM (label from pem_label, default: CERTIFICATE)",
+        option(pem_label = OptionDecl::string("PEM block label").default_value("CERTIFICATE"))
+    )]
+    pub fn der_to_pem(
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<Vec<u8>, ConvertError> {
+        // The label recorded when the DER was decoded from PEM, unless
+        // overridden
+        let label = options
+            .get("pem_label")
+            .or_else(|| props.get("pem_label"))
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .unwrap_or_else(|| "CERTIFICATE".to_string());
+
+        let pem = pem_rfc7468::encode_string(&label, pem_rfc7468::LineEnding::LF, input)
+            .map_err(|e| ConvertError::Failed(format!("PEM encode failed: {}", e)))?;
+        Ok(pem.into_bytes())
     }
 }
 
@@ -100,7 +112,7 @@ pub use pem_impl::{DerToPem, PemToDer};
 #[cfg(test)]
 mod tests {
     use super::*;
-    use paraphase_core::PropertiesExt;
+    use paraphase_core::{Converter, PropertiesExt};
 
     #[test]
     #[cfg(feature = "pem")]
@@ -0,0 +1,92 @@
+/*
+ * paraphase_plugin.h - C ABI for Paraphase converter plugins.
+ *
+ * A plugin is a shared library (.so/.dylib/.dll) exporting the functions
+ * below. The host discovers plugins in $PARAPHASE_PLUGIN_PATH,
+ * ~/.paraphase/plugins and ./paraphase-plugins. See ADR-0001.
+ */
+
+#ifndef PARAPHASE_PLUGIN_H
+#define PARAPHASE_PLUGIN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* API version as 0xMMmmpp (major, minor, patch). The host loads plugins with
+ * the same major version and a minor version no newer than its own. */
+#define PARAPHASE_PLUGIN_API_VERSION 0x010100
+
+/* Converter flags */
+#define PARAPHASE_FLAG_LOSSLESS  (1u << 0)
+#define PARAPHASE_FLAG_STREAMING (1u << 1)
+
+/* Return codes of paraphase_convert */
+#define PARAPHASE_OK                     0
+#define PARAPHASE_ERR_FAILED             1
+#define PARAPHASE_ERR_PANIC              2
+#define PARAPHASE_ERR_UNKNOWN_CONVERTER  3
+#define PARAPHASE_ERR_INVALID_ARGUMENT   4
+
+/* Converter metadata */
+typedef struct {
+    const char* id;        /* unique identifier, e.g. "acme.foo-to-json" */
+    const char* from_type; /* input "format" property, e.g. "foo" */
+    const char* to_type;   /* output "format" property, e.g. "json" */
+    uint32_t flags;        /* PARAPHASE_FLAG_* bitmask */
+} ParaphaseConverter;
+
+/* Required: API version the plugin was built against. */
+uint32_t paraphase_plugin_api_version(void);
+
+/* Optional: semver range of host API versions supported, e.g. "^1.0". */
+const char* paraphase_plugin_api_compat(void);
+
+/* Required: converters provided (caller does NOT free). */
+const ParaphaseConverter* paraphase_list_converters(size_t* count);
+
+/* Optional: JSON array of full converter declarations (ports, patterns,
+ * costs). When present it takes precedence over from_type/to_type. */
+const char* paraphase_describe_converters(void);
+
+/* Required: perform a conversion.
+ *
+ * props_json is a JSON object of the input properties (may be NULL). When
+ * the plugin does not export paraphase_convert_with_options, converter
+ * options are merged into it. Returns PARAPHASE_OK on success and sets
+ * *output and *output_len; the caller releases *output with
+ * paraphase_free. */
+int paraphase_convert(
+    const char* converter_id,
+    const uint8_t* input, size_t input_len,
+    uint8_t** output, size_t* output_len,
+    const char* props_json
+);
+
+/* Optional (since 1.1): perform a conversion with converter options.
+ *
+ * Like paraphase_convert, with the options (max_width, quality...) passed
+ * apart from the input properties in options_json (may be NULL). The host
+ * calls this instead of paraphase_convert when it is exported. */
+int paraphase_convert_with_options(
+    const char* converter_id,
+    const uint8_t* input, size_t input_len,
+    uint8_t** output, size_t* output_len,
+    const char* props_json,
+    const char* options_json
+);
+
+/* Required: free memory allocated by paraphase_convert. */
+void paraphase_free(void* ptr);
+
+/* Optional: message for the last failure on this thread (may return NULL). */
+const char* paraphase_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PARAPHASE_PLUGIN_H */
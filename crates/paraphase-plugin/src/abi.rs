@@ -0,0 +1,131 @@
+//! C ABI shared between the host and dynamic plugins.
+//!
+//! Mirrors `include/paraphase_plugin.h`. See ADR-0001 for the design.
+
+use std::ffi::{c_char, c_int, c_void};
+
+/// API version implemented by this crate, encoded as `0xMMmmpp`
+/// (major, minor, patch).
+pub const PARAPHASE_PLUGIN_API_VERSION: u32 = 0x01_01_00;
+
+/// The converter loses no information.
+pub const PARAPHASE_FLAG_LOSSLESS: u32 = 1 << 0;
+/// The converter can process input incrementally.
+pub const PARAPHASE_FLAG_STREAMING: u32 = 1 << 1;
+
+/// Conversion succeeded.
+pub const PARAPHASE_OK: c_int = 0;
+/// The converter reported an error.
+pub const PARAPHASE_ERR_FAILED: c_int = 1;
+/// The converter panicked.
+pub const PARAPHASE_ERR_PANIC: c_int = 2;
+/// No converter with the requested ID.
+pub const PARAPHASE_ERR_UNKNOWN_CONVERTER: c_int = 3;
+/// Null pointers, invalid UTF-8 or malformed properties or options.
+pub const PARAPHASE_ERR_INVALID_ARGUMENT: c_int = 4;
+
+/// Converter metadata exported by a plugin.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ParaphaseConverter {
+    /// Unique identifier, e.g. `acme.foo-to-bar`.
+    pub id: *const c_char,
+    /// Input `format` property.
+    pub from_type: *const c_char,
+    /// Output `format` property.
+    pub to_type: *const c_char,
+    /// `PARAPHASE_FLAG_*` bitmask.
+    pub flags: u32,
+}
+
+// Converter tables are immutable static data owned by the plugin.
+unsafe impl Send for ParaphaseConverter {}
+unsafe impl Sync for ParaphaseConverter {}
+
+/// Symbol names.
+pub mod symbols {
+    pub const API_VERSION: &[u8] = b"paraphase_plugin_api_version\0";
+    pub const API_COMPAT: &[u8] = b"paraphase_plugin_api_compat\0";
+    pub const LIST_CONVERTERS: &[u8] = b"paraphase_list_converters\0";
+    pub const DESCRIBE_CONVERTERS: &[u8] = b"paraphase_describe_converters\0";
+    pub const CONVERT: &[u8] = b"paraphase_convert\0";
+    pub const CONVERT_WITH_OPTIONS: &[u8] = b"paraphase_convert_with_options\0";
+    pub const FREE: &[u8] = b"paraphase_free\0";
+    pub const LAST_ERROR: &[u8] = b"paraphase_last_error\0";
+}
+
+/// WebAssembly plugin ABI.
+///
+/// A module exports its linear `memory` and the functions below, and
+/// imports nothing. Buffers are returned as `ptr << 32 | len` in an `i64`.
+/// The host instantiates the module afresh for every conversion, so guest
+/// allocations are never freed.
+///
+/// - `paraphase_plugin_api_version() -> i32`
+/// - `paraphase_alloc(len: i32) -> i32`: buffer for the host to write into
+/// - `paraphase_describe() -> i64`: JSON array of converter declarations
+/// - `paraphase_convert(id, id_len, input, input_len, props, props_len) -> i64`:
+///   a status byte (`PARAPHASE_OK` or an error code) followed by the
+///   output, or by an error message. `props` is a JSON object of the input
+///   properties, with any converter options merged in.
+pub mod wasm {
+    pub const MEMORY: &str = "memory";
+    pub const API_VERSION: &str = "paraphase_plugin_api_version";
+    pub const ALLOC: &str = "paraphase_alloc";
+    pub const DESCRIBE: &str = "paraphase_describe";
+    pub const CONVERT: &str = "paraphase_convert";
+}
+
+/// `uint32_t paraphase_plugin_api_version(void)` (required).
+pub type ApiVersionFn = unsafe extern "C" fn() -> u32;
+
+/// `const char* f(void)`: used by `paraphase_plugin_api_compat`,
+/// `paraphase_describe_converters` and `paraphase_last_error` (all optional).
+pub type StrFn = unsafe extern "C" fn() -> *const c_char;
+
+/// `const ParaphaseConverter* paraphase_list_converters(size_t* count)` (required).
+pub type ListConvertersFn = unsafe extern "C" fn(count: *mut usize) -> *const ParaphaseConverter;
+
+/// `int paraphase_convert(...)` (required).
+pub type ConvertFn = unsafe extern "C" fn(
+    converter_id: *const c_char,
+    input: *const u8,
+    input_len: usize,
+    output: *mut *mut u8,
+    output_len: *mut usize,
+    props_json: *const c_char,
+) -> c_int;
+
+/// `int paraphase_convert_with_options(...)` (optional, since 1.1).
+pub type ConvertWithOptionsFn = unsafe extern "C" fn(
+    converter_id: *const c_char,
+    input: *const u8,
+    input_len: usize,
+    output: *mut *mut u8,
+    output_len: *mut usize,
+    props_json: *const c_char,
+    options_json: *const c_char,
+) -> c_int;
+
+/// `void paraphase_free(void* ptr)` (required).
+pub type FreeFn = unsafe extern "C" fn(ptr: *mut c_void);
+
+/// Split an encoded API version into `(major, minor, patch)`.
+pub fn decode_version(version: u32) -> (u64, u64, u64) {
+    (
+        u64::from((version >> 16) & 0xff),
+        u64::from((version >> 8) & 0xff),
+        u64::from(version & 0xff),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_version() {
+        assert_eq!(decode_version(PARAPHASE_PLUGIN_API_VERSION), (1, 1, 0));
+        assert_eq!(decode_version(0x02_03_04), (2, 3, 4));
+    }
+}
@@ -0,0 +1,635 @@
+//! Plugin-side support for the C ABI exports.
+//!
+//! Plugins do not use this module directly; [`export!`](crate::export!)
+//! generates the `extern "C"` entry points and forwards them here.
+
+use crate::abi::{
+    PARAPHASE_ERR_FAILED, PARAPHASE_ERR_INVALID_ARGUMENT, PARAPHASE_ERR_PANIC,
+    PARAPHASE_ERR_UNKNOWN_CONVERTER, PARAPHASE_FLAG_LOSSLESS, PARAPHASE_OK, ParaphaseConverter,
+};
+use paraphase_core::{ConvertOutput, Converter, ConverterDecl, Predicate, Properties, Value};
+use std::alloc::{Layout, alloc, dealloc, handle_alloc_error};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+/// Compatibility range declared by plugins built with this crate.
+const API_COMPAT: &CStr = c"^1.1";
+
+/// Bytes reserved in front of each output buffer to record its length,
+/// so `paraphase_free` can release it from the pointer alone.
+const HEADER: usize = 16;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Converters exported by a plugin, with their C metadata.
+pub struct PluginExports {
+    converters: Vec<Box<dyn Converter>>,
+    table: Vec<ParaphaseConverter>,
+    decls_json: CString,
+    // Backing storage for the pointers in `table`
+    _strings: Vec<CString>,
+}
+
+impl PluginExports {
+    /// Build the export table for a set of converters.
+    pub fn new(converters: Vec<Box<dyn Converter>>) -> Self {
+        let mut strings = Vec::new();
+        let mut table = Vec::with_capacity(converters.len());
+        let mut cstr = |s: &str| {
+            let s = CString::new(s.replace('\0', "")).unwrap_or_default();
+            let ptr = s.as_ptr();
+            strings.push(s);
+            ptr
+        };
+
+        for converter in &converters {
+            let decl = converter.decl();
+            table.push(ParaphaseConverter {
+                id: cstr(&decl.id),
+                from_type: cstr(&port_format(decl.inputs.values().next())),
+                to_type: cstr(&port_format(decl.outputs.values().next())),
+                flags: flags(decl),
+            });
+        }
+
+        let decls: Vec<&ConverterDecl> = converters.iter().map(|c| c.decl()).collect();
+        let decls_json = serde_json::to_string(&decls)
+            .ok()
+            .and_then(|json| CString::new(json).ok())
+            .unwrap_or_default();
+
+        Self {
+            converters,
+            table,
+            decls_json,
+            _strings: strings,
+        }
+    }
+
+    /// Implementation of `paraphase_list_converters`.
+    ///
+    /// # Safety
+    ///
+    /// `count` must be null or valid for writes.
+    pub unsafe fn list(&self, count: *mut usize) -> *const ParaphaseConverter {
+        if !count.is_null() {
+            unsafe { *count = self.table.len() };
+        }
+        self.table.as_ptr()
+    }
+
+    /// Implementation of `paraphase_describe_converters`: the full
+    /// declarations as a JSON array.
+    pub fn describe(&self) -> *const c_char {
+        self.decls_json.as_ptr()
+    }
+
+    /// Implementation of `paraphase_convert`.
+    ///
+    /// # Safety
+    ///
+    /// Pointers must satisfy the contract in `paraphase_plugin.h`.
+    pub unsafe fn convert(
+        &self,
+        converter_id: *const c_char,
+        input: *const u8,
+        input_len: usize,
+        output: *mut *mut u8,
+        output_len: *mut usize,
+        props_json: *const c_char,
+    ) -> c_int {
+        unsafe {
+            self.convert_with_options(
+                converter_id,
+                input,
+                input_len,
+                output,
+                output_len,
+                props_json,
+                std::ptr::null(),
+            )
+        }
+    }
+
+    /// Implementation of `paraphase_convert_with_options`.
+    ///
+    /// # Safety
+    ///
+    /// Pointers must satisfy the contract in `paraphase_plugin.h`.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn convert_with_options(
+        &self,
+        converter_id: *const c_char,
+        input: *const u8,
+        input_len: usize,
+        output: *mut *mut u8,
+        output_len: *mut usize,
+        props_json: *const c_char,
+        options_json: *const c_char,
+    ) -> c_int {
+        if converter_id.is_null()
+            || output.is_null()
+            || output_len.is_null()
+            || (input.is_null() && input_len != 0)
+        {
+            set_last_error("null argument");
+            return PARAPHASE_ERR_INVALID_ARGUMENT;
+        }
+
+        let Ok(id) = unsafe { CStr::from_ptr(converter_id) }.to_str() else {
+            set_last_error("converter ID is not valid UTF-8");
+            return PARAPHASE_ERR_INVALID_ARGUMENT;
+        };
+        let json = |ptr: *const c_char| {
+            (!ptr.is_null()).then(|| unsafe { CStr::from_ptr(ptr) }.to_bytes())
+        };
+        let (props, options) = (json(props_json), json(options_json));
+        let input: &[u8] = if input_len == 0 {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(input, input_len) }
+        };
+
+        match self.run(id, input, props, options) {
+            Ok(data) => {
+                unsafe {
+                    *output_len = data.len();
+                    *output = alloc_output(&data);
+                }
+                PARAPHASE_OK
+            }
+            Err((code, message)) => {
+                set_last_error(&message);
+                code
+            }
+        }
+    }
+
+    /// Implementation of the WebAssembly `paraphase_convert`: a status byte
+    /// (`PARAPHASE_OK` or an error code) followed by the output, or by the
+    /// error message on failure.
+    pub fn convert_wasm(&self, converter_id: &[u8], input: &[u8], props: &[u8]) -> Vec<u8> {
+        let result = match std::str::from_utf8(converter_id) {
+            Ok(id) => self.run(id, input, (!props.is_empty()).then_some(props), None),
+            Err(_) => Err((
+                PARAPHASE_ERR_INVALID_ARGUMENT,
+                "converter ID is not valid UTF-8".into(),
+            )),
+        };
+        let (code, payload) = match result {
+            Ok(data) => (PARAPHASE_OK, data),
+            Err((code, message)) => (code, message.into_bytes()),
+        };
+        let mut out = Vec::with_capacity(payload.len() + 1);
+        out.push(code as u8);
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// Implementation of the WebAssembly `paraphase_describe`.
+    pub fn describe_wasm(&self) -> Vec<u8> {
+        self.decls_json.as_bytes().to_vec()
+    }
+
+    /// Run a converter, returning its output or an error code and message.
+    fn run(
+        &self,
+        id: &str,
+        input: &[u8],
+        props: Option<&[u8]>,
+        options: Option<&[u8]>,
+    ) -> Result<Vec<u8>, (c_int, String)> {
+        let Some(converter) = self.converters.iter().find(|c| c.decl().id == id) else {
+            return Err((
+                PARAPHASE_ERR_UNKNOWN_CONVERTER,
+                format!("unknown converter: {}", id),
+            ));
+        };
+
+        let parse = |json: Option<&[u8]>, what: &str| match json {
+            None => Ok(Properties::new()),
+            Some(json) => serde_json::from_slice::<Properties>(json).map_err(|e| {
+                (
+                    PARAPHASE_ERR_INVALID_ARGUMENT,
+                    format!("invalid {}: {}", what, e),
+                )
+            }),
+        };
+        let props = parse(props, "properties")?;
+        let options = parse(options, "options")?;
+
+        match catch_unwind(AssertUnwindSafe(|| {
+            converter.convert_with_options(input, &props, &options)
+        })) {
+            Ok(Ok(ConvertOutput::Single(data, _))) => Ok(data),
+            Ok(Ok(ConvertOutput::Multiple(_))) => Err((
+                PARAPHASE_ERR_FAILED,
+                "multiple outputs are not supported over the plugin ABI".into(),
+            )),
+            Ok(Err(e)) => Err((PARAPHASE_ERR_FAILED, e.to_string())),
+            Err(_) => Err((PARAPHASE_ERR_PANIC, format!("converter {} panicked", id))),
+        }
+    }
+}
+
+/// Implementation of the WebAssembly `paraphase_alloc`: a buffer the host
+/// writes arguments into. The instance is discarded after each call, so
+/// buffers are never freed.
+pub fn wasm_alloc(len: usize) -> *mut u8 {
+    let mut buf = std::mem::ManuallyDrop::new(Vec::<u8>::with_capacity(len));
+    buf.as_mut_ptr()
+}
+
+/// Hand a buffer to the host as `ptr << 32 | len` (32-bit linear memory).
+pub fn wasm_return(data: Vec<u8>) -> u64 {
+    let data = std::mem::ManuallyDrop::new(data.into_boxed_slice());
+    ((data.as_ptr() as usize as u64) << 32) | data.len() as u64
+}
+
+/// Borrow an argument the host wrote into linear memory.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` initialised bytes (or `len` must be zero).
+pub unsafe fn wasm_slice<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 || ptr.is_null() {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(ptr, len) }
+    }
+}
+
+/// Implementation of `paraphase_plugin_api_compat`.
+pub fn api_compat() -> *const c_char {
+    API_COMPAT.as_ptr()
+}
+
+/// Implementation of `paraphase_last_error`.
+///
+/// The message stays valid until the next failing call on the same thread.
+pub fn last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |s| s.as_ptr()))
+}
+
+/// Implementation of `paraphase_free`.
+///
+/// # Safety
+///
+/// `ptr` must be null or an output buffer returned by `paraphase_convert`
+/// that has not been freed yet.
+pub unsafe fn free(ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe {
+        let base = ptr.cast::<u8>().sub(HEADER);
+        let len = base.cast::<usize>().read();
+        dealloc(
+            base,
+            Layout::from_size_align_unchecked(HEADER + len, HEADER),
+        );
+    }
+}
+
+fn set_last_error(msg: &str) {
+    let msg = CString::new(msg.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+/// Copy `data` into a buffer that `free` can release.
+fn alloc_output(data: &[u8]) -> *mut u8 {
+    let layout = Layout::from_size_align(HEADER + data.len(), HEADER).expect("output too large");
+    unsafe {
+        let base = alloc(layout);
+        if base.is_null() {
+            handle_alloc_error(layout);
+        }
+        base.cast::<usize>().write(data.len());
+        std::ptr::copy_nonoverlapping(data.as_ptr(), base.add(HEADER), data.len());
+        base.add(HEADER)
+    }
+}
+
+/// The `format` a port pins down, or an empty string.
+fn port_format(port: Option<&paraphase_core::PortDecl>) -> String {
+    match port.and_then(|p| p.pattern.predicates.get("format")) {
+        Some(Predicate::Eq(Value::String(format))) => format.clone(),
+        _ => String::new(),
+    }
+}
+
+fn flags(decl: &ConverterDecl) -> u32 {
+    let lossless = decl
+        .costs
+        .get("quality_loss")
+        .and_then(|v| v.as_f64())
+        .is_some_and(|loss| loss == 0.0);
+    if lossless { PARAPHASE_FLAG_LOSSLESS } else { 0 }
+}
+
+/// Export converters from a plugin dynamic library.
+///
+/// Generates the `extern "C"` symbols described in `paraphase_plugin.h`.
+/// Use once per `cdylib` crate, with expressions evaluating to converters:
+///
+/// ```ignore
+/// paraphase_plugin::export![AcmeToJson, AcmeToCsv::new()];
+/// ```
+#[macro_export]
+macro_rules! export {
+    ($($converter:expr),* $(,)?) => {
+        fn __paraphase_exports() -> &'static $crate::export::PluginExports {
+            static EXPORTS: ::std::sync::OnceLock<$crate::export::PluginExports> =
+                ::std::sync::OnceLock::new();
+            EXPORTS.get_or_init(|| {
+                $crate::export::PluginExports::new(::std::vec![
+                    $(::std::boxed::Box::new($converter) as ::std::boxed::Box<dyn $crate::Converter>),*
+                ])
+            })
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn paraphase_plugin_api_version() -> u32 {
+            $crate::abi::PARAPHASE_PLUGIN_API_VERSION
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn paraphase_plugin_api_compat() -> *const ::std::ffi::c_char {
+            $crate::export::api_compat()
+        }
+
+        /// # Safety
+        ///
+        /// `count` must be null or valid for writes.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn paraphase_list_converters(
+            count: *mut usize,
+        ) -> *const $crate::abi::ParaphaseConverter {
+            unsafe { __paraphase_exports().list(count) }
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn paraphase_describe_converters() -> *const ::std::ffi::c_char {
+            __paraphase_exports().describe()
+        }
+
+        /// # Safety
+        ///
+        /// Pointers must satisfy the contract in `paraphase_plugin.h`.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn paraphase_convert(
+            converter_id: *const ::std::ffi::c_char,
+            input: *const u8,
+            input_len: usize,
+            output: *mut *mut u8,
+            output_len: *mut usize,
+            props_json: *const ::std::ffi::c_char,
+        ) -> ::std::ffi::c_int {
+            unsafe {
+                __paraphase_exports().convert(
+                    converter_id,
+                    input,
+                    input_len,
+                    output,
+                    output_len,
+                    props_json,
+                )
+            }
+        }
+
+        /// # Safety
+        ///
+        /// Pointers must satisfy the contract in `paraphase_plugin.h`.
+        #[unsafe(no_mangle)]
+        #[allow(clippy::too_many_arguments)]
+        pub unsafe extern "C" fn paraphase_convert_with_options(
+            converter_id: *const ::std::ffi::c_char,
+            input: *const u8,
+            input_len: usize,
+            output: *mut *mut u8,
+            output_len: *mut usize,
+            props_json: *const ::std::ffi::c_char,
+            options_json: *const ::std::ffi::c_char,
+        ) -> ::std::ffi::c_int {
+            unsafe {
+                __paraphase_exports().convert_with_options(
+                    converter_id,
+                    input,
+                    input_len,
+                    output,
+                    output_len,
+                    props_json,
+                    options_json,
+                )
+            }
+        }
+
+        /// # Safety
+        ///
+        /// `ptr` must be null or a buffer returned by `paraphase_convert`.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn paraphase_free(ptr: *mut ::std::ffi::c_void) {
+            unsafe { $crate::export::free(ptr) }
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn paraphase_last_error() -> *const ::std::ffi::c_char {
+            $crate::export::last_error()
+        }
+    };
+}
+
+/// Export converters from a WebAssembly plugin module.
+///
+/// Generates the exports the sandboxed host in
+/// [`wasm`](crate::wasm) instantiates (see [`abi::wasm`](crate::abi::wasm)).
+/// Build the crate as a `cdylib` for `wasm32-unknown-unknown`:
+///
+/// ```ignore
+/// paraphase_plugin::export_wasm![AcmeToJson, AcmeToCsv::new()];
+/// ```
+#[macro_export]
+macro_rules! export_wasm {
+    ($($converter:expr),* $(,)?) => {
+        fn __paraphase_exports() -> &'static $crate::export::PluginExports {
+            static EXPORTS: ::std::sync::OnceLock<$crate::export::PluginExports> =
+                ::std::sync::OnceLock::new();
+            EXPORTS.get_or_init(|| {
+                $crate::export::PluginExports::new(::std::vec![
+                    $(::std::boxed::Box::new($converter) as ::std::boxed::Box<dyn $crate::Converter>),*
+                ])
+            })
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn paraphase_plugin_api_version() -> u32 {
+            $crate::abi::PARAPHASE_PLUGIN_API_VERSION
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn paraphase_alloc(len: usize) -> *mut u8 {
+            $crate::export::wasm_alloc(len)
+        }
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn paraphase_describe() -> u64 {
+            $crate::export::wasm_return(__paraphase_exports().describe_wasm())
+        }
+
+        /// # Safety
+        ///
+        /// The host passes buffers obtained from `paraphase_alloc`.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn paraphase_convert(
+            id: *const u8,
+            id_len: usize,
+            input: *const u8,
+            input_len: usize,
+            props: *const u8,
+            props_len: usize,
+        ) -> u64 {
+            let out = unsafe {
+                __paraphase_exports().convert_wasm(
+                    $crate::export::wasm_slice(id, id_len),
+                    $crate::export::wasm_slice(input, input_len),
+                    $crate::export::wasm_slice(props, props_len),
+                )
+            };
+            $crate::export::wasm_return(out)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::decode_version;
+    use paraphase_core::{ConvertError, PropertiesExt, PropertyPattern};
+
+    struct Upper(ConverterDecl);
+
+    impl Converter for Upper {
+        fn decl(&self) -> &ConverterDecl {
+            &self.0
+        }
+
+        fn convert(
+            &self,
+            input: &[u8],
+            _props: &Properties,
+        ) -> Result<ConvertOutput, ConvertError> {
+            if input.is_empty() {
+                return Err(ConvertError::InvalidInput("empty".into()));
+            }
+            Ok(ConvertOutput::Single(
+                input.to_ascii_uppercase(),
+                Properties::new(),
+            ))
+        }
+    }
+
+    fn exports() -> PluginExports {
+        PluginExports::new(vec![Box::new(Upper(
+            ConverterDecl::simple(
+                "test.upper",
+                PropertyPattern::new().eq("format", "txt"),
+                PropertyPattern::new().eq("format", "TXT"),
+            )
+            .cost("quality_loss", 0.0),
+        ))])
+    }
+
+    #[test]
+    fn test_list_converters() {
+        let exports = exports();
+        let mut count = 0;
+        let table = unsafe { std::slice::from_raw_parts(exports.list(&mut count), count) };
+        assert_eq!(count, 1);
+
+        let str_at = |p| unsafe { CStr::from_ptr(p) }.to_str().unwrap();
+        assert_eq!(str_at(table[0].id), "test.upper");
+        assert_eq!(str_at(table[0].from_type), "txt");
+        assert_eq!(str_at(table[0].to_type), "TXT");
+        assert_eq!(table[0].flags, PARAPHASE_FLAG_LOSSLESS);
+
+        let json = unsafe { CStr::from_ptr(exports.describe()) }.to_bytes();
+        let decls: Vec<ConverterDecl> = serde_json::from_slice(json).unwrap();
+        assert_eq!(decls[0].id, "test.upper");
+    }
+
+    #[test]
+    fn test_convert_and_errors() {
+        let exports = exports();
+        let options =
+            CString::new(serde_json::to_string(&Properties::new().with("format", "txt")).unwrap())
+                .unwrap();
+        let mut output = std::ptr::null_mut();
+        let mut len = 0;
+
+        let code = unsafe {
+            exports.convert(
+                c"test.upper".as_ptr(),
+                b"abc".as_ptr(),
+                3,
+                &mut output,
+                &mut len,
+                options.as_ptr(),
+            )
+        };
+        assert_eq!(code, PARAPHASE_OK);
+        assert_eq!(unsafe { std::slice::from_raw_parts(output, len) }, b"ABC");
+        unsafe { free(output.cast()) };
+
+        let code = unsafe {
+            exports.convert(
+                c"test.upper".as_ptr(),
+                std::ptr::null(),
+                0,
+                &mut output,
+                &mut len,
+                std::ptr::null(),
+            )
+        };
+        assert_eq!(code, PARAPHASE_ERR_FAILED);
+        let msg = unsafe { CStr::from_ptr(last_error()) }.to_str().unwrap();
+        assert!(msg.contains("empty"));
+
+        let code = unsafe {
+            exports.convert(
+                c"test.missing".as_ptr(),
+                b"abc".as_ptr(),
+                3,
+                &mut output,
+                &mut len,
+                std::ptr::null(),
+            )
+        };
+        assert_eq!(code, PARAPHASE_ERR_UNKNOWN_CONVERTER);
+    }
+
+    #[test]
+    fn test_convert_wasm() {
+        let exports = exports();
+        let out = exports.convert_wasm(b"test.upper", b"abc", b"");
+        assert_eq!(out, b"\0ABC");
+
+        let out = exports.convert_wasm(b"test.upper", b"abc", b"{not json");
+        assert_eq!(out[0], PARAPHASE_ERR_INVALID_ARGUMENT as u8);
+        assert!(String::from_utf8_lossy(&out[1..]).contains("invalid properties"));
+
+        let out = exports.convert_wasm(b"test.missing", b"abc", b"");
+        assert_eq!(out[0], PARAPHASE_ERR_UNKNOWN_CONVERTER as u8);
+    }
+
+    #[test]
+    fn test_api_compat_matches_version() {
+        let (major, minor, _) = decode_version(crate::abi::PARAPHASE_PLUGIN_API_VERSION);
+        assert_eq!(
+            API_COMPAT.to_str().unwrap(),
+            format!("^{}.{}", major, minor)
+        );
+    }
+}
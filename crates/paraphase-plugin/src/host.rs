@@ -0,0 +1,134 @@
+//! Support shared by the native and WebAssembly plugin hosts.
+
+use crate::abi::{PARAPHASE_PLUGIN_API_VERSION, decode_version};
+use paraphase_core::{Converter, ConverterDecl, Predicate, Properties, Registry, RegistryError};
+use semver::{Version, VersionReq};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Errors that can occur while loading a plugin.
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[cfg(feature = "loader")]
+    #[error("failed to load {path}: {source}")]
+    Load {
+        path: PathBuf,
+        #[source]
+        source: libloading::Error,
+    },
+
+    #[error("{path} is missing required symbol {symbol}")]
+    MissingSymbol { path: PathBuf, symbol: String },
+
+    #[error("{path} targets plugin API {plugin}, incompatible with host API {host}")]
+    IncompatibleVersion {
+        path: PathBuf,
+        plugin: String,
+        host: String,
+    },
+
+    #[error("{path} has invalid metadata: {message}")]
+    InvalidMetadata { path: PathBuf, message: String },
+
+    #[error("{path} is not a valid WebAssembly plugin: {message}")]
+    Wasm { path: PathBuf, message: String },
+}
+
+/// Outcome of registering a plugin's converters.
+#[derive(Debug, Default)]
+pub struct Registration {
+    /// Number of converters registered.
+    pub registered: usize,
+    /// Converters the registry refused because their ID was taken, under
+    /// [`ConflictPolicy::Reject`](paraphase_core::ConflictPolicy::Reject).
+    pub rejected: Vec<RegistryError>,
+}
+
+/// Register `converters`, following the registry's conflict policy.
+pub(crate) fn register<C: Converter + 'static>(
+    converters: impl IntoIterator<Item = C>,
+    registry: &mut Registry,
+) -> Registration {
+    let mut registration = Registration::default();
+    for converter in converters {
+        match registry.try_register(converter) {
+            Ok(()) => registration.registered += 1,
+            Err(e) => registration.rejected.push(e),
+        }
+    }
+    registration
+}
+
+/// Check that a plugin built against `plugin_version` can be loaded.
+///
+/// The major versions must match and the plugin must not target a newer
+/// minor version than the host provides. If the plugin also declares a
+/// semver range (e.g. `^1.0`), the host version must satisfy it.
+pub fn check_compatibility(plugin_version: u32, compat: Option<&str>) -> Result<(), String> {
+    let host = version(PARAPHASE_PLUGIN_API_VERSION);
+    let plugin = version(plugin_version);
+
+    if plugin.major != host.major || plugin.minor > host.minor {
+        return Err(plugin.to_string());
+    }
+    if let Some(compat) = compat {
+        let req = VersionReq::parse(compat)
+            .map_err(|e| format!("{} (invalid range {:?}: {})", plugin, compat, e))?;
+        if !req.matches(&host) {
+            return Err(format!("{} (requires {})", plugin, compat));
+        }
+    }
+    Ok(())
+}
+
+/// Host API version, for error messages.
+pub(crate) fn host_version() -> String {
+    version(PARAPHASE_PLUGIN_API_VERSION).to_string()
+}
+
+/// Attribute a declaration to the plugin file it came from, unless the
+/// plugin names a provider itself.
+pub(crate) fn with_provider(mut decl: ConverterDecl, path: &Path) -> ConverterDecl {
+    if decl.provider.is_empty() {
+        decl.provider = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+    decl
+}
+
+/// Output properties for a converter behind a bytes-only ABI: the input
+/// properties updated with the values the output port pins down.
+pub(crate) fn output_props(decl: &ConverterDecl, props: &Properties) -> Properties {
+    let mut out = props.clone();
+    if let Some(port) = decl.outputs.values().next() {
+        for (key, predicate) in &port.pattern.predicates {
+            if let Predicate::Eq(value) = predicate {
+                out.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    out
+}
+
+fn version(encoded: u32) -> Version {
+    let (major, minor, patch) = decode_version(encoded);
+    Version::new(major, minor, patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_compatibility() {
+        assert!(check_compatibility(PARAPHASE_PLUGIN_API_VERSION, None).is_ok());
+        assert!(check_compatibility(PARAPHASE_PLUGIN_API_VERSION, Some("^1.0")).is_ok());
+        // Newer minor or different major
+        assert!(check_compatibility(0x01_02_00, None).is_err());
+        assert!(check_compatibility(0x02_00_00, None).is_err());
+        // Range the host does not satisfy
+        assert!(check_compatibility(PARAPHASE_PLUGIN_API_VERSION, Some(">=1.2")).is_err());
+    }
+}
@@ -2,11 +2,100 @@
 //!
 //! This crate provides utilities for writing Cambium plugins,
 //! including the C ABI exports and procedural macros.
+//!
+//! Plugins are `cdylib` crates that export their converters with
+//! [`export!`]; the host loads them with [`loader::PluginLoader`]
+//! (`loader` feature). The C ABI is declared in `include/paraphase_plugin.h`.
+//!
+//! Plugins built for `wasm32-unknown-unknown` use [`export_wasm!`] instead
+//! and run sandboxed through [`wasm::WasmPlugin`] (`wasm` feature), with
+//! memory and fuel limits on every call.
+
+// Lets `#[paraphase_converter]` paths resolve inside this crate's tests
+extern crate self as paraphase_plugin;
+
+pub mod abi;
+#[doc(hidden)]
+pub mod export;
+#[cfg(any(feature = "loader", feature = "wasm"))]
+mod host;
+#[cfg(feature = "loader")]
+pub mod loader;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(any(feature = "loader", feature = "wasm"))]
+pub use host::{PluginError, Registration, check_compatibility};
 
 pub use paraphase_core::{
     ConvertError, ConvertOutput, Converter, ConverterDecl, PortDecl, Predicate, Properties,
     PropertiesExt, PropertyPattern, Value,
 };
 
-// TODO: Add #[paraphase_converter] proc macro
-// TODO: Add C ABI export helpers
+/// Generate a `Converter` from a conversion function.
+///
+/// See `paraphase-macros` for the arguments. Pass the generated struct to
+/// [`export!`] to expose it over the C ABI:
+///
+/// ```ignore
+/// use paraphase_plugin::*;
+///
+/// #[paraphase_converter(id = "acme.foo-to-json", from = "foo", to = "json", lossless)]
+/// fn foo_to_json(input: &[u8]) -> Result<Vec<u8>, ConvertError> {
+///     // ...
+/// }
+///
+/// paraphase_plugin::export![FooToJson];
+/// ```
+pub use paraphase_macros::plugin_converter as paraphase_converter;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shout the input
+    #[paraphase_converter(from = "txt", to = "shout", cost(speed = 2))]
+    fn txt_to_shout(input: &[u8], props: &Properties) -> Result<Vec<u8>, ConvertError> {
+        if props.contains_key("quiet") {
+            return Err(ConvertError::InvalidInput("too quiet".into()));
+        }
+        Ok(input.to_ascii_uppercase())
+    }
+
+    #[paraphase_converter(id = "test.split", from = "txt", to = "line", name = SplitLines)]
+    fn split(input: &[u8]) -> Result<ConvertOutput, ConvertError> {
+        Ok(ConvertOutput::Multiple(
+            input
+                .split(|&b| b == b'\n')
+                .map(|line| (line.to_vec(), Properties::new().with("format", "line")))
+                .collect(),
+        ))
+    }
+
+    #[test]
+    fn test_paraphase_converter() {
+        let decl = TxtToShout.decl();
+        assert_eq!(decl.id, "txt-to-shout");
+        assert_eq!(decl.description, "Shout the input");
+        assert_eq!(decl.costs.get("speed").and_then(|v| v.as_f64()), Some(2.0));
+        let props = Properties::new().with("format", "txt");
+        assert_eq!(decl.matches_input(&props), Some("in"));
+
+        let ConvertOutput::Single(data, out) = TxtToShout.convert(b"hi", &props).unwrap() else {
+            panic!("expected single output");
+        };
+        assert_eq!(data, b"HI");
+        assert_eq!(out.get("format").and_then(|v| v.as_str()), Some("shout"));
+        assert!(
+            TxtToShout
+                .convert(b"hi", &props.clone().with("quiet", true))
+                .is_err()
+        );
+
+        assert_eq!(SplitLines.decl().id, "test.split");
+        match SplitLines.convert(b"a\nb", &props).unwrap() {
+            ConvertOutput::Multiple(items) => assert_eq!(items.len(), 2),
+            ConvertOutput::Single(..) => panic!("expected multiple outputs"),
+        }
+    }
+}
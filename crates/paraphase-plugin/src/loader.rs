@@ -0,0 +1,655 @@
+//! Host side: load converters from dynamic plugin libraries.
+//!
+//! Plugins are discovered from (later sources override earlier ones):
+//! 1. `$PARAPHASE_PLUGIN_PATH` (colon-separated on Unix, semicolon on Windows)
+//! 2. `~/.paraphase/plugins/`
+//! 3. `./paraphase-plugins/`
+//! 4. Directories added with [`PluginLoader::dir`]
+
+use crate::abi::{
+    self, ApiVersionFn, ConvertFn, ConvertWithOptionsFn, FreeFn, ListConvertersFn,
+    PARAPHASE_FLAG_LOSSLESS, StrFn, symbols,
+};
+pub use crate::host::{PluginError, Registration, check_compatibility};
+use crate::host::{host_version, output_props, with_provider};
+use libloading::Library;
+use paraphase_core::{
+    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+};
+use std::ffi::{CStr, CString, c_char};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Environment variable listing extra plugin directories.
+pub const PLUGIN_PATH_ENV: &str = "PARAPHASE_PLUGIN_PATH";
+
+/// Entry points resolved from a plugin.
+#[derive(Clone, Copy)]
+struct PluginApi {
+    api_version: ApiVersionFn,
+    api_compat: Option<StrFn>,
+    list_converters: ListConvertersFn,
+    describe_converters: Option<StrFn>,
+    convert: ConvertFn,
+    convert_with_options: Option<ConvertWithOptionsFn>,
+    free: FreeFn,
+    last_error: Option<StrFn>,
+}
+
+impl PluginApi {
+    /// Resolve the entry points exported by `library`.
+    fn resolve(library: &Library, path: &Path) -> Result<Self, PluginError> {
+        fn required<T: Copy>(
+            library: &Library,
+            path: &Path,
+            name: &[u8],
+        ) -> Result<T, PluginError> {
+            optional(library, name).ok_or_else(|| PluginError::MissingSymbol {
+                path: path.to_path_buf(),
+                symbol: String::from_utf8_lossy(&name[..name.len() - 1]).into_owned(),
+            })
+        }
+
+        fn optional<T: Copy>(library: &Library, name: &[u8]) -> Option<T> {
+            // Safety: the symbol types are fixed by the plugin ABI.
+            unsafe { library.get::<T>(name) }.ok().map(|s| *s)
+        }
+
+        Ok(Self {
+            api_version: required(library, path, symbols::API_VERSION)?,
+            api_compat: optional(library, symbols::API_COMPAT),
+            list_converters: required(library, path, symbols::LIST_CONVERTERS)?,
+            describe_converters: optional(library, symbols::DESCRIBE_CONVERTERS),
+            convert: required(library, path, symbols::CONVERT)?,
+            convert_with_options: optional(library, symbols::CONVERT_WITH_OPTIONS),
+            free: required(library, path, symbols::FREE)?,
+            last_error: optional(library, symbols::LAST_ERROR),
+        })
+    }
+}
+
+/// A loaded plugin and the converters it provides.
+pub struct Plugin {
+    path: PathBuf,
+    api_version: u32,
+    converters: Vec<DylibConverter>,
+}
+
+impl Plugin {
+    /// Load a plugin library.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialisers, and the plugin is trusted to
+    /// implement the ABI in `paraphase_plugin.h` correctly. Only load plugins
+    /// from trusted locations.
+    pub unsafe fn load(path: impl AsRef<Path>) -> Result<Self, PluginError> {
+        let path = path.as_ref();
+        let library = unsafe { Library::new(path) }.map_err(|source| PluginError::Load {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let api = PluginApi::resolve(&library, path)?;
+        unsafe { Self::from_api(path, api, Some(Arc::new(library))) }
+    }
+
+    /// Build a plugin from resolved entry points.
+    ///
+    /// # Safety
+    ///
+    /// The entry points must implement the plugin ABI and stay valid for as
+    /// long as `library` is alive.
+    unsafe fn from_api(
+        path: &Path,
+        api: PluginApi,
+        library: Option<Arc<Library>>,
+    ) -> Result<Self, PluginError> {
+        let invalid = |message: String| PluginError::InvalidMetadata {
+            path: path.to_path_buf(),
+            message,
+        };
+
+        let api_version = unsafe { (api.api_version)() };
+        let compat = api
+            .api_compat
+            .and_then(|f| unsafe { c_str(f()) })
+            .map(str::to_string);
+        check_compatibility(api_version, compat.as_deref()).map_err(|plugin| {
+            PluginError::IncompatibleVersion {
+                path: path.to_path_buf(),
+                plugin,
+                host: host_version(),
+            }
+        })?;
+
+        // Full declarations, when the plugin provides them
+        let described: Vec<ConverterDecl> = match api.describe_converters {
+            Some(describe) => match unsafe { c_str(describe()) } {
+                Some(json) => serde_json::from_str(json)
+                    .map_err(|e| invalid(format!("converter declarations: {}", e)))?,
+                None => Vec::new(),
+            },
+            None => Vec::new(),
+        };
+
+        let mut count = 0usize;
+        let table = unsafe { (api.list_converters)(&mut count) };
+        if table.is_null() && count > 0 {
+            return Err(invalid("null converter table".into()));
+        }
+        let entries = if count == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(table, count) }
+        };
+
+        let mut converters = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let id = unsafe { c_str(entry.id) }
+                .filter(|id| !id.is_empty())
+                .ok_or_else(|| invalid("converter without an ID".into()))?;
+            let decl = match described.iter().find(|d| d.id == id) {
+                Some(decl) => decl.clone(),
+                None => {
+                    let from = unsafe { c_str(entry.from_type) }.unwrap_or_default();
+                    let to = unsafe { c_str(entry.to_type) }.unwrap_or_default();
+                    simple_decl(id, from, to, entry.flags)
+                }
+            };
+            let decl = with_provider(decl, path);
+
+            converters.push(DylibConverter {
+                id: CString::new(id).map_err(|e| invalid(e.to_string()))?,
+                decl,
+                api,
+                _library: library.clone(),
+            });
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            api_version,
+            converters,
+        })
+    }
+
+    /// Path the plugin was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Plugin API version the plugin was built against.
+    pub fn api_version(&self) -> u32 {
+        self.api_version
+    }
+
+    /// Declarations of the converters this plugin provides.
+    pub fn converters(&self) -> impl Iterator<Item = &ConverterDecl> {
+        self.converters.iter().map(|c| &c.decl)
+    }
+
+    /// Register all converters into `registry`, following its conflict
+    /// policy for IDs already taken.
+    pub fn register(self, registry: &mut Registry) -> Registration {
+        crate::host::register(self.converters, registry)
+    }
+}
+
+/// Declaration for a plugin that only exports the C metadata table.
+fn simple_decl(id: &str, from: &str, to: &str, flags: u32) -> ConverterDecl {
+    let pattern = |format: &str| {
+        if format.is_empty() {
+            PropertyPattern::new().exists("format")
+        } else {
+            PropertyPattern::new().eq("format", format)
+        }
+    };
+    let decl = ConverterDecl::simple(id, pattern(from), pattern(to));
+    if flags & PARAPHASE_FLAG_LOSSLESS != 0 {
+        decl.cost("quality_loss", 0.0)
+    } else {
+        decl
+    }
+}
+
+/// Borrow a plugin-owned C string.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that outlives `'a`.
+unsafe fn c_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+/// A converter implemented by a plugin library.
+struct DylibConverter {
+    id: CString,
+    decl: ConverterDecl,
+    api: PluginApi,
+    // Keeps the entry points in `api` valid
+    _library: Option<Arc<Library>>,
+}
+
+impl Converter for DylibConverter {
+    fn decl(&self) -> &ConverterDecl {
+        &self.decl
+    }
+
+    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+        self.convert_with_options(input, props, &Properties::new())
+    }
+
+    fn convert_with_options(
+        &self,
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        let json = |value: &Properties, what: &str| {
+            serde_json::to_string(value)
+                .ok()
+                .and_then(|json| CString::new(json).ok())
+                .ok_or_else(|| ConvertError::InvalidInput(format!("{} not encodable", what)))
+        };
+
+        let mut output = std::ptr::null_mut();
+        let mut output_len = 0usize;
+        let code = match self.api.convert_with_options {
+            Some(convert) => {
+                let props_json = json(props, "properties")?;
+                let options_json = json(options, "options")?;
+                unsafe {
+                    convert(
+                        self.id.as_ptr(),
+                        input.as_ptr(),
+                        input.len(),
+                        &mut output,
+                        &mut output_len,
+                        props_json.as_ptr(),
+                        options_json.as_ptr(),
+                    )
+                }
+            }
+            None => {
+                // Plugins built against API 1.0 read options from their
+                // input properties
+                let mut merged = props.clone();
+                merged.extend(options.iter().map(|(k, v)| (k.clone(), v.clone())));
+                let props_json = json(&merged, "properties")?;
+                unsafe {
+                    (self.api.convert)(
+                        self.id.as_ptr(),
+                        input.as_ptr(),
+                        input.len(),
+                        &mut output,
+                        &mut output_len,
+                        props_json.as_ptr(),
+                    )
+                }
+            }
+        };
+
+        if code != abi::PARAPHASE_OK {
+            let message = self
+                .api
+                .last_error
+                .and_then(|f| unsafe { c_str(f()) })
+                .unwrap_or("no error message")
+                .to_string();
+            return Err(ConvertError::Failed(format!(
+                "{} (plugin error {}): {}",
+                self.decl.id, code, message
+            )));
+        }
+
+        let data = if output.is_null() {
+            Vec::new()
+        } else {
+            let data = unsafe { std::slice::from_raw_parts(output, output_len) }.to_vec();
+            unsafe { (self.api.free)(output.cast()) };
+            data
+        };
+
+        // The C ABI carries bytes only
+        Ok(ConvertOutput::Single(data, output_props(&self.decl, props)))
+    }
+}
+
+/// Discovers and loads plugins from a list of directories.
+#[derive(Debug, Clone, Default)]
+pub struct PluginLoader {
+    dirs: Vec<PathBuf>,
+}
+
+impl PluginLoader {
+    /// Create a loader with no directories.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a loader searching the standard plugin locations.
+    pub fn with_default_dirs() -> Self {
+        let mut dirs = Vec::new();
+        if let Some(paths) = std::env::var_os(PLUGIN_PATH_ENV) {
+            dirs.extend(std::env::split_paths(&paths).filter(|p| !p.as_os_str().is_empty()));
+        }
+        if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+            dirs.push(PathBuf::from(home).join(".paraphase").join("plugins"));
+        }
+        dirs.push(PathBuf::from("paraphase-plugins"));
+        Self { dirs }
+    }
+
+    /// Add a directory (searched after, and overriding, earlier ones).
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dirs.push(dir.into());
+        self
+    }
+
+    /// Directories searched, in order.
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Find plugin libraries in the configured directories.
+    ///
+    /// Missing directories are skipped. Files are returned in directory
+    /// order, sorted by name within each directory.
+    pub fn discover(&self) -> Vec<PathBuf> {
+        self.find(is_plugin_library)
+    }
+
+    /// Find WebAssembly plugin modules (`*.wasm`) in the configured
+    /// directories, in the same order as [`discover`](Self::discover).
+    #[cfg(feature = "wasm")]
+    pub fn discover_wasm(&self) -> Vec<PathBuf> {
+        self.find(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+    }
+
+    fn find(&self, is_match: fn(&Path) -> bool) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        for dir in &self.dirs {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            let mut files: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && is_match(p))
+                .collect();
+            files.sort();
+            found.extend(files);
+        }
+        found
+    }
+
+    /// Load every discovered plugin and register its converters.
+    ///
+    /// Returns one result per library; failures do not stop the others
+    /// from loading.
+    ///
+    /// # Safety
+    ///
+    /// See [`Plugin::load`]: every library in the configured directories is
+    /// trusted.
+    pub unsafe fn load_into(
+        &self,
+        registry: &mut Registry,
+    ) -> Vec<Result<(PathBuf, Registration), PluginError>> {
+        self.discover()
+            .into_iter()
+            .map(|path| {
+                let plugin = unsafe { Plugin::load(&path) }?;
+                Ok((path, plugin.register(registry)))
+            })
+            .collect()
+    }
+
+    /// Load every discovered WebAssembly plugin under `limits` and register
+    /// its converters.
+    ///
+    /// Unlike [`load_into`](Self::load_into) this is safe: modules run
+    /// sandboxed and cannot touch host memory.
+    #[cfg(feature = "wasm")]
+    pub fn load_wasm_into(
+        &self,
+        registry: &mut Registry,
+        limits: &crate::wasm::WasmLimits,
+    ) -> Vec<Result<(PathBuf, Registration), PluginError>> {
+        self.discover_wasm()
+            .into_iter()
+            .map(|path| {
+                let plugin = crate::wasm::WasmPlugin::load(&path, limits)?;
+                Ok((path, plugin.register(registry)))
+            })
+            .collect()
+    }
+}
+
+/// Check if a path has the platform's dynamic library extension.
+fn is_plugin_library(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PropertiesExt;
+    use crate::abi::ParaphaseConverter;
+    use paraphase_core::{ConflictPolicy, RegistryError};
+    use std::ffi::c_int;
+
+    // A plugin compiled into the test binary; the loader is pointed at its
+    // entry points directly instead of through dlopen.
+    mod plugin {
+        use crate::{ConvertError, ConvertOutput, Converter, ConverterDecl, Properties};
+        use crate::{PropertiesExt, PropertyPattern};
+
+        pub struct Reverse(ConverterDecl);
+
+        impl Reverse {
+            pub fn new() -> Self {
+                Self(ConverterDecl::simple(
+                    "test.reverse",
+                    PropertyPattern::new().eq("format", "fwd").exists("order"),
+                    PropertyPattern::new().eq("format", "rev"),
+                ))
+            }
+        }
+
+        impl Converter for Reverse {
+            fn decl(&self) -> &ConverterDecl {
+                &self.0
+            }
+
+            fn convert(
+                &self,
+                input: &[u8],
+                props: &Properties,
+            ) -> Result<ConvertOutput, ConvertError> {
+                if props.get("order").and_then(|v| v.as_str()) != Some("reverse") {
+                    return Err(ConvertError::InvalidInput("order must be reverse".into()));
+                }
+                let out: Vec<u8> = input.iter().rev().copied().collect();
+                Ok(ConvertOutput::Single(
+                    out,
+                    Properties::new().with("x", 1i64),
+                ))
+            }
+        }
+
+        crate::export![Reverse::new()];
+    }
+
+    fn api() -> PluginApi {
+        PluginApi {
+            api_version: plugin::paraphase_plugin_api_version,
+            api_compat: Some(plugin::paraphase_plugin_api_compat),
+            list_converters: plugin::paraphase_list_converters,
+            describe_converters: Some(plugin::paraphase_describe_converters),
+            convert: plugin::paraphase_convert,
+            convert_with_options: Some(plugin::paraphase_convert_with_options),
+            free: plugin::paraphase_free,
+            last_error: Some(plugin::paraphase_last_error),
+        }
+    }
+
+    #[test]
+    fn test_plugin_roundtrip() {
+        let plugin = unsafe { Plugin::from_api(Path::new("test"), api(), None) }.unwrap();
+        let decls: Vec<_> = plugin.converters().collect();
+        assert_eq!(decls.len(), 1);
+        // The full declaration survives, including the `order` predicate
+        assert!(
+            decls[0].inputs["in"]
+                .pattern
+                .predicates
+                .contains_key("order")
+        );
+
+        let mut registry = Registry::new();
+        assert_eq!(plugin.register(&mut registry).registered, 1);
+        let converter = registry.get("test.reverse").unwrap();
+
+        let props = Properties::new()
+            .with("format", "fwd")
+            .with("order", "reverse");
+        let ConvertOutput::Single(data, out) = converter.convert(b"abc", &props).unwrap() else {
+            panic!("expected single output");
+        };
+        assert_eq!(data, b"cba");
+        assert_eq!(out.get("format").and_then(|v| v.as_str()), Some("rev"));
+
+        let err = converter
+            .convert(b"abc", &Properties::new().with("format", "fwd"))
+            .err()
+            .expect("conversion should fail");
+        assert!(err.to_string().contains("order must be reverse"));
+    }
+
+    #[test]
+    fn test_plugin_options() {
+        let fwd = Properties::new().with("format", "fwd");
+        let options = Properties::new().with("order", "reverse");
+
+        // Options reach the plugin apart from the properties, and plugins
+        // without `paraphase_convert_with_options` see them merged in
+        let old = PluginApi {
+            convert_with_options: None,
+            ..api()
+        };
+        for api in [api(), old] {
+            let plugin = unsafe { Plugin::from_api(Path::new("test"), api, None) }.unwrap();
+            let converter = plugin.converters.into_iter().next().unwrap();
+            let ConvertOutput::Single(data, out) = converter
+                .convert_with_options(b"abc", &fwd, &options)
+                .unwrap()
+            else {
+                panic!("expected single output");
+            };
+            assert_eq!(data, b"cba");
+            assert!(out.get("order").is_none());
+        }
+    }
+
+    #[test]
+    fn test_register_conflicts() {
+        let load = || unsafe { Plugin::from_api(Path::new("test"), api(), None) }.unwrap();
+        let mut registry = Registry::new().conflict_policy(ConflictPolicy::Reject);
+        assert_eq!(load().register(&mut registry).registered, 1);
+
+        // A second copy is refused and reported, not silently dropped
+        let registration = load().register(&mut registry);
+        assert_eq!(registration.registered, 0);
+        assert_eq!(
+            registration.rejected,
+            vec![RegistryError::Conflict {
+                id: "test.reverse".into(),
+                provider: "test".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_metadata_only_plugin() {
+        unsafe extern "C" fn list(count: *mut usize) -> *const ParaphaseConverter {
+            static TABLE: [ParaphaseConverter; 1] = [ParaphaseConverter {
+                id: c"c.foo-to-bar".as_ptr(),
+                from_type: c"foo".as_ptr(),
+                to_type: c"bar".as_ptr(),
+                flags: PARAPHASE_FLAG_LOSSLESS,
+            }];
+            unsafe { *count = TABLE.len() };
+            TABLE.as_ptr()
+        }
+        unsafe extern "C" fn old_version() -> u32 {
+            0x01_00_00
+        }
+        unsafe extern "C" fn newer_version() -> u32 {
+            0x01_05_00
+        }
+        unsafe extern "C" fn convert(
+            _: *const c_char,
+            _: *const u8,
+            _: usize,
+            _: *mut *mut u8,
+            _: *mut usize,
+            _: *const c_char,
+        ) -> c_int {
+            abi::PARAPHASE_ERR_FAILED
+        }
+
+        let api = PluginApi {
+            api_version: old_version,
+            api_compat: None,
+            list_converters: list,
+            describe_converters: None,
+            convert,
+            convert_with_options: None,
+            free: plugin::paraphase_free,
+            last_error: None,
+        };
+        let plugin = unsafe { Plugin::from_api(Path::new("c"), api, None) }.unwrap();
+        let decl = plugin.converters().next().unwrap();
+        assert_eq!(decl.id, "c.foo-to-bar");
+        assert_eq!(
+            decl.costs.get("quality_loss").and_then(|v| v.as_f64()),
+            Some(0.0)
+        );
+        assert!(
+            decl.matches_input(&Properties::new().with("format", "foo"))
+                .is_some()
+        );
+
+        let api = PluginApi {
+            api_version: newer_version,
+            ..api
+        };
+        assert!(matches!(
+            unsafe { Plugin::from_api(Path::new("c"), api, None) },
+            Err(PluginError::IncompatibleVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_discover() {
+        let dir = std::env::temp_dir().join(format!("paraphase-plugins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lib = dir.join(format!("libacme.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&lib, b"").unwrap();
+        std::fs::write(dir.join("README.txt"), b"").unwrap();
+
+        let loader = PluginLoader::new().dir(&dir).dir(dir.join("missing"));
+        assert_eq!(loader.discover(), vec![lib]);
+
+        // Not a real library
+        let mut registry = Registry::new();
+        let results = unsafe { loader.load_into(&mut registry) };
+        assert!(matches!(results[0], Err(PluginError::Load { .. })));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
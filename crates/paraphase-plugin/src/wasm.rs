@@ -0,0 +1,449 @@
+//! Host side: run converters from sandboxed WebAssembly plugins.
+//!
+//! Every conversion instantiates the module in a fresh store with no
+//! imports, capped linear memory and a fuel budget. A faulty or malicious
+//! plugin can fail its own conversion, but cannot read or corrupt host
+//! memory, touch the filesystem or run forever. The guest side of the ABI
+//! is described in [`abi::wasm`](crate::abi::wasm) and generated by
+//! [`export_wasm!`](crate::export_wasm!).
+
+use crate::abi::{PARAPHASE_OK, wasm as symbols};
+use crate::host::{
+    PluginError, Registration, check_compatibility, host_version, output_props, with_provider,
+};
+use paraphase_core::{ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, Registry};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wasmtime::{
+    Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+    TypedFunc, WasmParams, WasmResults,
+};
+
+/// Resource limits applied to every call into a WebAssembly plugin.
+#[derive(Debug, Clone)]
+pub struct WasmLimits {
+    /// Maximum linear memory in bytes.
+    pub memory: usize,
+    /// Fuel per call; roughly one unit per executed instruction.
+    pub fuel: u64,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            memory: 256 * 1024 * 1024,
+            fuel: 10_000_000_000,
+        }
+    }
+}
+
+impl WasmLimits {
+    /// Default limits: 256 MiB of memory and 10^10 fuel per call.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the memory limit in bytes.
+    pub fn memory(mut self, bytes: usize) -> Self {
+        self.memory = bytes;
+        self
+    }
+
+    /// Set the fuel budget per call.
+    pub fn fuel(mut self, fuel: u64) -> Self {
+        self.fuel = fuel;
+        self
+    }
+}
+
+/// A loaded WebAssembly plugin and the converters it provides.
+pub struct WasmPlugin {
+    path: PathBuf,
+    api_version: u32,
+    converters: Vec<WasmConverter>,
+}
+
+impl WasmPlugin {
+    /// Load and validate a `.wasm` module.
+    pub fn load(path: impl AsRef<Path>, limits: &WasmLimits) -> Result<Self, PluginError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| wasm_error(path, e))?;
+        Self::from_bytes(path, &bytes, limits)
+    }
+
+    /// Load a plugin from module bytes; `path` is used in errors only.
+    pub fn from_bytes(
+        path: impl AsRef<Path>,
+        bytes: &[u8],
+        limits: &WasmLimits,
+    ) -> Result<Self, PluginError> {
+        let path = path.as_ref();
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| wasm_error(path, e))?;
+        let module = Module::new(&engine, bytes).map_err(|e| wasm_error(path, e))?;
+
+        for symbol in [
+            symbols::MEMORY,
+            symbols::API_VERSION,
+            symbols::ALLOC,
+            symbols::DESCRIBE,
+            symbols::CONVERT,
+        ] {
+            if module.get_export(symbol).is_none() {
+                return Err(PluginError::MissingSymbol {
+                    path: path.to_path_buf(),
+                    symbol: symbol.to_string(),
+                });
+            }
+        }
+        if let Some(import) = module.imports().next() {
+            return Err(wasm_error(
+                path,
+                format!(
+                    "imports are not available to plugins ({}::{})",
+                    import.module(),
+                    import.name()
+                ),
+            ));
+        }
+
+        let sandbox = Arc::new(Sandbox {
+            engine,
+            module,
+            limits: limits.clone(),
+        });
+        let mut guest = sandbox.instantiate().map_err(|e| wasm_error(path, e))?;
+
+        let api_version = guest
+            .func::<(), i32>(symbols::API_VERSION)
+            .and_then(|f| f.call(&mut guest.store, ()))
+            .map_err(|e| wasm_error(path, e))? as u32;
+        check_compatibility(api_version, None).map_err(|plugin| {
+            PluginError::IncompatibleVersion {
+                path: path.to_path_buf(),
+                plugin,
+                host: host_version(),
+            }
+        })?;
+
+        let json = guest
+            .func::<(), i64>(symbols::DESCRIBE)
+            .and_then(|f| f.call(&mut guest.store, ()))
+            .and_then(|packed| guest.read(packed))
+            .map_err(|e| wasm_error(path, e))?;
+        let decls: Vec<ConverterDecl> =
+            serde_json::from_slice(&json).map_err(|e| PluginError::InvalidMetadata {
+                path: path.to_path_buf(),
+                message: format!("converter declarations: {}", e),
+            })?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            api_version,
+            converters: decls
+                .into_iter()
+                .map(|decl| WasmConverter {
+                    decl: with_provider(decl, path),
+                    sandbox: Arc::clone(&sandbox),
+                })
+                .collect(),
+        })
+    }
+
+    /// Path the plugin was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Plugin API version the plugin was built against.
+    pub fn api_version(&self) -> u32 {
+        self.api_version
+    }
+
+    /// Declarations of the converters this plugin provides.
+    pub fn converters(&self) -> impl Iterator<Item = &ConverterDecl> {
+        self.converters.iter().map(|c| &c.decl)
+    }
+
+    /// Register all converters into `registry`, following its conflict
+    /// policy for IDs already taken.
+    pub fn register(self, registry: &mut Registry) -> Registration {
+        crate::host::register(self.converters, registry)
+    }
+}
+
+fn wasm_error(path: &Path, message: impl ToString) -> PluginError {
+    PluginError::Wasm {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    }
+}
+
+/// A compiled module and the limits its instances run under.
+struct Sandbox {
+    engine: Engine,
+    module: Module,
+    limits: WasmLimits,
+}
+
+impl Sandbox {
+    /// Create a fresh, isolated instance.
+    fn instantiate(&self) -> wasmtime::Result<Guest> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.memory)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.limits.fuel)?;
+
+        let instance = Instance::new(&mut store, &self.module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, symbols::MEMORY)
+            .ok_or_else(|| wasmtime::Error::msg("`memory` is not a linear memory"))?;
+        Ok(Guest {
+            store,
+            instance,
+            memory,
+        })
+    }
+
+    /// Run `paraphase_convert` in a fresh instance.
+    fn convert(&self, id: &str, input: &[u8], props: &[u8]) -> wasmtime::Result<Vec<u8>> {
+        let mut guest = self.instantiate()?;
+        let (id_ptr, id_len) = guest.write(id.as_bytes())?;
+        let (in_ptr, in_len) = guest.write(input)?;
+        let (props_ptr, props_len) = guest.write(props)?;
+        let packed = guest
+            .func::<(i32, i32, i32, i32, i32, i32), i64>(symbols::CONVERT)?
+            .call(
+                &mut guest.store,
+                (id_ptr, id_len, in_ptr, in_len, props_ptr, props_len),
+            )?;
+        guest.read(packed)
+    }
+}
+
+/// A live instance; dropped after one call.
+struct Guest {
+    store: Store<StoreLimits>,
+    instance: Instance,
+    memory: Memory,
+}
+
+impl Guest {
+    fn func<P: WasmParams, R: WasmResults>(
+        &mut self,
+        name: &str,
+    ) -> wasmtime::Result<TypedFunc<P, R>> {
+        self.instance.get_typed_func(&mut self.store, name)
+    }
+
+    /// Copy `data` into a guest-allocated buffer.
+    fn write(&mut self, data: &[u8]) -> wasmtime::Result<(i32, i32)> {
+        let len = i32::try_from(data.len())?;
+        let ptr = self
+            .func::<i32, i32>(symbols::ALLOC)?
+            .call(&mut self.store, len)?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, data)?;
+        Ok((ptr, len))
+    }
+
+    /// Copy out a buffer returned as `ptr << 32 | len`.
+    fn read(&self, packed: i64) -> wasmtime::Result<Vec<u8>> {
+        let packed = packed as u64;
+        let ptr = (packed >> 32) as usize;
+        let len = (packed & 0xffff_ffff) as usize;
+        self.memory
+            .data(&self.store)
+            .get(ptr..ptr + len)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| wasmtime::Error::msg("returned buffer is out of bounds"))
+    }
+}
+
+/// A converter implemented by a WebAssembly plugin.
+struct WasmConverter {
+    decl: ConverterDecl,
+    sandbox: Arc<Sandbox>,
+}
+
+impl Converter for WasmConverter {
+    fn decl(&self) -> &ConverterDecl {
+        &self.decl
+    }
+
+    fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+        let props_json = serde_json::to_vec(props)
+            .map_err(|_| ConvertError::InvalidInput("properties not encodable".into()))?;
+
+        let out = self
+            .sandbox
+            .convert(&self.decl.id, input, &props_json)
+            .map_err(|e| {
+                let reason = match e.downcast_ref::<Trap>() {
+                    Some(Trap::OutOfFuel) => "fuel limit exceeded".to_string(),
+                    Some(trap) => trap.to_string(),
+                    None => e.to_string(),
+                };
+                ConvertError::Failed(format!("{} (wasm plugin): {}", self.decl.id, reason))
+            })?;
+
+        match out.split_first() {
+            Some((&status, data)) if status == PARAPHASE_OK as u8 => Ok(ConvertOutput::Single(
+                data.to_vec(),
+                output_props(&self.decl, props),
+            )),
+            Some((&status, message)) => Err(ConvertError::Failed(format!(
+                "{} (plugin error {}): {}",
+                self.decl.id,
+                status,
+                String::from_utf8_lossy(message)
+            ))),
+            None => Err(ConvertError::Failed(format!(
+                "{} (wasm plugin): empty result",
+                self.decl.id
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PropertiesExt, PropertyPattern};
+
+    /// Assemble a module written in the text format.
+    fn compile(text: &str) -> Vec<u8> {
+        wat::parse_str(text).unwrap()
+    }
+
+    /// A hand-written plugin module. `convert` is the body of
+    /// `paraphase_convert`; the echo body returns status 0 and the input.
+    fn module(convert: &str) -> String {
+        let decls = vec![ConverterDecl::simple(
+            "test.echo",
+            PropertyPattern::new().eq("format", "a"),
+            PropertyPattern::new().eq("format", "b"),
+        )];
+        let json = serde_json::to_vec(&decls).unwrap();
+        let escaped: String = json.iter().map(|b| format!("\\{:02x}", b)).collect();
+        format!(
+            r#"(module
+              (memory (export "memory") 1)
+              (global $heap (mut i32) (i32.const 4096))
+              (data (i32.const 0) "{escaped}")
+              (func (export "paraphase_plugin_api_version") (result i32) (i32.const 0x010000))
+              (func $alloc (export "paraphase_alloc") (param $len i32) (result i32)
+                (local $p i32)
+                (local.set $p (global.get $heap))
+                (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                (local.get $p))
+              (func (export "paraphase_describe") (result i64) (i64.const {len}))
+              (func (export "paraphase_convert")
+                (param $id i32) (param $id_len i32) (param $in i32) (param $in_len i32)
+                (param $opts i32) (param $opts_len i32) (result i64)
+                (local $out i32)
+                {convert}))"#,
+            len = json.len(),
+        )
+    }
+
+    const ECHO: &str = r#"
+        (local.set $out (call $alloc (i32.add (local.get $in_len) (i32.const 1))))
+        (i32.store8 (local.get $out) (i32.const 0))
+        (memory.copy (i32.add (local.get $out) (i32.const 1)) (local.get $in) (local.get $in_len))
+        (i64.or
+          (i64.shl (i64.extend_i32_u (local.get $out)) (i64.const 32))
+          (i64.extend_i32_u (i32.add (local.get $in_len) (i32.const 1))))"#;
+
+    fn load(convert: &str, limits: &WasmLimits) -> Result<WasmPlugin, PluginError> {
+        WasmPlugin::from_bytes("test.wasm", &compile(&module(convert)), limits)
+    }
+
+    fn run(plugin: WasmPlugin) -> Result<ConvertOutput, ConvertError> {
+        let mut registry = Registry::new();
+        assert_eq!(plugin.register(&mut registry).registered, 1);
+        let converter = registry.get("test.echo").unwrap();
+        converter.convert(b"hello", &Properties::new().with("format", "a"))
+    }
+
+    #[test]
+    fn test_echo_plugin() {
+        let plugin = load(ECHO, &WasmLimits::default()).unwrap();
+        assert_eq!(plugin.api_version(), 0x01_00_00);
+        assert_eq!(plugin.converters().next().unwrap().id, "test.echo");
+
+        let ConvertOutput::Single(data, props) = run(plugin).unwrap() else {
+            panic!("expected single output");
+        };
+        assert_eq!(data, b"hello");
+        assert_eq!(props.get("format").and_then(|v| v.as_str()), Some("b"));
+    }
+
+    #[test]
+    fn test_guest_error() {
+        // Status 1, message "bad" written at offset 2048
+        let fail = r#"
+            (i32.store8 (i32.const 2048) (i32.const 1))
+            (i32.store8 (i32.const 2049) (i32.const 98))
+            (i32.store8 (i32.const 2050) (i32.const 97))
+            (i32.store8 (i32.const 2051) (i32.const 100))
+            (i64.or (i64.shl (i64.const 2048) (i64.const 32)) (i64.const 4))"#;
+        let err = run(load(fail, &WasmLimits::default()).unwrap())
+            .err()
+            .expect("conversion should fail");
+        assert!(err.to_string().contains("plugin error 1): bad"));
+    }
+
+    #[test]
+    fn test_fuel_limit() {
+        let spin = "(loop $spin (br $spin)) (unreachable)";
+        let plugin = load(spin, &WasmLimits::new().fuel(1_000_000)).unwrap();
+        let err = run(plugin).err().expect("conversion should fail");
+        assert!(err.to_string().contains("fuel"));
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        // Grow by 64 pages (4 MiB) past a 1 MiB limit, trapping on failure
+        let grow = r#"
+            (if (i32.eq (memory.grow (i32.const 64)) (i32.const -1)) (then (unreachable)))
+            (i64.const 0)"#;
+        let plugin = load(grow, &WasmLimits::new().memory(1024 * 1024)).unwrap();
+        assert!(run(plugin).is_err());
+
+        // Out-of-bounds results are rejected rather than read
+        let oob = "(i64.const 0x7fff000000001000)";
+        assert!(run(load(oob, &WasmLimits::default()).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_modules() {
+        assert!(matches!(
+            WasmPlugin::from_bytes("x.wasm", b"not wasm", &WasmLimits::default()),
+            Err(PluginError::Wasm { .. })
+        ));
+        assert!(matches!(
+            WasmPlugin::from_bytes(
+                "x.wasm",
+                &compile(r#"(module (memory (export "memory") 1))"#),
+                &WasmLimits::default()
+            ),
+            Err(PluginError::MissingSymbol { .. })
+        ));
+
+        // Host imports (e.g. WASI) are refused
+        let importing = module(ECHO).replacen(
+            "(module",
+            r#"(module (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))"#,
+            1,
+        );
+        let err = WasmPlugin::from_bytes("x.wasm", &compile(&importing), &WasmLimits::default())
+            .err()
+            .expect("load should fail");
+        assert!(err.to_string().contains("imports are not available"));
+    }
+}
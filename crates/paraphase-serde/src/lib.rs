@@ -53,13 +53,57 @@
 //! ## Feature group
 //! - `all` - All formats
 
+#[cfg(any(feature = "tar", feature = "zip"))]
+use paraphase_core::Timestamp;
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, PortDecl, Properties, PropertyPattern,
-    Registry,
+    Comparator, ComparatorDecl, ConvertError, ConvertOutput, Converter, ConverterDecl, FormatDecl,
+    Formula, LossNote, PortDecl, Predicate, Properties, PropertyDecl, PropertyPattern,
+    PropertyType, Registry, Severity, Value, report_loss,
 };
 
 /// Register all enabled serde converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Property keys produced by the converters and inspectors below
+    registry.declare_property(PropertyDecl::new(
+        "inner_format",
+        PropertyType::String,
+        "Format of the data inside an encoding or compression wrapper",
+    ));
+    registry.declare_property(PropertyDecl::new(
+        "charset",
+        PropertyType::String,
+        "Text character set",
+    ));
+    registry.declare_property(PropertyDecl::new(
+        "entry_type",
+        PropertyType::String,
+        "Kind of archive entry: file, directory, symlink or hardlink",
+    ));
+    registry.declare_property(PropertyDecl::new(
+        "mode",
+        PropertyType::Int,
+        "Unix permission bits of an archive entry",
+    ));
+    registry.declare_property(PropertyDecl::new(
+        "mtime",
+        PropertyType::Timestamp,
+        "Modification time of an archive entry",
+    ));
+    registry.declare_property(PropertyDecl::new(
+        "link_target",
+        PropertyType::String,
+        "Target of a link entry",
+    ));
+
+    // Formats handled by the converters below
+    for decl in format_decls() {
+        registry.register_format(decl);
+    }
+
     let formats = enabled_formats();
 
     // Register converters between all pairs of enabled formats
@@ -70,6 +114,7 @@ pub fn register_all(registry: &mut Registry) {
             }
         }
     }
+    registry.register_comparator(SerdeComparator::new());
 
     // Register encoding converters (base64, hex)
     #[cfg(feature = "base64")]
@@ -222,6 +267,208 @@ pub fn enabled_formats() -> Vec<&'static str> {
     .into()
 }
 
+/// Format descriptors for the formats handled by enabled features.
+///
+/// Zip-based formats (zip, xlsx, ods) share the `PK` signature, so they are
+/// left to extension and MIME detection.
+pub fn format_decls() -> Vec<FormatDecl> {
+    vec![
+        #[cfg(feature = "json")]
+        FormatDecl::new("json")
+            .extension("json")
+            .mime("application/json")
+            .description("JSON"),
+        #[cfg(feature = "yaml")]
+        FormatDecl::new("yaml")
+            .extensions(["yaml", "yml"])
+            .mime("application/yaml")
+            .mime("application/x-yaml")
+            .mime("text/yaml")
+            .alias("yml")
+            .description("YAML"),
+        #[cfg(feature = "toml")]
+        FormatDecl::new("toml")
+            .extension("toml")
+            .mime("application/toml")
+            .mime("text/x-toml")
+            .description("TOML"),
+        #[cfg(feature = "ron")]
+        FormatDecl::new("ron")
+            .extension("ron")
+            .description("Rusty Object Notation"),
+        #[cfg(feature = "json5")]
+        FormatDecl::new("json5")
+            .extension("json5")
+            .mime("application/json5")
+            .description("JSON5"),
+        #[cfg(feature = "xml")]
+        FormatDecl::new("xml")
+            .extension("xml")
+            .mime("application/xml")
+            .mime("text/xml")
+            .description("XML"),
+        #[cfg(feature = "lexpr")]
+        FormatDecl::new("lexpr")
+            .extensions(["lisp", "sexp", "lexpr"])
+            .alias("sexp")
+            .description("S-expressions"),
+        #[cfg(feature = "urlencoded")]
+        FormatDecl::new("urlencoded")
+            .mime("application/x-www-form-urlencoded")
+            .description("URL-encoded form data"),
+        #[cfg(feature = "qs")]
+        FormatDecl::new("qs").description("Query string"),
+        #[cfg(feature = "csv")]
+        FormatDecl::new("csv")
+            .extension("csv")
+            .mime("text/csv")
+            .description("Comma-separated values"),
+        #[cfg(feature = "csv")]
+        FormatDecl::new("tsv")
+            .extension("tsv")
+            .mime("text/tab-separated-values")
+            .description("Tab-separated values"),
+        #[cfg(feature = "ndjson")]
+        FormatDecl::new("ndjson")
+            .extensions(["ndjson", "jsonl"])
+            .mime("application/x-ndjson")
+            .alias("jsonl")
+            .description("Newline-delimited JSON"),
+        #[cfg(feature = "msgpack")]
+        FormatDecl::new("msgpack")
+            .extensions(["msgpack", "mp"])
+            .mime("application/msgpack")
+            .mime("application/x-msgpack")
+            .description("MessagePack"),
+        #[cfg(feature = "cbor")]
+        FormatDecl::new("cbor")
+            .extension("cbor")
+            .mime("application/cbor")
+            .description("Concise Binary Object Representation"),
+        #[cfg(feature = "bincode")]
+        FormatDecl::new("bincode")
+            .extensions(["bincode", "bc"])
+            .description("Bincode"),
+        #[cfg(feature = "postcard")]
+        FormatDecl::new("postcard")
+            .extensions(["postcard", "pc"])
+            .description("Postcard"),
+        #[cfg(feature = "bson")]
+        FormatDecl::new("bson")
+            .extension("bson")
+            .mime("application/bson")
+            .description("Binary JSON"),
+        #[cfg(feature = "flexbuffers")]
+        FormatDecl::new("flexbuffers")
+            .extensions(["flexbuf", "flexbuffers"])
+            .description("FlexBuffers"),
+        #[cfg(feature = "bencode")]
+        FormatDecl::new("bencode")
+            .extensions(["bencode", "torrent"])
+            .mime("application/x-bittorrent")
+            .description("Bencode"),
+        #[cfg(feature = "pickle")]
+        FormatDecl::new("pickle")
+            .extensions(["pickle", "pkl"])
+            .description("Python pickle"),
+        #[cfg(feature = "plist")]
+        FormatDecl::new("plist")
+            .extension("plist")
+            .mime("application/x-plist")
+            .magic(b"bplist00")
+            .description("Property list"),
+        #[cfg(feature = "base64")]
+        FormatDecl::new("base64")
+            .extension("b64")
+            .description("Base64 text"),
+        #[cfg(feature = "hex")]
+        FormatDecl::new("hex").description("Hexadecimal text"),
+        #[cfg(feature = "gzip")]
+        FormatDecl::new("gzip")
+            .extension("gz")
+            .mime("application/gzip")
+            .magic(b"\x1F\x8B")
+            .alias("gz")
+            .description("Gzip compressed data"),
+        #[cfg(feature = "zstd")]
+        FormatDecl::new("zstd")
+            .extension("zst")
+            .mime("application/zstd")
+            .magic(b"\x28\xB5\x2F\xFD")
+            .alias("zst")
+            .description("Zstandard compressed data"),
+        #[cfg(feature = "brotli")]
+        FormatDecl::new("brotli")
+            .extension("br")
+            .mime("application/x-brotli")
+            .alias("br")
+            .description("Brotli compressed data"),
+        #[cfg(feature = "ini")]
+        FormatDecl::new("ini")
+            .extensions(["ini", "cfg"])
+            .description("INI configuration"),
+        #[cfg(feature = "markdown")]
+        FormatDecl::new("markdown")
+            .extensions(["md", "markdown"])
+            .mime("text/markdown")
+            .alias("md")
+            .description("Markdown"),
+        #[cfg(any(feature = "markdown", feature = "html2text"))]
+        FormatDecl::new("html")
+            .extensions(["html", "htm"])
+            .mime("text/html")
+            .description("HTML"),
+        #[cfg(feature = "html2text")]
+        FormatDecl::new("text")
+            .extension("txt")
+            .alias("txt")
+            .description("Plain text"),
+        #[cfg(feature = "tar")]
+        FormatDecl::new("tar")
+            .extension("tar")
+            .mime("application/x-tar")
+            .magic_at(257, b"ustar")
+            .description("Tar archive"),
+        #[cfg(feature = "zip")]
+        FormatDecl::new("zip")
+            .extension("zip")
+            .mime("application/zip")
+            .description("Zip archive"),
+        #[cfg(any(feature = "spreadsheet", feature = "xlsxwrite"))]
+        FormatDecl::new("xlsx")
+            .extension("xlsx")
+            .mime("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+            .description("Excel workbook"),
+        #[cfg(feature = "spreadsheet")]
+        FormatDecl::new("ods")
+            .extension("ods")
+            .mime("application/vnd.oasis.opendocument.spreadsheet")
+            .description("OpenDocument spreadsheet"),
+        #[cfg(feature = "spreadsheet")]
+        FormatDecl::new("xls")
+            .extension("xls")
+            .mime("application/vnd.ms-excel")
+            .description("Excel 97-2003 workbook"),
+        #[cfg(feature = "spreadsheet")]
+        FormatDecl::new("xlsb")
+            .extension("xlsb")
+            .mime("application/vnd.ms-excel.sheet.binary.macroenabled.12")
+            .description("Excel binary workbook"),
+        #[cfg(feature = "avro")]
+        FormatDecl::new("avro")
+            .extension("avro")
+            .mime("application/avro")
+            .magic(b"Obj\x01")
+            .description("Avro object container"),
+        #[cfg(feature = "parquet")]
+        FormatDecl::new("parquet")
+            .extension("parquet")
+            .mime("application/vnd.apache.parquet")
+            .magic(b"PAR1")
+            .description("Apache Parquet"),
+    ]
+}
+
 /// A converter between two serde-compatible formats.
 pub struct SerdeConverter {
     decl: ConverterDecl,
@@ -256,6 +503,19 @@ impl Converter for SerdeConverter {
         // Deserialize from source format
         let value: serde_json::Value = deserialize(self.from, input)?;
 
+        // Comments never survive the trip through a value tree
+        let comments = count_comment_lines(self.from, input);
+        if comments > 0 {
+            report_loss(
+                LossNote::new("comments", Severity::Minor)
+                    .count(comments)
+                    .detail(format!(
+                        "{} comments are not carried into {}",
+                        self.from, self.to
+                    )),
+            );
+        }
+
         // Serialize to target format
         let output = serialize(self.to, &value)?;
 
@@ -267,6 +527,93 @@ impl Converter for SerdeConverter {
     }
 }
 
+/// Compares two documents of the same serde format by value.
+///
+/// Key order, whitespace and number spelling (`1` vs `1.0`) are ignored.
+pub struct SerdeComparator {
+    decl: ComparatorDecl,
+}
+
+impl SerdeComparator {
+    /// Most differences listed before the rest are summarized.
+    const MAX_DIFFS: usize = 20;
+
+    pub fn new() -> Self {
+        let formats = enabled_formats().into_iter().map(Value::from).collect();
+        let decl = ComparatorDecl::new(
+            "serde.value",
+            PropertyPattern::new().with("format", Predicate::OneOf(formats)),
+        )
+        .description("Compare serde documents by value");
+        Self { decl }
+    }
+}
+
+impl Default for SerdeComparator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Comparator for SerdeComparator {
+    fn decl(&self) -> &ComparatorDecl {
+        &self.decl
+    }
+
+    fn compare(
+        &self,
+        original: &[u8],
+        roundtrip: &[u8],
+        props: &Properties,
+    ) -> Result<Vec<String>, ConvertError> {
+        let format = props
+            .get("format")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ConvertError::InvalidInput("Missing format property".into()))?;
+        let a = deserialize(format, original)?;
+        let b = deserialize(format, roundtrip)?;
+
+        let mut diffs = Vec::new();
+        diff_values("$", &a, &b, &mut diffs);
+        if diffs.len() > Self::MAX_DIFFS {
+            let more = diffs.len() - Self::MAX_DIFFS;
+            diffs.truncate(Self::MAX_DIFFS);
+            diffs.push(format!("... and {} more", more));
+        }
+        Ok(diffs)
+    }
+}
+
+/// Collect differences between two values, labelled with JSONPath-like paths.
+fn diff_values(path: &str, a: &serde_json::Value, b: &serde_json::Value, out: &mut Vec<String>) {
+    use serde_json::Value as J;
+    match (a, b) {
+        (J::Object(x), J::Object(y)) => {
+            for (key, va) in x {
+                let child = format!("{}.{}", path, key);
+                match y.get(key) {
+                    Some(vb) => diff_values(&child, va, vb, out),
+                    None => out.push(format!("{}: missing", child)),
+                }
+            }
+            for key in y.keys().filter(|k| !x.contains_key(*k)) {
+                out.push(format!("{}.{}: unexpected", path, key));
+            }
+        }
+        (J::Array(x), J::Array(y)) => {
+            if x.len() != y.len() {
+                out.push(format!("{}: {} items, got {}", path, x.len(), y.len()));
+            }
+            for (i, (va, vb)) in x.iter().zip(y).enumerate() {
+                diff_values(&format!("{}[{}]", path, i), va, vb, out);
+            }
+        }
+        (J::Number(x), J::Number(y)) if x.as_f64() == y.as_f64() => {}
+        _ if a == b => {}
+        _ => out.push(format!("{}: expected {}, got {}", path, a, b)),
+    }
+}
+
 // ============================================
 // Base64 encoding/decoding
 // ============================================
@@ -521,6 +868,8 @@ mod gzip_impl {
                     PropertyPattern::new().eq("format", "gzip"),
                 )
                 .description("Compress with gzip")
+                // Incompressible input grows slightly
+                .memory_model(Formula::input_size() * 1.1)
             })
         }
 
@@ -557,11 +906,11 @@ mod gzip_impl {
         }
 
         fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-            let mut decoder = GzDecoder::new(input);
-            let mut output = Vec::new();
-            decoder.read_to_end(&mut output).map_err(|e| {
-                ConvertError::InvalidInput(format!("Gzip decompression failed: {}", e))
-            })?;
+            let output = paraphase_core::current_limits().read_to_end(
+                GzDecoder::new(input),
+                input.len(),
+                "Gzip decompression",
+            )?;
             let mut out_props = props.clone();
             out_props.insert("format".into(), "raw".into());
             Ok(ConvertOutput::Single(output, out_props))
@@ -590,6 +939,7 @@ mod zstd_impl {
                     PropertyPattern::new().eq("format", "zstd"),
                 )
                 .description("Compress with zstd")
+                .memory_model(Formula::input_size() * 1.1)
             })
         }
 
@@ -622,9 +972,14 @@ mod zstd_impl {
         }
 
         fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-            let output = zstd::decode_all(input).map_err(|e| {
+            let decoder = zstd::Decoder::new(input).map_err(|e| {
                 ConvertError::InvalidInput(format!("Zstd decompression failed: {}", e))
             })?;
+            let output = paraphase_core::current_limits().read_to_end(
+                decoder,
+                input.len(),
+                "Zstd decompression",
+            )?;
             let mut out_props = props.clone();
             out_props.insert("format".into(), "raw".into());
             Ok(ConvertOutput::Single(output, out_props))
@@ -654,6 +1009,7 @@ mod brotli_impl {
                     PropertyPattern::new().eq("format", "brotli"),
                 )
                 .description("Compress with brotli")
+                .memory_model(Formula::input_size() * 1.1)
             })
         }
 
@@ -689,11 +1045,11 @@ mod brotli_impl {
         }
 
         fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-            let mut output = Vec::new();
-            let mut decompressor = brotli::Decompressor::new(input, 4096);
-            decompressor.read_to_end(&mut output).map_err(|e| {
-                ConvertError::InvalidInput(format!("Brotli decompression failed: {}", e))
-            })?;
+            let output = paraphase_core::current_limits().read_to_end(
+                brotli::Decompressor::new(input, 4096),
+                input.len(),
+                "Brotli decompression",
+            )?;
             let mut out_props = props.clone();
             out_props.insert("format".into(), "raw".into());
             Ok(ConvertOutput::Single(output, out_props))
@@ -1001,6 +1357,263 @@ mod html2text_impl {
 #[cfg(feature = "html2text")]
 pub use html2text_impl::HtmlToText;
 
+/// Entry types of extracted archive entries (`entry_type` property).
+#[cfg(any(feature = "tar", feature = "zip"))]
+const ENTRY_FILE: &str = "file";
+#[cfg(any(feature = "tar", feature = "zip"))]
+const ENTRY_DIRECTORY: &str = "directory";
+#[cfg(any(feature = "tar", feature = "zip"))]
+const ENTRY_SYMLINK: &str = "symlink";
+#[cfg(any(feature = "tar", feature = "zip"))]
+const ENTRY_HARDLINK: &str = "hardlink";
+
+/// Turn an archive entry path into a safe relative path.
+///
+/// Backslashes count as separators; leading slashes, empty and `.`
+/// components are dropped. Paths with `..` components or a drive prefix
+/// are rejected. Returns an empty string for the archive root.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn sanitize_entry_path(raw: &str) -> Result<String, ConvertError> {
+    let mut parts = Vec::new();
+    for part in raw.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                return Err(ConvertError::InvalidInput(format!(
+                    "Archive entry '{}' escapes the archive",
+                    raw
+                )));
+            }
+            _ if parts.is_empty() && is_drive(part) || part.contains('\0') => {
+                return Err(ConvertError::InvalidInput(format!(
+                    "Archive entry '{}' is not a relative path",
+                    raw
+                )));
+            }
+            _ => parts.push(part),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+/// Whether a path component is a Windows drive, e.g. `C:`.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn is_drive(part: &str) -> bool {
+    let b = part.as_bytes();
+    b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':'
+}
+
+/// Symlinks seen so far in an archive.
+///
+/// Checking each link on its own is not enough: with `a/l -> ..`, the
+/// entry `a/l/m -> ..` looks harmless but resolves outside the archive. So
+/// nothing may be placed at or below an earlier symlink, and link targets
+/// may not pass through one.
+#[cfg(any(feature = "tar", feature = "zip"))]
+#[derive(Default)]
+struct LinkGuard(std::collections::HashSet<String>);
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+impl LinkGuard {
+    /// Fail if `path` is, or lies below, a symlink seen earlier.
+    fn check_path(&self, path: &str) -> Result<(), ConvertError> {
+        let through_link = path
+            .match_indices('/')
+            .map(|(i, _)| &path[..i])
+            .chain([path])
+            .find(|prefix| self.0.contains(*prefix));
+        match through_link {
+            Some(link) => Err(ConvertError::InvalidInput(format!(
+                "Archive entry '{}' goes through symlink '{}'",
+                path, link
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Check a symlink at `path` pointing to `target`, then remember it.
+    ///
+    /// The target must resolve inside the archive without passing through
+    /// an earlier symlink.
+    fn add_symlink(&mut self, path: &str, target: &str) -> Result<(), ConvertError> {
+        self.check_path(path)?;
+        let escapes = || {
+            ConvertError::InvalidInput(format!(
+                "Symlink '{}' -> '{}' escapes the archive",
+                path, target
+            ))
+        };
+        if target.starts_with(['/', '\\']) || target.split(['/', '\\']).next().is_some_and(is_drive)
+        {
+            return Err(escapes());
+        }
+
+        // Resolve from the directory holding the link
+        let mut resolved: Vec<&str> = path.split('/').collect();
+        resolved.pop();
+        let parts: Vec<&str> = target
+            .split(['/', '\\'])
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect();
+        for (i, part) in parts.iter().enumerate() {
+            if *part == ".." {
+                resolved.pop().ok_or_else(escapes)?;
+                continue;
+            }
+            resolved.push(part);
+            // Following an earlier symlink could lead anywhere
+            if i + 1 < parts.len() && self.0.contains(&resolved.join("/")) {
+                return Err(escapes());
+            }
+        }
+        self.0.insert(path.to_string());
+        Ok(())
+    }
+}
+
+/// Properties of an extracted entry: the archive's properties with the
+/// entry's path and metadata.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn entry_props(
+    props: &Properties,
+    path: String,
+    entry_type: &str,
+    mode: Option<u32>,
+    mtime: Option<Timestamp>,
+    link_target: Option<String>,
+) -> Properties {
+    let mut out_props = props.clone();
+    for key in ["mode", "mtime", "link_target"] {
+        out_props.shift_remove(key);
+    }
+    out_props.insert("format".into(), "raw".into());
+    out_props.insert("path".into(), path.into());
+    out_props.insert("entry_type".into(), entry_type.into());
+    if let Some(mode) = mode {
+        out_props.insert("mode".into(), i64::from(mode).into());
+    }
+    if let Some(mtime) = mtime {
+        out_props.insert("mtime".into(), mtime.into());
+    }
+    if let Some(target) = link_target {
+        out_props.insert("link_target".into(), target.into());
+    }
+    out_props
+}
+
+/// An entry to write into an archive, described by an input's properties.
+#[cfg(any(feature = "tar", feature = "zip"))]
+struct NewEntry<'a> {
+    path: String,
+    entry_type: &'a str,
+    mode: Option<u32>,
+    mtime: Option<Timestamp>,
+    link_target: Option<String>,
+    data: &'a [u8],
+}
+
+/// Read the entries to write into a `kind` archive from the inputs, sorted
+/// by path in deterministic mode.
+///
+/// Paths and link targets are checked like on extraction, so the archive
+/// extracts safely.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn new_entries<'a>(
+    inputs: &[(&'a [u8], &'a Properties)],
+    kind: &str,
+) -> Result<Vec<NewEntry<'a>>, ConvertError> {
+    let mut entries = Vec::with_capacity(inputs.len());
+    for &(data, props) in inputs {
+        let path = props.get("path").and_then(|v| v.as_str()).ok_or_else(|| {
+            ConvertError::InvalidInput(format!("Missing 'path' property for {} entry", kind))
+        })?;
+        let entry_type = props
+            .get("entry_type")
+            .and_then(|v| v.as_str())
+            .unwrap_or(ENTRY_FILE);
+        let link_target = props.get("link_target").and_then(|v| v.as_str());
+        match entry_type {
+            ENTRY_FILE | ENTRY_DIRECTORY => {}
+            ENTRY_SYMLINK | ENTRY_HARDLINK if link_target.is_some() => {}
+            ENTRY_SYMLINK | ENTRY_HARDLINK => {
+                return Err(ConvertError::InvalidInput(format!(
+                    "Missing 'link_target' property for {} entry '{}'",
+                    kind, path
+                )));
+            }
+            other => {
+                return Err(ConvertError::InvalidInput(format!(
+                    "Unknown entry type '{}' for {} entry '{}'",
+                    other, kind, path
+                )));
+            }
+        }
+        let safe_path = sanitize_entry_path(path)?;
+        if safe_path.is_empty() {
+            return Err(ConvertError::InvalidInput(format!(
+                "Empty path for {} entry '{}'",
+                kind, path
+            )));
+        }
+        // Hard links name another entry of the archive
+        let link_target = match link_target {
+            Some(target) if entry_type == ENTRY_HARDLINK => Some(sanitize_entry_path(target)?),
+            other => other.map(String::from),
+        };
+        entries.push(NewEntry {
+            path: safe_path,
+            entry_type,
+            mode: props
+                .get("mode")
+                .and_then(|v| v.as_i64())
+                .map(|m| m as u32 & 0o7777),
+            mtime: props.get("mtime").and_then(|v| v.as_timestamp()),
+            link_target,
+            data,
+        });
+    }
+    if paraphase_core::is_deterministic() {
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    let mut links = LinkGuard::default();
+    for entry in &entries {
+        match (entry.entry_type, &entry.link_target) {
+            (ENTRY_SYMLINK, Some(target)) => links.add_symlink(&entry.path, target)?,
+            (ENTRY_HARDLINK, Some(target)) => {
+                links.check_path(&entry.path)?;
+                links.check_path(target)?;
+            }
+            _ => links.check_path(&entry.path)?,
+        }
+    }
+    Ok(entries)
+}
+
+/// Read one archive entry, keeping the archive's total output within the
+/// current limits. `extracted` bytes came from earlier entries.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn read_entry(
+    entry: impl std::io::Read,
+    limits: &paraphase_core::Limits,
+    archive_len: usize,
+    extracted: u64,
+) -> Result<Vec<u8>, ConvertError> {
+    use std::io::Read;
+
+    // One byte past the budget tells a full read from an overflow
+    let cap = limits
+        .output_budget(archive_len)
+        .map_or(u64::MAX, |b| b.saturating_sub(extracted).saturating_add(1));
+    let mut data = Vec::new();
+    entry
+        .take(cap)
+        .read_to_end(&mut data)
+        .map_err(|e| ConvertError::InvalidInput(format!("Failed to read entry: {}", e)))?;
+    limits.check_output(archive_len, extracted + data.len() as u64)?;
+    Ok(data)
+}
+
 // ============================================
 // Tar archives
 // ============================================
@@ -1008,9 +1621,13 @@ pub use html2text_impl::HtmlToText;
 #[cfg(feature = "tar")]
 mod tar_impl {
     use super::*;
-    use std::io::{Cursor, Read};
+    use std::io::Cursor;
 
     /// Extract files from a tar archive.
+    ///
+    /// Each entry's path is made relative and checked not to escape the
+    /// archive. Directories and links come out as empty items; `entry_type`,
+    /// `mode`, `mtime` and `link_target` carry the entry's metadata.
     pub struct TarExtract;
 
     impl Converter for TarExtract {
@@ -1029,6 +1646,10 @@ mod tar_impl {
         fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
             let cursor = Cursor::new(input);
             let mut archive = tar::Archive::new(cursor);
+            let limits = paraphase_core::current_limits();
+            let mut links = LinkGuard::default();
+            let mut entries = 0u64;
+            let mut total = 0u64;
 
             let mut outputs = Vec::new();
             for entry in archive
@@ -1037,27 +1658,61 @@ mod tar_impl {
             {
                 let mut entry = entry
                     .map_err(|e| ConvertError::InvalidInput(format!("Invalid tar entry: {}", e)))?;
+                entries += 1;
+                limits.check_entries(entries)?;
+
+                let kind = entry.header().entry_type();
+                let entry_type = if kind.is_file() {
+                    ENTRY_FILE
+                } else if kind.is_dir() {
+                    ENTRY_DIRECTORY
+                } else if kind.is_symlink() {
+                    ENTRY_SYMLINK
+                } else if kind.is_hard_link() {
+                    ENTRY_HARDLINK
+                } else {
+                    // Devices, FIFOs and global headers hold no file
+                    continue;
+                };
 
-                // Skip directories
-                if entry.header().entry_type().is_dir() {
+                let path = sanitize_entry_path(&String::from_utf8_lossy(&entry.path_bytes()))?;
+                if path.is_empty() {
                     continue;
                 }
+                if entry_type != ENTRY_SYMLINK {
+                    links.check_path(&path)?;
+                }
 
-                let path = entry
-                    .path()
-                    .map_err(|e| ConvertError::InvalidInput(format!("Invalid path: {}", e)))?
-                    .to_string_lossy()
-                    .to_string();
-
-                let mut data = Vec::new();
-                entry.read_to_end(&mut data).map_err(|e| {
-                    ConvertError::InvalidInput(format!("Failed to read entry: {}", e))
-                })?;
+                let header = entry.header();
+                let mode = header.mode().ok().map(|m| m & 0o7777);
+                let mtime = header
+                    .mtime()
+                    .ok()
+                    .map(|t| Timestamp::from_unix(t as i64, 0));
+
+                let link_target = match entry.link_name_bytes() {
+                    Some(target) if entry_type == ENTRY_SYMLINK => {
+                        let target = String::from_utf8_lossy(&target).into_owned();
+                        links.add_symlink(&path, &target)?;
+                        Some(target)
+                    }
+                    // Hard links name another entry of the archive
+                    Some(target) if entry_type == ENTRY_HARDLINK => {
+                        let target = sanitize_entry_path(&String::from_utf8_lossy(&target))?;
+                        links.check_path(&target)?;
+                        Some(target)
+                    }
+                    _ => None,
+                };
 
-                let mut out_props = props.clone();
-                out_props.insert("format".into(), "raw".into());
-                out_props.insert("path".into(), path.into());
+                let data = if entry_type == ENTRY_FILE {
+                    read_entry(&mut entry, &limits, input.len(), total)?
+                } else {
+                    Vec::new()
+                };
+                total += data.len() as u64;
 
+                let out_props = entry_props(props, path, entry_type, mode, mtime, link_target);
                 outputs.push((data, out_props));
             }
 
@@ -1066,6 +1721,9 @@ mod tar_impl {
     }
 
     /// Create a tar archive from multiple files.
+    ///
+    /// Restores the metadata `TarExtract` records; entries without an
+    /// `mtime` get [`FIXED_MTIME`](paraphase_core::FIXED_MTIME).
     pub struct TarCreate;
 
     impl Converter for TarCreate {
@@ -1097,21 +1755,51 @@ mod tar_impl {
             &self,
             inputs: &[(&[u8], &Properties)],
         ) -> Result<ConvertOutput, ConvertError> {
+            let entries = new_entries(inputs, "tar")?;
+            let deterministic = paraphase_core::is_deterministic();
+
             let mut output = Vec::new();
             {
                 let mut builder = tar::Builder::new(&mut output);
 
-                for (data, props) in inputs {
-                    let path = props.get("path").and_then(|v| v.as_str()).ok_or_else(|| {
-                        ConvertError::InvalidInput("Missing 'path' property for tar entry".into())
-                    })?;
-
+                for entry in entries {
+                    let path = &entry.path;
                     let mut header = tar::Header::new_gnu();
-                    header.set_size(data.len() as u64);
-                    header.set_mode(0o644);
-                    header.set_cksum();
+                    let mtime = entry.mtime.filter(|_| !deterministic);
+                    header.set_mtime(mtime.map_or(paraphase_core::FIXED_MTIME, |t| {
+                        t.unix_seconds().max(0) as u64
+                    }));
+                    if deterministic {
+                        header.set_uid(0);
+                        header.set_gid(0);
+                    }
 
-                    builder.append_data(&mut header, path, *data).map_err(|e| {
+                    let result = match (entry.entry_type, entry.link_target.as_deref()) {
+                        (ENTRY_DIRECTORY, _) => {
+                            header.set_entry_type(tar::EntryType::Directory);
+                            header.set_size(0);
+                            header.set_mode(entry.mode.unwrap_or(0o755));
+                            builder.append_data(&mut header, path, std::io::empty())
+                        }
+                        (ENTRY_SYMLINK, Some(target)) => {
+                            header.set_entry_type(tar::EntryType::Symlink);
+                            header.set_size(0);
+                            header.set_mode(entry.mode.unwrap_or(0o777));
+                            builder.append_link(&mut header, path, target)
+                        }
+                        (ENTRY_HARDLINK, Some(target)) => {
+                            header.set_entry_type(tar::EntryType::Link);
+                            header.set_size(0);
+                            header.set_mode(entry.mode.unwrap_or(0o644));
+                            builder.append_link(&mut header, path, target)
+                        }
+                        _ => {
+                            header.set_size(entry.data.len() as u64);
+                            header.set_mode(entry.mode.unwrap_or(0o644));
+                            builder.append_data(&mut header, path, entry.data)
+                        }
+                    };
+                    result.map_err(|e| {
                         ConvertError::Failed(format!("Failed to add entry '{}': {}", path, e))
                     })?;
                 }
@@ -1138,9 +1826,12 @@ pub use tar_impl::{TarCreate, TarExtract};
 #[cfg(feature = "zip")]
 mod zip_impl {
     use super::*;
-    use std::io::{Cursor, Read, Write};
+    use std::io::{Cursor, Write};
 
     /// Extract files from a zip archive.
+    ///
+    /// Paths are checked like [`TarExtract`](crate::TarExtract)'s, and the
+    /// same metadata properties are recorded.
     pub struct ZipExtract;
 
     impl Converter for ZipExtract {
@@ -1160,6 +1851,10 @@ mod zip_impl {
             let cursor = Cursor::new(input);
             let mut archive = zip::ZipArchive::new(cursor)
                 .map_err(|e| ConvertError::InvalidInput(format!("Invalid zip archive: {}", e)))?;
+            let limits = paraphase_core::current_limits();
+            limits.check_entries(archive.len() as u64)?;
+            let mut links = LinkGuard::default();
+            let mut total = 0u64;
 
             let mut outputs = Vec::new();
             for i in 0..archive.len() {
@@ -1167,22 +1862,45 @@ mod zip_impl {
                     .by_index(i)
                     .map_err(|e| ConvertError::InvalidInput(format!("Invalid zip entry: {}", e)))?;
 
-                // Skip directories
-                if file.is_dir() {
+                let path = sanitize_entry_path(file.name())?;
+                if path.is_empty() {
                     continue;
                 }
+                let mode = file.unix_mode().map(|m| m & 0o7777);
+                let mtime = file.last_modified().and_then(|t| {
+                    Timestamp::from_civil(
+                        t.year().into(),
+                        t.month().into(),
+                        t.day().into(),
+                        t.hour().into(),
+                        t.minute().into(),
+                        t.second().into(),
+                    )
+                });
+
+                let (entry_type, link_target) = if file.is_dir() {
+                    links.check_path(&path)?;
+                    (ENTRY_DIRECTORY, None)
+                } else if file.is_symlink() {
+                    // A symlink's data is its target
+                    let target = read_entry(&mut file, &limits, input.len(), total)?;
+                    total += target.len() as u64;
+                    let target = String::from_utf8_lossy(&target).into_owned();
+                    links.add_symlink(&path, &target)?;
+                    (ENTRY_SYMLINK, Some(target))
+                } else {
+                    links.check_path(&path)?;
+                    (ENTRY_FILE, None)
+                };
 
-                let path = file.name().to_string();
-
-                let mut data = Vec::new();
-                file.read_to_end(&mut data).map_err(|e| {
-                    ConvertError::InvalidInput(format!("Failed to read entry: {}", e))
-                })?;
-
-                let mut out_props = props.clone();
-                out_props.insert("format".into(), "raw".into());
-                out_props.insert("path".into(), path.into());
+                let data = if entry_type == ENTRY_FILE {
+                    read_entry(&mut file, &limits, input.len(), total)?
+                } else {
+                    Vec::new()
+                };
+                total += data.len() as u64;
 
+                let out_props = entry_props(props, path, entry_type, mode, mtime, link_target);
                 outputs.push((data, out_props));
             }
 
@@ -1191,6 +1909,9 @@ mod zip_impl {
     }
 
     /// Create a zip archive from multiple files.
+    ///
+    /// Restores the metadata `ZipExtract` records. Zip has no hard links, so
+    /// those entries are dropped and reported as a loss.
     pub struct ZipCreate;
 
     impl Converter for ZipCreate {
@@ -1222,36 +1943,77 @@ mod zip_impl {
             &self,
             inputs: &[(&[u8], &Properties)],
         ) -> Result<ConvertOutput, ConvertError> {
+            let entries = new_entries(inputs, "zip")?;
+            let mut defaults = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            let deterministic = paraphase_core::is_deterministic();
+            if deterministic {
+                // Zip times start in 1980, so FIXED_MTIME maps to the earliest one.
+                defaults = defaults
+                    .last_modified_time(zip::DateTime::default())
+                    .compression_level(Some(6));
+            }
+
             let mut output = Cursor::new(Vec::new());
+            let mut hard_links = 0;
             {
                 let mut writer = zip::ZipWriter::new(&mut output);
-                let options = zip::write::SimpleFileOptions::default()
-                    .compression_method(zip::CompressionMethod::Deflated);
 
-                for (data, props) in inputs {
-                    let path = props.get("path").and_then(|v| v.as_str()).ok_or_else(|| {
-                        ConvertError::InvalidInput("Missing 'path' property for zip entry".into())
-                    })?;
+                for entry in entries {
+                    let path = &entry.path;
+                    let mut options = defaults;
+                    if let Some(time) = entry.mtime.filter(|_| !deterministic).and_then(zip_time) {
+                        options = options.last_modified_time(time);
+                    }
+                    if let Some(mode) = entry.mode {
+                        options = options.unix_permissions(mode);
+                    }
 
-                    writer.start_file(path, options).map_err(|e| {
+                    let result = match (entry.entry_type, entry.link_target.as_deref()) {
+                        (ENTRY_DIRECTORY, _) => writer.add_directory(path, options),
+                        (ENTRY_SYMLINK, Some(target)) => writer.add_symlink(path, target, options),
+                        (ENTRY_HARDLINK, _) => {
+                            hard_links += 1;
+                            continue;
+                        }
+                        _ => writer.start_file(path, options).and_then(|()| {
+                            writer
+                                .write_all(entry.data)
+                                .map_err(zip::result::ZipError::from)
+                        }),
+                    };
+                    result.map_err(|e| {
                         ConvertError::Failed(format!("Failed to add entry '{}': {}", path, e))
                     })?;
-
-                    writer.write_all(data).map_err(|e| {
-                        ConvertError::Failed(format!("Failed to write entry '{}': {}", path, e))
-                    })?;
                 }
 
                 writer
                     .finish()
                     .map_err(|e| ConvertError::Failed(format!("Failed to finalize zip: {}", e)))?;
             }
+            if hard_links > 0 {
+                report_loss(LossNote::new("hard links", Severity::Minor).count(hard_links));
+            }
 
             let mut out_props = Properties::new();
             out_props.insert("format".into(), "zip".into());
             Ok(ConvertOutput::Single(output.into_inner(), out_props))
         }
     }
+
+    /// Zip time of a timestamp; `None` outside zip's 1980-2107 range.
+    fn zip_time(t: Timestamp) -> Option<zip::DateTime> {
+        let (year, month, day, hour, minute, second) = t.to_civil();
+        zip::DateTime::from_date_and_time(
+            u16::try_from(year).ok()?,
+            month as u8,
+            day as u8,
+            hour as u8,
+            minute as u8,
+            second as u8,
+        )
+        .ok()
+    }
 }
 
 #[cfg(feature = "zip")]
@@ -1281,7 +2043,7 @@ mod spreadsheet_impl {
 
     impl SpreadsheetToJson {
         fn decl() -> ConverterDecl {
-            use paraphase_core::{Predicate, Value};
+            use paraphase_core::{OptionDecl, Predicate, Value};
             ConverterDecl::simple(
                 "spreadsheet-to-json",
                 PropertyPattern::new().with(
@@ -1296,6 +2058,13 @@ mod spreadsheet_impl {
                 PropertyPattern::new().eq("format", "json"),
             )
             .description("Read spreadsheet to JSON")
+            .option(
+                "headers",
+                OptionDecl::string("Use the first row as column names")
+                    .choices(["true", "false"])
+                    .default_value("false"),
+            )
+            .option("sheet", OptionDecl::string("Only read the named sheet"))
         }
     }
 
@@ -1306,18 +2075,27 @@ mod spreadsheet_impl {
         }
 
         fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+            self.convert_with_options(input, props, &Properties::new())
+        }
+
+        fn convert_with_options(
+            &self,
+            input: &[u8],
+            _props: &Properties,
+            options: &Properties,
+        ) -> Result<ConvertOutput, ConvertError> {
             let cursor = Cursor::new(input);
             let mut workbook = open_workbook_auto_from_rs(cursor).map_err(|e| {
                 ConvertError::InvalidInput(format!("Failed to open spreadsheet: {}", e))
             })?;
 
-            let use_headers = props
+            let use_headers = options
                 .get("headers")
                 .and_then(|v| v.as_str())
                 .map(|s| s == "true")
                 .unwrap_or(false);
 
-            let sheet_filter = props.get("sheet").and_then(|v| v.as_str());
+            let sheet_filter = options.get("sheet").and_then(|v| v.as_str());
 
             let sheet_names: Vec<String> = workbook.sheet_names().to_vec();
             let mut sheets = serde_json::Map::new();
@@ -1961,6 +2739,16 @@ mod xlsxwrite_impl {
             })?;
 
             let mut workbook = rust_xlsxwriter::Workbook::new();
+            if paraphase_core::is_deterministic() {
+                // The creation time defaults to now.
+                let created = rust_xlsxwriter::ExcelDateTime::from_timestamp(
+                    paraphase_core::FIXED_MTIME as i64,
+                )
+                .map_err(|e| ConvertError::Failed(format!("XLSX write error: {}", e)))?;
+                workbook.set_properties(
+                    &rust_xlsxwriter::DocProperties::new().set_creation_datetime(&created),
+                );
+            }
             let worksheet = workbook.add_worksheet();
 
             if !array.is_empty() {
@@ -2044,6 +2832,24 @@ mod xlsxwrite_impl {
 #[cfg(feature = "xlsxwrite")]
 pub use xlsxwrite_impl::JsonToXlsx;
 
+/// Count whole-line comments in a source document.
+///
+/// Trailing comments after a value are not counted; telling them apart from
+/// comment markers inside strings would need a real parser.
+fn count_comment_lines(format: &str, data: &[u8]) -> u64 {
+    let marker = match format {
+        "yaml" | "toml" => "#",
+        "json5" | "ron" => "//",
+        _ => return 0,
+    };
+    let Ok(text) = std::str::from_utf8(data) else {
+        return 0;
+    };
+    text.lines()
+        .filter(|line| line.trim_start().starts_with(marker))
+        .count() as u64
+}
+
 /// Deserialize bytes to a serde Value.
 fn deserialize(format: &str, data: &[u8]) -> Result<serde_json::Value, ConvertError> {
     match format {
@@ -2307,6 +3113,46 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(all(feature = "yaml", feature = "json"))]
+    fn test_yaml_to_json_reports_comments() {
+        let converter = SerdeConverter::new("yaml", "json");
+        let input = b"# config\nname: test\n  # nested note\nvalue: 42\n";
+        let props = Properties::new().with("format", "yaml");
+
+        let (result, losses) = paraphase_core::collect_losses("serde.yaml-to-json", || {
+            converter.convert(input, &props)
+        });
+        assert!(result.is_ok());
+        assert_eq!(losses.len(), 1);
+        assert_eq!(losses[0].feature, "comments");
+        assert_eq!(losses[0].count, 2);
+        assert_eq!(losses[0].severity, Severity::Minor);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_serde_comparator() {
+        let comparator = SerdeComparator::new();
+        let props = Properties::new().with("format", "json");
+
+        let a = br#"{"name": "test", "values": [1, 2.5], "nested": {"x": true}}"#;
+        let b = br#"{"nested":{"x":true},"values":[1.0,2.5],"name":"test"}"#;
+        assert!(comparator.compare(a, b, &props).unwrap().is_empty());
+
+        let c = br#"{"name": "other", "values": [1], "extra": null}"#;
+        let diffs = comparator.compare(a, c, &props).unwrap();
+        assert_eq!(
+            diffs,
+            vec![
+                r#"$.name: expected "test", got "other""#,
+                "$.nested: missing",
+                "$.values: 2 items, got 1",
+                "$.extra: unexpected",
+            ]
+        );
+    }
+
     #[test]
     #[cfg(all(feature = "json", feature = "toml"))]
     fn test_json_to_toml() {
@@ -2574,6 +3420,62 @@ mod tests {
         assert_eq!(original_value, roundtrip_value);
     }
 
+    #[test]
+    #[cfg(all(feature = "gzip", feature = "tar"))]
+    fn test_decompression_limits() {
+        use crate::{GzipCompress, GzipDecompress, TarCreate, TarExtract};
+        use paraphase_core::{Limits, with_limits};
+
+        // A megabyte of zeros compresses about a thousandfold
+        let bomb = match GzipCompress
+            .convert(&vec![0u8; 1 << 20], &Properties::new())
+            .unwrap()
+        {
+            ConvertOutput::Single(b, _) => b,
+            _ => panic!("Expected single"),
+        };
+        let props = Properties::new().with("format", "gzip");
+        assert!(GzipDecompress.convert(&bomb, &props).is_ok());
+
+        for limits in [
+            Limits::new().max_output_bytes(64 * 1024),
+            Limits::new().max_expansion_ratio(100.0),
+        ] {
+            assert!(matches!(
+                with_limits(limits, || GzipDecompress.convert(&bomb, &props)),
+                Err(ConvertError::LimitExceeded(_))
+            ));
+        }
+
+        let files: Vec<(Vec<u8>, Properties)> = (0..3)
+            .map(|i| {
+                (
+                    vec![b'x'; 100],
+                    Properties::new().with("path", format!("file{i}.txt")),
+                )
+            })
+            .collect();
+        let inputs: Vec<(&[u8], &Properties)> =
+            files.iter().map(|(d, p)| (d.as_slice(), p)).collect();
+        let archive = match TarCreate.convert_batch(&inputs).unwrap() {
+            ConvertOutput::Single(b, _) => b,
+            _ => panic!("Expected single"),
+        };
+        let props = Properties::new().with("format", "tar");
+        let extract = |limits| with_limits(limits, || TarExtract.convert(&archive, &props));
+        assert!(extract(Limits::new().max_entries(3)).is_ok());
+        assert!(matches!(
+            extract(Limits::new().max_entries(2)),
+            Err(ConvertError::LimitExceeded(_))
+        ));
+        // Entries are counted together against the output limit
+        assert!(extract(Limits::new().max_output_bytes(300)).is_ok());
+        assert!(matches!(
+            extract(Limits::new().max_output_bytes(299)),
+            Err(ConvertError::LimitExceeded(_))
+        ));
+    }
+
     #[test]
     #[cfg(feature = "gzip")]
     fn test_gzip_roundtrip() {
@@ -2800,6 +3702,255 @@ mod tests {
         assert_eq!(file2.0, b"Content of file 2");
     }
 
+    #[test]
+    #[cfg(all(feature = "tar", feature = "zip"))]
+    fn test_archive_path_safety() {
+        use crate::{TarExtract, ZipExtract};
+
+        // The tar builder refuses these names, so write the header by hand
+        let tar_with = |name: &str| {
+            let mut builder = tar::Builder::new(Vec::new());
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(1);
+            header.set_cksum();
+            builder.append(&header, &b"x"[..]).unwrap();
+            builder.into_inner().unwrap()
+        };
+        let zip_with = |name: &str| {
+            let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut writer, b"x").unwrap();
+            writer.finish().unwrap().into_inner()
+        };
+        let tar_props = Properties::new().with("format", "tar");
+        let zip_props = Properties::new().with("format", "zip");
+        let path_of = |output: ConvertOutput| match output {
+            ConvertOutput::Multiple(files) => files[0].1.get("path").unwrap().clone(),
+            _ => panic!("Expected multiple"),
+        };
+
+        for (name, expected) in [
+            ("/etc/passwd", "etc/passwd"),
+            ("./a//b/./c.txt", "a/b/c.txt"),
+            ("dir\\file.txt", "dir/file.txt"),
+        ] {
+            let tar = TarExtract.convert(&tar_with(name), &tar_props).unwrap();
+            assert_eq!(path_of(tar).as_str(), Some(expected));
+            let zip = ZipExtract.convert(&zip_with(name), &zip_props).unwrap();
+            assert_eq!(path_of(zip).as_str(), Some(expected));
+        }
+        for name in ["../../etc/x", "a/../../x", "C:/Windows/x"] {
+            assert!(matches!(
+                TarExtract.convert(&tar_with(name), &tar_props),
+                Err(ConvertError::InvalidInput(_))
+            ));
+            assert!(matches!(
+                ZipExtract.convert(&zip_with(name), &zip_props),
+                Err(ConvertError::InvalidInput(_))
+            ));
+        }
+
+        let mut links = LinkGuard::default();
+        assert!(links.add_symlink("a/b/link", "../c").is_ok());
+        assert!(links.add_symlink("a/link", "../../c").is_err());
+        assert!(links.add_symlink("link", "/etc/passwd").is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "tar", feature = "zip"))]
+    fn test_chained_symlinks() {
+        use crate::{TarCreate, TarExtract, ZipCreate};
+
+        // Each link stays inside on its own, but a/l/m resolves to a/../..
+        let chained = [("a/l", ".."), ("a/l/m", "..")];
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, target) in chained {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, path, target).unwrap();
+        }
+        let tar = builder.into_inner().unwrap();
+        assert!(matches!(
+            TarExtract.convert(&tar, &Properties::new().with("format", "tar")),
+            Err(ConvertError::InvalidInput(_))
+        ));
+
+        let mut links = LinkGuard::default();
+        links.add_symlink("a/l", "..").unwrap();
+        assert!(links.check_path("a/l/file.txt").is_err());
+        assert!(links.check_path("a/l").is_err());
+        assert!(links.check_path("a/other").is_ok());
+        assert!(links.add_symlink("b", "a/l/x").is_err());
+        assert!(links.add_symlink("c", "a/l").is_ok());
+
+        // Creating an archive checks entries the same way
+        let entries: Vec<Properties> = chained
+            .iter()
+            .map(|(path, target)| {
+                Properties::new()
+                    .with("path", *path)
+                    .with("entry_type", "symlink")
+                    .with("link_target", *target)
+            })
+            .collect();
+        let inputs: Vec<(&[u8], &Properties)> = entries.iter().map(|p| (&b""[..], p)).collect();
+        for create in [&TarCreate as &dyn Converter, &ZipCreate] {
+            assert!(matches!(
+                create.convert_batch(&inputs),
+                Err(ConvertError::InvalidInput(_))
+            ));
+        }
+        let escaping = Properties::new().with("path", "../x");
+        assert!(matches!(
+            TarCreate.convert_batch(&[(&b"x"[..], &escaping)]),
+            Err(ConvertError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(all(feature = "tar", feature = "zip"))]
+    fn test_archive_metadata_roundtrip() {
+        use crate::{TarCreate, TarExtract, ZipCreate, ZipExtract};
+
+        let mtime = Timestamp::parse("2024-05-01T12:30:00Z").unwrap();
+        let files = vec![
+            (
+                Vec::new(),
+                Properties::new()
+                    .with("path", "bin")
+                    .with("entry_type", "directory")
+                    .with("mode", 0o750i64)
+                    .with("mtime", mtime),
+            ),
+            (
+                b"#!/bin/sh\n".to_vec(),
+                Properties::new()
+                    .with("path", "bin/run.sh")
+                    .with("mode", 0o755i64)
+                    .with("mtime", mtime),
+            ),
+            (
+                Vec::new(),
+                Properties::new()
+                    .with("path", "run")
+                    .with("entry_type", "symlink")
+                    .with("link_target", "bin/run.sh")
+                    .with("mtime", mtime),
+            ),
+            (
+                Vec::new(),
+                Properties::new()
+                    .with("path", "empty")
+                    .with("entry_type", "directory"),
+            ),
+        ];
+        let inputs: Vec<(&[u8], &Properties)> =
+            files.iter().map(|(d, p)| (d.as_slice(), p)).collect();
+
+        let roundtrip = |create: &dyn Converter, extract: &dyn Converter, format: &str| {
+            let archive = match create.convert_batch(&inputs).unwrap() {
+                ConvertOutput::Single(b, _) => b,
+                _ => panic!("Expected single"),
+            };
+            match extract
+                .convert(&archive, &Properties::new().with("format", format))
+                .unwrap()
+            {
+                ConvertOutput::Multiple(files) => files,
+                _ => panic!("Expected multiple"),
+            }
+        };
+
+        for (create, extract, format) in [
+            (
+                &TarCreate as &dyn Converter,
+                &TarExtract as &dyn Converter,
+                "tar",
+            ),
+            (&ZipCreate, &ZipExtract, "zip"),
+        ] {
+            let extracted = roundtrip(create, extract, format);
+            assert_eq!(extracted.len(), 4, "{format}");
+            for ((data, props), (out_data, out_props)) in files.iter().zip(&extracted) {
+                assert_eq!(out_data, data, "{format}");
+                for key in ["path", "link_target"] {
+                    assert_eq!(out_props.get(key), props.get(key), "{format} {key}");
+                }
+                let entry_type = props.get("entry_type").and_then(|v| v.as_str());
+                assert_eq!(
+                    out_props.get("entry_type").unwrap().as_str(),
+                    Some(entry_type.unwrap_or("file"))
+                );
+                // Entries without metadata get defaults
+                for key in ["mode", "mtime"] {
+                    if let Some(value) = props.get(key) {
+                        assert_eq!(out_props.get(key), Some(value), "{format} {key}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "tar", feature = "zip"))]
+    fn test_deterministic_archives() {
+        use crate::{TarCreate, ZipCreate};
+
+        // The same files, as if read at different times
+        let files = |mtime: &str| {
+            let mtime = Timestamp::parse(mtime).unwrap();
+            vec![
+                (
+                    b"Content of file 2".to_vec(),
+                    Properties::new()
+                        .with("path", "file2.txt")
+                        .with("format", "raw")
+                        .with("mtime", mtime),
+                ),
+                (
+                    b"Hello from file 1".to_vec(),
+                    Properties::new()
+                        .with("path", "dir/file1.txt")
+                        .with("format", "raw")
+                        .with("mtime", mtime),
+                ),
+            ]
+        };
+        let early = files("2020-01-01T00:00:00Z");
+        let late = files("2024-06-15T08:30:00Z");
+        fn inputs(files: &[(Vec<u8>, Properties)]) -> Vec<(&[u8], &Properties)> {
+            files.iter().map(|(d, p)| (d.as_slice(), p)).collect()
+        }
+        let (early, late) = (inputs(&early), inputs(&late));
+        let late_backward: Vec<_> = late.iter().rev().copied().collect();
+
+        let create = |converter: &dyn Converter, inputs: &[(&[u8], &Properties)], deterministic| {
+            paraphase_core::with_determinism(deterministic, || {
+                match converter.convert_batch(inputs).unwrap() {
+                    ConvertOutput::Single(data, _) => data,
+                    _ => panic!("Expected single"),
+                }
+            })
+        };
+
+        for converter in [&TarCreate as &dyn Converter, &ZipCreate] {
+            // The times show up in normal output...
+            assert_ne!(
+                create(converter, &early, false),
+                create(converter, &late, false)
+            );
+            // ...but not in deterministic output, whatever the input order
+            assert_eq!(
+                create(converter, &early, true),
+                create(converter, &late_backward, true)
+            );
+        }
+    }
+
     #[test]
     #[cfg(feature = "spreadsheet")]
     fn test_spreadsheet_invalid_input() {
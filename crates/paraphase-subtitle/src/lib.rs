@@ -8,12 +8,41 @@
 //! - `sbv` (default) — SubViewer format (YouTube)
 
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    ConvertError, ConvertOutput, Converter, ConverterDecl, FormatDecl, LossNote, Properties,
+    PropertyPattern, Registry, Severity, report_loss,
 };
 use std::time::Duration;
 
 /// Register all enabled subtitle converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Formats handled by the converters below
+    #[cfg(feature = "srt")]
+    registry.register_format(
+        FormatDecl::new("srt")
+            .extension("srt")
+            .mime("application/x-subrip")
+            .description("SubRip subtitles"),
+    );
+    #[cfg(feature = "vtt")]
+    registry.register_format(
+        FormatDecl::new("vtt")
+            .extension("vtt")
+            .mime("text/vtt")
+            .magic(b"WEBVTT")
+            .alias("webvtt")
+            .description("WebVTT subtitles"),
+    );
+    #[cfg(feature = "sbv")]
+    registry.register_format(
+        FormatDecl::new("sbv")
+            .extension("sbv")
+            .description("YouTube SubViewer subtitles"),
+    );
+
     #[cfg(all(feature = "srt", feature = "vtt"))]
     {
         registry.register(SrtToVtt);
@@ -421,6 +450,48 @@ pub fn serialize_sbv(file: &SubtitleFile) -> String {
     output
 }
 
+// ============================================
+// Fidelity
+// ============================================
+
+/// Whether a cue contains styling markup (`<i>`, `<font ...>`, `{\an8}`).
+fn has_styling(text: &str) -> bool {
+    let tag = text.find('<').is_some_and(|i| {
+        text[i + 1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/')
+            && text[i..].contains('>')
+    });
+    tag || text.contains("{\\")
+}
+
+/// Report cues whose styling the target format cannot express.
+///
+/// SBV has no markup at all; WebVTT keeps HTML-like tags but not the
+/// `{\...}` override tags some SRT files carry.
+fn report_styling_loss(file: &SubtitleFile, to: &str) {
+    let lost = file
+        .entries
+        .iter()
+        .filter(|e| match to {
+            "sbv" => has_styling(&e.text),
+            "vtt" => e.text.contains("{\\"),
+            _ => false,
+        })
+        .count();
+    if lost > 0 {
+        report_loss(
+            LossNote::new("styling", Severity::Minor)
+                .count(lost as u64)
+                .detail(format!(
+                    "{} has no equivalent markup; tags kept as text",
+                    to
+                )),
+        );
+    }
+}
+
 // ============================================
 // Converter structs
 // ============================================
@@ -448,6 +519,7 @@ macro_rules! subtitle_converter {
                 props: &Properties,
             ) -> Result<ConvertOutput, ConvertError> {
                 let parsed = $parse_fn(input)?;
+                report_styling_loss(&parsed, $out_format);
                 let serialized = $serialize_fn(&parsed);
                 let mut out_props = props.clone();
                 out_props.insert("format".into(), $out_format.into());
@@ -614,4 +686,24 @@ mod tests {
         assert!(out_str.contains("00:00:01.000 --> 00:00:04.000"));
         assert!(out_str.contains("Hello, world!"));
     }
+
+    #[test]
+    #[cfg(all(feature = "srt", feature = "sbv"))]
+    fn test_srt_to_sbv_reports_styling() {
+        use paraphase_core::{PropertiesExt, collect_losses};
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\n<i>Hello</i>\n\n2\n00:00:03,000 --> 00:00:04,000\n{\\an8}Top\n\n3\n00:00:05,000 --> 00:00:06,000\n1 < 2 > 0\n\n";
+        let props = Properties::new().with("format", "srt");
+        let (result, losses) = collect_losses("subtitle.srt-to-sbv", || {
+            SrtToSbv.convert(srt.as_bytes(), &props)
+        });
+        assert!(result.is_ok());
+        assert_eq!(losses.len(), 1);
+        assert_eq!(losses[0].feature, "styling");
+        assert_eq!(losses[0].count, 2);
+
+        let (_, losses) = collect_losses("subtitle.srt-to-sbv", || {
+            SrtToSbv.convert(SRT_SAMPLE.as_bytes(), &props)
+        });
+        assert!(losses.is_empty());
+    }
 }
@@ -6,11 +6,25 @@
 //! - `svg` (default) — SVG → PNG/JPEG/WebP via resvg
 
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    ConvertError, ConvertOutput, Converter, ConverterDecl, FormatDecl, Properties, PropertyPattern,
+    Registry,
 };
 
 /// Register all enabled vector converters with the registry.
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Formats handled by the converters below
+    #[cfg(feature = "svg")]
+    registry.register_format(
+        FormatDecl::new("svg")
+            .extensions(["svg", "svgz"])
+            .mime("image/svg+xml")
+            .description("Scalable Vector Graphics"),
+    );
+
     #[cfg(feature = "svg")]
     {
         registry.register(SvgToPng);
@@ -35,9 +49,16 @@ mod svg_impl {
         width: Option<u32>,
         height: Option<u32>,
     ) -> Result<(tiny_skia::Pixmap, u32, u32), ConvertError> {
+        let limits = paraphase_core::current_limits();
+        // Fail deep documents before usvg builds a tree for them. The root
+        // and leaf elements are not groups, so they are allowed for.
+        limits.check_depth(xml_depth(input).saturating_sub(2))?;
+
         let options = resvg::usvg::Options::default();
         let tree = resvg::usvg::Tree::from_data(input, &options)
             .map_err(|e| ConvertError::InvalidInput(format!("Invalid SVG: {}", e)))?;
+        // `use` references can still nest deeper than the markup
+        limits.check_depth(group_depth(tree.root()))?;
 
         let svg_size = tree.size();
         let svg_w = svg_size.width();
@@ -59,6 +80,7 @@ mod svg_impl {
             }
         };
 
+        limits.check_pixels(target_w as u64, target_h as u64)?;
         let mut pixmap = tiny_skia::Pixmap::new(target_w, target_h)
             .ok_or_else(|| ConvertError::Failed("Failed to create pixmap".into()))?;
 
@@ -70,6 +92,77 @@ mod svg_impl {
         Ok((pixmap, target_w, target_h))
     }
 
+    /// Deepest nesting of groups below `root`.
+    fn group_depth(root: &resvg::usvg::Group) -> usize {
+        let mut deepest = 0;
+        let mut stack = vec![(root, 0)];
+        while let Some((group, depth)) = stack.pop() {
+            deepest = deepest.max(depth);
+            for node in group.children() {
+                if let resvg::usvg::Node::Group(child) = node {
+                    stack.push((child, depth + 1));
+                }
+            }
+        }
+        deepest
+    }
+
+    /// Deepest element nesting of raw XML, found by scanning tags without
+    /// parsing the document.
+    fn xml_depth(input: &[u8]) -> usize {
+        let find = |from: usize, needle: &[u8]| {
+            input[from..]
+                .windows(needle.len())
+                .position(|w| w == needle)
+                .map(|i| from + i + needle.len())
+        };
+
+        let (mut depth, mut deepest) = (0usize, 0usize);
+        let mut pos = 0;
+        while let Some(open) = input[pos..].iter().position(|&b| b == b'<') {
+            let start = pos + open + 1;
+            let rest = &input[start..];
+            let end = if rest.starts_with(b"!--") {
+                find(start, b"-->")
+            } else if rest.starts_with(b"![CDATA[") {
+                find(start, b"]]>")
+            } else if rest.starts_with(b"!") || rest.starts_with(b"?") {
+                find(start, b">")
+            } else if rest.starts_with(b"/") {
+                depth = depth.saturating_sub(1);
+                find(start, b">")
+            } else {
+                // Start tag: find its end outside quoted attribute values
+                let mut quote = None;
+                let close = rest.iter().position(|&b| match quote {
+                    Some(q) => {
+                        if b == q {
+                            quote = None;
+                        }
+                        false
+                    }
+                    None if b == b'"' || b == b'\'' => {
+                        quote = Some(b);
+                        false
+                    }
+                    None => b == b'>',
+                });
+                if let Some(close) = close
+                    && !rest[..close].ends_with(b"/")
+                {
+                    depth += 1;
+                    deepest = deepest.max(depth);
+                }
+                close.map(|close| start + close + 1)
+            };
+            match end {
+                Some(end) => pos = end,
+                None => break,
+            }
+        }
+        deepest
+    }
+
     /// Convert Pixmap to RGBA image buffer.
     fn pixmap_to_rgba(pixmap: &tiny_skia::Pixmap) -> RgbaImage {
         let w = pixmap.width();
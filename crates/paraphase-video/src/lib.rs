@@ -3,7 +3,9 @@
 //! Provides video transcoding via FFmpeg. Requires FFmpeg libraries at runtime.
 
 use paraphase_core::{
-    ConvertError, ConvertOutput, Converter, ConverterDecl, Properties, PropertyPattern, Registry,
+    ConvertError, ConvertOutput, Converter, ConverterDecl, FormatDecl, Formula, Inspector,
+    InspectorDecl, OptionDecl, Properties, PropertyDecl, PropertyPattern, PropertyType, Registry,
+    Value,
 };
 
 mod transcode;
@@ -54,6 +56,39 @@ impl Container {
         }
     }
 
+    /// Format descriptor for the registry
+    ///
+    /// MP4 and MOV share the `ftyp` box, and WebM and Matroska the EBML
+    /// header, so those are left to extension and MIME detection.
+    pub fn format_decl(&self) -> FormatDecl {
+        let mut decl = FormatDecl::new(self.as_str()).extensions(self.extensions().iter().copied());
+        if *self != Container::Gif {
+            // Subtitle files next to the video are gathered into a bundle
+            decl = decl.sidecar("srt").sidecar("vtt");
+        }
+        match self {
+            Container::Mp4 => decl.mime("video/mp4").description("MPEG-4 video"),
+            Container::Webm => decl.mime("video/webm").description("WebM video"),
+            Container::Mkv => decl
+                .mime("video/x-matroska")
+                .alias("matroska")
+                .description("Matroska video"),
+            Container::Avi => decl
+                .mime("video/x-msvideo")
+                .magic_at(8, b"AVI ")
+                .description("Audio Video Interleave"),
+            Container::Mov => decl
+                .mime("video/quicktime")
+                .alias("quicktime")
+                .description("QuickTime movie"),
+            Container::Gif => decl
+                .mime("image/gif")
+                .magic(b"GIF87a")
+                .magic(b"GIF89a")
+                .description("Animated GIF"),
+        }
+    }
+
     /// Default video codec for this container
     pub fn default_video_codec(&self) -> &'static str {
         match self {
@@ -123,6 +158,26 @@ impl Quality {
     }
 }
 
+/// Declare the encoding options shared by the video converters.
+fn with_encode_options(decl: ConverterDecl) -> ConverterDecl {
+    decl.option(
+        "quality",
+        OptionDecl::string("Quality preset: low, medium, high or lossless").default_value("medium"),
+    )
+    .option(
+        "max_width",
+        OptionDecl::int("Fit within this width, preserving aspect ratio").min(1.0),
+    )
+    .option(
+        "max_height",
+        OptionDecl::int("Fit within this height, preserving aspect ratio").min(1.0),
+    )
+    .option(
+        "scale",
+        OptionDecl::float("Scale factor (e.g. 0.5 for half size)").min(0.0),
+    )
+}
+
 /// Video format converter
 pub struct VideoConverter {
     decl: ConverterDecl,
@@ -143,9 +198,18 @@ impl VideoConverter {
             "Convert {} to {}",
             from.as_str().to_uppercase(),
             to.as_str().to_uppercase()
-        ));
-
-        Self { decl, from, to }
+        ))
+        // Frames are transcoded one at a time: the encoded output plus a
+        // decoded RGBA frame
+        .memory_model(
+            Formula::input_size() + Formula::prop("width") * Formula::prop("height") * 4.0,
+        );
+
+        Self {
+            decl: with_encode_options(decl),
+            from,
+            to,
+        }
     }
 }
 
@@ -155,24 +219,32 @@ impl Converter for VideoConverter {
     }
 
     fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
-        // Get options from properties
-        let quality = props
+        self.convert_with_options(input, props, &Properties::new())
+    }
+
+    fn convert_with_options(
+        &self,
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
+        let quality = options
             .get("quality")
             .and_then(|v| v.as_str())
             .and_then(Quality::parse)
             .unwrap_or_default();
 
-        let max_width = props
+        let max_width = options
             .get("max_width")
             .and_then(|v| v.as_i64())
             .map(|v| v as u32);
 
-        let max_height = props
+        let max_height = options
             .get("max_height")
             .and_then(|v| v.as_i64())
             .map(|v| v as u32);
 
-        let scale = props.get("scale").and_then(|v| v.as_f64());
+        let scale = options.get("scale").and_then(|v| v.as_f64());
 
         // Transcode
         let (output, out_props) = transcode::transcode(
@@ -205,7 +277,9 @@ impl VideoResizeConverter {
         )
         .description("Resize video");
 
-        Self { decl }
+        Self {
+            decl: with_encode_options(decl),
+        }
     }
 }
 
@@ -221,29 +295,38 @@ impl Converter for VideoResizeConverter {
     }
 
     fn convert(&self, input: &[u8], props: &Properties) -> Result<ConvertOutput, ConvertError> {
+        self.convert_with_options(input, props, &Properties::new())
+    }
+
+    fn convert_with_options(
+        &self,
+        input: &[u8],
+        props: &Properties,
+        options: &Properties,
+    ) -> Result<ConvertOutput, ConvertError> {
         let format = props
             .get("format")
             .and_then(|v| v.as_str())
             .and_then(Container::parse)
             .ok_or_else(|| ConvertError::InvalidInput("Unknown video format".into()))?;
 
-        let quality = props
+        let quality = options
             .get("quality")
             .and_then(|v| v.as_str())
             .and_then(Quality::parse)
             .unwrap_or_default();
 
-        let max_width = props
+        let max_width = options
             .get("max_width")
             .and_then(|v| v.as_i64())
             .map(|v| v as u32);
 
-        let max_height = props
+        let max_height = options
             .get("max_height")
             .and_then(|v| v.as_i64())
             .map(|v| v as u32);
 
-        let scale = props.get("scale").and_then(|v| v.as_f64());
+        let scale = options.get("scale").and_then(|v| v.as_f64());
 
         if max_width.is_none() && max_height.is_none() && scale.is_none() {
             return Err(ConvertError::InvalidInput(
@@ -263,8 +346,73 @@ impl Converter for VideoResizeConverter {
     }
 }
 
+/// Inspector reading codec, dimensions and duration from a container
+pub struct VideoInspector {
+    decl: InspectorDecl,
+    format: Container,
+}
+
+impl VideoInspector {
+    pub fn new(format: Container) -> Self {
+        let decl = InspectorDecl::new(
+            format!("video.inspect-{}", format.as_str()),
+            PropertyPattern::new().eq("format", format.as_str()),
+        )
+        .description(format!(
+            "Read {} codec, dimensions and duration",
+            format.as_str().to_uppercase()
+        ))
+        .provides("video_codec")
+        .provides("audio_codec")
+        .provides("width")
+        .provides("height")
+        .provides("duration");
+
+        Self { decl, format }
+    }
+}
+
+impl Inspector for VideoInspector {
+    fn decl(&self) -> &InspectorDecl {
+        &self.decl
+    }
+
+    fn inspect(&self, input: &[u8], _props: &Properties) -> Result<Properties, ConvertError> {
+        let probed = transcode::probe(input, self.format)?;
+
+        // Numeric fields are typed so patterns and resource models can use them
+        Ok(probed
+            .into_iter()
+            .map(|(k, v)| {
+                let value = match k.as_str() {
+                    "width" | "height" => v.parse::<i64>().map(Value::from).unwrap_or(v.into()),
+                    "duration" => v.parse::<f64>().map(Value::from).unwrap_or(v.into()),
+                    _ => v.into(),
+                };
+                (k, value)
+            })
+            .collect())
+    }
+}
+
 /// Register all video converters
 pub fn register_all(registry: &mut Registry) {
+    registry.with_provider(env!("CARGO_PKG_NAME"), register_enabled);
+}
+
+fn register_enabled(registry: &mut Registry) {
+    // Property keys produced by the converters and inspectors below
+    registry.declare_property(PropertyDecl::new(
+        "video_codec",
+        PropertyType::String,
+        "Video stream codec",
+    ));
+    registry.declare_property(PropertyDecl::new(
+        "audio_codec",
+        PropertyType::String,
+        "Audio stream codec",
+    ));
+
     let containers = [
         #[cfg(feature = "mp4")]
         Container::Mp4,
@@ -280,6 +428,11 @@ pub fn register_all(registry: &mut Registry) {
         Container::Gif,
     ];
 
+    // Formats handled by the converters below
+    for format in &containers {
+        registry.register_format(format.format_decl());
+    }
+
     // Register format converters
     for &from in &containers {
         for &to in &containers {
@@ -291,6 +444,11 @@ pub fn register_all(registry: &mut Registry) {
 
     // Register resize converter
     registry.register(VideoResizeConverter::new());
+
+    // Register inspectors
+    for &format in &containers {
+        registry.register_inspector(VideoInspector::new(format));
+    }
 }
 
 /// Check if FFmpeg is available
@@ -97,6 +97,11 @@ pub fn transcode(
     let mut opts = ffmpeg::Dictionary::new();
     opts.set("crf", &quality.crf().to_string());
     opts.set("preset", "medium");
+    let deterministic = paraphase_core::is_deterministic();
+    if deterministic {
+        // Threaded encoding can vary between runs
+        opts.set("threads", "1");
+    }
 
     let encoder = encoder
         .open_with(opts)
@@ -104,8 +109,12 @@ pub fn transcode(
 
     video_stream.set_parameters(&encoder);
 
-    // Write header
-    octx.write_header()
+    // Write header; bitexact leaves out encoder versions and creation times
+    let mut muxer_opts = ffmpeg::Dictionary::new();
+    if deterministic {
+        muxer_opts.set("fflags", "+bitexact");
+    }
+    octx.write_header_with(muxer_opts)
         .map_err(|e| ConvertError::InvalidInput(format!("Failed to write header: {}", e)))?;
 
     // Create scaler if needed
@@ -176,6 +185,56 @@ pub fn transcode(
     Ok((output, out_props))
 }
 
+/// Read stream parameters without decoding any frames
+pub fn probe(input: &[u8], format: Container) -> Result<HashMap<String, String>, ConvertError> {
+    ffmpeg::init().map_err(|e| ConvertError::InvalidInput(format!("FFmpeg init failed: {}", e)))?;
+
+    let temp_dir =
+        std::env::temp_dir().join(format!("paraphase-video-probe-{}", std::process::id()));
+    std::fs::create_dir_all(&temp_dir)
+        .map_err(|e| ConvertError::InvalidInput(format!("Failed to create temp dir: {}", e)))?;
+
+    let input_path = temp_dir.join(format!("input.{}", format.as_str()));
+    std::fs::write(&input_path, input)
+        .map_err(|e| ConvertError::InvalidInput(format!("Failed to write input: {}", e)))?;
+
+    let result = probe_file(&input_path);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    result
+}
+
+fn probe_file(path: &std::path::Path) -> Result<HashMap<String, String>, ConvertError> {
+    let ictx = ffmpeg::format::input(&path)
+        .map_err(|e| ConvertError::InvalidInput(format!("Failed to open input: {}", e)))?;
+
+    let mut props = HashMap::new();
+
+    // Container duration is in AV_TIME_BASE units (microseconds)
+    if ictx.duration() > 0 {
+        let seconds = ictx.duration() as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE);
+        props.insert("duration".into(), seconds.to_string());
+    }
+
+    if let Some(stream) = ictx.streams().best(ffmpeg::media::Type::Video) {
+        let params = stream.parameters();
+        props.insert("video_codec".into(), params.id().name().into());
+
+        let decoder = ffmpeg::codec::context::Context::from_parameters(params)
+            .and_then(|ctx| ctx.decoder().video())
+            .map_err(|e| {
+                ConvertError::InvalidInput(format!("Failed to read video stream: {}", e))
+            })?;
+        props.insert("width".into(), decoder.width().to_string());
+        props.insert("height".into(), decoder.height().to_string());
+    }
+
+    if let Some(stream) = ictx.streams().best(ffmpeg::media::Type::Audio) {
+        props.insert("audio_codec".into(), stream.parameters().id().name().into());
+    }
+
+    Ok(props)
+}
+
 /// Calculate output dimensions based on constraints
 fn calculate_dimensions(
     src_width: u32,